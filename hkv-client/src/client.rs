@@ -5,7 +5,7 @@
 //!
 //! ## Usage
 //! ```no_run
-//! use hkv_client::{ClientConfig, KVClient};
+//! use hkv_client::{ClientConfig, KVClient, ReadMode};
 //! use std::time::Duration;
 //!
 //! let client = KVClient::connect("127.0.0.1:6379").expect("connect");
@@ -22,7 +22,9 @@
 //!     write_timeout: Some(Duration::from_secs(1)),
 //!     connect_timeout: Some(Duration::from_secs(1)),
 //! };
-//! let client = KVClient::with_config(config).expect("connect");
+//! let client = KVClient::with_config(config)
+//!     .expect("connect")
+//!     .with_read_mode(ReadMode::KernelThenServer);
 //! let _ = client.ping(None).expect("ping");
 //! ```
 //!
@@ -31,6 +33,12 @@
 //! - If the pool hits `max_total`, callers get a `PoolExhausted` error immediately.
 //! - Connections that hit IO/protocol errors are discarded to avoid reusing bad state.
 //!
+//! ## Kernel Fast Path
+//! - `get` reads from the server unless `KVClient::with_read_mode` opts into
+//!   the kernel cache; it then only borrows a connection when it has to fall
+//!   back to the server.
+//! - All other commands always go to the server.
+//!
 //! ## Design Principles
 //! 1. **Facade Pattern**: `KVClient` hides pooling and protocol details.
 //! 2. **Borrow-Friendly API**: Accept `&[u8]` to avoid unnecessary copies.
@@ -40,6 +48,9 @@
 use std::fmt;
 use std::time::Duration;
 
+use hkv_common::HkvError;
+
+use crate::kernel::{KernelLookup, KernelReadStats, KernelReader, ReadMode};
 use crate::pool::{ConnectionPool, PoolConfig};
 use crate::resp::RespValue;
 
@@ -61,6 +72,10 @@ pub enum ClientError {
     PoolExhausted,
     /// Address could not be parsed into a socket address.
     InvalidAddress,
+    /// `ReadMode::KernelOnly` was requested but no kernel device is open.
+    KernelUnavailable,
+    /// The kernel cache failed a read in `ReadMode::KernelOnly`.
+    Kernel(HkvError),
 }
 
 impl fmt::Display for ClientError {
@@ -74,6 +89,8 @@ impl fmt::Display for ClientError {
             ClientError::UnexpectedResponse => write!(f, "unexpected response"),
             ClientError::PoolExhausted => write!(f, "connection pool exhausted"),
             ClientError::InvalidAddress => write!(f, "invalid address"),
+            ClientError::KernelUnavailable => write!(f, "kernel cache device unavailable"),
+            ClientError::Kernel(err) => write!(f, "kernel cache error: {}", err),
         }
     }
 }
@@ -133,17 +150,23 @@ impl Default for ClientConfig {
 /// a connection, executes one command, and returns the connection to the pool.
 pub struct KVClient {
     pool: ConnectionPool,
+    kernel: KernelReader,
 }
 
 impl KVClient {
     /// Creates a client with default configuration.
     pub fn connect(addr: impl Into<String>) -> ClientResult<Self> {
-        let mut config = ClientConfig::default();
-        config.addr = addr.into();
+        let config = ClientConfig {
+            addr: addr.into(),
+            ..ClientConfig::default()
+        };
         Self::with_config(config)
     }
 
     /// Creates a client with a custom configuration.
+    ///
+    /// `get` reads from the server only until `with_read_mode` selects the
+    /// kernel fast path.
     pub fn with_config(config: ClientConfig) -> ClientResult<Self> {
         let pool = ConnectionPool::new(PoolConfig {
             addr: config.addr,
@@ -153,15 +176,62 @@ impl KVClient {
             write_timeout: config.write_timeout,
             connect_timeout: config.connect_timeout,
         })?;
-        Ok(KVClient { pool })
+        Ok(KVClient {
+            pool,
+            kernel: KernelReader::new(ReadMode::ServerOnly, None),
+        })
+    }
+
+    /// Makes `get` consult the tiers `mode` names.
+    ///
+    /// Opens the kernel device when `mode` uses it; a missing device only
+    /// makes `get` fall back to the server.
+    pub fn with_read_mode(self, mode: ReadMode) -> Self {
+        self.with_kernel_reader(KernelReader::open(mode))
+    }
+
+    /// Replaces the kernel fast path with a prepared reader; its mode
+    /// decides which tiers `get` consults.
+    pub fn with_kernel_reader(mut self, kernel: KernelReader) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// Returns the kernel fast-path counters.
+    pub fn kernel_stats(&self) -> KernelReadStats {
+        self.kernel.stats()
     }
 
     /// Fetches a value by key.
     ///
     /// Returns `Ok(None)` when the key is missing.
     ///
+    /// The kernel cache is tried first unless the mode is `ServerOnly`. In
+    /// `KernelThenServer` mode a kernel miss, stale entry, error, or missing
+    /// device falls back to the server; in `KernelOnly` mode a miss is
+    /// `Ok(None)` and device problems are errors.
+    ///
     /// The server response is expected to be a bulk string or null bulk string.
     pub fn get(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+        match self.kernel.mode() {
+            ReadMode::ServerOnly => self.get_from_server(key),
+            ReadMode::KernelOnly => match self.kernel.lookup(key) {
+                KernelLookup::Hit(value) => Ok(Some(value)),
+                KernelLookup::Miss => Ok(None),
+                KernelLookup::Unavailable => Err(ClientError::KernelUnavailable),
+                KernelLookup::Failed(err) => Err(err.into()),
+            },
+            ReadMode::KernelThenServer => match self.kernel.lookup(key) {
+                KernelLookup::Hit(value) => Ok(Some(value)),
+                _ => {
+                    self.kernel.record_fallback();
+                    self.get_from_server(key)
+                }
+            },
+        }
+    }
+
+    fn get_from_server(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
         let mut conn = self.pool.acquire()?;
         match conn.exec(&[b"GET", key])? {
             RespValue::Bulk(data) => Ok(data),
@@ -226,8 +296,8 @@ impl KVClient {
     pub fn ttl(&self, key: &[u8]) -> ClientResult<ClientTtl> {
         let mut conn = self.pool.acquire()?;
         match conn.exec(&[b"TTL", key])? {
-            RespValue::Integer(value) if value == -2 => Ok(ClientTtl::Missing),
-            RespValue::Integer(value) if value == -1 => Ok(ClientTtl::NoExpiry),
+            RespValue::Integer(-2) => Ok(ClientTtl::Missing),
+            RespValue::Integer(-1) => Ok(ClientTtl::NoExpiry),
             RespValue::Integer(value) if value >= 0 => {
                 Ok(ClientTtl::ExpiresIn(Duration::from_secs(value as u64)))
             }
@@ -0,0 +1,733 @@
+//! # Kernel Fast-Path Reader
+//!
+//! Serve `GET` from the kernel cache with one `CMD_READ` ioctl on
+//! `DEVICE_PATH`, falling back to the server over RESP when the kernel
+//! cannot answer.
+//!
+//! ## Read Modes
+//! - `KernelOnly`: only the kernel is asked; a miss is `Ok(None)`.
+//! - `KernelThenServer`: the kernel is asked first; a miss, a stale entry,
+//!   a device error, or a missing device falls back to the server.
+//! - `ServerOnly` (the default): the kernel is never touched.
+//!
+//! ## Protocol Negotiation
+//! Reads start at `PROTOCOL_VERSION` and use `CMD_READ_EXT`, so values up to
+//! `MAX_EXT_VALUE_SIZE` come back through a user buffer. A `VersionMismatch`
+//! answer, or an ioctl failing with `ENOTTY` because the module does not know
+//! the command, steps the reader down one version for good, never below
+//! `MIN_PROTOCOL_VERSION`. Against a version 1 module the reader uses
+//! `CMD_READ` with `ReadRequestV1`: large values fall back to the server, and
+//! so does every read of a non-default tenant, which version 1 cannot name.
+//!
+//! ## Design Principles
+//! 1. **Device Behind a Trait**: `KernelDevice` is the only code that
+//!    touches the ioctl, so tests plug in an in-process fake.
+//! 2. **Absence Is Not an Error**: A missing device is detected once at
+//!    open time and turns every read into a fallback, not a failure.
+//! 3. **Counters Are Cheap**: Hits, misses, fallbacks, and device errors
+//!    are relaxed atomics bumped on the hot path without locks.
+
+use std::fs::File;
+use std::io;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+use hkv_common::{
+    DEFAULT_TENANT, HkvError, IoctlCommand, IoctlHeader, Key, MAX_EXT_VALUE_SIZE,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, ReadExtRequest, ReadExtResponse, ReadRequest,
+    ReadRequestV1, ReadResponse, STATUS_OK, Value, errno,
+};
+
+/// First user buffer offered to `CMD_READ_EXT`; larger values cost one retry.
+const INITIAL_READ_BUFFER: usize = 8 * 1024;
+
+/// Which tiers `KVClient::get` consults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadMode {
+    /// Read from the kernel cache only.
+    KernelOnly,
+    /// Try the kernel cache, then the server.
+    KernelThenServer,
+    /// Read from the server only.
+    #[default]
+    ServerOnly,
+}
+
+impl ReadMode {
+    /// Returns true when the kernel cache is consulted.
+    pub const fn uses_kernel(self) -> bool {
+        !matches!(self, ReadMode::ServerOnly)
+    }
+}
+
+/// Access to the kernel cache device.
+///
+/// Implementations return the response the module wrote (its `status`
+/// carries `STATUS_OK` or an `HkvError` code) and reserve `Err` for
+/// failures of the call itself.
+pub trait KernelDevice: Send + Sync {
+    /// Issues `CMD_READ`.
+    fn read(&self, request: &ReadRequest) -> io::Result<ReadResponse>;
+
+    /// Issues `CMD_READ_EXT` with `buf` as the memory behind
+    /// `request.buf_ptr`.
+    ///
+    /// The default answers `VersionMismatch`, like a module that predates
+    /// the command.
+    fn read_ext(&self, request: &ReadExtRequest, buf: &mut [u8]) -> io::Result<ReadExtResponse> {
+        let _ = (request, buf);
+        Ok(ReadExtResponse::new(HkvError::VersionMismatch.code(), 0))
+    }
+
+    /// Issues a version 1 `CMD_READ`.
+    ///
+    /// The default answers `VersionMismatch`, like a module that no longer
+    /// speaks version 1.
+    fn read_v1(&self, request: &ReadRequestV1) -> io::Result<ReadResponse> {
+        let _ = request;
+        let status = HkvError::VersionMismatch.code();
+        Ok(ReadResponse::new(
+            status,
+            Value::new(&[]).expect("empty value"),
+        ))
+    }
+}
+
+/// Outcome of one kernel lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KernelLookup {
+    /// The kernel returned a fresh value.
+    Hit(Vec<u8>),
+    /// The key is not cached, expired, or was invalidated (or is too large
+    /// for the negotiated protocol version).
+    Miss,
+    /// No device is open.
+    Unavailable,
+    /// The device call failed or returned an unexpected status.
+    Failed(KernelError),
+}
+
+/// A failed kernel read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KernelError {
+    /// The ioctl itself failed.
+    Io(io::ErrorKind),
+    /// The ioctl failed with `ENOTTY`: the module does not know the command.
+    UnknownCommand,
+    /// The module answered with an error status other than a miss.
+    Status(HkvError),
+    /// The module answered with a status this build does not know.
+    UnknownStatus(u16),
+}
+
+impl From<KernelError> for crate::client::ClientError {
+    fn from(err: KernelError) -> Self {
+        match err {
+            KernelError::Io(kind) => Self::Io(io::Error::from(kind)),
+            KernelError::UnknownCommand => Self::Kernel(HkvError::UnsupportedCommand),
+            KernelError::Status(err) => Self::Kernel(err),
+            KernelError::UnknownStatus(_) => Self::Protocol,
+        }
+    }
+}
+
+/// Counters snapshot of a `KernelReader`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KernelReadStats {
+    /// Lookups answered by the kernel.
+    pub hits: u64,
+    /// Lookups the kernel answered with a miss (including stale entries).
+    pub misses: u64,
+    /// Reads that went to the server after the kernel could not answer.
+    pub fallbacks: u64,
+    /// Device calls that failed.
+    pub errors: u64,
+}
+
+/// Kernel fast-path reader used by `KVClient::get`.
+pub struct KernelReader {
+    mode: ReadMode,
+    tenant: u16,
+    device: Option<Box<dyn KernelDevice>>,
+    /// Negotiated protocol version; only ever steps down.
+    protocol: AtomicU8,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    fallbacks: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl KernelReader {
+    /// Creates a reader over an explicit device (or none).
+    pub fn new(mode: ReadMode, device: Option<Box<dyn KernelDevice>>) -> Self {
+        KernelReader {
+            mode,
+            tenant: DEFAULT_TENANT,
+            device,
+            protocol: AtomicU8::new(PROTOCOL_VERSION),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            fallbacks: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Opens `DEVICE_PATH` unless the mode never reads from the kernel.
+    ///
+    /// A device that cannot be opened leaves the reader without one; reads
+    /// then fall back (or fail in `KernelOnly` mode).
+    pub fn open(mode: ReadMode) -> Self {
+        let device = if mode.uses_kernel() {
+            IoctlDevice::open(hkv_common::DEVICE_PATH)
+                .ok()
+                .map(|device| Box::new(device) as Box<dyn KernelDevice>)
+        } else {
+            None
+        };
+        Self::new(mode, device)
+    }
+
+    /// Reads from `tenant`'s partition instead of the default tenant.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// Returns the configured read mode.
+    pub fn mode(&self) -> ReadMode {
+        self.mode
+    }
+
+    /// Returns the protocol version reads currently use.
+    pub fn protocol_version(&self) -> u8 {
+        self.protocol.load(Ordering::Relaxed)
+    }
+
+    /// Returns true when a device is open.
+    pub fn has_device(&self) -> bool {
+        self.device.is_some()
+    }
+
+    /// Looks `key` up in the kernel cache and updates the hit/miss counters.
+    ///
+    /// Keys longer than the kernel's key buffer can never be cached and are
+    /// reported as misses without a device call.
+    pub fn lookup(&self, key: &[u8]) -> KernelLookup {
+        let Some(device) = &self.device else {
+            return KernelLookup::Unavailable;
+        };
+        let Ok(key) = Key::new(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return KernelLookup::Miss;
+        };
+        let lookup = match self.fetch(device.as_ref(), &key) {
+            Ok(Some(value)) => KernelLookup::Hit(value),
+            Ok(None) => KernelLookup::Miss,
+            Err(err) => KernelLookup::Failed(err),
+        };
+        let counter = match lookup {
+            KernelLookup::Hit(_) => &self.hits,
+            KernelLookup::Miss => &self.misses,
+            _ => &self.errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        lookup
+    }
+
+    /// Reads `key` with the negotiated protocol, stepping down a version
+    /// whenever the module answers `VersionMismatch` or the ioctl fails with
+    /// `ENOTTY`. `Ok(None)` is a miss.
+    fn fetch(&self, device: &dyn KernelDevice, key: &Key) -> Result<Option<Vec<u8>>, KernelError> {
+        loop {
+            let version = self.protocol.load(Ordering::Relaxed);
+            let result = if IoctlCommand::ReadExt.supports_version(version) {
+                self.fetch_ext(device, key, version)
+            } else {
+                self.fetch_inline(device, key, version)
+            };
+            match result {
+                Err(
+                    KernelError::Status(HkvError::VersionMismatch) | KernelError::UnknownCommand,
+                ) if version > MIN_PROTOCOL_VERSION => {
+                    // Lost races are fine: another reader stepped down already.
+                    let _ = self.protocol.compare_exchange(
+                        version,
+                        version - 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// `CMD_READ` with the value inline in the response.
+    ///
+    /// Version 1 has no tenant field, so only default-tenant reads go to the
+    /// device; other tenants miss and the server answers.
+    fn fetch_inline(
+        &self,
+        device: &dyn KernelDevice,
+        key: &Key,
+        version: u8,
+    ) -> Result<Option<Vec<u8>>, KernelError> {
+        let response = if version == ReadRequestV1::VERSION {
+            if self.tenant != DEFAULT_TENANT {
+                return Ok(None);
+            }
+            device.read_v1(&ReadRequestV1::new(key.clone()))
+        } else {
+            let mut request = ReadRequest::new(key.clone()).with_tenant(self.tenant);
+            request.header = IoctlHeader::with_version(IoctlCommand::Read, version);
+            device.read(&request)
+        };
+        let response = response.map_err(device_error)?;
+        Ok(check_status(response.status)?.then(|| response.value.as_bytes().to_vec()))
+    }
+
+    /// `CMD_READ_EXT` into a user buffer, retrying once with the reported
+    /// length when the first buffer is too small.
+    fn fetch_ext(
+        &self,
+        device: &dyn KernelDevice,
+        key: &Key,
+        version: u8,
+    ) -> Result<Option<Vec<u8>>, KernelError> {
+        let mut buf = vec![0u8; INITIAL_READ_BUFFER];
+        for _ in 0..2 {
+            let mut request = ReadExtRequest::new(key.clone(), &mut buf).with_tenant(self.tenant);
+            request.header = IoctlHeader::with_version(IoctlCommand::ReadExt, version);
+            let response = device.read_ext(&request, &mut buf).map_err(device_error)?;
+            if !check_status(response.status)? {
+                return Ok(None);
+            }
+            let len = response.value_len as usize;
+            if response.copied(request.buf_len) {
+                buf.truncate(len);
+                return Ok(Some(buf));
+            }
+            if len > MAX_EXT_VALUE_SIZE {
+                return Err(KernelError::Status(HkvError::ProtocolViolation));
+            }
+            buf.resize(len, 0);
+        }
+        // Replaced by a larger value between the two calls; let the server
+        // answer rather than chase it.
+        Ok(None)
+    }
+
+    /// Records that a read was served by the server after a kernel attempt.
+    pub(crate) fn record_fallback(&self) {
+        self.fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns a snapshot of the counters.
+    pub fn stats(&self) -> KernelReadStats {
+        KernelReadStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            fallbacks: self.fallbacks.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Maps a response status to hit (`true`), miss (`false`), or failure.
+///
+/// `ValueTooLong` is a miss: the value exists but cannot travel in the
+/// negotiated command, so the server has to serve it.
+fn check_status(status: u16) -> Result<bool, KernelError> {
+    if status == STATUS_OK {
+        return Ok(true);
+    }
+    match HkvError::from_code(status) {
+        Some(HkvError::NotFound | HkvError::StaleVersion | HkvError::ValueTooLong) => Ok(false),
+        Some(err) => Err(KernelError::Status(err)),
+        None => Err(KernelError::UnknownStatus(status)),
+    }
+}
+
+/// Maps a failed device call, singling out `ENOTTY` for negotiation.
+fn device_error(err: io::Error) -> KernelError {
+    if err.raw_os_error() == Some(errno::ENOTTY) {
+        KernelError::UnknownCommand
+    } else {
+        KernelError::Io(err.kind())
+    }
+}
+
+/// The character device exported by the kernel module.
+pub struct IoctlDevice {
+    file: File,
+}
+
+impl IoctlDevice {
+    /// Opens the device node read-write.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = File::options().read(true).write(true).open(path)?;
+        Ok(IoctlDevice { file })
+    }
+}
+
+impl IoctlDevice {
+    /// Issues a read-write ioctl whose argument holds `request` on entry and
+    /// the module's `Resp` on return.
+    #[cfg(target_os = "linux")]
+    fn call<Req: Clone, Resp>(&self, command: IoctlCommand, request: &Req) -> io::Result<Resp> {
+        use std::mem::ManuallyDrop;
+        use std::os::fd::AsRawFd;
+        use std::os::raw::{c_int, c_ulong};
+
+        unsafe extern "C" {
+            fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        }
+
+        /// `_IOWR` argument: the request goes in, the response comes back
+        /// in the same buffer.
+        #[repr(C)]
+        union InOut<Req, Resp> {
+            request: ManuallyDrop<Req>,
+            response: ManuallyDrop<Resp>,
+        }
+        if std::mem::size_of::<InOut<Req, Resp>>() != command.arg_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ioctl argument size does not match the request number",
+            ));
+        }
+
+        let mut arg = InOut::<Req, Resp> {
+            request: ManuallyDrop::new(request.clone()),
+        };
+        // SAFETY: `arg` is a live buffer of exactly the size encoded in the
+        // request number, and the module only writes a `Resp` to it.
+        let rc = unsafe {
+            ioctl(
+                self.file.as_raw_fd(),
+                command.request_number() as c_ulong,
+                &mut arg as *mut InOut<Req, Resp>,
+            )
+        };
+        if rc < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: on success the module has overwritten the buffer with a
+        // `Resp`; the ABI structs are plain bytes with no drop glue.
+        Ok(ManuallyDrop::into_inner(unsafe { arg.response }))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn call<Req: Clone, Resp>(&self, _command: IoctlCommand, _request: &Req) -> io::Result<Resp> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+impl KernelDevice for IoctlDevice {
+    fn read(&self, request: &ReadRequest) -> io::Result<ReadResponse> {
+        self.call(IoctlCommand::Read, request).or_else(|err| {
+            let status = errno_status(&err).ok_or(err)?;
+            Ok(ReadResponse::new(
+                status,
+                Value::new(&[]).expect("empty value"),
+            ))
+        })
+    }
+
+    fn read_ext(&self, request: &ReadExtRequest, _buf: &mut [u8]) -> io::Result<ReadExtResponse> {
+        // `_buf` is what `request.buf_ptr` points at; borrowing it keeps the
+        // memory alive and unaliased while the module writes to it.
+        self.call(IoctlCommand::ReadExt, request).or_else(|err| {
+            let status = errno_status(&err).ok_or(err)?;
+            Ok(ReadExtResponse::new(status, 0))
+        })
+    }
+
+    fn read_v1(&self, request: &ReadRequestV1) -> io::Result<ReadResponse> {
+        // Same request number as version 2: the response sets the size.
+        self.call(IoctlCommand::Read, request).or_else(|err| {
+            let status = errno_status(&err).ok_or(err)?;
+            Ok(ReadResponse::new(
+                status,
+                Value::new(&[]).expect("empty value"),
+            ))
+        })
+    }
+}
+
+/// Status for the errnos the module uses to answer rather than to fail.
+///
+/// Misses are `ENOENT` and protocol mismatches `VersionMismatch`. Other
+/// errnos stay I/O failures; `ENOTTY`, from a module that predates the
+/// command, reaches negotiation as `KernelError::UnknownCommand`.
+fn errno_status(err: &io::Error) -> Option<u16> {
+    match HkvError::from_io_error(err) {
+        err @ (HkvError::NotFound | HkvError::VersionMismatch) => Some(err.code()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::MAX_KEY_SIZE;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// A module holding small values, answering both read commands.
+    struct MapDevice {
+        entries: HashMap<Vec<u8>, Result<Vec<u8>, HkvError>>,
+    }
+
+    impl MapDevice {
+        fn answer(&self, key: &Key) -> io::Result<(u16, &[u8])> {
+            Ok(match self.entries.get(key.as_bytes()) {
+                Some(Ok(value)) => (STATUS_OK, value.as_slice()),
+                Some(Err(HkvError::Interrupted)) => {
+                    return Err(io::Error::from(io::ErrorKind::Interrupted));
+                }
+                Some(Err(err)) => (err.code(), &[][..]),
+                None => (HkvError::NotFound.code(), &[][..]),
+            })
+        }
+    }
+
+    impl KernelDevice for MapDevice {
+        fn read(&self, request: &ReadRequest) -> io::Result<ReadResponse> {
+            let (status, value) = self.answer(&request.key)?;
+            Ok(ReadResponse::new(status, Value::new(value).unwrap()))
+        }
+
+        fn read_ext(
+            &self,
+            request: &ReadExtRequest,
+            buf: &mut [u8],
+        ) -> io::Result<ReadExtResponse> {
+            let (status, value) = self.answer(&request.key)?;
+            buf[..value.len()].copy_from_slice(value);
+            Ok(ReadExtResponse::new(status, value.len() as u32))
+        }
+    }
+
+    fn reader() -> KernelReader {
+        let entries = HashMap::from([
+            (b"hot".to_vec(), Ok(b"v".to_vec())),
+            (b"stale".to_vec(), Err(HkvError::StaleVersion)),
+            (b"busy".to_vec(), Err(HkvError::Busy)),
+            (b"eintr".to_vec(), Err(HkvError::Interrupted)),
+        ]);
+        KernelReader::new(
+            ReadMode::KernelThenServer,
+            Some(Box::new(MapDevice { entries })),
+        )
+    }
+
+    #[test]
+    fn classifies_device_answers() {
+        let reader = reader();
+        assert_eq!(reader.lookup(b"hot"), KernelLookup::Hit(b"v".to_vec()));
+        assert_eq!(reader.lookup(b"cold"), KernelLookup::Miss);
+        assert_eq!(reader.lookup(b"stale"), KernelLookup::Miss);
+        assert_eq!(
+            reader.lookup(b"busy"),
+            KernelLookup::Failed(KernelError::Status(HkvError::Busy))
+        );
+        assert_eq!(
+            reader.lookup(b"eintr"),
+            KernelLookup::Failed(KernelError::Io(io::ErrorKind::Interrupted))
+        );
+        assert_eq!(reader.lookup(&[b'k'; MAX_KEY_SIZE + 1]), KernelLookup::Miss);
+        assert_eq!(
+            reader.stats(),
+            KernelReadStats {
+                hits: 1,
+                misses: 3,
+                fallbacks: 0,
+                errors: 2,
+            }
+        );
+    }
+
+    /// A version 2 module holding one large value, logging buffer sizes.
+    struct ExtDevice {
+        value: Vec<u8>,
+        offered: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl KernelDevice for ExtDevice {
+        fn read(&self, _request: &ReadRequest) -> io::Result<ReadResponse> {
+            unreachable!("a version 2 reader uses CMD_READ_EXT");
+        }
+
+        fn read_ext(
+            &self,
+            request: &ReadExtRequest,
+            buf: &mut [u8],
+        ) -> io::Result<ReadExtResponse> {
+            assert_eq!(request.buf_ptr, buf.as_ptr() as u64);
+            self.offered.lock().unwrap().push(request.buf_len);
+            if let Some(dst) = buf.get_mut(..self.value.len()) {
+                dst.copy_from_slice(&self.value);
+            }
+            Ok(ReadExtResponse::new(STATUS_OK, self.value.len() as u32))
+        }
+    }
+
+    /// A version 1 module: tenant-less inline reads only.
+    struct VersionOneDevice(MapDevice);
+
+    impl KernelDevice for VersionOneDevice {
+        fn read(&self, _request: &ReadRequest) -> io::Result<ReadResponse> {
+            let status = HkvError::VersionMismatch.code();
+            Ok(ReadResponse::new(status, Value::new(&[]).unwrap()))
+        }
+
+        fn read_v1(&self, request: &ReadRequestV1) -> io::Result<ReadResponse> {
+            assert_eq!(request.header.version, 1);
+            let (status, value) = self.0.answer(&request.key)?;
+            Ok(ReadResponse::new(status, Value::new(value).unwrap()))
+        }
+    }
+
+    fn version_one_reader() -> KernelReader {
+        let entries = HashMap::from([(b"hot".to_vec(), Ok(b"v".to_vec()))]);
+        let device = VersionOneDevice(MapDevice { entries });
+        KernelReader::new(ReadMode::KernelThenServer, Some(Box::new(device)))
+    }
+
+    #[test]
+    fn negotiates_down_to_a_version_one_module() {
+        let reader = version_one_reader();
+        assert_eq!(reader.protocol_version(), PROTOCOL_VERSION);
+        assert_eq!(reader.lookup(b"hot"), KernelLookup::Hit(b"v".to_vec()));
+        assert_eq!(reader.protocol_version(), MIN_PROTOCOL_VERSION);
+        assert_eq!(reader.lookup(b"hot"), KernelLookup::Hit(b"v".to_vec()));
+        assert_eq!(reader.lookup(b"cold"), KernelLookup::Miss);
+        assert_eq!(reader.stats().errors, 0);
+
+        // Version 1 cannot address another tenant, so its reads miss.
+        let reader = version_one_reader().with_tenant(3);
+        assert_eq!(reader.lookup(b"hot"), KernelLookup::Miss);
+        assert_eq!(reader.protocol_version(), MIN_PROTOCOL_VERSION);
+    }
+
+    /// A module that speaks none of the supported versions.
+    struct FutureDevice;
+
+    impl KernelDevice for FutureDevice {
+        fn read(&self, _request: &ReadRequest) -> io::Result<ReadResponse> {
+            let status = HkvError::VersionMismatch.code();
+            Ok(ReadResponse::new(status, Value::new(&[]).unwrap()))
+        }
+    }
+
+    #[test]
+    fn negotiates_no_lower_than_the_oldest_supported_version() {
+        let reader = KernelReader::new(ReadMode::KernelOnly, Some(Box::new(FutureDevice)));
+        assert_eq!(
+            reader.lookup(b"hot"),
+            KernelLookup::Failed(KernelError::Status(HkvError::VersionMismatch))
+        );
+        assert_eq!(reader.protocol_version(), MIN_PROTOCOL_VERSION);
+        assert_eq!(reader.stats().errors, 1);
+    }
+
+    #[test]
+    fn reads_large_values_through_user_buffers() {
+        let cases = [
+            (2 * 1024, vec![8192]),
+            (INITIAL_READ_BUFFER, vec![8192]),
+            (12 * 1024, vec![8192, 12 * 1024]),
+        ];
+        for (size, expected) in cases {
+            let value: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            let offered = Arc::new(Mutex::new(Vec::new()));
+            let device = ExtDevice {
+                value: value.clone(),
+                offered: offered.clone(),
+            };
+            let reader = KernelReader::new(ReadMode::KernelOnly, Some(Box::new(device)));
+            assert_eq!(reader.lookup(b"blob"), KernelLookup::Hit(value));
+            assert_eq!(*offered.lock().unwrap(), expected);
+            assert_eq!(reader.stats().hits, 1);
+        }
+    }
+
+    #[test]
+    fn missing_device_is_unavailable() {
+        let reader = KernelReader::new(ReadMode::KernelOnly, None);
+        assert!(!reader.has_device());
+        assert_eq!(reader.lookup(b"hot"), KernelLookup::Unavailable);
+        assert_eq!(reader.stats(), KernelReadStats::default());
+
+        let reader = KernelReader::open(ReadMode::default());
+        assert_eq!(reader.mode(), ReadMode::ServerOnly);
+        assert!(!reader.has_device());
+    }
+
+    #[test]
+    fn errno_answers_become_statuses() {
+        let status = |err: io::Error| errno_status(&err);
+        assert_eq!(
+            status(io::Error::from(io::ErrorKind::NotFound)),
+            Some(HkvError::NotFound.code())
+        );
+        assert_eq!(
+            status(io::Error::from(HkvError::VersionMismatch)),
+            Some(HkvError::VersionMismatch.code())
+        );
+        assert_eq!(status(io::Error::from(HkvError::Busy)), None);
+        assert_eq!(status(io::Error::from(io::ErrorKind::Interrupted)), None);
+        if cfg!(target_os = "linux") {
+            assert_eq!(status(io::Error::from_raw_os_error(errno::ENOTTY)), None);
+            assert_eq!(status(io::Error::from_raw_os_error(errno::EINVAL)), None);
+        }
+        assert_eq!(check_status(999), Err(KernelError::UnknownStatus(999)));
+    }
+
+    /// A module that rejects both read commands with `ENOTTY`, or answers
+    /// them with an `UnsupportedCommand` status.
+    struct UnknownCommandDevice {
+        errno: bool,
+    }
+
+    impl KernelDevice for UnknownCommandDevice {
+        fn read(&self, _request: &ReadRequest) -> io::Result<ReadResponse> {
+            unreachable!("CMD_READ_EXT is tried first, then version 1");
+        }
+
+        fn read_v1(&self, _request: &ReadRequestV1) -> io::Result<ReadResponse> {
+            if self.errno {
+                return Err(io::Error::from_raw_os_error(errno::ENOTTY));
+            }
+            let status = HkvError::UnsupportedCommand.code();
+            Ok(ReadResponse::new(status, Value::new(&[]).unwrap()))
+        }
+
+        fn read_ext(
+            &self,
+            _request: &ReadExtRequest,
+            _buf: &mut [u8],
+        ) -> io::Result<ReadExtResponse> {
+            if self.errno {
+                return Err(io::Error::from_raw_os_error(errno::ENOTTY));
+            }
+            Ok(ReadExtResponse::new(HkvError::UnsupportedCommand.code(), 0))
+        }
+    }
+
+    #[test]
+    fn only_an_enotty_ioctl_means_an_unknown_command() {
+        let lookup = |errno| {
+            let device = UnknownCommandDevice { errno };
+            KernelReader::new(ReadMode::KernelOnly, Some(Box::new(device))).lookup(b"k")
+        };
+        assert_eq!(
+            lookup(true),
+            KernelLookup::Failed(KernelError::UnknownCommand)
+        );
+        assert_eq!(
+            lookup(false),
+            KernelLookup::Failed(KernelError::Status(HkvError::UnsupportedCommand))
+        );
+    }
+}
@@ -1,10 +1,15 @@
 //! # HybridKV Sync Client
 //!
 //! Provide a lightweight, synchronous Redis-compatible client with
-//! connection pooling to minimize TCP handshake overhead.
+//! connection pooling to minimize TCP handshake overhead, and a kernel
+//! cache fast path for reads.
 
 mod client;
+mod kernel;
 mod pool;
 mod resp;
 
 pub use client::{ClientConfig, ClientError, ClientResult, ClientTtl, KVClient};
+pub use kernel::{
+    IoctlDevice, KernelDevice, KernelError, KernelLookup, KernelReadStats, KernelReader, ReadMode,
+};
@@ -145,7 +145,7 @@ fn parse_i64(data: &[u8]) -> ClientResult<i64> {
     let mut value: i64 = 0;
     while idx < data.len() {
         let b = data[idx];
-        if b < b'0' || b > b'9' {
+        if !b.is_ascii_digit() {
             return Err(ClientError::Protocol);
         }
         value = value.saturating_mul(10).saturating_add((b - b'0') as i64);
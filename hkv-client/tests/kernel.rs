@@ -0,0 +1,227 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use hkv_client::{
+    ClientConfig, ClientError, KVClient, KernelDevice, KernelReadStats, KernelReader, ReadMode,
+};
+use hkv_common::{
+    CMD_READ, CMD_READ_EXT, InvalidateRequest, Key, PromoteExtRequest, PromoteRequest,
+    ReadExtRequest, ReadExtResponse, ReadRequest, ReadRequestV1, ReadResponse, Ttl, Value, Version,
+};
+use hkv_kernel::{IoctlArg, KernelCache};
+
+/// In-process kernel: the user-space simulation behind the ioctl dispatcher.
+struct FakeDevice(Arc<KernelCache>);
+
+impl KernelDevice for FakeDevice {
+    fn read(&self, request: &ReadRequest) -> std::io::Result<ReadResponse> {
+        let mut response = ReadResponse::new(u16::MAX, Value::new(&[]).unwrap());
+        // Errors are also reported through `response.status`.
+        let _ = self
+            .0
+            .ioctl(CMD_READ, IoctlArg::Read(request, &mut response));
+        Ok(response)
+    }
+
+    fn read_ext(
+        &self,
+        request: &ReadExtRequest,
+        buf: &mut [u8],
+    ) -> std::io::Result<ReadExtResponse> {
+        let mut response = ReadExtResponse::new(u16::MAX, 0);
+        let _ = self
+            .0
+            .ioctl(CMD_READ_EXT, IoctlArg::ReadExt(request, &mut response, buf));
+        Ok(response)
+    }
+
+    fn read_v1(&self, request: &ReadRequestV1) -> std::io::Result<ReadResponse> {
+        let mut response = ReadResponse::new(u16::MAX, Value::new(&[]).unwrap());
+        let _ = self
+            .0
+            .ioctl(CMD_READ, IoctlArg::ReadV1(request, &mut response));
+        Ok(response)
+    }
+}
+
+/// Answers every `GET` with `server:<key>` until the client disconnects.
+fn spawn_get_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr").to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+        while let Some(args) = read_command(&mut reader) {
+            assert_eq!(args[0], b"GET");
+            let mut value = b"server:".to_vec();
+            value.extend_from_slice(&args[1]);
+            let _ = write!(stream, "${}\r\n", value.len());
+            let _ = stream.write_all(&value);
+            let _ = stream.write_all(b"\r\n");
+        }
+    });
+    addr
+}
+
+fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+        let mut data = vec![0u8; len + 2];
+        reader.read_exact(&mut data).ok()?;
+        data.truncate(len);
+        args.push(data);
+    }
+    Some(args)
+}
+
+fn connect(addr: String, mode: ReadMode, device: Option<Box<dyn KernelDevice>>) -> KVClient {
+    let config = ClientConfig {
+        addr,
+        max_idle: 1,
+        max_total: 1,
+        read_timeout: Some(Duration::from_secs(1)),
+        write_timeout: Some(Duration::from_secs(1)),
+        connect_timeout: Some(Duration::from_secs(1)),
+    };
+    KVClient::with_config(config)
+        .expect("client")
+        .with_kernel_reader(KernelReader::new(mode, device))
+}
+
+fn kernel_with(key: &[u8], value: &[u8]) -> Arc<KernelCache> {
+    let cache = Arc::new(KernelCache::new());
+    cache
+        .promote(&PromoteRequest::new(
+            Key::new(key).unwrap(),
+            Value::new(value).unwrap(),
+            Version::new(1),
+            Ttl::INFINITE,
+        ))
+        .unwrap();
+    cache
+}
+
+#[test]
+fn kernel_then_server_serves_hits_and_falls_back_on_miss_and_stale() {
+    let cache = kernel_with(b"hot", b"kernel");
+    let client = connect(
+        spawn_get_server(),
+        ReadMode::KernelThenServer,
+        Some(Box::new(FakeDevice(cache.clone()))),
+    );
+
+    assert_eq!(client.get(b"hot").unwrap().as_deref(), Some(&b"kernel"[..]));
+    assert_eq!(
+        client.get(b"cold").unwrap().as_deref(),
+        Some(&b"server:cold"[..])
+    );
+
+    cache
+        .invalidate(&InvalidateRequest::new(
+            Key::new(b"hot").unwrap(),
+            Version::new(2),
+        ))
+        .unwrap();
+    assert_eq!(
+        client.get(b"hot").unwrap().as_deref(),
+        Some(&b"server:hot"[..])
+    );
+
+    assert_eq!(
+        client.kernel_stats(),
+        KernelReadStats {
+            hits: 1,
+            misses: 2,
+            fallbacks: 2,
+            errors: 0,
+        }
+    );
+}
+
+#[test]
+fn session_blobs_use_the_fast_path() {
+    let cache = Arc::new(KernelCache::new());
+    let blobs: Vec<Vec<u8>> = [2 * 1024, 5 * 1024, 8 * 1024, 11 * 1024]
+        .iter()
+        .map(|&size| (0..size).map(|i| (i % 253) as u8).collect())
+        .collect();
+    for (index, blob) in blobs.iter().enumerate() {
+        let key = Key::new(format!("session:{index}").as_bytes()).unwrap();
+        let request = PromoteExtRequest::new(key, blob, Version::new(1), Ttl::INFINITE).unwrap();
+        cache.promote_ext(&request, blob).unwrap();
+    }
+
+    let client = connect(
+        "127.0.0.1:1".to_string(),
+        ReadMode::KernelOnly,
+        Some(Box::new(FakeDevice(cache))),
+    );
+    for (index, blob) in blobs.iter().enumerate() {
+        let value = client.get(format!("session:{index}").as_bytes()).unwrap();
+        assert_eq!(value.as_ref(), Some(blob));
+    }
+    assert_eq!(client.kernel_stats().hits, blobs.len() as u64);
+}
+
+#[test]
+fn kernel_only_never_contacts_the_server() {
+    // Nothing listens here; any server round-trip would fail.
+    let addr = "127.0.0.1:1".to_string();
+    let cache = kernel_with(b"hot", b"kernel");
+    let client = connect(
+        addr.clone(),
+        ReadMode::KernelOnly,
+        Some(Box::new(FakeDevice(cache))),
+    );
+
+    assert_eq!(client.get(b"hot").unwrap().as_deref(), Some(&b"kernel"[..]));
+    assert_eq!(client.get(b"cold").unwrap(), None);
+    assert_eq!(client.kernel_stats().fallbacks, 0);
+
+    let client = connect(addr, ReadMode::KernelOnly, None);
+    assert!(matches!(
+        client.get(b"hot"),
+        Err(ClientError::KernelUnavailable)
+    ));
+}
+
+#[test]
+fn missing_device_falls_back_to_server() {
+    let client = connect(spawn_get_server(), ReadMode::KernelThenServer, None);
+
+    assert_eq!(client.get(b"k").unwrap().as_deref(), Some(&b"server:k"[..]));
+    assert_eq!(
+        client.kernel_stats(),
+        KernelReadStats {
+            fallbacks: 1,
+            ..KernelReadStats::default()
+        }
+    );
+}
+
+#[test]
+fn server_only_skips_the_kernel() {
+    let cache = kernel_with(b"hot", b"kernel");
+    let client = connect(
+        spawn_get_server(),
+        ReadMode::ServerOnly,
+        Some(Box::new(FakeDevice(cache))),
+    );
+
+    assert_eq!(
+        client.get(b"hot").unwrap().as_deref(),
+        Some(&b"server:hot"[..])
+    );
+    assert_eq!(client.kernel_stats(), KernelReadStats::default());
+}
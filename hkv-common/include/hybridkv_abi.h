@@ -0,0 +1,438 @@
+/* hybridkv_abi.h - HybridKV user/kernel ABI.
+ *
+ * Generated by hkv_common::layout::c_header(); do not edit.
+ */
+#ifndef HYBRIDKV_ABI_H
+#define HYBRIDKV_ABI_H
+
+#ifdef __KERNEL__
+#include <linux/stddef.h>
+#include <linux/types.h>
+#else
+#include <stddef.h>
+#include <stdint.h>
+#endif
+
+#define HKV_DEVICE_PATH "/dev/hybridkv"
+#define HKV_IOCTL_MAGIC 0x48
+#define HKV_PROTOCOL_VERSION 2
+#define HKV_MIN_PROTOCOL_VERSION 1
+#define HKV_STATUS_OK 0
+#define HKV_MAX_KEY_SIZE 256
+#define HKV_MAX_VALUE_SIZE 1024
+#define HKV_MAX_EXT_VALUE_SIZE 32768
+#define HKV_MAX_BATCH_SIZE 1000
+#define HKV_CONSISTENCY_INHERIT 255
+
+typedef uint64_t hkv_version_t;
+typedef uint64_t hkv_ttl_t;
+typedef uint8_t hkv_entry_flags_t;
+
+/* Key */
+struct hkv_key {
+	uint16_t len;
+	uint8_t data[256];
+};
+_Static_assert(sizeof(struct hkv_key) == 258, "hkv_key size");
+_Static_assert(_Alignof(struct hkv_key) == 2, "hkv_key alignment");
+_Static_assert(offsetof(struct hkv_key, len) == 0, "hkv_key.len offset");
+_Static_assert(offsetof(struct hkv_key, data) == 2, "hkv_key.data offset");
+
+/* Value */
+struct hkv_value {
+	uint16_t len;
+	uint8_t data[1024];
+};
+_Static_assert(sizeof(struct hkv_value) == 1026, "hkv_value size");
+_Static_assert(_Alignof(struct hkv_value) == 2, "hkv_value alignment");
+_Static_assert(offsetof(struct hkv_value, len) == 0, "hkv_value.len offset");
+_Static_assert(offsetof(struct hkv_value, data) == 2, "hkv_value.data offset");
+
+/* IoctlHeader */
+struct hkv_ioctl_header {
+	uint8_t magic;
+	uint8_t version;
+	uint8_t command;
+	uint8_t reserved;
+};
+_Static_assert(sizeof(struct hkv_ioctl_header) == 4, "hkv_ioctl_header size");
+_Static_assert(_Alignof(struct hkv_ioctl_header) == 1, "hkv_ioctl_header alignment");
+_Static_assert(offsetof(struct hkv_ioctl_header, magic) == 0, "hkv_ioctl_header.magic offset");
+_Static_assert(offsetof(struct hkv_ioctl_header, version) == 1, "hkv_ioctl_header.version offset");
+_Static_assert(offsetof(struct hkv_ioctl_header, command) == 2, "hkv_ioctl_header.command offset");
+_Static_assert(offsetof(struct hkv_ioctl_header, reserved) == 3, "hkv_ioctl_header.reserved offset");
+
+/* ReadRequest */
+struct hkv_read_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+};
+_Static_assert(sizeof(struct hkv_read_request) == 264, "hkv_read_request size");
+_Static_assert(_Alignof(struct hkv_read_request) == 2, "hkv_read_request alignment");
+_Static_assert(offsetof(struct hkv_read_request, header) == 0, "hkv_read_request.header offset");
+_Static_assert(offsetof(struct hkv_read_request, tenant) == 4, "hkv_read_request.tenant offset");
+_Static_assert(offsetof(struct hkv_read_request, key) == 6, "hkv_read_request.key offset");
+
+/* ReadRequestV1 */
+struct hkv_read_request_v1 {
+	struct hkv_ioctl_header header;
+	struct hkv_key key;
+};
+_Static_assert(sizeof(struct hkv_read_request_v1) == 262, "hkv_read_request_v1 size");
+_Static_assert(_Alignof(struct hkv_read_request_v1) == 2, "hkv_read_request_v1 alignment");
+_Static_assert(offsetof(struct hkv_read_request_v1, header) == 0, "hkv_read_request_v1.header offset");
+_Static_assert(offsetof(struct hkv_read_request_v1, key) == 4, "hkv_read_request_v1.key offset");
+
+/* ReadResponse */
+struct hkv_read_response {
+	struct hkv_ioctl_header header;
+	uint16_t status;
+	struct hkv_value value;
+};
+_Static_assert(sizeof(struct hkv_read_response) == 1032, "hkv_read_response size");
+_Static_assert(_Alignof(struct hkv_read_response) == 2, "hkv_read_response alignment");
+_Static_assert(offsetof(struct hkv_read_response, header) == 0, "hkv_read_response.header offset");
+_Static_assert(offsetof(struct hkv_read_response, status) == 4, "hkv_read_response.status offset");
+_Static_assert(offsetof(struct hkv_read_response, value) == 6, "hkv_read_response.value offset");
+
+/* PromoteRequest */
+struct hkv_promote_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+	struct hkv_value value;
+	uint8_t _pad0[6];
+	hkv_version_t version;
+	hkv_ttl_t ttl;
+};
+_Static_assert(sizeof(struct hkv_promote_request) == 1312, "hkv_promote_request size");
+_Static_assert(_Alignof(struct hkv_promote_request) == 8, "hkv_promote_request alignment");
+_Static_assert(offsetof(struct hkv_promote_request, header) == 0, "hkv_promote_request.header offset");
+_Static_assert(offsetof(struct hkv_promote_request, tenant) == 4, "hkv_promote_request.tenant offset");
+_Static_assert(offsetof(struct hkv_promote_request, key) == 6, "hkv_promote_request.key offset");
+_Static_assert(offsetof(struct hkv_promote_request, value) == 264, "hkv_promote_request.value offset");
+_Static_assert(offsetof(struct hkv_promote_request, version) == 1296, "hkv_promote_request.version offset");
+_Static_assert(offsetof(struct hkv_promote_request, ttl) == 1304, "hkv_promote_request.ttl offset");
+
+/* PromoteResponse */
+struct hkv_promote_response {
+	struct hkv_ioctl_header header;
+	uint16_t status;
+	uint16_t reserved;
+};
+_Static_assert(sizeof(struct hkv_promote_response) == 8, "hkv_promote_response size");
+_Static_assert(_Alignof(struct hkv_promote_response) == 2, "hkv_promote_response alignment");
+_Static_assert(offsetof(struct hkv_promote_response, header) == 0, "hkv_promote_response.header offset");
+_Static_assert(offsetof(struct hkv_promote_response, status) == 4, "hkv_promote_response.status offset");
+_Static_assert(offsetof(struct hkv_promote_response, reserved) == 6, "hkv_promote_response.reserved offset");
+
+/* BatchPromoteDescriptor */
+struct hkv_batch_promote_descriptor {
+	uint64_t key_ptr;
+	uint64_t value_ptr;
+	uint16_t key_len;
+	uint16_t reserved;
+	uint32_t value_len;
+	hkv_version_t version;
+	hkv_ttl_t ttl;
+};
+_Static_assert(sizeof(struct hkv_batch_promote_descriptor) == 40, "hkv_batch_promote_descriptor size");
+_Static_assert(_Alignof(struct hkv_batch_promote_descriptor) == 8, "hkv_batch_promote_descriptor alignment");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, key_ptr) == 0, "hkv_batch_promote_descriptor.key_ptr offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, value_ptr) == 8, "hkv_batch_promote_descriptor.value_ptr offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, key_len) == 16, "hkv_batch_promote_descriptor.key_len offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, reserved) == 18, "hkv_batch_promote_descriptor.reserved offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, value_len) == 20, "hkv_batch_promote_descriptor.value_len offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, version) == 24, "hkv_batch_promote_descriptor.version offset");
+_Static_assert(offsetof(struct hkv_batch_promote_descriptor, ttl) == 32, "hkv_batch_promote_descriptor.ttl offset");
+
+/* BatchPromoteRequest */
+struct hkv_batch_promote_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	uint16_t count;
+	uint64_t entries_ptr;
+	uint64_t statuses_ptr;
+};
+_Static_assert(sizeof(struct hkv_batch_promote_request) == 24, "hkv_batch_promote_request size");
+_Static_assert(_Alignof(struct hkv_batch_promote_request) == 8, "hkv_batch_promote_request alignment");
+_Static_assert(offsetof(struct hkv_batch_promote_request, header) == 0, "hkv_batch_promote_request.header offset");
+_Static_assert(offsetof(struct hkv_batch_promote_request, tenant) == 4, "hkv_batch_promote_request.tenant offset");
+_Static_assert(offsetof(struct hkv_batch_promote_request, count) == 6, "hkv_batch_promote_request.count offset");
+_Static_assert(offsetof(struct hkv_batch_promote_request, entries_ptr) == 8, "hkv_batch_promote_request.entries_ptr offset");
+_Static_assert(offsetof(struct hkv_batch_promote_request, statuses_ptr) == 16, "hkv_batch_promote_request.statuses_ptr offset");
+
+/* DemoteRequest */
+struct hkv_demote_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+};
+_Static_assert(sizeof(struct hkv_demote_request) == 264, "hkv_demote_request size");
+_Static_assert(_Alignof(struct hkv_demote_request) == 2, "hkv_demote_request alignment");
+_Static_assert(offsetof(struct hkv_demote_request, header) == 0, "hkv_demote_request.header offset");
+_Static_assert(offsetof(struct hkv_demote_request, tenant) == 4, "hkv_demote_request.tenant offset");
+_Static_assert(offsetof(struct hkv_demote_request, key) == 6, "hkv_demote_request.key offset");
+
+/* InvalidateRequest */
+struct hkv_invalidate_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+	hkv_version_t version;
+};
+_Static_assert(sizeof(struct hkv_invalidate_request) == 272, "hkv_invalidate_request size");
+_Static_assert(_Alignof(struct hkv_invalidate_request) == 8, "hkv_invalidate_request alignment");
+_Static_assert(offsetof(struct hkv_invalidate_request, header) == 0, "hkv_invalidate_request.header offset");
+_Static_assert(offsetof(struct hkv_invalidate_request, tenant) == 4, "hkv_invalidate_request.tenant offset");
+_Static_assert(offsetof(struct hkv_invalidate_request, key) == 6, "hkv_invalidate_request.key offset");
+_Static_assert(offsetof(struct hkv_invalidate_request, version) == 264, "hkv_invalidate_request.version offset");
+
+/* CacheStats */
+struct hkv_cache_stats {
+	uint64_t lookups;
+	uint64_t hits;
+	uint64_t misses;
+	uint64_t stale_hits;
+	uint64_t promotions;
+	uint64_t demotions;
+	uint64_t evictions;
+	uint64_t invalidations;
+	uint64_t used_bytes;
+	uint64_t max_bytes;
+	uint64_t entry_count;
+	uint64_t lock_contentions;
+	uint64_t rcu_grace_periods;
+	uint32_t tenant;
+	uint32_t tenant_count;
+	uint64_t budget_bytes;
+};
+_Static_assert(sizeof(struct hkv_cache_stats) == 120, "hkv_cache_stats size");
+_Static_assert(_Alignof(struct hkv_cache_stats) == 8, "hkv_cache_stats alignment");
+_Static_assert(offsetof(struct hkv_cache_stats, lookups) == 0, "hkv_cache_stats.lookups offset");
+_Static_assert(offsetof(struct hkv_cache_stats, hits) == 8, "hkv_cache_stats.hits offset");
+_Static_assert(offsetof(struct hkv_cache_stats, misses) == 16, "hkv_cache_stats.misses offset");
+_Static_assert(offsetof(struct hkv_cache_stats, stale_hits) == 24, "hkv_cache_stats.stale_hits offset");
+_Static_assert(offsetof(struct hkv_cache_stats, promotions) == 32, "hkv_cache_stats.promotions offset");
+_Static_assert(offsetof(struct hkv_cache_stats, demotions) == 40, "hkv_cache_stats.demotions offset");
+_Static_assert(offsetof(struct hkv_cache_stats, evictions) == 48, "hkv_cache_stats.evictions offset");
+_Static_assert(offsetof(struct hkv_cache_stats, invalidations) == 56, "hkv_cache_stats.invalidations offset");
+_Static_assert(offsetof(struct hkv_cache_stats, used_bytes) == 64, "hkv_cache_stats.used_bytes offset");
+_Static_assert(offsetof(struct hkv_cache_stats, max_bytes) == 72, "hkv_cache_stats.max_bytes offset");
+_Static_assert(offsetof(struct hkv_cache_stats, entry_count) == 80, "hkv_cache_stats.entry_count offset");
+_Static_assert(offsetof(struct hkv_cache_stats, lock_contentions) == 88, "hkv_cache_stats.lock_contentions offset");
+_Static_assert(offsetof(struct hkv_cache_stats, rcu_grace_periods) == 96, "hkv_cache_stats.rcu_grace_periods offset");
+_Static_assert(offsetof(struct hkv_cache_stats, tenant) == 104, "hkv_cache_stats.tenant offset");
+_Static_assert(offsetof(struct hkv_cache_stats, tenant_count) == 108, "hkv_cache_stats.tenant_count offset");
+_Static_assert(offsetof(struct hkv_cache_stats, budget_bytes) == 112, "hkv_cache_stats.budget_bytes offset");
+
+/* StatsRequest */
+struct hkv_stats_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	uint16_t reserved;
+};
+_Static_assert(sizeof(struct hkv_stats_request) == 8, "hkv_stats_request size");
+_Static_assert(_Alignof(struct hkv_stats_request) == 2, "hkv_stats_request alignment");
+_Static_assert(offsetof(struct hkv_stats_request, header) == 0, "hkv_stats_request.header offset");
+_Static_assert(offsetof(struct hkv_stats_request, tenant) == 4, "hkv_stats_request.tenant offset");
+_Static_assert(offsetof(struct hkv_stats_request, reserved) == 6, "hkv_stats_request.reserved offset");
+
+/* StatsResponse */
+struct hkv_stats_response {
+	struct hkv_ioctl_header header;
+	uint16_t status;
+	uint16_t reserved;
+	struct hkv_cache_stats stats;
+};
+_Static_assert(sizeof(struct hkv_stats_response) == 128, "hkv_stats_response size");
+_Static_assert(_Alignof(struct hkv_stats_response) == 8, "hkv_stats_response alignment");
+_Static_assert(offsetof(struct hkv_stats_response, header) == 0, "hkv_stats_response.header offset");
+_Static_assert(offsetof(struct hkv_stats_response, status) == 4, "hkv_stats_response.status offset");
+_Static_assert(offsetof(struct hkv_stats_response, reserved) == 6, "hkv_stats_response.reserved offset");
+_Static_assert(offsetof(struct hkv_stats_response, stats) == 8, "hkv_stats_response.stats offset");
+
+/* ConfigRequest */
+struct hkv_config_request {
+	struct hkv_ioctl_header header;
+	uint32_t max_stale_ms;
+	uint64_t max_bytes;
+	uint64_t max_entries;
+	uint32_t high_watermark;
+	uint32_t low_watermark;
+	uint8_t eviction_policy;
+	uint8_t admission_policy;
+	uint8_t hotness_estimator;
+	uint8_t tenant_policy;
+	uint8_t consistency_mode;
+	uint8_t reserved;
+	uint16_t consistency_tenant;
+};
+_Static_assert(sizeof(struct hkv_config_request) == 40, "hkv_config_request size");
+_Static_assert(_Alignof(struct hkv_config_request) == 8, "hkv_config_request alignment");
+_Static_assert(offsetof(struct hkv_config_request, header) == 0, "hkv_config_request.header offset");
+_Static_assert(offsetof(struct hkv_config_request, max_stale_ms) == 4, "hkv_config_request.max_stale_ms offset");
+_Static_assert(offsetof(struct hkv_config_request, max_bytes) == 8, "hkv_config_request.max_bytes offset");
+_Static_assert(offsetof(struct hkv_config_request, max_entries) == 16, "hkv_config_request.max_entries offset");
+_Static_assert(offsetof(struct hkv_config_request, high_watermark) == 24, "hkv_config_request.high_watermark offset");
+_Static_assert(offsetof(struct hkv_config_request, low_watermark) == 28, "hkv_config_request.low_watermark offset");
+_Static_assert(offsetof(struct hkv_config_request, eviction_policy) == 32, "hkv_config_request.eviction_policy offset");
+_Static_assert(offsetof(struct hkv_config_request, admission_policy) == 33, "hkv_config_request.admission_policy offset");
+_Static_assert(offsetof(struct hkv_config_request, hotness_estimator) == 34, "hkv_config_request.hotness_estimator offset");
+_Static_assert(offsetof(struct hkv_config_request, tenant_policy) == 35, "hkv_config_request.tenant_policy offset");
+_Static_assert(offsetof(struct hkv_config_request, consistency_mode) == 36, "hkv_config_request.consistency_mode offset");
+_Static_assert(offsetof(struct hkv_config_request, reserved) == 37, "hkv_config_request.reserved offset");
+_Static_assert(offsetof(struct hkv_config_request, consistency_tenant) == 38, "hkv_config_request.consistency_tenant offset");
+
+/* FlushRequest */
+struct hkv_flush_request {
+	struct hkv_ioctl_header header;
+};
+_Static_assert(sizeof(struct hkv_flush_request) == 4, "hkv_flush_request size");
+_Static_assert(_Alignof(struct hkv_flush_request) == 1, "hkv_flush_request alignment");
+_Static_assert(offsetof(struct hkv_flush_request, header) == 0, "hkv_flush_request.header offset");
+
+/* TenantConfigRequest */
+struct hkv_tenant_config_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	uint8_t priority;
+	uint8_t reserved;
+	uint32_t weight;
+	uint8_t _pad0[4];
+	uint64_t quota_bytes;
+	uint64_t min_guarantee_bytes;
+};
+_Static_assert(sizeof(struct hkv_tenant_config_request) == 32, "hkv_tenant_config_request size");
+_Static_assert(_Alignof(struct hkv_tenant_config_request) == 8, "hkv_tenant_config_request alignment");
+_Static_assert(offsetof(struct hkv_tenant_config_request, header) == 0, "hkv_tenant_config_request.header offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, tenant) == 4, "hkv_tenant_config_request.tenant offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, priority) == 6, "hkv_tenant_config_request.priority offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, reserved) == 7, "hkv_tenant_config_request.reserved offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, weight) == 8, "hkv_tenant_config_request.weight offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, quota_bytes) == 16, "hkv_tenant_config_request.quota_bytes offset");
+_Static_assert(offsetof(struct hkv_tenant_config_request, min_guarantee_bytes) == 24, "hkv_tenant_config_request.min_guarantee_bytes offset");
+
+/* ReadExtRequest */
+struct hkv_read_ext_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+	uint64_t buf_ptr;
+	uint32_t buf_len;
+	uint32_t reserved;
+};
+_Static_assert(sizeof(struct hkv_read_ext_request) == 280, "hkv_read_ext_request size");
+_Static_assert(_Alignof(struct hkv_read_ext_request) == 8, "hkv_read_ext_request alignment");
+_Static_assert(offsetof(struct hkv_read_ext_request, header) == 0, "hkv_read_ext_request.header offset");
+_Static_assert(offsetof(struct hkv_read_ext_request, tenant) == 4, "hkv_read_ext_request.tenant offset");
+_Static_assert(offsetof(struct hkv_read_ext_request, key) == 6, "hkv_read_ext_request.key offset");
+_Static_assert(offsetof(struct hkv_read_ext_request, buf_ptr) == 264, "hkv_read_ext_request.buf_ptr offset");
+_Static_assert(offsetof(struct hkv_read_ext_request, buf_len) == 272, "hkv_read_ext_request.buf_len offset");
+_Static_assert(offsetof(struct hkv_read_ext_request, reserved) == 276, "hkv_read_ext_request.reserved offset");
+
+/* ReadExtResponse */
+struct hkv_read_ext_response {
+	struct hkv_ioctl_header header;
+	uint16_t status;
+	uint16_t reserved;
+	uint32_t value_len;
+};
+_Static_assert(sizeof(struct hkv_read_ext_response) == 12, "hkv_read_ext_response size");
+_Static_assert(_Alignof(struct hkv_read_ext_response) == 4, "hkv_read_ext_response alignment");
+_Static_assert(offsetof(struct hkv_read_ext_response, header) == 0, "hkv_read_ext_response.header offset");
+_Static_assert(offsetof(struct hkv_read_ext_response, status) == 4, "hkv_read_ext_response.status offset");
+_Static_assert(offsetof(struct hkv_read_ext_response, reserved) == 6, "hkv_read_ext_response.reserved offset");
+_Static_assert(offsetof(struct hkv_read_ext_response, value_len) == 8, "hkv_read_ext_response.value_len offset");
+
+/* PromoteExtRequest */
+struct hkv_promote_ext_request {
+	struct hkv_ioctl_header header;
+	uint16_t tenant;
+	struct hkv_key key;
+	uint64_t value_ptr;
+	uint32_t value_len;
+	uint32_t reserved;
+	hkv_version_t version;
+	hkv_ttl_t ttl;
+};
+_Static_assert(sizeof(struct hkv_promote_ext_request) == 296, "hkv_promote_ext_request size");
+_Static_assert(_Alignof(struct hkv_promote_ext_request) == 8, "hkv_promote_ext_request alignment");
+_Static_assert(offsetof(struct hkv_promote_ext_request, header) == 0, "hkv_promote_ext_request.header offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, tenant) == 4, "hkv_promote_ext_request.tenant offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, key) == 6, "hkv_promote_ext_request.key offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, value_ptr) == 264, "hkv_promote_ext_request.value_ptr offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, value_len) == 272, "hkv_promote_ext_request.value_len offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, reserved) == 276, "hkv_promote_ext_request.reserved offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, version) == 280, "hkv_promote_ext_request.version offset");
+_Static_assert(offsetof(struct hkv_promote_ext_request, ttl) == 288, "hkv_promote_ext_request.ttl offset");
+
+/* EntryMetadata */
+struct hkv_entry_metadata {
+	hkv_version_t version;
+	hkv_ttl_t ttl;
+	uint64_t created_at;
+	uint64_t accessed_at;
+	hkv_entry_flags_t flags;
+	uint8_t _pad0[1];
+	uint16_t key_len;
+	uint16_t value_len;
+	uint8_t _pad1[2];
+};
+_Static_assert(sizeof(struct hkv_entry_metadata) == 40, "hkv_entry_metadata size");
+_Static_assert(_Alignof(struct hkv_entry_metadata) == 8, "hkv_entry_metadata alignment");
+_Static_assert(offsetof(struct hkv_entry_metadata, version) == 0, "hkv_entry_metadata.version offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, ttl) == 8, "hkv_entry_metadata.ttl offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, created_at) == 16, "hkv_entry_metadata.created_at offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, accessed_at) == 24, "hkv_entry_metadata.accessed_at offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, flags) == 32, "hkv_entry_metadata.flags offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, key_len) == 34, "hkv_entry_metadata.key_len offset");
+_Static_assert(offsetof(struct hkv_entry_metadata, value_len) == 36, "hkv_entry_metadata.value_len offset");
+
+/* Entry */
+struct hkv_entry {
+	struct hkv_key key;
+	struct hkv_value value;
+	uint8_t _pad0[4];
+	struct hkv_entry_metadata metadata;
+};
+_Static_assert(sizeof(struct hkv_entry) == 1328, "hkv_entry size");
+_Static_assert(_Alignof(struct hkv_entry) == 8, "hkv_entry alignment");
+_Static_assert(offsetof(struct hkv_entry, key) == 0, "hkv_entry.key offset");
+_Static_assert(offsetof(struct hkv_entry, value) == 258, "hkv_entry.value offset");
+_Static_assert(offsetof(struct hkv_entry, metadata) == 1288, "hkv_entry.metadata offset");
+
+/* CacheEvent */
+struct hkv_cache_event {
+	uint8_t magic;
+	uint8_t protocol;
+	uint8_t kind;
+	uint8_t reserved;
+	uint16_t tenant;
+	struct hkv_key key;
+	hkv_version_t version;
+};
+_Static_assert(sizeof(struct hkv_cache_event) == 272, "hkv_cache_event size");
+_Static_assert(_Alignof(struct hkv_cache_event) == 8, "hkv_cache_event alignment");
+_Static_assert(offsetof(struct hkv_cache_event, magic) == 0, "hkv_cache_event.magic offset");
+_Static_assert(offsetof(struct hkv_cache_event, protocol) == 1, "hkv_cache_event.protocol offset");
+_Static_assert(offsetof(struct hkv_cache_event, kind) == 2, "hkv_cache_event.kind offset");
+_Static_assert(offsetof(struct hkv_cache_event, reserved) == 3, "hkv_cache_event.reserved offset");
+_Static_assert(offsetof(struct hkv_cache_event, tenant) == 4, "hkv_cache_event.tenant offset");
+_Static_assert(offsetof(struct hkv_cache_event, key) == 6, "hkv_cache_event.key offset");
+_Static_assert(offsetof(struct hkv_cache_event, version) == 264, "hkv_cache_event.version offset");
+
+#define HKV_IOC_READ 0xC4084800u
+#define HKV_IOC_PROMOTE 0xC5204801u
+#define HKV_IOC_BATCH_PROMOTE 0x40184802u
+#define HKV_IOC_DEMOTE 0x41084803u
+#define HKV_IOC_INVALIDATE 0x41104804u
+#define HKV_IOC_STATS 0xC0804805u
+#define HKV_IOC_CONFIG 0x40284806u
+#define HKV_IOC_FLUSH 0x40044807u
+#define HKV_IOC_TENANT_CONFIG 0x40204808u
+#define HKV_IOC_READ_EXT 0xC1184809u
+#define HKV_IOC_PROMOTE_EXT 0x4128480Au
+
+#endif /* HYBRIDKV_ABI_H */
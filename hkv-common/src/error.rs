@@ -6,8 +6,55 @@
 //! 2. **Categorized Ranges**: Codes are grouped by intent (client, server, transient, protocol).
 //! 3. **Low Overhead**: Enums are `Copy` and `repr(u16)` to keep payloads small.
 //! 4. **Recoverability Hints**: Transient errors are explicitly marked as retryable.
+//! 5. **One Errno per Error**: Every variant maps to a distinct Linux errno, so
+//!    an ioctl's `-errno` return decodes back to the same variant; a few
+//!    extra errnos (`EFAULT`, `EAGAIN`, `EOPNOTSUPP`) decode as aliases.
+//! 6. **`io::Error` Carries the Variant**: Converting to `std::io::Error`
+//!    wraps the typed error, so converting back is lossless; foreign I/O
+//!    errors decode from their errno, then from their `ErrorKind`.
 
 use core::fmt;
+use std::io;
+
+/// Linux errno values used at the ioctl boundary (asm-generic numbering).
+pub mod errno {
+    /// No such file or directory.
+    pub const ENOENT: i32 = 2;
+    /// Interrupted system call.
+    pub const EINTR: i32 = 4;
+    /// I/O error.
+    pub const EIO: i32 = 5;
+    /// Try again.
+    pub const EAGAIN: i32 = 11;
+    /// Out of memory.
+    pub const ENOMEM: i32 = 12;
+    /// Bad address.
+    pub const EFAULT: i32 = 14;
+    /// Device or resource busy.
+    pub const EBUSY: i32 = 16;
+    /// Invalid argument.
+    pub const EINVAL: i32 = 22;
+    /// Inappropriate ioctl for device (unknown command number).
+    pub const ENOTTY: i32 = 25;
+    /// No space left on device.
+    pub const ENOSPC: i32 = 28;
+    /// File name too long.
+    pub const ENAMETOOLONG: i32 = 36;
+    /// Protocol error.
+    pub const EPROTO: i32 = 71;
+    /// Message too long.
+    pub const EMSGSIZE: i32 = 90;
+    /// Protocol not supported.
+    pub const EPROTONOSUPPORT: i32 = 93;
+    /// Operation not supported.
+    pub const EOPNOTSUPP: i32 = 95;
+    /// Connection timed out.
+    pub const ETIMEDOUT: i32 = 110;
+    /// Stale file handle.
+    pub const ESTALE: i32 = 116;
+    /// Wrong medium type.
+    pub const EMEDIUMTYPE: i32 = 124;
+}
 
 /// Result type used across HybridKV components.
 pub type HkvResult<T> = core::result::Result<T, HkvError>;
@@ -44,6 +91,10 @@ pub enum HkvError {
     KeyTooLong = 3,
     /// Client error: value length exceeds MAX_VALUE_SIZE (code 4).
     ValueTooLong = 4,
+    /// Client error: operation against a key holding another type (code 5).
+    WrongType = 5,
+    /// Client error: write carries a version older than the cached one (code 6).
+    StaleVersion = 6,
 
     /// Server error: kernel memory limit reached (code 10).
     OutOfMemory = 10,
@@ -76,9 +127,12 @@ impl HkvError {
     /// Returns the coarse category of the error.
     pub const fn category(self) -> HkvErrorCategory {
         match self {
-            Self::InvalidInput | Self::NotFound | Self::KeyTooLong | Self::ValueTooLong => {
-                HkvErrorCategory::Client
-            }
+            Self::InvalidInput
+            | Self::NotFound
+            | Self::KeyTooLong
+            | Self::ValueTooLong
+            | Self::WrongType
+            | Self::StaleVersion => HkvErrorCategory::Client,
             Self::OutOfMemory | Self::CapacityExceeded | Self::InternalError => {
                 HkvErrorCategory::Server
             }
@@ -101,6 +155,8 @@ impl HkvError {
             2 => Some(Self::NotFound),
             3 => Some(Self::KeyTooLong),
             4 => Some(Self::ValueTooLong),
+            5 => Some(Self::WrongType),
+            6 => Some(Self::StaleVersion),
             10 => Some(Self::OutOfMemory),
             11 => Some(Self::CapacityExceeded),
             12 => Some(Self::InternalError),
@@ -113,6 +169,114 @@ impl HkvError {
             _ => None,
         }
     }
+
+    /// Returns the positive Linux errno the module returns (negated) for
+    /// the error.
+    pub const fn errno(self) -> i32 {
+        match self {
+            Self::InvalidInput => errno::EINVAL,
+            Self::NotFound => errno::ENOENT,
+            Self::KeyTooLong => errno::ENAMETOOLONG,
+            Self::ValueTooLong => errno::EMSGSIZE,
+            Self::WrongType => errno::EMEDIUMTYPE,
+            Self::StaleVersion => errno::ESTALE,
+            Self::OutOfMemory => errno::ENOMEM,
+            Self::CapacityExceeded => errno::ENOSPC,
+            Self::InternalError => errno::EIO,
+            Self::Busy => errno::EBUSY,
+            Self::Timeout => errno::ETIMEDOUT,
+            Self::Interrupted => errno::EINTR,
+            Self::VersionMismatch => errno::EPROTONOSUPPORT,
+            Self::ProtocolViolation => errno::EPROTO,
+            Self::UnsupportedCommand => errno::ENOTTY,
+        }
+    }
+
+    /// Converts a positive Linux errno into a typed error.
+    ///
+    /// Inverts `errno()` and also accepts `EFAULT` (bad user pointer, so
+    /// `InvalidInput`), `EAGAIN` (`Busy`), and `EOPNOTSUPP`
+    /// (`UnsupportedCommand`).
+    pub const fn from_errno(value: i32) -> Option<Self> {
+        match value {
+            errno::EINVAL | errno::EFAULT => Some(Self::InvalidInput),
+            errno::ENOENT => Some(Self::NotFound),
+            errno::ENAMETOOLONG => Some(Self::KeyTooLong),
+            errno::EMSGSIZE => Some(Self::ValueTooLong),
+            errno::EMEDIUMTYPE => Some(Self::WrongType),
+            errno::ESTALE => Some(Self::StaleVersion),
+            errno::ENOMEM => Some(Self::OutOfMemory),
+            errno::ENOSPC => Some(Self::CapacityExceeded),
+            errno::EIO => Some(Self::InternalError),
+            errno::EBUSY | errno::EAGAIN => Some(Self::Busy),
+            errno::ETIMEDOUT => Some(Self::Timeout),
+            errno::EINTR => Some(Self::Interrupted),
+            errno::EPROTONOSUPPORT => Some(Self::VersionMismatch),
+            errno::EPROTO => Some(Self::ProtocolViolation),
+            errno::ENOTTY | errno::EOPNOTSUPP => Some(Self::UnsupportedCommand),
+            _ => None,
+        }
+    }
+
+    /// Returns the `std::io::ErrorKind` closest to the error.
+    pub const fn io_kind(self) -> io::ErrorKind {
+        match self {
+            Self::InvalidInput | Self::KeyTooLong | Self::ValueTooLong | Self::WrongType => {
+                io::ErrorKind::InvalidInput
+            }
+            Self::NotFound => io::ErrorKind::NotFound,
+            Self::OutOfMemory => io::ErrorKind::OutOfMemory,
+            Self::CapacityExceeded => io::ErrorKind::StorageFull,
+            Self::Busy => io::ErrorKind::ResourceBusy,
+            Self::Timeout => io::ErrorKind::TimedOut,
+            Self::Interrupted => io::ErrorKind::Interrupted,
+            Self::VersionMismatch | Self::ProtocolViolation => io::ErrorKind::InvalidData,
+            Self::UnsupportedCommand => io::ErrorKind::Unsupported,
+            Self::StaleVersion | Self::InternalError => io::ErrorKind::Other,
+        }
+    }
+
+    /// Decodes an I/O error from any layer into a typed error.
+    ///
+    /// A wrapped `HkvError` is returned as is. Otherwise the OS errno is
+    /// decoded on Linux, then the `ErrorKind`; anything else is
+    /// `InternalError`.
+    pub fn from_io_error(err: &io::Error) -> Self {
+        if let Some(inner) = err.get_ref().and_then(|inner| inner.downcast_ref::<Self>()) {
+            return *inner;
+        }
+        if cfg!(target_os = "linux") {
+            if let Some(decoded) = err.raw_os_error().and_then(Self::from_errno) {
+                return decoded;
+            }
+        }
+        match err.kind() {
+            io::ErrorKind::InvalidInput => Self::InvalidInput,
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::OutOfMemory => Self::OutOfMemory,
+            io::ErrorKind::StorageFull => Self::CapacityExceeded,
+            io::ErrorKind::ResourceBusy | io::ErrorKind::WouldBlock => Self::Busy,
+            io::ErrorKind::TimedOut => Self::Timeout,
+            io::ErrorKind::Interrupted => Self::Interrupted,
+            io::ErrorKind::InvalidData => Self::ProtocolViolation,
+            io::ErrorKind::Unsupported => Self::UnsupportedCommand,
+            _ => Self::InternalError,
+        }
+    }
+}
+
+impl std::error::Error for HkvError {}
+
+impl From<HkvError> for io::Error {
+    fn from(err: HkvError) -> Self {
+        io::Error::new(err.io_kind(), err)
+    }
+}
+
+impl From<io::Error> for HkvError {
+    fn from(err: io::Error) -> Self {
+        HkvError::from_io_error(&err)
+    }
 }
 
 impl fmt::Display for HkvError {
@@ -122,6 +286,8 @@ impl fmt::Display for HkvError {
             Self::NotFound => "not found",
             Self::KeyTooLong => "key too long",
             Self::ValueTooLong => "value too long",
+            Self::WrongType => "wrong type",
+            Self::StaleVersion => "stale version",
             Self::OutOfMemory => "out of memory",
             Self::CapacityExceeded => "capacity exceeded",
             Self::InternalError => "internal error",
@@ -138,7 +304,26 @@ impl fmt::Display for HkvError {
 
 #[cfg(test)]
 mod tests {
-    use super::{HkvError, HkvErrorCategory};
+    use super::{errno, HkvError, HkvErrorCategory};
+    use std::io;
+
+    const ALL: [HkvError; 15] = [
+        HkvError::InvalidInput,
+        HkvError::NotFound,
+        HkvError::KeyTooLong,
+        HkvError::ValueTooLong,
+        HkvError::WrongType,
+        HkvError::StaleVersion,
+        HkvError::OutOfMemory,
+        HkvError::CapacityExceeded,
+        HkvError::InternalError,
+        HkvError::Busy,
+        HkvError::Timeout,
+        HkvError::Interrupted,
+        HkvError::VersionMismatch,
+        HkvError::ProtocolViolation,
+        HkvError::UnsupportedCommand,
+    ];
 
     #[test]
     fn maps_error_categories() {
@@ -160,6 +345,51 @@ mod tests {
     #[test]
     fn converts_from_code() {
         assert_eq!(HkvError::from_code(1), Some(HkvError::InvalidInput));
+        assert_eq!(HkvError::from_code(5), Some(HkvError::WrongType));
+        assert_eq!(HkvError::from_code(6), Some(HkvError::StaleVersion));
         assert_eq!(HkvError::from_code(99), None);
     }
+
+    #[test]
+    fn errno_round_trips() {
+        let mut seen = Vec::new();
+        for err in ALL {
+            assert!(!seen.contains(&err.errno()), "{err} shares an errno");
+            seen.push(err.errno());
+            assert_eq!(HkvError::from_errno(err.errno()), Some(err));
+        }
+        assert_eq!(HkvError::InvalidInput.errno(), errno::EINVAL);
+        assert_eq!(HkvError::Timeout.errno(), errno::ETIMEDOUT);
+        assert_eq!(
+            HkvError::from_errno(errno::EFAULT),
+            Some(HkvError::InvalidInput)
+        );
+        assert_eq!(HkvError::from_errno(errno::EAGAIN), Some(HkvError::Busy));
+        assert_eq!(HkvError::from_errno(0), None);
+        assert_eq!(HkvError::from_errno(-errno::EINVAL), None);
+    }
+
+    #[test]
+    fn io_error_round_trips() {
+        for err in ALL {
+            let io_err = io::Error::from(err);
+            assert_eq!(io_err.kind(), err.io_kind());
+            assert_eq!(HkvError::from(io_err), err);
+        }
+
+        assert_eq!(
+            HkvError::from(io::Error::from(io::ErrorKind::TimedOut)),
+            HkvError::Timeout
+        );
+        assert_eq!(
+            HkvError::from(io::Error::other("boom")),
+            HkvError::InternalError
+        );
+        if cfg!(target_os = "linux") {
+            let ebusy = io::Error::from_raw_os_error(errno::EBUSY);
+            assert_eq!(HkvError::from(ebusy), HkvError::Busy);
+            let enotty = io::Error::from_raw_os_error(errno::ENOTTY);
+            assert_eq!(HkvError::from(enotty), HkvError::UnsupportedCommand);
+        }
+    }
 }
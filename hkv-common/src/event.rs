@@ -0,0 +1,277 @@
+//! # Cache Event Records
+//!
+//! Define the fixed-size records the kernel cache sends to user space when
+//! an entry enters or leaves it (the Netlink notification stream), and the
+//! refresh requests async-refresh reads queue for user space.
+//!
+//! ## Design Principles
+//!
+//! 1. **One Record, One Change**: Each record names a single key, the tenant
+//!    it belongs to, what happened to it, and the version involved.
+//! 2. **Struct Image on the Wire**: `to_bytes` writes the `#[repr(C)]` layout
+//!    field by field in native byte order, so a record read from a socket is
+//!    byte-identical to the kernel's struct without any `unsafe` cast.
+//! 3. **Batched Datagrams**: A datagram may carry several records back to
+//!    back; `records` splits and validates them one by one.
+//!
+//! ## Memory Layout
+//!
+//! ```text
+//! CacheEvent (272 bytes total):
+//! +---------+-------------+---------+-------------+-----------+----------+
+//! | magic:1B| protocol:1B | kind:1B | reserved:1B | tenant:2B | key:258B |
+//! +---------+-------------+---------+-------------+-----------+----------+
+//! | pad:2B  | version:8B  |
+//! +---------+-------------+
+//! ```
+
+use crate::error::{HkvError, HkvResult};
+use crate::ioctl::IOCTL_MAGIC;
+use crate::protocol::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use crate::types::{Key, Version, MAX_KEY_SIZE};
+
+/// Size of one encoded `CacheEvent`.
+pub const EVENT_RECORD_SIZE: usize = std::mem::size_of::<CacheEvent>();
+
+/// Byte offsets of the `CacheEvent` fields (`#[repr(C)]` layout).
+const KIND_OFFSET: usize = 2;
+const TENANT_OFFSET: usize = 4;
+const KEY_LEN_OFFSET: usize = 6;
+const KEY_DATA_OFFSET: usize = 8;
+const VERSION_OFFSET: usize = 264;
+
+/// What happened to a cached entry.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheEventKind {
+    /// The entry was inserted or replaced by a promotion.
+    Promoted = 1,
+    /// The entry was evicted by policy or memory pressure.
+    Evicted = 2,
+    /// The entry was removed by `CMD_DEMOTE` or `CMD_FLUSH`.
+    Demoted = 3,
+    /// The entry's TTL passed and it was reclaimed.
+    Expired = 4,
+    /// The entry was marked stale by a newer write.
+    Invalidated = 5,
+}
+
+impl CacheEventKind {
+    /// Every kind, in ABI order.
+    pub const ALL: &'static [CacheEventKind] = &[
+        CacheEventKind::Promoted,
+        CacheEventKind::Evicted,
+        CacheEventKind::Demoted,
+        CacheEventKind::Expired,
+        CacheEventKind::Invalidated,
+    ];
+
+    /// Returns the ABI number of the kind.
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Converts an ABI number into a kind.
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(CacheEventKind::Promoted),
+            2 => Some(CacheEventKind::Evicted),
+            3 => Some(CacheEventKind::Demoted),
+            4 => Some(CacheEventKind::Expired),
+            5 => Some(CacheEventKind::Invalidated),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowercase name used in INFO output.
+    pub const fn name(self) -> &'static str {
+        match self {
+            CacheEventKind::Promoted => "promoted",
+            CacheEventKind::Evicted => "evicted",
+            CacheEventKind::Demoted => "demoted",
+            CacheEventKind::Expired => "expired",
+            CacheEventKind::Invalidated => "invalidated",
+        }
+    }
+
+    /// Returns true when the key no longer serves fresh reads afterwards.
+    pub const fn removes(self) -> bool {
+        !matches!(self, CacheEventKind::Promoted)
+    }
+}
+
+/// One kernel cache change notification.
+///
+/// `version` is the entry's version for promotions and removals, and the
+/// invalidating write's version for `Invalidated`.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEvent {
+    /// Magic number (`IOCTL_MAGIC`).
+    pub magic: u8,
+    /// Protocol version the record was written with.
+    pub protocol: u8,
+    /// `CacheEventKind` ABI number.
+    pub kind: u8,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u8,
+    /// Tenant the key belongs to.
+    pub tenant: u16,
+    /// Affected key.
+    pub key: Key,
+    /// Version involved in the change.
+    pub version: Version,
+}
+
+impl CacheEvent {
+    /// Builds a record for `key` in `tenant`.
+    pub fn new(kind: CacheEventKind, tenant: u16, key: Key, version: Version) -> Self {
+        CacheEvent {
+            magic: IOCTL_MAGIC,
+            protocol: PROTOCOL_VERSION,
+            kind: kind.as_u8(),
+            reserved: 0,
+            tenant,
+            key,
+            version,
+        }
+    }
+
+    /// Returns the decoded kind, or `None` for an unknown number.
+    pub fn event_kind(&self) -> Option<CacheEventKind> {
+        CacheEventKind::from_u8(self.kind)
+    }
+
+    /// Encodes the record as its `#[repr(C)]` byte image (padding zeroed).
+    pub fn to_bytes(&self) -> [u8; EVENT_RECORD_SIZE] {
+        let mut buf = [0u8; EVENT_RECORD_SIZE];
+        buf[0] = self.magic;
+        buf[1] = self.protocol;
+        buf[KIND_OFFSET] = self.kind;
+        buf[3] = self.reserved;
+        buf[TENANT_OFFSET..KEY_LEN_OFFSET].copy_from_slice(&self.tenant.to_ne_bytes());
+        let key = self.key.as_bytes();
+        buf[KEY_LEN_OFFSET..KEY_DATA_OFFSET].copy_from_slice(&(key.len() as u16).to_ne_bytes());
+        buf[KEY_DATA_OFFSET..KEY_DATA_OFFSET + key.len()].copy_from_slice(key);
+        buf[VERSION_OFFSET..].copy_from_slice(&self.version.0.to_ne_bytes());
+        buf
+    }
+
+    /// Decodes and validates one record.
+    ///
+    /// # Errors
+    /// - `InvalidInput`: wrong length or unknown kind
+    /// - `ProtocolViolation`: bad magic or non-zero reserved byte
+    /// - `VersionMismatch`: unsupported protocol version
+    /// - `KeyTooLong`: key length past `MAX_KEY_SIZE`
+    pub fn from_bytes(buf: &[u8]) -> HkvResult<Self> {
+        let buf: &[u8; EVENT_RECORD_SIZE] = buf.try_into().map_err(|_| HkvError::InvalidInput)?;
+        if buf[0] != IOCTL_MAGIC || buf[3] != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&buf[1]) {
+            return Err(HkvError::VersionMismatch);
+        }
+        let kind = CacheEventKind::from_u8(buf[KIND_OFFSET]).ok_or(HkvError::InvalidInput)?;
+        let tenant = u16::from_ne_bytes([buf[TENANT_OFFSET], buf[TENANT_OFFSET + 1]]);
+        let key_len = u16::from_ne_bytes([buf[KEY_LEN_OFFSET], buf[KEY_LEN_OFFSET + 1]]) as usize;
+        if key_len > MAX_KEY_SIZE {
+            return Err(HkvError::KeyTooLong);
+        }
+        let key = Key::new(&buf[KEY_DATA_OFFSET..KEY_DATA_OFFSET + key_len])?;
+        let mut version = [0u8; 8];
+        version.copy_from_slice(&buf[VERSION_OFFSET..]);
+
+        Ok(CacheEvent {
+            protocol: buf[1],
+            ..CacheEvent::new(kind, tenant, key, Version(u64::from_ne_bytes(version)))
+        })
+    }
+}
+
+/// A request for user space to re-promote a key served stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshRequest {
+    /// Tenant the key belongs to.
+    pub tenant: u16,
+    /// Key to re-read and promote.
+    pub key: Key,
+    /// Version of the write that invalidated the cached copy; a refresh
+    /// must promote this version or newer.
+    pub version: Version,
+}
+
+/// Splits a datagram into records and decodes each one.
+///
+/// A trailing partial record decodes as `InvalidInput`.
+pub fn records(datagram: &[u8]) -> impl Iterator<Item = HkvResult<CacheEvent>> + '_ {
+    datagram
+        .chunks(EVENT_RECORD_SIZE)
+        .map(CacheEvent::from_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    fn event(kind: CacheEventKind, key: &[u8], version: u64) -> CacheEvent {
+        CacheEvent::new(kind, 3, Key::new(key).unwrap(), Version::new(version))
+    }
+
+    #[test]
+    fn test_layout_matches_offsets() {
+        assert_eq!(EVENT_RECORD_SIZE, 272);
+        assert_eq!(offset_of!(CacheEvent, kind), KIND_OFFSET);
+        assert_eq!(offset_of!(CacheEvent, tenant), TENANT_OFFSET);
+        assert_eq!(offset_of!(CacheEvent, key), KEY_LEN_OFFSET);
+        assert_eq!(offset_of!(CacheEvent, version), VERSION_OFFSET);
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        for kind in CacheEventKind::ALL {
+            assert_eq!(CacheEventKind::from_u8(kind.as_u8()), Some(*kind));
+            let original = event(*kind, b"user:42", 9);
+            let decoded = CacheEvent::from_bytes(&original.to_bytes()).unwrap();
+            assert_eq!(decoded, original);
+            assert_eq!(decoded.event_kind(), Some(*kind));
+        }
+        assert!(!CacheEventKind::Promoted.removes());
+        assert!(CacheEventKind::Expired.removes());
+    }
+
+    #[test]
+    fn test_batched_datagram_splits_records() {
+        let mut datagram = Vec::new();
+        datagram.extend_from_slice(&event(CacheEventKind::Promoted, b"a", 1).to_bytes());
+        datagram.extend_from_slice(&event(CacheEventKind::Evicted, b"b", 2).to_bytes());
+        datagram.extend_from_slice(&[0u8; 10]);
+
+        let decoded: Vec<_> = records(&datagram).collect();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1].as_ref().unwrap().key.as_bytes(), b"b");
+        assert_eq!(decoded[2], Err(HkvError::InvalidInput));
+    }
+
+    #[test]
+    fn test_rejects_malformed_records() {
+        let good = event(CacheEventKind::Demoted, b"k", 1).to_bytes();
+
+        let mut bad = good;
+        bad[0] = 0;
+        assert_eq!(
+            CacheEvent::from_bytes(&bad),
+            Err(HkvError::ProtocolViolation)
+        );
+        let mut bad = good;
+        bad[1] = PROTOCOL_VERSION + 1;
+        assert_eq!(CacheEvent::from_bytes(&bad), Err(HkvError::VersionMismatch));
+        let mut bad = good;
+        bad[KIND_OFFSET] = 0;
+        assert_eq!(CacheEvent::from_bytes(&bad), Err(HkvError::InvalidInput));
+        let mut bad = good;
+        bad[KEY_LEN_OFFSET..KEY_DATA_OFFSET].copy_from_slice(&300u16.to_ne_bytes());
+        assert_eq!(CacheEvent::from_bytes(&bad), Err(HkvError::KeyTooLong));
+    }
+}
@@ -0,0 +1,623 @@
+//! # Eviction Policies
+//!
+//! Decide which entry a cache gives up when it is over budget. Each engine
+//! shard and each kernel cache domain owns one `EvictionPolicy`, fed with
+//! slot indices, so policies never see keys or values.
+//!
+//! ## Usage
+//!
+//! - The engine picks a policy with `MemoryEngine::with_eviction_policy(kind)`;
+//!   the default is `EvictionPolicyKind::Lru`.
+//! - The kernel cache selects one by `EvictionPolicyId` through `CMD_CONFIG`
+//!   and maps its keys onto slots.
+//! - `EvictionPolicyKind::parse` accepts the names reported by `name`, for
+//!   config files and environment variables.
+//!
+//! ## Design Principles
+//!
+//! 1. **Slot-Based Hooks**: Policies track arena slots (`on_insert`,
+//!    `on_access`, `on_remove`) and only nominate a `victim`; the shard does
+//!    the removal, byte accounting, and AOF logging.
+//! 2. **Index-Linked Queues**: Recency lists are intrusive doubly linked lists
+//!    over slot indices, keeping every hook O(1) without heap pointers.
+//! 3. **One Implementation for Both Tiers**: The engine and the kernel policy
+//!    plane run these same policies (LRU, LFU, SLRU, 2Q, FIFO), so hit rates
+//!    measured in user space carry over to the kernel cache.
+//! 4. **Hashes, Not Keys**: Policies that remember evicted entries (2Q's ghost
+//!    queue) keep 64-bit key hashes only, bounding their memory.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt;
+
+use crate::policy::EvictionPolicyId;
+
+/// Sentinel for "no slot" in queue links.
+const NIL: usize = usize::MAX;
+
+/// Sentinel queue id for slots not linked into any queue.
+const UNLINKED: u8 = u8::MAX;
+
+/// Share of resident entries SLRU keeps in its protected segment.
+const SLRU_PROTECTED_RATIO: f64 = 0.8;
+
+/// Share of resident entries 2Q keeps in its `A1in` FIFO.
+const TWO_Q_IN_RATIO: f64 = 0.25;
+
+/// Ghost entries 2Q remembers, relative to resident entries.
+const TWO_Q_OUT_RATIO: f64 = 0.5;
+
+/// Minimum ghost capacity so small shards still detect re-references.
+const TWO_Q_MIN_GHOSTS: usize = 16;
+
+/// Available eviction policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicyKind {
+    /// Least recently used.
+    #[default]
+    Lru,
+    /// Least frequently used; ties go to the least recently used.
+    Lfu,
+    /// Segmented LRU: new entries wait in a probation segment and move to a
+    /// protected segment on their second access.
+    Slru,
+    /// 2Q: a FIFO for first-time entries, a ghost queue of recently evicted
+    /// ones, and an LRU for entries seen again.
+    TwoQ,
+    /// First in, first out; accesses do not change the order.
+    Fifo,
+}
+
+impl EvictionPolicyKind {
+    /// Every policy, in declaration order.
+    pub const ALL: [Self; 5] = [Self::Lru, Self::Lfu, Self::Slru, Self::TwoQ, Self::Fifo];
+
+    /// Parses a policy name (`lru`, `lfu`, `slru`, `2q`, `fifo`), ignoring case.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "lru" => Some(Self::Lru),
+            "lfu" => Some(Self::Lfu),
+            "slru" => Some(Self::Slru),
+            "2q" | "twoq" => Some(Self::TwoQ),
+            "fifo" => Some(Self::Fifo),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowercase policy name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lru => "lru",
+            Self::Lfu => "lfu",
+            Self::Slru => "slru",
+            Self::TwoQ => "2q",
+            Self::Fifo => "fifo",
+        }
+    }
+
+    /// Creates an empty policy instance of this kind.
+    pub fn build(self) -> Box<dyn EvictionPolicy> {
+        match self {
+            Self::Lru => Box::new(LruPolicy::default()),
+            Self::Lfu => Box::new(LfuPolicy::default()),
+            Self::Slru => Box::new(SlruPolicy::default()),
+            Self::TwoQ => Box::new(TwoQPolicy::default()),
+            Self::Fifo => Box::new(FifoPolicy::default()),
+        }
+    }
+}
+
+impl From<EvictionPolicyId> for EvictionPolicyKind {
+    fn from(id: EvictionPolicyId) -> Self {
+        match id {
+            EvictionPolicyId::Lru => Self::Lru,
+            EvictionPolicyId::Lfu => Self::Lfu,
+            EvictionPolicyId::Slru => Self::Slru,
+            EvictionPolicyId::TwoQ => Self::TwoQ,
+            EvictionPolicyId::Fifo => Self::Fifo,
+        }
+    }
+}
+
+/// Victim selection for one shard or cache domain.
+///
+/// Slots are the owner's arena indices. The owner guarantees that
+/// `on_insert` is called once before any other hook for a slot, and that
+/// `on_remove`/`on_evict` end its lifetime; a freed slot may later be
+/// inserted again for a different key.
+pub trait EvictionPolicy: Send + Sync + fmt::Debug {
+    /// Returns which policy this is.
+    fn kind(&self) -> EvictionPolicyKind;
+
+    /// Registers a newly inserted slot; `key_hash` identifies its key.
+    fn on_insert(&mut self, slot: usize, key_hash: u64);
+
+    /// Records a read or overwrite of a resident slot.
+    fn on_access(&mut self, slot: usize);
+
+    /// Forgets a slot whose key was deleted or expired.
+    fn on_remove(&mut self, slot: usize);
+
+    /// Forgets a slot that was evicted after being returned by `victim`.
+    ///
+    /// Policies with history (2Q) override this to remember the key.
+    fn on_evict(&mut self, slot: usize) {
+        self.on_remove(slot);
+    }
+
+    /// Returns the slot to evict next, or `None` when nothing is tracked.
+    fn victim(&self) -> Option<usize>;
+}
+
+/// Per-slot membership in one of `N` index-linked queues.
+#[derive(Debug, Clone, Copy)]
+struct QueueLink {
+    prev: usize,
+    next: usize,
+    queue: u8,
+}
+
+impl Default for QueueLink {
+    fn default() -> Self {
+        QueueLink {
+            prev: NIL,
+            next: NIL,
+            queue: UNLINKED,
+        }
+    }
+}
+
+/// `N` doubly linked queues over slot indices sharing one link arena.
+///
+/// Each slot is in at most one queue; fronts are the oldest entries.
+#[derive(Debug)]
+struct SlotQueues<const N: usize> {
+    links: Vec<QueueLink>,
+    heads: [usize; N],
+    tails: [usize; N],
+    lens: [usize; N],
+}
+
+impl<const N: usize> Default for SlotQueues<N> {
+    fn default() -> Self {
+        SlotQueues {
+            links: Vec::new(),
+            heads: [NIL; N],
+            tails: [NIL; N],
+            lens: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> SlotQueues<N> {
+    /// Returns the queue `slot` is linked into, if any.
+    fn queue_of(&self, slot: usize) -> Option<usize> {
+        self.links
+            .get(slot)
+            .filter(|link| link.queue != UNLINKED)
+            .map(|link| usize::from(link.queue))
+    }
+
+    fn front(&self, queue: usize) -> Option<usize> {
+        let head = self.heads[queue];
+        (head != NIL).then_some(head)
+    }
+
+    fn len(&self, queue: usize) -> usize {
+        self.lens[queue]
+    }
+
+    fn total_len(&self) -> usize {
+        self.lens.iter().sum()
+    }
+
+    /// Appends `slot` to the back of `queue`, unlinking it first if needed.
+    fn push_back(&mut self, queue: usize, slot: usize) {
+        self.unlink(slot);
+        if slot >= self.links.len() {
+            self.links.resize(slot + 1, QueueLink::default());
+        }
+        let tail = self.tails[queue];
+        self.links[slot] = QueueLink {
+            prev: tail,
+            next: NIL,
+            queue: queue as u8,
+        };
+        if tail == NIL {
+            self.heads[queue] = slot;
+        } else {
+            self.links[tail].next = slot;
+        }
+        self.tails[queue] = slot;
+        self.lens[queue] += 1;
+    }
+
+    /// Moves `slot` to the back of its current queue.
+    fn move_to_back(&mut self, slot: usize) {
+        match self.queue_of(slot) {
+            Some(queue) if self.tails[queue] != slot => self.push_back(queue, slot),
+            _ => {}
+        }
+    }
+
+    /// Detaches `slot`; returns the queue it was in.
+    fn unlink(&mut self, slot: usize) -> Option<usize> {
+        let queue = self.queue_of(slot)?;
+        let QueueLink { prev, next, .. } = self.links[slot];
+        if prev == NIL {
+            self.heads[queue] = next;
+        } else {
+            self.links[prev].next = next;
+        }
+        if next == NIL {
+            self.tails[queue] = prev;
+        } else {
+            self.links[next].prev = prev;
+        }
+        self.links[slot] = QueueLink::default();
+        self.lens[queue] -= 1;
+        Some(queue)
+    }
+}
+
+/// Least recently used: one queue, accesses move a slot to the back.
+#[derive(Debug, Default)]
+pub struct LruPolicy {
+    queue: SlotQueues<1>,
+}
+
+impl EvictionPolicy for LruPolicy {
+    fn kind(&self) -> EvictionPolicyKind {
+        EvictionPolicyKind::Lru
+    }
+
+    fn on_insert(&mut self, slot: usize, _key_hash: u64) {
+        self.queue.push_back(0, slot);
+    }
+
+    fn on_access(&mut self, slot: usize) {
+        self.queue.move_to_back(slot);
+    }
+
+    fn on_remove(&mut self, slot: usize) {
+        self.queue.unlink(slot);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.queue.front(0)
+    }
+}
+
+/// First in, first out: insertion order only.
+#[derive(Debug, Default)]
+pub struct FifoPolicy {
+    queue: SlotQueues<1>,
+}
+
+impl EvictionPolicy for FifoPolicy {
+    fn kind(&self) -> EvictionPolicyKind {
+        EvictionPolicyKind::Fifo
+    }
+
+    fn on_insert(&mut self, slot: usize, _key_hash: u64) {
+        self.queue.push_back(0, slot);
+    }
+
+    fn on_access(&mut self, _slot: usize) {}
+
+    fn on_remove(&mut self, slot: usize) {
+        self.queue.unlink(slot);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.queue.front(0)
+    }
+}
+
+/// Least frequently used, with ties broken by recency.
+///
+/// Counts are exact and never decay, so a formerly hot key stays resident
+/// until colder keys are gone; SLRU or 2Q suit shifting working sets better.
+#[derive(Debug, Default)]
+pub struct LfuPolicy {
+    /// Per-slot `(hits, last_tick)`; `None` for untracked slots.
+    entries: Vec<Option<(u64, u64)>>,
+    /// Ordered `(hits, last_tick, slot)`; the first element is the victim.
+    order: BTreeSet<(u64, u64, usize)>,
+    tick: u64,
+}
+
+impl LfuPolicy {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl EvictionPolicy for LfuPolicy {
+    fn kind(&self) -> EvictionPolicyKind {
+        EvictionPolicyKind::Lfu
+    }
+
+    fn on_insert(&mut self, slot: usize, _key_hash: u64) {
+        self.on_remove(slot);
+        if slot >= self.entries.len() {
+            self.entries.resize(slot + 1, None);
+        }
+        let tick = self.next_tick();
+        self.entries[slot] = Some((1, tick));
+        self.order.insert((1, tick, slot));
+    }
+
+    fn on_access(&mut self, slot: usize) {
+        let Some(Some((hits, last))) = self.entries.get(slot).copied() else {
+            return;
+        };
+        self.order.remove(&(hits, last, slot));
+        let tick = self.next_tick();
+        let hits = hits.saturating_add(1);
+        self.entries[slot] = Some((hits, tick));
+        self.order.insert((hits, tick, slot));
+    }
+
+    fn on_remove(&mut self, slot: usize) {
+        if let Some((hits, last)) = self.entries.get_mut(slot).and_then(Option::take) {
+            self.order.remove(&(hits, last, slot));
+        }
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.order.first().map(|&(_, _, slot)| slot)
+    }
+}
+
+/// Segmented LRU with a probation and a protected segment.
+///
+/// One-hit entries are evicted from probation first; the protected segment is
+/// capped at 80% of resident entries and demotes its oldest slot back to
+/// probation when it overflows.
+#[derive(Debug, Default)]
+pub struct SlruPolicy {
+    queues: SlotQueues<2>,
+}
+
+impl SlruPolicy {
+    const PROBATION: usize = 0;
+    const PROTECTED: usize = 1;
+
+    fn protected_capacity(&self) -> usize {
+        ((self.queues.total_len() as f64 * SLRU_PROTECTED_RATIO) as usize).max(1)
+    }
+}
+
+impl EvictionPolicy for SlruPolicy {
+    fn kind(&self) -> EvictionPolicyKind {
+        EvictionPolicyKind::Slru
+    }
+
+    fn on_insert(&mut self, slot: usize, _key_hash: u64) {
+        self.queues.push_back(Self::PROBATION, slot);
+    }
+
+    fn on_access(&mut self, slot: usize) {
+        match self.queues.queue_of(slot) {
+            Some(Self::PROBATION) => {
+                self.queues.push_back(Self::PROTECTED, slot);
+                while self.queues.len(Self::PROTECTED) > self.protected_capacity() {
+                    let Some(oldest) = self.queues.front(Self::PROTECTED) else {
+                        break;
+                    };
+                    self.queues.push_back(Self::PROBATION, oldest);
+                }
+            }
+            Some(_) => self.queues.move_to_back(slot),
+            None => {}
+        }
+    }
+
+    fn on_remove(&mut self, slot: usize) {
+        self.queues.unlink(slot);
+    }
+
+    fn victim(&self) -> Option<usize> {
+        self.queues
+            .front(Self::PROBATION)
+            .or_else(|| self.queues.front(Self::PROTECTED))
+    }
+}
+
+/// Full 2Q (Johnson & Shasha): `A1in` FIFO, `A1out` ghost hashes, `Am` LRU.
+///
+/// First-time keys enter `A1in` and are evicted from there without touching
+/// `Am`; a key inserted again while its hash is still in `A1out` was
+/// re-referenced soon after eviction and goes straight to `Am`.
+#[derive(Debug, Default)]
+pub struct TwoQPolicy {
+    queues: SlotQueues<2>,
+    /// Key hash per slot, remembered into `A1out` on eviction.
+    hashes: Vec<u64>,
+    ghosts: VecDeque<u64>,
+    ghost_set: HashSet<u64>,
+}
+
+impl TwoQPolicy {
+    const A1IN: usize = 0;
+    const AM: usize = 1;
+
+    fn remember(&mut self, key_hash: u64) {
+        if !self.ghost_set.insert(key_hash) {
+            return;
+        }
+        self.ghosts.push_back(key_hash);
+        let capacity =
+            ((self.queues.total_len() as f64 * TWO_Q_OUT_RATIO) as usize).max(TWO_Q_MIN_GHOSTS);
+        while self.ghosts.len() > capacity {
+            if let Some(old) = self.ghosts.pop_front() {
+                self.ghost_set.remove(&old);
+            }
+        }
+    }
+}
+
+impl EvictionPolicy for TwoQPolicy {
+    fn kind(&self) -> EvictionPolicyKind {
+        EvictionPolicyKind::TwoQ
+    }
+
+    fn on_insert(&mut self, slot: usize, key_hash: u64) {
+        if slot >= self.hashes.len() {
+            self.hashes.resize(slot + 1, 0);
+        }
+        self.hashes[slot] = key_hash;
+        // The deque keeps a stale copy of a consumed ghost; if the key is
+        // evicted again its new ghost may age out a little early.
+        let queue = if self.ghost_set.remove(&key_hash) {
+            Self::AM
+        } else {
+            Self::A1IN
+        };
+        self.queues.push_back(queue, slot);
+    }
+
+    fn on_access(&mut self, slot: usize) {
+        if self.queues.queue_of(slot) == Some(Self::AM) {
+            self.queues.move_to_back(slot);
+        }
+    }
+
+    fn on_remove(&mut self, slot: usize) {
+        self.queues.unlink(slot);
+    }
+
+    fn on_evict(&mut self, slot: usize) {
+        if self.queues.unlink(slot) == Some(Self::A1IN) {
+            self.remember(self.hashes[slot]);
+        }
+    }
+
+    fn victim(&self) -> Option<usize> {
+        let in_capacity = ((self.queues.total_len() as f64 * TWO_Q_IN_RATIO) as usize).max(1);
+        if self.queues.len(Self::A1IN) > in_capacity || self.queues.len(Self::AM) == 0 {
+            self.queues.front(Self::A1IN)
+        } else {
+            self.queues.front(Self::AM)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evicts until `remaining` slots are left and returns the evicted order.
+    fn drain(
+        policy: &mut dyn EvictionPolicy,
+        remaining: usize,
+        resident: &mut usize,
+    ) -> Vec<usize> {
+        let mut evicted = Vec::new();
+        while *resident > remaining {
+            let slot = policy.victim().expect("victim while resident");
+            policy.on_evict(slot);
+            evicted.push(slot);
+            *resident -= 1;
+        }
+        evicted
+    }
+
+    #[test]
+    fn parses_policy_names() {
+        for kind in EvictionPolicyKind::ALL {
+            assert_eq!(EvictionPolicyKind::parse(kind.name()), Some(kind));
+            assert_eq!(kind.build().kind(), kind);
+        }
+        assert_eq!(
+            EvictionPolicyKind::parse("2Q"),
+            Some(EvictionPolicyKind::TwoQ)
+        );
+        assert_eq!(EvictionPolicyKind::parse("random"), None);
+        assert_eq!(EvictionPolicyKind::default(), EvictionPolicyKind::Lru);
+        for id in EvictionPolicyId::ALL {
+            assert_eq!(EvictionPolicyKind::from(*id).name(), id.name());
+        }
+    }
+
+    #[test]
+    fn lru_and_fifo_differ_on_access() {
+        let mut lru = LruPolicy::default();
+        let mut fifo = FifoPolicy::default();
+        for slot in 0..3 {
+            lru.on_insert(slot, slot as u64);
+            fifo.on_insert(slot, slot as u64);
+        }
+        lru.on_access(0);
+        fifo.on_access(0);
+        assert_eq!(lru.victim(), Some(1));
+        assert_eq!(fifo.victim(), Some(0));
+
+        lru.on_remove(1);
+        assert_eq!(lru.victim(), Some(2));
+        lru.on_remove(2);
+        lru.on_remove(0);
+        assert_eq!(lru.victim(), None);
+    }
+
+    #[test]
+    fn lfu_prefers_rarely_used_then_oldest() {
+        let mut lfu = LfuPolicy::default();
+        for slot in 0..3 {
+            lfu.on_insert(slot, 0);
+        }
+        lfu.on_access(0);
+        lfu.on_access(0);
+        lfu.on_access(2);
+        assert_eq!(lfu.victim(), Some(1));
+        lfu.on_remove(1);
+        assert_eq!(lfu.victim(), Some(2));
+
+        // A recycled slot starts over at one hit.
+        lfu.on_remove(0);
+        lfu.on_insert(0, 0);
+        assert_eq!(lfu.victim(), Some(0));
+    }
+
+    #[test]
+    fn slru_protects_reused_entries_from_scans() {
+        let mut slru = SlruPolicy::default();
+        slru.on_insert(0, 0);
+        slru.on_insert(1, 1);
+        slru.on_access(0);
+        // A scan of one-hit entries is evicted before the reused slot.
+        for slot in 2..6 {
+            slru.on_insert(slot, slot as u64);
+        }
+        let mut resident = 6;
+        let evicted = drain(&mut slru, 1, &mut resident);
+        assert_eq!(evicted, vec![1, 2, 3, 4, 5]);
+        assert_eq!(slru.victim(), Some(0));
+    }
+
+    #[test]
+    fn two_q_promotes_keys_seen_again_after_eviction() {
+        let mut two_q = TwoQPolicy::default();
+        for slot in 0..4 {
+            two_q.on_insert(slot, 100 + slot as u64);
+        }
+        let mut resident = 4;
+        assert_eq!(drain(&mut two_q, 3, &mut resident), vec![0]);
+
+        // Key 100 returns in a recycled slot and lands in Am, so the
+        // remaining first-timers in A1in go first.
+        two_q.on_insert(0, 100);
+        assert_eq!(two_q.queues.queue_of(0), Some(TwoQPolicy::AM));
+        resident += 1;
+        assert_eq!(drain(&mut two_q, 2, &mut resident), vec![1, 2]);
+        assert_eq!(two_q.queues.queue_of(0), Some(TwoQPolicy::AM));
+        assert_eq!(two_q.queues.queue_of(3), Some(TwoQPolicy::A1IN));
+
+        // Deletions are not remembered as ghosts.
+        two_q.on_insert(5, 200);
+        two_q.on_remove(5);
+        two_q.on_insert(5, 200);
+        assert_eq!(two_q.queues.queue_of(5), Some(TwoQPolicy::A1IN));
+    }
+}
@@ -99,17 +99,25 @@
 //!   bits 15-8:  Magic number (unique per driver, 'H' for HybridKV)
 //!   bits 7-0:   Command number (0-255)
 //!
-//! However, for simplicity, we use just the command number (0-255) and handle
-//! the full encoding in the hkv-client crate using Linux's ioctl macros:
+//! The `CMD_*` constants are the command numbers (bits 7-0). The full request
+//! numbers are built by const fns mirroring Linux's ioctl macros:
 //!
-//!   _IO(magic, nr)         - No data transfer
-//!   _IOR(magic, nr, type)  - Read from kernel
-//!   _IOW(magic, nr, type)  - Write to kernel
-//!   _IOWR(magic, nr, type) - Read and write
+//!   io(magic, nr)         - _IO: No data transfer
+//!   ior(magic, nr, size)  - _IOR: Read from kernel
+//!   iow(magic, nr, size)  - _IOW: Write to kernel
+//!   iowr(magic, nr, size) - _IOWR: Read and write
+//!
+//! `IoctlCommand::request_number` applies them with the command's direction
+//! and argument size, and `IoctlCommand::from_request_number` decodes and
+//! validates a number on the way back.
 //!
 //! Example:
-//!   CMD_READ is encoded as _IOWR('H', 0, ReadRequest)
-//!   This tells the kernel: "HybridKV command 0, bidirectional data transfer"
+//!   CMD_READ is encoded as _IOWR('H', 0, 1032): the argument buffer holds a
+//!   ReadRequest on entry and the (larger) ReadResponse on return.
+//!
+//! Commands that carry user pointers (CMD_READ_EXT, CMD_PROMOTE_EXT,
+//! CMD_BATCH_PROMOTE) encode only their fixed-size argument; the buffers it
+//! points at are bounded by the lengths and counts inside it.
 //!
 //! ## SAFETY CONSIDERATIONS
 //!
@@ -127,7 +135,8 @@
 //!
 //! 3. **Bounds checking**: Enforce maximum sizes for keys/values
 //!    - MAX_KEY_SIZE = 256 bytes
-//!    - MAX_VALUE_SIZE = 1024 bytes
+//!    - MAX_VALUE_SIZE = 1024 bytes (inline values)
+//!    - MAX_EXT_VALUE_SIZE = 32 KB (values behind a user pointer)
 //!    - Reject oversized requests before allocation
 //!
 //! 4. **Error handling**: Always return proper error codes
@@ -161,7 +170,15 @@
 //! - Commands follow Linux ioctl conventions
 //! - All commands go through the /dev/hybridkv device file
 //! - Magic number 'H' (0x48) identifies HybridKV commands
-//! - Commands are grouped logically: data ops (0-4), monitoring (5), control (6-7)
+//! - Commands are grouped logically: data ops (0-4), monitoring (5), control (6-8)
+
+use crate::error::{HkvError, HkvResult};
+use crate::protocol::{
+    BatchPromoteRequest, ConfigRequest, DemoteRequest, FlushRequest, InvalidateRequest,
+    PromoteExtRequest, PromoteRequest, PromoteResponse, ReadExtRequest, ReadExtResponse,
+    ReadRequest, ReadResponse, StatsRequest, StatsResponse, TenantConfigRequest,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 
 /// ioctl magic number for HybridKV device
 ///
@@ -279,6 +296,133 @@ pub const CMD_CONFIG: u8 = 6;
 /// ensure no readers are accessing the entries being freed.
 pub const CMD_FLUSH: u8 = 7;
 
+/// Command number for TENANT_CONFIG operation
+///
+/// Register a tenant or update its budget settings
+/// - Input: Tenant ID + quota, minimum guarantee, weight, priority
+/// - Output: Success or validation error
+///
+/// Tenants partition the cache into independent eviction domains. The
+/// active tenant budget policy turns these settings into per-tenant byte
+/// budgets that decide which domain gives up memory under pressure.
+pub const CMD_TENANT_CONFIG: u8 = 8;
+
+/// Command number for READ_EXT operation (protocol version 2)
+///
+/// Read a value of up to MAX_EXT_VALUE_SIZE into a user buffer
+/// - Input: Key + user buffer pointer and length
+/// - Output: Full value length; the value is copied when it fits
+///
+/// A buffer too small for the value is left untouched and the call still
+/// reports the length, so the caller can retry once with the right size.
+pub const CMD_READ_EXT: u8 = 9;
+
+/// Command number for PROMOTE_EXT operation (protocol version 2)
+///
+/// Add one entry whose value is read from a user buffer
+/// - Input: Key + user value pointer and length + Metadata (version, TTL)
+/// - Output: Success or error (ValueTooLong, CapacityExceeded, etc.)
+///
+/// Lets values between MAX_VALUE_SIZE and MAX_EXT_VALUE_SIZE be cached.
+pub const CMD_PROMOTE_EXT: u8 = 10;
+
+// ============================================================================
+// REQUEST NUMBER ENCODING
+// ============================================================================
+
+/// Bits of the command number field.
+pub const IOC_NRBITS: u32 = 8;
+/// Bits of the magic (type) field.
+pub const IOC_TYPEBITS: u32 = 8;
+/// Bits of the argument size field.
+pub const IOC_SIZEBITS: u32 = 14;
+/// Bits of the direction field.
+pub const IOC_DIRBITS: u32 = 2;
+
+/// Shift of the command number field.
+pub const IOC_NRSHIFT: u32 = 0;
+/// Shift of the magic field.
+pub const IOC_TYPESHIFT: u32 = IOC_NRSHIFT + IOC_NRBITS;
+/// Shift of the size field.
+pub const IOC_SIZESHIFT: u32 = IOC_TYPESHIFT + IOC_TYPEBITS;
+/// Shift of the direction field.
+pub const IOC_DIRSHIFT: u32 = IOC_SIZESHIFT + IOC_SIZEBITS;
+
+/// Direction: no data transfer.
+pub const IOC_NONE: u32 = 0;
+/// Direction: user space writes, the kernel reads (`copy_from_user`).
+pub const IOC_WRITE: u32 = 1;
+/// Direction: the kernel writes, user space reads (`copy_to_user`).
+pub const IOC_READ: u32 = 2;
+
+/// Largest argument size the size field can encode.
+pub const IOC_SIZE_MAX: usize = (1 << IOC_SIZEBITS) - 1;
+
+/// Builds a request number, like Linux's `_IOC`.
+///
+/// Panics (at compile time in const contexts) if `size` exceeds
+/// `IOC_SIZE_MAX` or `dir` is not a 2-bit direction.
+pub const fn ioc(dir: u32, magic: u8, nr: u8, size: usize) -> u32 {
+    assert!(dir < (1 << IOC_DIRBITS), "ioctl direction out of range");
+    assert!(
+        size <= IOC_SIZE_MAX,
+        "ioctl argument too large for the size field"
+    );
+    (dir << IOC_DIRSHIFT)
+        | ((size as u32) << IOC_SIZESHIFT)
+        | ((magic as u32) << IOC_TYPESHIFT)
+        | ((nr as u32) << IOC_NRSHIFT)
+}
+
+/// `_IO`: a command without an argument.
+pub const fn io(magic: u8, nr: u8) -> u32 {
+    ioc(IOC_NONE, magic, nr, 0)
+}
+
+/// `_IOR`: the kernel fills a `size`-byte argument.
+pub const fn ior(magic: u8, nr: u8, size: usize) -> u32 {
+    ioc(IOC_READ, magic, nr, size)
+}
+
+/// `_IOW`: the kernel reads a `size`-byte argument.
+pub const fn iow(magic: u8, nr: u8, size: usize) -> u32 {
+    ioc(IOC_WRITE, magic, nr, size)
+}
+
+/// `_IOWR`: the kernel reads and then overwrites a `size`-byte argument.
+pub const fn iowr(magic: u8, nr: u8, size: usize) -> u32 {
+    ioc(IOC_READ | IOC_WRITE, magic, nr, size)
+}
+
+/// Extracts the direction field, like `_IOC_DIR`.
+pub const fn ioc_dir(request: u32) -> u32 {
+    (request >> IOC_DIRSHIFT) & ((1 << IOC_DIRBITS) - 1)
+}
+
+/// Extracts the magic field, like `_IOC_TYPE`.
+pub const fn ioc_type(request: u32) -> u8 {
+    ((request >> IOC_TYPESHIFT) & ((1 << IOC_TYPEBITS) - 1)) as u8
+}
+
+/// Extracts the command number field, like `_IOC_NR`.
+pub const fn ioc_nr(request: u32) -> u8 {
+    ((request >> IOC_NRSHIFT) & ((1 << IOC_NRBITS) - 1)) as u8
+}
+
+/// Extracts the size field, like `_IOC_SIZE`.
+pub const fn ioc_size(request: u32) -> usize {
+    ((request >> IOC_SIZESHIFT) & ((1 << IOC_SIZEBITS) - 1)) as usize
+}
+
+/// Size of an argument buffer that carries `request` in and `response` out.
+const fn in_out(request: usize, response: usize) -> usize {
+    if request > response {
+        request
+    } else {
+        response
+    }
+}
+
 // ============================================================================
 // COMMAND ENUMERATION
 // ============================================================================
@@ -313,6 +457,15 @@ pub enum IoctlCommand {
 
     /// Flush all entries from cache
     Flush = CMD_FLUSH,
+
+    /// Register or update a tenant
+    TenantConfig = CMD_TENANT_CONFIG,
+
+    /// Read a value into a user buffer (version 2)
+    ReadExt = CMD_READ_EXT,
+
+    /// Promote an entry whose value lives in a user buffer (version 2)
+    PromoteExt = CMD_PROMOTE_EXT,
 }
 
 impl IoctlCommand {
@@ -332,6 +485,9 @@ impl IoctlCommand {
             CMD_STATS => Some(Self::Stats),
             CMD_CONFIG => Some(Self::Config),
             CMD_FLUSH => Some(Self::Flush),
+            CMD_TENANT_CONFIG => Some(Self::TenantConfig),
+            CMD_READ_EXT => Some(Self::ReadExt),
+            CMD_PROMOTE_EXT => Some(Self::PromoteExt),
             _ => None,
         }
     }
@@ -347,25 +503,113 @@ impl IoctlCommand {
             Self::Stats => "STATS",
             Self::Config => "CONFIG",
             Self::Flush => "FLUSH",
+            Self::TenantConfig => "TENANT_CONFIG",
+            Self::ReadExt => "READ_EXT",
+            Self::PromoteExt => "PROMOTE_EXT",
         }
     }
 
     /// Check if command is read-only (doesn't modify cache)
     pub const fn is_readonly(self) -> bool {
-        matches!(self, Self::Read | Self::Stats)
+        matches!(self, Self::Read | Self::Stats | Self::ReadExt)
     }
 
     /// Check if command modifies cache
     pub const fn is_write(self) -> bool {
         matches!(
             self,
-            Self::Promote | Self::BatchPromote | Self::Demote | Self::Invalidate | Self::Flush
+            Self::Promote
+                | Self::BatchPromote
+                | Self::Demote
+                | Self::Invalidate
+                | Self::Flush
+                | Self::PromoteExt
         )
     }
 
     /// Check if command is a configuration operation
     pub const fn is_config(self) -> bool {
-        matches!(self, Self::Config)
+        matches!(self, Self::Config | Self::TenantConfig)
+    }
+
+    /// Oldest protocol version that knows this command.
+    ///
+    /// `Read` (as `ReadRequestV1`) and `Flush` keep their version 1 layouts;
+    /// every other command was added in version 2 or changed layout there.
+    pub const fn min_version(self) -> u8 {
+        match self {
+            Self::Read | Self::Flush => MIN_PROTOCOL_VERSION,
+            _ => 2,
+        }
+    }
+
+    /// Returns true when a header carrying `version` may issue this command.
+    ///
+    /// The kernel accepts every version from the command's `min_version` up
+    /// to its own `PROTOCOL_VERSION`; anything else is `VersionMismatch`, the
+    /// cue for user space to retry with an older version.
+    pub const fn supports_version(self, version: u8) -> bool {
+        version >= self.min_version() && version <= PROTOCOL_VERSION
+    }
+
+    /// Transfer direction (`IOC_WRITE`, or `IOC_READ | IOC_WRITE` when the
+    /// kernel writes a response back into the argument).
+    pub const fn direction(self) -> u32 {
+        match self {
+            Self::Read | Self::Promote | Self::Stats | Self::ReadExt => IOC_READ | IOC_WRITE,
+            Self::BatchPromote
+            | Self::Demote
+            | Self::Invalidate
+            | Self::Config
+            | Self::Flush
+            | Self::TenantConfig
+            | Self::PromoteExt => IOC_WRITE,
+        }
+    }
+
+    /// Size of the argument buffer encoded in the request number.
+    ///
+    /// For read-write commands this is the larger of the request and
+    /// response structs.
+    pub const fn arg_size(self) -> usize {
+        use std::mem::size_of;
+        match self {
+            Self::Read => in_out(size_of::<ReadRequest>(), size_of::<ReadResponse>()),
+            Self::Promote => in_out(size_of::<PromoteRequest>(), size_of::<PromoteResponse>()),
+            Self::BatchPromote => size_of::<BatchPromoteRequest>(),
+            Self::Demote => size_of::<DemoteRequest>(),
+            Self::Invalidate => size_of::<InvalidateRequest>(),
+            Self::Stats => in_out(size_of::<StatsRequest>(), size_of::<StatsResponse>()),
+            Self::Config => size_of::<ConfigRequest>(),
+            Self::Flush => size_of::<FlushRequest>(),
+            Self::TenantConfig => size_of::<TenantConfigRequest>(),
+            Self::ReadExt => in_out(size_of::<ReadExtRequest>(), size_of::<ReadExtResponse>()),
+            Self::PromoteExt => size_of::<PromoteExtRequest>(),
+        }
+    }
+
+    /// Full 32-bit ioctl request number (`_IOWR('H', nr, size)` and friends).
+    pub const fn request_number(self) -> u32 {
+        ioc(self.direction(), IOCTL_MAGIC, self.as_u8(), self.arg_size())
+    }
+
+    /// Decodes a request number, checking magic, direction, and size.
+    ///
+    /// # Errors
+    /// - `UnsupportedCommand` for a foreign magic or unknown command number.
+    /// - `InvalidInput` when direction or size disagree with this build's
+    ///   structs (the caller was compiled against a different layout).
+    pub const fn from_request_number(request: u32) -> HkvResult<Self> {
+        if ioc_type(request) != IOCTL_MAGIC {
+            return Err(HkvError::UnsupportedCommand);
+        }
+        let Some(command) = Self::from_u8(ioc_nr(request)) else {
+            return Err(HkvError::UnsupportedCommand);
+        };
+        if ioc_dir(request) != command.direction() || ioc_size(request) != command.arg_size() {
+            return Err(HkvError::InvalidInput);
+        }
+        Ok(command)
     }
 }
 
@@ -395,6 +639,9 @@ mod tests {
             IoctlCommand::Stats,
             IoctlCommand::Config,
             IoctlCommand::Flush,
+            IoctlCommand::TenantConfig,
+            IoctlCommand::ReadExt,
+            IoctlCommand::PromoteExt,
         ];
 
         for cmd in commands {
@@ -427,6 +674,7 @@ mod tests {
 
         // Config operations
         assert!(IoctlCommand::Config.is_config());
+        assert!(IoctlCommand::TenantConfig.is_config());
         assert!(!IoctlCommand::Read.is_config());
     }
 
@@ -450,6 +698,91 @@ mod tests {
         assert_eq!(IOCTL_MAGIC, 0x48);
     }
 
+    #[test]
+    fn test_ioc_matches_linux_macros() {
+        // Same bit layout as <asm-generic/ioctl.h>: dir 31-30, size 29-16, type 15-8, nr 7-0.
+        assert_eq!(io(b'T', 0x01), 0x0000_5401);
+        assert_eq!(ior(b'T', 0x13, 8), 0x8008_5413);
+        assert_eq!(iow(b'T', 0x14, 8), 0x4008_5414);
+        assert_eq!(iowr(b'H', 0xff, IOC_SIZE_MAX), 0xFFFF_48FF);
+
+        let request = iowr(IOCTL_MAGIC, 3, 264);
+        assert_eq!(ioc_dir(request), IOC_READ | IOC_WRITE);
+        assert_eq!(ioc_type(request), IOCTL_MAGIC);
+        assert_eq!(ioc_nr(request), 3);
+        assert_eq!(ioc_size(request), 264);
+    }
+
+    #[test]
+    fn test_request_numbers_are_pinned() {
+        // Changing any of these breaks the ABI with the kernel module.
+        let pinned = [
+            (IoctlCommand::Read, 0xC408_4800),
+            (IoctlCommand::Promote, 0xC520_4801),
+            (IoctlCommand::BatchPromote, 0x4018_4802),
+            (IoctlCommand::Demote, 0x4108_4803),
+            (IoctlCommand::Invalidate, 0x4110_4804),
+            (IoctlCommand::Stats, 0xC080_4805),
+            (IoctlCommand::Config, 0x4028_4806),
+            (IoctlCommand::Flush, 0x4004_4807),
+            (IoctlCommand::TenantConfig, 0x4020_4808),
+            (IoctlCommand::ReadExt, 0xC118_4809),
+            (IoctlCommand::PromoteExt, 0x4128_480A),
+        ];
+        for (command, number) in pinned {
+            assert_eq!(command.request_number(), number, "{command}");
+            assert_eq!(IoctlCommand::from_request_number(number), Ok(command));
+        }
+    }
+
+    #[test]
+    fn test_request_number_decoding_validates() {
+        let read = IoctlCommand::Read.request_number();
+        let foreign = (read & !(0xff << IOC_TYPESHIFT)) | ((b'X' as u32) << IOC_TYPESHIFT);
+        assert_eq!(
+            IoctlCommand::from_request_number(foreign),
+            Err(HkvError::UnsupportedCommand)
+        );
+        assert_eq!(
+            IoctlCommand::from_request_number(iowr(IOCTL_MAGIC, 99, 8)),
+            Err(HkvError::UnsupportedCommand)
+        );
+        // An older ReadRequest-sized argument.
+        assert_eq!(
+            IoctlCommand::from_request_number(iowr(IOCTL_MAGIC, CMD_READ, 262)),
+            Err(HkvError::InvalidInput)
+        );
+        assert_eq!(
+            IoctlCommand::from_request_number(ior(IOCTL_MAGIC, CMD_READ, 1032)),
+            Err(HkvError::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn test_version_negotiation_window() {
+        assert!(IoctlCommand::Read.supports_version(MIN_PROTOCOL_VERSION));
+        assert!(IoctlCommand::Read.supports_version(PROTOCOL_VERSION));
+        assert!(!IoctlCommand::Read.supports_version(PROTOCOL_VERSION + 1));
+        assert!(!IoctlCommand::Read.supports_version(0));
+        assert!(!IoctlCommand::ReadExt.supports_version(1));
+        assert!(IoctlCommand::PromoteExt.supports_version(2));
+        assert!(!IoctlCommand::BatchPromote.supports_version(1));
+        assert!(!IoctlCommand::Config.supports_version(1));
+        assert!(!IoctlCommand::Promote.supports_version(1));
+        assert!(IoctlCommand::Flush.supports_version(1));
+    }
+
+    #[test]
+    fn test_version_one_read_shares_the_request_number() {
+        use crate::protocol::ReadRequestV1;
+        use std::mem::size_of;
+
+        assert_eq!(
+            in_out(size_of::<ReadRequestV1>(), size_of::<ReadResponse>()),
+            IoctlCommand::Read.arg_size()
+        );
+    }
+
     #[test]
     fn test_command_uniqueness() {
         // All command numbers should be unique
@@ -462,6 +795,9 @@ mod tests {
             CMD_STATS,
             CMD_CONFIG,
             CMD_FLUSH,
+            CMD_TENANT_CONFIG,
+            CMD_READ_EXT,
+            CMD_PROMOTE_EXT,
         ];
 
         for i in 0..numbers.len() {
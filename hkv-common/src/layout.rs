@@ -0,0 +1,638 @@
+//! # ABI Layout Manifest
+//!
+//! Pin the size, alignment, and field offsets of every `#[repr(C)]` struct
+//! that crosses the user/kernel boundary, and generate the matching C header
+//! from the same table.
+//!
+//! ## Design Principles
+//!
+//! 1. **One Table**: Each struct's expected layout is written once, in
+//!    `abi_layouts!`; the compile-time checks and `c_header` both read it.
+//! 2. **Fail the Build, Not the Kernel**: Size, alignment, and offsets are
+//!    `const` assertions, and every table entry destructures its struct
+//!    without `..`, so adding, moving, or retyping a field stops
+//!    compilation until the table (and the header) are updated. Entries
+//!    marked `private` (`Key`, `Value`) keep their fields out of reach here;
+//!    their module checks names and offsets against the table with
+//!    `check_private_fields!`.
+//! 3. **Explicit Padding**: The header spells every gap out as a `_padN`
+//!    byte array, so C code sees the same bytes Rust does and can zero them.
+//! 4. **Self-Checking Header**: The header carries its own `_Static_assert`s,
+//!    so a C module compiled against a stale copy fails too.
+//!
+//! The ABI is defined for 64-bit targets; other targets fail these checks
+//! instead of silently using a different layout.
+//!
+//! ## Regenerating the Header
+//!
+//! `include/hybridkv_abi.h` is checked in. After a layout change run
+//! `HKV_BLESS_ABI=1 cargo test -p hkv-common --test abi_header` to rewrite it.
+
+use std::fmt::Write;
+use std::mem::{align_of, offset_of, size_of};
+
+use crate::event::CacheEvent;
+use crate::ioctl::{IoctlCommand, DEVICE_PATH, IOCTL_MAGIC};
+use crate::policy::CONSISTENCY_INHERIT;
+use crate::protocol::{
+    BatchPromoteDescriptor, BatchPromoteRequest, CacheStats, ConfigRequest, DemoteRequest,
+    FlushRequest, InvalidateRequest, IoctlHeader, PromoteExtRequest, PromoteRequest,
+    PromoteResponse, ReadExtRequest, ReadExtResponse, ReadRequest, ReadRequestV1, ReadResponse,
+    StatsRequest, StatsResponse, TenantConfigRequest, MAX_BATCH_SIZE, MIN_PROTOCOL_VERSION,
+    PROTOCOL_VERSION, STATUS_OK,
+};
+use crate::types::{
+    Entry, EntryFlags, EntryMetadata, Key, Ttl, Value, Version, MAX_EXT_VALUE_SIZE, MAX_KEY_SIZE,
+    MAX_VALUE_SIZE,
+};
+
+/// A type that may appear as an ABI struct field.
+pub trait AbiType {
+    /// C spelling of the type.
+    const C_TYPE: &'static str;
+    /// Element count when the field is a byte array.
+    const ARRAY_LEN: Option<usize> = None;
+    /// Fields in declaration order when the type is an ABI struct.
+    const FIELDS: &'static [FieldLayout] = &[];
+}
+
+impl AbiType for u8 {
+    const C_TYPE: &'static str = "uint8_t";
+}
+
+impl AbiType for u16 {
+    const C_TYPE: &'static str = "uint16_t";
+}
+
+impl AbiType for u32 {
+    const C_TYPE: &'static str = "uint32_t";
+}
+
+impl AbiType for u64 {
+    const C_TYPE: &'static str = "uint64_t";
+}
+
+impl<const N: usize> AbiType for [u8; N] {
+    const C_TYPE: &'static str = "uint8_t";
+    const ARRAY_LEN: Option<usize> = Some(N);
+}
+
+/// Newtypes that cross the ABI as a plain integer, emitted as `typedef`s.
+const SCALAR_TYPEDEFS: &[(&str, &str)] = &[
+    ("hkv_version_t", "uint64_t"),
+    ("hkv_ttl_t", "uint64_t"),
+    ("hkv_entry_flags_t", "uint8_t"),
+];
+
+impl AbiType for Version {
+    const C_TYPE: &'static str = "hkv_version_t";
+}
+
+impl AbiType for Ttl {
+    const C_TYPE: &'static str = "hkv_ttl_t";
+}
+
+impl AbiType for EntryFlags {
+    const C_TYPE: &'static str = "hkv_entry_flags_t";
+}
+
+const _: () = {
+    assert!(size_of::<Version>() == 8 && align_of::<Version>() == 8);
+    assert!(size_of::<Ttl>() == 8 && align_of::<Ttl>() == 8);
+    assert!(size_of::<EntryFlags>() == 1);
+};
+
+/// One field of an ABI struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// Field name, shared by Rust and C.
+    pub name: &'static str,
+    /// Byte offset from the start of the struct.
+    pub offset: usize,
+    /// Size in bytes.
+    pub size: usize,
+    /// C spelling of the field's type.
+    pub c_type: &'static str,
+    /// Element count when the field is a byte array.
+    pub array_len: Option<usize>,
+}
+
+/// Layout of one ABI struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructLayout {
+    /// Rust type name.
+    pub rust_name: &'static str,
+    /// C struct tag (without `struct`).
+    pub c_name: &'static str,
+    /// Size in bytes, including trailing padding.
+    pub size: usize,
+    /// Alignment in bytes.
+    pub align: usize,
+    /// Fields in declaration order.
+    pub fields: &'static [FieldLayout],
+}
+
+impl StructLayout {
+    /// Returns the field named `name`.
+    pub fn field(&self, name: &str) -> Option<&FieldLayout> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Returns true when `(offset, size)` pairs are ordered, disjoint, and end
+/// within `size`.
+const fn fields_fit(fields: &[(usize, usize)], size: usize) -> bool {
+    let mut end = 0;
+    let mut index = 0;
+    while index < fields.len() {
+        if fields[index].0 < end {
+            return false;
+        }
+        end = fields[index].0 + fields[index].1;
+        index += 1;
+    }
+    end <= size
+}
+
+/// `str` equality usable in `const` checks.
+pub(crate) const fn same_name(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < a.len() {
+        if a[index] != b[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Checks the fields of a struct that are only visible where the struct is
+/// defined: offsets and names must match its `abi_layouts!` entry, and the
+/// destructure lists every field without `..`.
+macro_rules! check_private_fields {
+    ($ty:ident { $($field:ident),+ $(,)? }) => {
+        const _: () = {
+            let fields = <$ty as $crate::layout::AbiType>::FIELDS;
+            let names = [$(stringify!($field)),+];
+            let offsets = [$(::core::mem::offset_of!($ty, $field)),+];
+            assert!(
+                fields.len() == names.len(),
+                concat!(stringify!($ty), " fields changed")
+            );
+            let mut index = 0;
+            while index < fields.len() {
+                assert!(
+                    $crate::layout::same_name(fields[index].name, names[index]),
+                    concat!(stringify!($ty), " field renamed")
+                );
+                assert!(
+                    fields[index].offset == offsets[index],
+                    concat!(stringify!($ty), " field moved")
+                );
+                index += 1;
+            }
+        };
+
+        const _: fn(&$ty) = |value| {
+            let $ty { $($field: _),+ } = value;
+        };
+    };
+}
+pub(crate) use check_private_fields;
+
+/// Field checks of one `abi_layouts!` entry; `private` entries are checked
+/// by `check_private_fields!` next to their struct instead.
+macro_rules! check_fields {
+    (private $ty:ident { $($field:ident @ $offset:literal: $field_ty:ty),+ }) => {};
+    ($ty:ident { $($field:ident @ $offset:literal: $field_ty:ty),+ }) => {
+        const _: () = {
+            $(
+                assert!(
+                    offset_of!($ty, $field) == $offset,
+                    concat!(stringify!($ty), ".", stringify!($field), " moved")
+                );
+            )+
+        };
+
+        // Every field is listed (no `..`) with its declared type.
+        const _: fn(&$ty) = |value| {
+            let $ty { $($field: _),+ } = value;
+            $(let _: &$field_ty = &value.$field;)+
+        };
+    };
+}
+
+/// Declares the expected layout of each ABI struct, checks it at compile
+/// time, and records it in `STRUCT_LAYOUTS`.
+macro_rules! abi_layouts {
+    ($(
+        $ty:ident => $c_name:literal, size $size:literal, align $align:literal $(, $private:ident)? {
+            $($field:ident @ $offset:literal: $field_ty:ty),+ $(,)?
+        }
+    )+) => {
+        $(
+            impl AbiType for $ty {
+                const C_TYPE: &'static str = concat!("struct ", $c_name);
+                const FIELDS: &'static [FieldLayout] = &[$(
+                    FieldLayout {
+                        name: stringify!($field),
+                        offset: $offset,
+                        size: size_of::<$field_ty>(),
+                        c_type: <$field_ty as AbiType>::C_TYPE,
+                        array_len: <$field_ty as AbiType>::ARRAY_LEN,
+                    },
+                )+];
+            }
+
+            const _: () = {
+                assert!(
+                    size_of::<$ty>() == $size,
+                    concat!(stringify!($ty), " size changed")
+                );
+                assert!(
+                    align_of::<$ty>() == $align,
+                    concat!(stringify!($ty), " alignment changed")
+                );
+                assert!(
+                    fields_fit(&[$(($offset, size_of::<$field_ty>())),+], $size),
+                    concat!(stringify!($ty), " fields overlap")
+                );
+            };
+
+            check_fields!($($private)? $ty { $($field @ $offset: $field_ty),+ });
+        )+
+
+        /// Every ABI struct, each listed after the structs it embeds.
+        pub static STRUCT_LAYOUTS: &[StructLayout] = &[$(
+            StructLayout {
+                rust_name: stringify!($ty),
+                c_name: $c_name,
+                size: $size,
+                align: $align,
+                fields: <$ty as AbiType>::FIELDS,
+            },
+        )+];
+    };
+}
+
+abi_layouts! {
+    Key => "hkv_key", size 258, align 2, private {
+        len @ 0: u16,
+        data @ 2: [u8; MAX_KEY_SIZE],
+    }
+    Value => "hkv_value", size 1026, align 2, private {
+        len @ 0: u16,
+        data @ 2: [u8; MAX_VALUE_SIZE],
+    }
+    IoctlHeader => "hkv_ioctl_header", size 4, align 1 {
+        magic @ 0: u8,
+        version @ 1: u8,
+        command @ 2: u8,
+        reserved @ 3: u8,
+    }
+    ReadRequest => "hkv_read_request", size 264, align 2 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+    }
+    ReadRequestV1 => "hkv_read_request_v1", size 262, align 2 {
+        header @ 0: IoctlHeader,
+        key @ 4: Key,
+    }
+    ReadResponse => "hkv_read_response", size 1032, align 2 {
+        header @ 0: IoctlHeader,
+        status @ 4: u16,
+        value @ 6: Value,
+    }
+    PromoteRequest => "hkv_promote_request", size 1312, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+        value @ 264: Value,
+        version @ 1296: Version,
+        ttl @ 1304: Ttl,
+    }
+    PromoteResponse => "hkv_promote_response", size 8, align 2 {
+        header @ 0: IoctlHeader,
+        status @ 4: u16,
+        reserved @ 6: u16,
+    }
+    BatchPromoteDescriptor => "hkv_batch_promote_descriptor", size 40, align 8 {
+        key_ptr @ 0: u64,
+        value_ptr @ 8: u64,
+        key_len @ 16: u16,
+        reserved @ 18: u16,
+        value_len @ 20: u32,
+        version @ 24: Version,
+        ttl @ 32: Ttl,
+    }
+    BatchPromoteRequest => "hkv_batch_promote_request", size 24, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        count @ 6: u16,
+        entries_ptr @ 8: u64,
+        statuses_ptr @ 16: u64,
+    }
+    DemoteRequest => "hkv_demote_request", size 264, align 2 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+    }
+    InvalidateRequest => "hkv_invalidate_request", size 272, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+        version @ 264: Version,
+    }
+    CacheStats => "hkv_cache_stats", size 120, align 8 {
+        lookups @ 0: u64,
+        hits @ 8: u64,
+        misses @ 16: u64,
+        stale_hits @ 24: u64,
+        promotions @ 32: u64,
+        demotions @ 40: u64,
+        evictions @ 48: u64,
+        invalidations @ 56: u64,
+        used_bytes @ 64: u64,
+        max_bytes @ 72: u64,
+        entry_count @ 80: u64,
+        lock_contentions @ 88: u64,
+        rcu_grace_periods @ 96: u64,
+        tenant @ 104: u32,
+        tenant_count @ 108: u32,
+        budget_bytes @ 112: u64,
+    }
+    StatsRequest => "hkv_stats_request", size 8, align 2 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        reserved @ 6: u16,
+    }
+    StatsResponse => "hkv_stats_response", size 128, align 8 {
+        header @ 0: IoctlHeader,
+        status @ 4: u16,
+        reserved @ 6: u16,
+        stats @ 8: CacheStats,
+    }
+    ConfigRequest => "hkv_config_request", size 40, align 8 {
+        header @ 0: IoctlHeader,
+        max_stale_ms @ 4: u32,
+        max_bytes @ 8: u64,
+        max_entries @ 16: u64,
+        high_watermark @ 24: u32,
+        low_watermark @ 28: u32,
+        eviction_policy @ 32: u8,
+        admission_policy @ 33: u8,
+        hotness_estimator @ 34: u8,
+        tenant_policy @ 35: u8,
+        consistency_mode @ 36: u8,
+        reserved @ 37: u8,
+        consistency_tenant @ 38: u16,
+    }
+    FlushRequest => "hkv_flush_request", size 4, align 1 {
+        header @ 0: IoctlHeader,
+    }
+    TenantConfigRequest => "hkv_tenant_config_request", size 32, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        priority @ 6: u8,
+        reserved @ 7: u8,
+        weight @ 8: u32,
+        quota_bytes @ 16: u64,
+        min_guarantee_bytes @ 24: u64,
+    }
+    ReadExtRequest => "hkv_read_ext_request", size 280, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+        buf_ptr @ 264: u64,
+        buf_len @ 272: u32,
+        reserved @ 276: u32,
+    }
+    ReadExtResponse => "hkv_read_ext_response", size 12, align 4 {
+        header @ 0: IoctlHeader,
+        status @ 4: u16,
+        reserved @ 6: u16,
+        value_len @ 8: u32,
+    }
+    PromoteExtRequest => "hkv_promote_ext_request", size 296, align 8 {
+        header @ 0: IoctlHeader,
+        tenant @ 4: u16,
+        key @ 6: Key,
+        value_ptr @ 264: u64,
+        value_len @ 272: u32,
+        reserved @ 276: u32,
+        version @ 280: Version,
+        ttl @ 288: Ttl,
+    }
+    EntryMetadata => "hkv_entry_metadata", size 40, align 8 {
+        version @ 0: Version,
+        ttl @ 8: Ttl,
+        created_at @ 16: u64,
+        accessed_at @ 24: u64,
+        flags @ 32: EntryFlags,
+        key_len @ 34: u16,
+        value_len @ 36: u16,
+    }
+    Entry => "hkv_entry", size 1328, align 8 {
+        key @ 0: Key,
+        value @ 258: Value,
+        metadata @ 1288: EntryMetadata,
+    }
+    CacheEvent => "hkv_cache_event", size 272, align 8 {
+        magic @ 0: u8,
+        protocol @ 1: u8,
+        kind @ 2: u8,
+        reserved @ 3: u8,
+        tenant @ 4: u16,
+        key @ 6: Key,
+        version @ 264: Version,
+    }
+}
+
+/// Returns the layout of the Rust struct named `rust_name`.
+pub fn struct_layout(rust_name: &str) -> Option<&'static StructLayout> {
+    STRUCT_LAYOUTS
+        .iter()
+        .find(|layout| layout.rust_name == rust_name)
+}
+
+/// Generates the C header describing every ABI struct, the protocol limits,
+/// and the ioctl request numbers.
+pub fn c_header() -> String {
+    let mut out = String::new();
+    out.push_str(concat!(
+        "/* hybridkv_abi.h - HybridKV user/kernel ABI.\n",
+        " *\n",
+        " * Generated by hkv_common::layout::c_header(); do not edit.\n",
+        " */\n",
+        "#ifndef HYBRIDKV_ABI_H\n",
+        "#define HYBRIDKV_ABI_H\n",
+        "\n",
+        "#ifdef __KERNEL__\n",
+        "#include <linux/stddef.h>\n",
+        "#include <linux/types.h>\n",
+        "#else\n",
+        "#include <stddef.h>\n",
+        "#include <stdint.h>\n",
+        "#endif\n",
+        "\n",
+    ));
+
+    let _ = writeln!(out, "#define HKV_DEVICE_PATH \"{DEVICE_PATH}\"");
+    let _ = writeln!(out, "#define HKV_IOCTL_MAGIC {IOCTL_MAGIC:#04x}");
+    for (name, value) in [
+        ("HKV_PROTOCOL_VERSION", PROTOCOL_VERSION as usize),
+        ("HKV_MIN_PROTOCOL_VERSION", MIN_PROTOCOL_VERSION as usize),
+        ("HKV_STATUS_OK", STATUS_OK as usize),
+        ("HKV_MAX_KEY_SIZE", MAX_KEY_SIZE),
+        ("HKV_MAX_VALUE_SIZE", MAX_VALUE_SIZE),
+        ("HKV_MAX_EXT_VALUE_SIZE", MAX_EXT_VALUE_SIZE),
+        ("HKV_MAX_BATCH_SIZE", MAX_BATCH_SIZE),
+        ("HKV_CONSISTENCY_INHERIT", CONSISTENCY_INHERIT as usize),
+    ] {
+        let _ = writeln!(out, "#define {name} {value}");
+    }
+    out.push('\n');
+
+    for (name, c_type) in SCALAR_TYPEDEFS {
+        let _ = writeln!(out, "typedef {c_type} {name};");
+    }
+
+    for layout in STRUCT_LAYOUTS {
+        write_struct(&mut out, layout);
+    }
+
+    out.push('\n');
+    for command in (0..=u8::MAX).filter_map(IoctlCommand::from_u8) {
+        let _ = writeln!(
+            out,
+            "#define HKV_IOC_{} {:#010X}u",
+            command.name(),
+            command.request_number()
+        );
+    }
+    out.push_str("\n#endif /* HYBRIDKV_ABI_H */\n");
+    out
+}
+
+/// Writes one struct definition and its `_Static_assert`s.
+fn write_struct(out: &mut String, layout: &StructLayout) {
+    let _ = writeln!(
+        out,
+        "\n/* {} */\nstruct {} {{",
+        layout.rust_name, layout.c_name
+    );
+    let mut end = 0;
+    let mut pads = 0;
+    for field in layout.fields {
+        if field.offset > end {
+            write_pad(out, &mut pads, field.offset - end);
+        }
+        match field.array_len {
+            Some(len) => {
+                let _ = writeln!(out, "\t{} {}[{len}];", field.c_type, field.name);
+            }
+            None => {
+                let _ = writeln!(out, "\t{} {};", field.c_type, field.name);
+            }
+        }
+        end = field.offset + field.size;
+    }
+    if layout.size > end {
+        write_pad(out, &mut pads, layout.size - end);
+    }
+    out.push_str("};\n");
+
+    let _ = writeln!(
+        out,
+        "_Static_assert(sizeof(struct {0}) == {1}, \"{0} size\");",
+        layout.c_name, layout.size
+    );
+    let _ = writeln!(
+        out,
+        "_Static_assert(_Alignof(struct {0}) == {1}, \"{0} alignment\");",
+        layout.c_name, layout.align
+    );
+    for field in layout.fields {
+        let _ = writeln!(
+            out,
+            "_Static_assert(offsetof(struct {0}, {1}) == {2}, \"{0}.{1} offset\");",
+            layout.c_name, field.name, field.offset
+        );
+    }
+}
+
+fn write_pad(out: &mut String, pads: &mut usize, len: usize) {
+    let _ = writeln!(out, "\tuint8_t _pad{pads}[{len}];");
+    *pads += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_ioctl_argument_is_described() {
+        let arg_structs: &[(IoctlCommand, &[&str])] = &[
+            (IoctlCommand::Read, &["ReadRequest", "ReadResponse"]),
+            (
+                IoctlCommand::Promote,
+                &["PromoteRequest", "PromoteResponse"],
+            ),
+            (IoctlCommand::BatchPromote, &["BatchPromoteRequest"]),
+            (IoctlCommand::Demote, &["DemoteRequest"]),
+            (IoctlCommand::Invalidate, &["InvalidateRequest"]),
+            (IoctlCommand::Stats, &["StatsRequest", "StatsResponse"]),
+            (IoctlCommand::Config, &["ConfigRequest"]),
+            (IoctlCommand::Flush, &["FlushRequest"]),
+            (IoctlCommand::TenantConfig, &["TenantConfigRequest"]),
+            (
+                IoctlCommand::ReadExt,
+                &["ReadExtRequest", "ReadExtResponse"],
+            ),
+            (IoctlCommand::PromoteExt, &["PromoteExtRequest"]),
+        ];
+        for (command, structs) in arg_structs {
+            let largest = structs
+                .iter()
+                .map(|name| struct_layout(name).expect(name).size)
+                .max();
+            assert_eq!(largest, Some(command.arg_size()), "{command}");
+        }
+        assert_eq!(
+            struct_layout("BatchPromoteDescriptor").map(|layout| layout.size),
+            Some(size_of::<BatchPromoteDescriptor>())
+        );
+    }
+
+    #[test]
+    fn test_embedded_structs_come_first() {
+        for (index, layout) in STRUCT_LAYOUTS.iter().enumerate() {
+            for field in layout.fields {
+                if let Some(tag) = field.c_type.strip_prefix("struct ") {
+                    let position = STRUCT_LAYOUTS.iter().position(|other| other.c_name == tag);
+                    assert!(position < Some(index), "{} before {tag}", layout.c_name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_header_spells_out_padding_and_numbers() {
+        let header = c_header();
+        assert!(header.contains(
+            "struct hkv_promote_request {\n\tstruct hkv_ioctl_header header;\n\tuint16_t tenant;\n\tstruct hkv_key key;\n\tstruct hkv_value value;\n\tuint8_t _pad0[6];\n\thkv_version_t version;\n"
+        ));
+        assert!(header.contains(
+            "_Static_assert(offsetof(struct hkv_stats_response, stats) == 8, \"hkv_stats_response.stats offset\");"
+        ));
+        assert!(header.contains("\tuint8_t data[256];\n"));
+        assert!(header.contains("#define HKV_IOC_READ 0xC4084800u\n"));
+        assert!(header.contains("#define HKV_IOC_PROMOTE_EXT 0x4128480Au\n"));
+        assert!(header.contains("#define HKV_IOCTL_MAGIC 0x48\n"));
+    }
+}
@@ -3,12 +3,20 @@
 // This crate defines the ioctl interface for user/kernel communication
 
 pub mod error;
+pub mod event;
+pub mod eviction;
 pub mod ioctl;
+pub mod layout;
+pub mod policy;
 pub mod protocol;
+pub mod sketch;
 pub mod types;
 
 // Re-export for convenience
 pub use error::*;
+pub use event::*;
 pub use ioctl::*;
+pub use policy::*;
 pub use protocol::*;
+pub use sketch::*;
 pub use types::*;
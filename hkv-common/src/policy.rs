@@ -0,0 +1,177 @@
+//! # Policy Identifiers
+//!
+//! Stable numeric IDs for the kernel cache's pluggable policies and
+//! consistency modes, carried in `ConfigRequest` to select or swap them at
+//! runtime.
+//!
+//! ## Design Principles
+//!
+//! 1. **Zero Means Keep**: `POLICY_KEEP` (0) leaves the active policy alone,
+//!    so a zeroed config request only changes limits.
+//! 2. **Stable Numbers**: IDs are part of the ABI; new policies get new IDs
+//!    and existing ones are never renumbered.
+//! 3. **Open Set**: IDs from `POLICY_CUSTOM_BASE` up are left for policies
+//!    registered at runtime, outside the built-in enums.
+
+/// Policy ID meaning "leave the active policy unchanged".
+pub const POLICY_KEEP: u8 = 0;
+
+/// First ID available for runtime-registered (non built-in) policies.
+pub const POLICY_CUSTOM_BASE: u8 = 128;
+
+/// Consistency mode value that drops a tenant's override, so the tenant
+/// follows the cache-wide mode again.
+pub const CONSISTENCY_INHERIT: u8 = u8::MAX;
+
+macro_rules! policy_ids {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $value:literal => $label:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(u8)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant = $value,)+
+        }
+
+        impl $name {
+            /// Every built-in variant, in ID order.
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            /// Returns the ABI number of the policy.
+            pub const fn as_u8(self) -> u8 {
+                self as u8
+            }
+
+            /// Converts an ABI number into a built-in policy.
+            pub const fn from_u8(value: u8) -> Option<Self> {
+                match value {
+                    $($value => Some($name::$variant),)+
+                    _ => None,
+                }
+            }
+
+            /// Returns the configuration name of the policy.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $label,)+
+                }
+            }
+
+            /// Parses a configuration name (case-insensitive).
+            pub fn parse(input: &str) -> Option<Self> {
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|policy| policy.name().eq_ignore_ascii_case(input))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+}
+
+policy_ids! {
+    /// Built-in eviction policies.
+    EvictionPolicyId {
+        /// Least recently used.
+        Lru = 1 => "lru",
+        /// Least frequently used.
+        Lfu = 2 => "lfu",
+        /// Segmented LRU (probation + protected).
+        Slru = 3 => "slru",
+        /// 2Q (FIFO admission queue + LRU main queue + ghost list).
+        TwoQ = 4 => "2q",
+        /// First in, first out.
+        Fifo = 5 => "fifo",
+    }
+}
+
+policy_ids! {
+    /// Built-in admission policies, consulted when a new key needs room.
+    AdmissionPolicyId {
+        /// Admit every candidate.
+        Always = 1 => "always",
+        /// Admit candidates whose estimated hotness reaches a threshold.
+        Threshold = 2 => "threshold",
+        /// Admit candidates hotter than the eviction victim.
+        TinyLfu = 3 => "tinylfu",
+        /// Compare hotness per byte against the victim.
+        SizeAware = 4 => "size-aware",
+    }
+}
+
+policy_ids! {
+    /// Built-in hotness estimators.
+    HotnessEstimatorId {
+        /// Count-Min Sketch.
+        CountMinSketch = 1 => "cms",
+        /// Counts one in N accesses and scales up.
+        Sampling = 2 => "sampling",
+        /// Exact counters for a bounded hot tier, sketch for the rest.
+        TieredCounters = 3 => "tiered",
+    }
+}
+
+policy_ids! {
+    /// Built-in tenant budget policies.
+    TenantBudgetPolicyId {
+        /// Every tenant is capped at its own quota.
+        HardQuota = 1 => "hard-quota",
+        /// Minimum guarantees, then the rest split by weight.
+        Proportional = 2 => "proportional",
+        /// Minimum guarantees, then demand served in priority order.
+        PriorityBased = 3 => "priority",
+    }
+}
+
+policy_ids! {
+    /// What a read of a stale (invalidated) entry returns.
+    ConsistencyModeId {
+        /// Stale entries are misses (strict invalidation).
+        Strict = 1 => "strict",
+        /// Stale values are served until `max_stale_ms` after invalidation.
+        BoundedStaleness = 2 => "bounded-staleness",
+        /// Stale values are served (within `max_stale_ms` when non-zero) and
+        /// a refresh of the key is queued for user space.
+        AsyncRefresh = 3 => "async-refresh",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip_and_skip_reserved_values() {
+        for policy in EvictionPolicyId::ALL {
+            assert_eq!(EvictionPolicyId::from_u8(policy.as_u8()), Some(*policy));
+            assert_eq!(EvictionPolicyId::parse(policy.name()), Some(*policy));
+        }
+        for policy in AdmissionPolicyId::ALL {
+            assert_eq!(AdmissionPolicyId::from_u8(policy.as_u8()), Some(*policy));
+        }
+        for policy in HotnessEstimatorId::ALL {
+            assert_eq!(HotnessEstimatorId::parse(policy.name()), Some(*policy));
+        }
+        for policy in TenantBudgetPolicyId::ALL {
+            assert_ne!(policy.as_u8(), POLICY_KEEP);
+            assert!(policy.as_u8() < POLICY_CUSTOM_BASE);
+        }
+        for mode in ConsistencyModeId::ALL {
+            assert_eq!(ConsistencyModeId::parse(mode.name()), Some(*mode));
+        }
+        assert_eq!(EvictionPolicyId::from_u8(POLICY_KEEP), None);
+        assert_eq!(
+            AdmissionPolicyId::parse("TinyLFU"),
+            Some(AdmissionPolicyId::TinyLfu)
+        );
+    }
+}
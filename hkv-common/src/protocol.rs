@@ -12,7 +12,8 @@
 //!
 //! - Request types carry the command in `IoctlHeader` so the kernel can validate
 //!   metadata before touching payloads.
-//! - Response types return `STATUS_OK` on success or `HkvError::code()` on failure.
+//! - Response types return `STATUS_OK` on success or `HkvError::code()` on failure;
+//!   `decode_status` (or each response's `result()`) turns that back into `HkvResult`.
 //! - Fixed-size buffers keep the ABI stable even when payloads are partially filled.
 //!
 //! ## Memory Layout Example
@@ -25,7 +26,12 @@
 //! | 1B     | 1B      | 1B       | 1B       |
 //! +--------+---------+----------+----------+
 //!
-//! ReadRequest (262 bytes total):
+//! ReadRequest (264 bytes total):
+//! +------------+-----------+---------+
+//! | header:4B  | tenant:2B | key:258B|
+//! +------------+-----------+---------+
+//!
+//! ReadRequestV1 (262 bytes total, protocol version 1):
 //! +------------+---------+
 //! | header:4B  | key:258B|
 //! +------------+---------+
@@ -35,76 +41,168 @@
 //! | header:4B  | status:2B | value:1026B |
 //! +------------+-----------+-------------+
 //!
-//! PromoteRequest (1304 bytes total):
-//! +------------+---------+-----------+-----------+--------+
-//! | header:4B  | key:258B| value:1026B| version:8B| ttl:8B |
-//! +------------+---------+-----------+-----------+--------+
+//! PromoteRequest (1312 bytes total):
+//! +------------+-----------+---------+------------+--------+
+//! | header:4B  | tenant:2B | key:258B| value:1026B| pad:6B |
+//! +------------+-----------+---------+------------+--------+
+//! | version:8B | ttl:8B    |
+//! +------------+-----------+
 //!
 //! PromoteResponse (8 bytes total):
 //! +------------+-----------+-------------+
 //! | header:4B  | status:2B | reserved:2B |
 //! +------------+-----------+-------------+
 //!
-//! BatchPromoteRequest (1304008 bytes total):
-//! +------------+----------+------------+-----------------------+
-//! | header:4B  | count:2B | reserved:2B| entries:1304000B      |
-//! +------------+----------+------------+-----------------------+
+//! BatchPromoteRequest (24 bytes total):
+//! +------------+-----------+----------+----------------+-----------------+
+//! | header:4B  | tenant:2B | count:2B | entries_ptr:8B | statuses_ptr:8B |
+//! +------------+-----------+----------+----------------+-----------------+
 //!
-//! BatchPromoteResponse (134 bytes total):
-//! +------------+----------+------------+-----------------------+
-//! | header:4B  | count:2B | reserved:2B| results:125B          |
-//! +------------+----------+------------+-----------------------+
+//! BatchPromoteDescriptor (40 bytes, `count` of them at entries_ptr):
+//! +------------+--------------+-----------+-------------+--------------+
+//! | key_ptr:8B | value_ptr:8B | key_len:2B| reserved:2B | value_len:4B |
+//! +------------+--------------+-----------+-------------+--------------+
+//! | version:8B | ttl:8B       |
+//! +------------+--------------+
 //!
-//! DemoteRequest (262 bytes total):
-//! +------------+---------+
-//! | header:4B  | key:258B|
-//! +------------+---------+
+//! Batch statuses (`count` x u16 at statuses_ptr, written by the kernel)
+//!
+//! DemoteRequest (264 bytes total):
+//! +------------+-----------+---------+
+//! | header:4B  | tenant:2B | key:258B|
+//! +------------+-----------+---------+
 //!
 //! InvalidateRequest (272 bytes total):
-//! +------------+---------+-----------+
-//! | header:4B  | key:258B| pad:2B    |
-//! +------------+---------+-----------+
+//! +------------+-----------+---------+
+//! | header:4B  | tenant:2B | key:258B|
+//! +------------+-----------+---------+
 //! | version:8B                       |
 //! +----------------------------------+
 //!
-//! StatsRequest (4 bytes total):
-//! +------------+
-//! | header:4B  |
-//! +------------+
+//! StatsRequest (8 bytes total):
+//! +------------+-----------+-------------+
+//! | header:4B  | tenant:2B | reserved:2B |
+//! +------------+-----------+-------------+
 //!
-//! StatsResponse (112 bytes total):
+//! StatsResponse (128 bytes total):
 //! +------------+-----------+-------------+-------------------+
-//! | header:4B  | status:2B | reserved:2B | stats:104B        |
+//! | header:4B  | status:2B | reserved:2B | stats:120B        |
 //! +------------+-----------+-------------+-------------------+
 //!
 //! ConfigRequest (40 bytes total):
-//! +------------+-------------+-------------+-----------+-----------+
-//! | header:4B  | pad:4B      | max_bytes:8B| max_entries:8B        |
-//! +------------+-------------+-------------+-----------+-----------+
-//! | high:4B    | low:4B      | reserved:8B                           |
-//! +------------+-------------+---------------------------------------+
+//! +------------+-----------------+-------------+----------------+
+//! | header:4B  | max_stale_ms:4B | max_bytes:8B| max_entries:8B |
+//! +------------+-----------------+-------------+----------------+
+//! | high:4B    | low:4B      | evict:1B | admit:1B | hot:1B | tenant:1B |
+//! +------------+-------------+----------+----------+--------+----------+
+//! | consistency:1B | reserved:1B | consistency_tenant:2B |
+//! +----------------+-------------+-----------------------+
 //!
 //! FlushRequest (4 bytes total):
 //! +------------+
 //! | header:4B  |
 //! +------------+
+//!
+//! TenantConfigRequest (32 bytes total):
+//! +------------+-----------+-------------+-------------+-----------+--------+
+//! | header:4B  | tenant:2B | priority:1B | reserved:1B | weight:4B | pad:4B |
+//! +------------+-----------+-------------+-------------+-----------+--------+
+//! | quota_bytes:8B         | min_guarantee_bytes:8B                          |
+//! +------------------------+-------------------------------------------------+
+//!
+//! ReadExtRequest (280 bytes total, protocol version 2):
+//! +------------+-----------+---------+
+//! | header:4B  | tenant:2B | key:258B|
+//! +------------+-----------+---------+
+//! | buf_ptr:8B | buf_len:4B| reserved:4B |
+//! +------------+-----------+-------------+
+//!
+//! ReadExtResponse (12 bytes total, protocol version 2):
+//! +------------+-----------+-------------+--------------+
+//! | header:4B  | status:2B | reserved:2B | value_len:4B |
+//! +------------+-----------+-------------+--------------+
+//!
+//! PromoteExtRequest (296 bytes total, protocol version 2):
+//! +------------+-----------+---------+
+//! | header:4B  | tenant:2B | key:258B|
+//! +------------+-----------+---------+
+//! | value_ptr:8B | value_len:4B | reserved:4B |
+//! +--------------+--------------+-------------+
+//! | version:8B   | ttl:8B       |
+//! +--------------+--------------+
 //! ```
+//!
+//! These sizes and offsets are enforced at compile time by `crate::layout`,
+//! which also generates the matching C header (`include/hybridkv_abi.h`).
+//!
+//! ## Version Negotiation
+//!
+//! Headers carry `PROTOCOL_VERSION`. The kernel accepts any version from the
+//! command's `min_version` up to its own and answers `VersionMismatch`
+//! otherwise. A newer client that gets `VersionMismatch` retries with the
+//! previous version and stops using commands that version lacks.
+//!
+//! Version 2 changed the layout of most version 1 requests (tenant IDs, the
+//! scatter-list batch, the policy selectors), so those commands require it.
+//! `CMD_READ` keeps its version 1 layout as `ReadRequestV1`, which shares the
+//! version 2 request number, so v2 clients still read inline values from v1
+//! modules and v1 clients still read from v2 modules.
 
+use crate::error::{HkvError, HkvResult};
 use crate::ioctl::{IoctlCommand, IOCTL_MAGIC};
-use crate::types::{Key, Ttl, Value, Version};
+use crate::policy::{
+    AdmissionPolicyId, ConsistencyModeId, EvictionPolicyId, HotnessEstimatorId,
+    TenantBudgetPolicyId, CONSISTENCY_INHERIT, POLICY_KEEP,
+};
+use crate::types::{Key, Ttl, Value, Version, MAX_EXT_VALUE_SIZE, MAX_KEY_SIZE};
 
 /// Protocol version for user/kernel ABI compatibility.
-pub const PROTOCOL_VERSION: u8 = 1;
+///
+/// Version 2 adds the user-pointer commands `CMD_READ_EXT` and
+/// `CMD_PROMOTE_EXT` for values up to `MAX_EXT_VALUE_SIZE`, replaces the
+/// fixed-array `CMD_BATCH_PROMOTE` payload with a scatter-list, adds the
+/// policy selectors to `ConfigRequest`, and adds tenant IDs to the read,
+/// promote, demote, batch and stats requests (growing `CacheStats`).
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// Oldest protocol version still accepted for the version 1 commands.
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
 
 /// Status code indicating success in ioctl responses.
 pub const STATUS_OK: u16 = 0;
 
+/// Encodes a result as a response status: `STATUS_OK` or the error's code.
+pub fn status_code<T>(result: &HkvResult<T>) -> u16 {
+    match result {
+        Ok(_) => STATUS_OK,
+        Err(err) => err.code(),
+    }
+}
+
+/// Decodes a response status written by the kernel.
+///
+/// # Errors
+/// Returns the status's `HkvError`, or `HkvError::ProtocolViolation` for a
+/// code this build does not know.
+pub fn decode_status(status: u16) -> HkvResult<()> {
+    if status == STATUS_OK {
+        return Ok(());
+    }
+    Err(HkvError::from_code(status).unwrap_or(HkvError::ProtocolViolation))
+}
+
+/// Tenant that requests address unless they select another one.
+///
+/// The default tenant always exists; other tenants are registered with
+/// `TenantConfigRequest` before use.
+pub const DEFAULT_TENANT: u16 = 0;
+
+/// `StatsRequest` tenant selecting cache-wide statistics.
+pub const ALL_TENANTS: u16 = u16::MAX;
+
 /// Maximum number of entries in a batch promote request.
 pub const MAX_BATCH_SIZE: usize = 1000;
 
-/// Result bitmap size for batch responses (1 bit per entry).
-pub const BATCH_RESULT_BYTES: usize = (MAX_BATCH_SIZE + 7) / 8;
-
 /// Common header prepended to ioctl request/response payloads.
 ///
 /// This header is `repr(C)` to preserve C ABI layout for kernel interop.
@@ -131,6 +229,14 @@ impl IoctlHeader {
             reserved: 0,
         }
     }
+
+    /// Builds a header that speaks an older (negotiated) protocol version.
+    pub const fn with_version(command: IoctlCommand, version: u8) -> Self {
+        IoctlHeader {
+            version,
+            ..Self::new(command)
+        }
+    }
 }
 
 /// Read request payload for a cache lookup.
@@ -144,15 +250,50 @@ impl IoctlHeader {
 pub struct ReadRequest {
     /// Common ioctl header (command must be READ).
     pub header: IoctlHeader,
+    /// Tenant whose cache partition is searched.
+    pub tenant: u16,
     /// Lookup key (length-prefixed, fixed-capacity buffer).
     pub key: Key,
 }
 
 impl ReadRequest {
-    /// Builds a read request for the provided key.
+    /// Builds a read request for the provided key in the default tenant.
     pub fn new(key: Key) -> Self {
         ReadRequest {
             header: IoctlHeader::new(IoctlCommand::Read),
+            tenant: DEFAULT_TENANT,
+            key,
+        }
+    }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
+}
+
+/// Version 1 read request, from before tenants.
+///
+/// A module reads the header first and, for a version 1 `CMD_READ`, takes
+/// the argument as this layout and searches the default tenant.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadRequestV1 {
+    /// Common ioctl header (command must be READ, version must be 1).
+    pub header: IoctlHeader,
+    /// Lookup key (length-prefixed, fixed-capacity buffer).
+    pub key: Key,
+}
+
+impl ReadRequestV1 {
+    /// Protocol version whose `CMD_READ` uses this layout.
+    pub const VERSION: u8 = 1;
+
+    /// Builds a version 1 read request for the provided key.
+    pub fn new(key: Key) -> Self {
+        ReadRequestV1 {
+            header: IoctlHeader::with_version(IoctlCommand::Read, Self::VERSION),
             key,
         }
     }
@@ -184,6 +325,11 @@ impl ReadResponse {
             value,
         }
     }
+
+    /// Returns the value, or the decoded error status.
+    pub fn result(&self) -> HkvResult<&Value> {
+        decode_status(self.status).map(|()| &self.value)
+    }
 }
 
 /// Promote request payload for inserting a single entry into the kernel cache.
@@ -198,6 +344,8 @@ impl ReadResponse {
 pub struct PromoteRequest {
     /// Common ioctl header (command must be PROMOTE).
     pub header: IoctlHeader,
+    /// Tenant the entry is charged to.
+    pub tenant: u16,
     /// Entry key to insert.
     pub key: Key,
     /// Entry value to insert.
@@ -209,16 +357,24 @@ pub struct PromoteRequest {
 }
 
 impl PromoteRequest {
-    /// Builds a promote request for the provided entry data.
+    /// Builds a promote request for the provided entry data in the default
+    /// tenant.
     pub fn new(key: Key, value: Value, version: Version, ttl: Ttl) -> Self {
         PromoteRequest {
             header: IoctlHeader::new(IoctlCommand::Promote),
+            tenant: DEFAULT_TENANT,
             key,
             value,
             version,
             ttl,
         }
     }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
 }
 
 /// Promote response payload indicating success or failure.
@@ -246,96 +402,279 @@ impl PromoteResponse {
             reserved: 0,
         }
     }
+
+    /// Returns the decoded status.
+    pub fn result(&self) -> HkvResult<()> {
+        decode_status(self.status)
+    }
 }
 
-/// Single batch promote entry (payload-only).
+/// One entry of a scatter-list batch promotion.
 ///
-/// This keeps the batch payload compact by avoiding per-entry headers.
+/// Key and value bytes stay in user memory; the descriptor only points at
+/// them, so an entry costs 40 bytes instead of two fixed-capacity buffers.
+///
+/// Use: Element of the descriptor array a `BatchPromoteRequest` points at.
 #[repr(C)]
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BatchPromoteEntry {
-    /// Entry key to insert.
-    pub key: Key,
-    /// Entry value to insert.
-    pub value: Value,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchPromoteDescriptor {
+    /// User address of the key bytes.
+    pub key_ptr: u64,
+    /// User address of the value bytes.
+    pub value_ptr: u64,
+    /// Key length in bytes (<= MAX_KEY_SIZE).
+    pub key_len: u16,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u16,
+    /// Value length in bytes (<= MAX_EXT_VALUE_SIZE).
+    pub value_len: u32,
     /// Version to associate with the entry.
     pub version: Version,
     /// Absolute expiration timestamp for the entry.
     pub ttl: Ttl,
 }
 
-impl BatchPromoteEntry {
-    /// Builds a batch entry for the provided data.
-    pub fn new(key: Key, value: Value, version: Version, ttl: Ttl) -> Self {
-        BatchPromoteEntry {
-            key,
-            value,
-            version,
-            ttl,
-        }
-    }
-}
-
 /// Batch promote request payload for inserting multiple entries.
 ///
-/// Uses the header+payload pattern to amortize syscall overhead while
-/// preserving a flat, FFI-friendly layout.
+/// Uses a scatter-list: the request carries only a count and two user
+/// addresses, one for `count` descriptors and one for `count` `u16`
+/// statuses the kernel fills with `STATUS_OK` or an `HkvError::code()`.
+/// Build it with `BatchPromoteBuilder`, which owns all three buffers.
 ///
 /// Use: Issued by user space to promote multiple entries in one ioctl call.
 #[repr(C)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BatchPromoteRequest {
     /// Common ioctl header (command must be BATCH_PROMOTE).
     pub header: IoctlHeader,
-    /// Number of valid entries in the batch (<= MAX_BATCH_SIZE).
+    /// Tenant every entry of the batch is charged to.
+    pub tenant: u16,
+    /// Number of descriptors and statuses (<= MAX_BATCH_SIZE).
     pub count: u16,
-    /// Reserved for alignment/future flags; must be zero.
-    pub reserved: u16,
-    /// Fixed-capacity entry array (only first `count` are valid).
-    pub entries: [BatchPromoteEntry; MAX_BATCH_SIZE],
+    /// User address of the `BatchPromoteDescriptor` array.
+    pub entries_ptr: u64,
+    /// User address of the per-entry `u16` status array.
+    pub statuses_ptr: u64,
 }
 
-impl BatchPromoteRequest {
-    /// Builds a batch promote request for the provided entries.
-    pub fn new(entries: [BatchPromoteEntry; MAX_BATCH_SIZE], count: u16) -> Self {
-        debug_assert!(count as usize <= MAX_BATCH_SIZE);
-        BatchPromoteRequest {
-            header: IoctlHeader::new(IoctlCommand::BatchPromote),
-            count,
+/// Safe builder for a `BatchPromoteRequest` and the memory it points into.
+///
+/// Key and value bytes are copied into one owned arena as entries are
+/// pushed; `prepare` fixes up the descriptor pointers once the arena stops
+/// growing, so the request can never outlive or dangle into its buffers.
+///
+/// # Examples
+/// ```rust
+/// use hkv_common::{BatchPromoteBuilder, Ttl, Version};
+///
+/// let mut batch = BatchPromoteBuilder::new();
+/// batch.push(b"user:1", b"alice", Version::new(3), Ttl::INFINITE).unwrap();
+/// let (request, memory) = batch.prepare();
+/// assert_eq!(request.count, 1);
+/// assert_eq!(memory.descriptors(&request).unwrap().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BatchPromoteBuilder {
+    tenant: u16,
+    descriptors: Vec<BatchPromoteDescriptor>,
+    /// Arena offsets of each entry's key and value.
+    offsets: Vec<(usize, usize)>,
+    data: Vec<u8>,
+    statuses: Vec<u16>,
+}
+
+impl BatchPromoteBuilder {
+    /// Creates an empty batch for the default tenant.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Addresses the batch to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// Appends an entry and returns its index.
+    ///
+    /// # Errors
+    /// Returns `HkvError::KeyTooLong` or `HkvError::ValueTooLong` past the
+    /// ABI limits, and `HkvError::CapacityExceeded` once the batch holds
+    /// `MAX_BATCH_SIZE` entries.
+    pub fn push(
+        &mut self,
+        key: &[u8],
+        value: &[u8],
+        version: Version,
+        ttl: Ttl,
+    ) -> HkvResult<usize> {
+        if key.len() > MAX_KEY_SIZE {
+            return Err(HkvError::KeyTooLong);
+        }
+        if value.len() > MAX_EXT_VALUE_SIZE {
+            return Err(HkvError::ValueTooLong);
+        }
+        if self.descriptors.len() >= MAX_BATCH_SIZE {
+            return Err(HkvError::CapacityExceeded);
+        }
+        let key_offset = self.data.len();
+        self.data.extend_from_slice(key);
+        let value_offset = self.data.len();
+        self.data.extend_from_slice(value);
+        self.offsets.push((key_offset, value_offset));
+        self.descriptors.push(BatchPromoteDescriptor {
+            key_ptr: 0,
+            value_ptr: 0,
+            key_len: key.len() as u16,
             reserved: 0,
-            entries,
+            value_len: value.len() as u32,
+            version,
+            ttl,
+        });
+        Ok(self.descriptors.len() - 1)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    /// Returns true if no entry was pushed.
+    pub fn is_empty(&self) -> bool {
+        self.descriptors.is_empty()
+    }
+
+    /// Returns the descriptor of entry `index`.
+    pub fn descriptor(&self, index: usize) -> Option<&BatchPromoteDescriptor> {
+        self.descriptors.get(index)
+    }
+
+    /// Builds the request and lends out the memory it points into.
+    ///
+    /// Every status is reset to `HkvError::InternalError` so an entry the
+    /// kernel never reached does not read as promoted.
+    pub fn prepare(&mut self) -> (BatchPromoteRequest, BatchPromoteMemory<'_>) {
+        let base = self.data.as_ptr() as u64;
+        for (descriptor, &(key, value)) in self.descriptors.iter_mut().zip(&self.offsets) {
+            descriptor.key_ptr = base + key as u64;
+            descriptor.value_ptr = base + value as u64;
+        }
+        self.statuses.clear();
+        self.statuses
+            .resize(self.descriptors.len(), HkvError::InternalError.code());
+
+        let request = BatchPromoteRequest {
+            header: IoctlHeader::new(IoctlCommand::BatchPromote),
+            tenant: self.tenant,
+            count: self.descriptors.len() as u16,
+            entries_ptr: self.descriptors.as_ptr() as u64,
+            statuses_ptr: self.statuses.as_ptr() as u64,
+        };
+        let memory = BatchPromoteMemory {
+            descriptors: &self.descriptors,
+            data: &self.data,
+            statuses: &mut self.statuses,
+        };
+        (request, memory)
+    }
+
+    /// Returns the per-entry statuses written by the last call.
+    pub fn statuses(&self) -> &[u16] {
+        &self.statuses
+    }
+
+    /// Returns the outcome of entry `index` from the last call.
+    ///
+    /// # Errors
+    /// Returns the entry's decoded error, or `HkvError::InvalidInput` for an
+    /// index past the batch.
+    pub fn status(&self, index: usize) -> HkvResult<()> {
+        match self.statuses.get(index) {
+            Some(&status) => decode_status(status),
+            None => Err(HkvError::InvalidInput),
         }
     }
+
+    /// Returns true if entry `index` was promoted by the last call.
+    pub fn succeeded(&self, index: usize) -> bool {
+        self.status(index).is_ok()
+    }
 }
 
-/// Batch promote response payload with per-entry success bitmap.
+/// User memory behind a prepared `BatchPromoteRequest`.
 ///
-/// Uses a bitmap pattern: bit=1 indicates success, bit=0 indicates failure.
-///
-/// Use: Returned by the kernel to report batch promotion results.
-#[repr(C)]
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BatchPromoteResponse {
-    /// Common ioctl header (command must be BATCH_PROMOTE).
-    pub header: IoctlHeader,
-    /// Number of valid results (matches request count).
-    pub count: u16,
-    /// Reserved for alignment/future flags; must be zero.
-    pub reserved: u16,
-    /// Success bitmap (1 bit per entry, LSB-first within each byte).
-    pub results: [u8; BATCH_RESULT_BYTES],
+/// Handlers that cannot dereference user addresses (the user-space kernel
+/// simulation, test backends) resolve every pointer in the request against
+/// these buffers instead; a pointer outside them fails with
+/// `HkvError::InvalidInput`, as `copy_from_user` fails with `EFAULT`.
+#[derive(Debug)]
+pub struct BatchPromoteMemory<'a> {
+    descriptors: &'a [BatchPromoteDescriptor],
+    data: &'a [u8],
+    statuses: &'a mut [u16],
 }
 
-impl BatchPromoteResponse {
-    /// Builds an empty batch promote response for the given count.
-    pub fn new(count: u16) -> Self {
-        debug_assert!(count as usize <= MAX_BATCH_SIZE);
-        BatchPromoteResponse {
-            header: IoctlHeader::new(IoctlCommand::BatchPromote),
-            count,
-            reserved: 0,
-            results: [0u8; BATCH_RESULT_BYTES],
+impl<'a> BatchPromoteMemory<'a> {
+    /// Returns the request's descriptors after checking that both of its
+    /// arrays are the ones this memory maps.
+    ///
+    /// # Errors
+    /// Returns `HkvError::InvalidInput` for a count above `MAX_BATCH_SIZE`
+    /// or an array address or length that does not match.
+    pub fn descriptors(
+        &self,
+        request: &BatchPromoteRequest,
+    ) -> HkvResult<&'a [BatchPromoteDescriptor]> {
+        let count = request.count as usize;
+        if count > MAX_BATCH_SIZE
+            || request.entries_ptr != self.descriptors.as_ptr() as u64
+            || count > self.descriptors.len()
+            || request.statuses_ptr != self.statuses.as_ptr() as u64
+            || count > self.statuses.len()
+        {
+            return Err(HkvError::InvalidInput);
+        }
+        Ok(&self.descriptors[..count])
+    }
+
+    /// Returns the key bytes `descriptor` points at.
+    ///
+    /// # Errors
+    /// Returns `HkvError::KeyTooLong` past `MAX_KEY_SIZE` and
+    /// `HkvError::InvalidInput` for a range outside the mapped data.
+    pub fn key(&self, descriptor: &BatchPromoteDescriptor) -> HkvResult<&'a [u8]> {
+        if descriptor.key_len as usize > MAX_KEY_SIZE {
+            return Err(HkvError::KeyTooLong);
         }
+        self.resolve(descriptor.key_ptr, descriptor.key_len as usize)
+    }
+
+    /// Returns the value bytes `descriptor` points at.
+    ///
+    /// # Errors
+    /// Returns `HkvError::ValueTooLong` past `MAX_EXT_VALUE_SIZE` and
+    /// `HkvError::InvalidInput` for a range outside the mapped data.
+    pub fn value(&self, descriptor: &BatchPromoteDescriptor) -> HkvResult<&'a [u8]> {
+        if descriptor.value_len as usize > MAX_EXT_VALUE_SIZE {
+            return Err(HkvError::ValueTooLong);
+        }
+        self.resolve(descriptor.value_ptr, descriptor.value_len as usize)
+    }
+
+    /// Writes the outcome of entry `index`.
+    pub fn set_status(&mut self, index: usize, result: HkvResult<()>) {
+        if let Some(status) = self.statuses.get_mut(index) {
+            *status = status_code(&result);
+        }
+    }
+
+    fn resolve(&self, ptr: u64, len: usize) -> HkvResult<&'a [u8]> {
+        let offset = ptr
+            .checked_sub(self.data.as_ptr() as u64)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .ok_or(HkvError::InvalidInput)?;
+        let end = offset.checked_add(len).ok_or(HkvError::InvalidInput)?;
+        self.data.get(offset..end).ok_or(HkvError::InvalidInput)
     }
 }
 
@@ -347,18 +686,27 @@ impl BatchPromoteResponse {
 pub struct DemoteRequest {
     /// Common ioctl header (command must be DEMOTE).
     pub header: IoctlHeader,
+    /// Tenant whose entry is removed.
+    pub tenant: u16,
     /// Entry key to remove.
     pub key: Key,
 }
 
 impl DemoteRequest {
-    /// Builds a demote request for the provided key.
+    /// Builds a demote request for the provided key in the default tenant.
     pub fn new(key: Key) -> Self {
         DemoteRequest {
             header: IoctlHeader::new(IoctlCommand::Demote),
+            tenant: DEFAULT_TENANT,
             key,
         }
     }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
 }
 
 /// Invalidate request payload for marking a cached entry as stale.
@@ -369,6 +717,8 @@ impl DemoteRequest {
 pub struct InvalidateRequest {
     /// Common ioctl header (command must be INVALIDATE).
     pub header: IoctlHeader,
+    /// Tenant whose entry is invalidated.
+    pub tenant: u16,
     /// Entry key to invalidate.
     pub key: Key,
     /// New version number for the entry.
@@ -376,22 +726,31 @@ pub struct InvalidateRequest {
 }
 
 impl InvalidateRequest {
-    /// Builds an invalidate request for the provided key and version.
+    /// Builds an invalidate request for the provided key and version in the
+    /// default tenant.
     pub fn new(key: Key, version: Version) -> Self {
         InvalidateRequest {
             header: IoctlHeader::new(IoctlCommand::Invalidate),
+            tenant: DEFAULT_TENANT,
             key,
             version,
         }
     }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
 }
 
 /// Snapshot of kernel cache statistics for telemetry.
 ///
 /// All fields are plain counters or gauges so user space can render telemetry
-/// without extra parsing or allocations.
+/// without extra parsing or allocations. A snapshot covers either the whole
+/// cache (`tenant == ALL_TENANTS`) or one tenant's partition.
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct CacheStats {
     /// Total lookup attempts.
     pub lookups: u64,
@@ -419,6 +778,13 @@ pub struct CacheStats {
     pub lock_contentions: u64,
     /// Completed RCU grace periods.
     pub rcu_grace_periods: u64,
+    /// Tenant the snapshot covers, or `ALL_TENANTS`.
+    pub tenant: u32,
+    /// Number of registered tenants.
+    pub tenant_count: u32,
+    /// Bytes the tenant budget policy currently grants (`max_bytes` for the
+    /// whole cache).
+    pub budget_bytes: u64,
 }
 
 /// Stats request payload for fetching kernel cache telemetry.
@@ -429,17 +795,38 @@ pub struct CacheStats {
 pub struct StatsRequest {
     /// Common ioctl header (command must be STATS).
     pub header: IoctlHeader,
+    /// Tenant to report on, or `ALL_TENANTS` for the whole cache.
+    pub tenant: u16,
+    /// Reserved for alignment/future flags; must be zero.
+    pub reserved: u16,
 }
 
 impl StatsRequest {
-    /// Builds a stats request.
+    /// Builds a request for cache-wide stats.
     pub const fn new() -> Self {
         StatsRequest {
             header: IoctlHeader::new(IoctlCommand::Stats),
+            tenant: ALL_TENANTS,
+            reserved: 0,
+        }
+    }
+
+    /// Builds a request for one tenant's stats.
+    pub const fn for_tenant(tenant: u16) -> Self {
+        StatsRequest {
+            header: IoctlHeader::new(IoctlCommand::Stats),
+            tenant,
+            reserved: 0,
         }
     }
 }
 
+impl Default for StatsRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Stats response payload with a snapshot of cache telemetry.
 ///
 /// Uses `STATUS_OK` on success or an `HkvError::code()` value on failure.
@@ -468,6 +855,11 @@ impl StatsResponse {
             stats,
         }
     }
+
+    /// Returns the stats snapshot, or the decoded error status.
+    pub fn result(&self) -> HkvResult<&CacheStats> {
+        decode_status(self.status).map(|()| &self.stats)
+    }
 }
 
 /// Runtime configuration update for the kernel cache.
@@ -475,12 +867,20 @@ impl StatsResponse {
 /// This keeps configuration fields aligned and explicit for easy validation
 /// inside the kernel module.
 ///
-/// Use: Issued by user space to update cache limits and watermarks.
+/// Use: Issued by user space to update cache limits, watermarks, and the
+/// active policies. Policy fields hold a `crate::policy` ID, or `POLICY_KEEP`
+/// to leave that policy unchanged.
+///
+/// The consistency fields set the stale-read behavior of the whole cache
+/// (`consistency_tenant == ALL_TENANTS`) or override it for one tenant.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ConfigRequest {
     /// Common ioctl header (command must be CONFIG).
     pub header: IoctlHeader,
+    /// Maximum age of a served stale value in milliseconds, measured from
+    /// its invalidation (used by the consistency mode).
+    pub max_stale_ms: u32,
     /// Maximum memory allowed for the cache (bytes).
     pub max_bytes: u64,
     /// Maximum number of entries allowed.
@@ -489,8 +889,21 @@ pub struct ConfigRequest {
     pub high_watermark: u32,
     /// Low watermark percentage (0-100) for eviction stop.
     pub low_watermark: u32,
+    /// Eviction policy ID (`POLICY_KEEP` for no change).
+    pub eviction_policy: u8,
+    /// Admission policy ID (`POLICY_KEEP` for no change).
+    pub admission_policy: u8,
+    /// Hotness estimator ID (`POLICY_KEEP` for no change).
+    pub hotness_estimator: u8,
+    /// Tenant budget policy ID (`POLICY_KEEP` for no change).
+    pub tenant_policy: u8,
+    /// Consistency mode ID (`POLICY_KEEP` for no change, or
+    /// `CONSISTENCY_INHERIT` to drop a tenant's override).
+    pub consistency_mode: u8,
     /// Reserved for future configuration fields; must be zero.
-    pub reserved: u64,
+    pub reserved: u8,
+    /// Tenant the consistency mode applies to, or `ALL_TENANTS`.
+    pub consistency_tenant: u16,
 }
 
 impl ConfigRequest {
@@ -498,13 +911,71 @@ impl ConfigRequest {
     pub fn new(max_bytes: u64, max_entries: u64, high_watermark: u32, low_watermark: u32) -> Self {
         ConfigRequest {
             header: IoctlHeader::new(IoctlCommand::Config),
+            max_stale_ms: 0,
             max_bytes,
             max_entries,
             high_watermark,
             low_watermark,
+            eviction_policy: POLICY_KEEP,
+            admission_policy: POLICY_KEEP,
+            hotness_estimator: POLICY_KEEP,
+            tenant_policy: POLICY_KEEP,
+            consistency_mode: POLICY_KEEP,
             reserved: 0,
+            consistency_tenant: ALL_TENANTS,
         }
     }
+
+    /// Selects the eviction policy.
+    pub fn with_eviction(mut self, policy: EvictionPolicyId) -> Self {
+        self.eviction_policy = policy.as_u8();
+        self
+    }
+
+    /// Selects the admission policy.
+    pub fn with_admission(mut self, policy: AdmissionPolicyId) -> Self {
+        self.admission_policy = policy.as_u8();
+        self
+    }
+
+    /// Selects the hotness estimator.
+    pub fn with_hotness(mut self, estimator: HotnessEstimatorId) -> Self {
+        self.hotness_estimator = estimator.as_u8();
+        self
+    }
+
+    /// Selects the tenant budget policy.
+    pub fn with_tenant_policy(mut self, policy: TenantBudgetPolicyId) -> Self {
+        self.tenant_policy = policy.as_u8();
+        self
+    }
+
+    /// Sets the cache-wide consistency mode and stale-age bound.
+    pub fn with_consistency(self, mode: ConsistencyModeId, max_stale_ms: u32) -> Self {
+        self.with_tenant_consistency(ALL_TENANTS, mode, max_stale_ms)
+    }
+
+    /// Overrides the consistency mode and stale-age bound for `tenant`.
+    pub fn with_tenant_consistency(
+        mut self,
+        tenant: u16,
+        mode: ConsistencyModeId,
+        max_stale_ms: u32,
+    ) -> Self {
+        self.consistency_mode = mode.as_u8();
+        self.consistency_tenant = tenant;
+        self.max_stale_ms = max_stale_ms;
+        self
+    }
+
+    /// Drops `tenant`'s consistency override so it follows the cache-wide
+    /// mode again.
+    pub fn with_inherited_consistency(mut self, tenant: u16) -> Self {
+        self.consistency_mode = CONSISTENCY_INHERIT;
+        self.consistency_tenant = tenant;
+        self.max_stale_ms = 0;
+        self
+    }
 }
 
 /// Flush request payload for clearing all kernel cache entries.
@@ -526,6 +997,221 @@ impl FlushRequest {
     }
 }
 
+impl Default for FlushRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-tenant budget settings.
+///
+/// Registers the tenant on first use and replaces its settings afterwards.
+/// Zero `quota_bytes` means the tenant is only bounded by the cache limit.
+///
+/// Use: Issued by user space to register a tenant or change its quota,
+/// guarantee, weight, or priority.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantConfigRequest {
+    /// Common ioctl header (command must be TENANT_CONFIG).
+    pub header: IoctlHeader,
+    /// Tenant being configured (not `ALL_TENANTS`).
+    pub tenant: u16,
+    /// Priority for priority-based budgets; higher is served first.
+    pub priority: u8,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u8,
+    /// Share of the pool left after minimum guarantees.
+    pub weight: u32,
+    /// Hard cap on the tenant's bytes (0 for none).
+    pub quota_bytes: u64,
+    /// Bytes other tenants can never evict the tenant below.
+    pub min_guarantee_bytes: u64,
+}
+
+impl TenantConfigRequest {
+    /// Builds a tenant config with weight 1, priority 0, and no quota or
+    /// guarantee.
+    pub fn new(tenant: u16) -> Self {
+        TenantConfigRequest {
+            header: IoctlHeader::new(IoctlCommand::TenantConfig),
+            tenant,
+            priority: 0,
+            reserved: 0,
+            weight: 1,
+            quota_bytes: 0,
+            min_guarantee_bytes: 0,
+        }
+    }
+
+    /// Sets the hard quota.
+    pub fn with_quota(mut self, bytes: u64) -> Self {
+        self.quota_bytes = bytes;
+        self
+    }
+
+    /// Sets the minimum guarantee.
+    pub fn with_min_guarantee(mut self, bytes: u64) -> Self {
+        self.min_guarantee_bytes = bytes;
+        self
+    }
+
+    /// Sets the pool weight.
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets the priority.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Read request whose value is copied into a user buffer.
+///
+/// `buf_ptr`/`buf_len` describe memory owned by the caller; it must stay
+/// valid and unaliased until the ioctl returns.
+///
+/// Use: Issued by user space (protocol version 2) to read values larger
+/// than the inline `Value` buffer.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadExtRequest {
+    /// Common ioctl header (command must be READ_EXT, version 2 or newer).
+    pub header: IoctlHeader,
+    /// Tenant whose cache partition is searched.
+    pub tenant: u16,
+    /// Lookup key (length-prefixed, fixed-capacity buffer).
+    pub key: Key,
+    /// User address the value is copied to.
+    pub buf_ptr: u64,
+    /// Capacity of the user buffer in bytes.
+    pub buf_len: u32,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u32,
+}
+
+impl ReadExtRequest {
+    /// Builds a read request copying into `buf`, in the default tenant.
+    pub fn new(key: Key, buf: &mut [u8]) -> Self {
+        ReadExtRequest {
+            header: IoctlHeader::new(IoctlCommand::ReadExt),
+            tenant: DEFAULT_TENANT,
+            key,
+            buf_ptr: buf.as_mut_ptr() as u64,
+            buf_len: u32::try_from(buf.len()).unwrap_or(u32::MAX),
+            reserved: 0,
+        }
+    }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
+}
+
+/// Read response for `ReadExtRequest`.
+///
+/// On `STATUS_OK`, `value_len` is the full value length. The value was
+/// copied only when `value_len <= buf_len`; otherwise the buffer is
+/// untouched and the caller should retry with `value_len` bytes.
+///
+/// Use: Returned by the kernel after a user-buffer read.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadExtResponse {
+    /// Common ioctl header (command must be READ_EXT).
+    pub header: IoctlHeader,
+    /// Status code (0 on success, error code on failure).
+    pub status: u16,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u16,
+    /// Length of the cached value in bytes.
+    pub value_len: u32,
+}
+
+impl ReadExtResponse {
+    /// Builds a response with an explicit status and value length.
+    pub fn new(status: u16, value_len: u32) -> Self {
+        ReadExtResponse {
+            header: IoctlHeader::new(IoctlCommand::ReadExt),
+            status,
+            reserved: 0,
+            value_len,
+        }
+    }
+
+    /// Returns true when the value was copied into the request's buffer.
+    pub fn copied(&self, buf_len: u32) -> bool {
+        self.status == STATUS_OK && self.value_len <= buf_len
+    }
+
+    /// Returns the full value length, or the decoded error status.
+    pub fn result(&self) -> HkvResult<u32> {
+        decode_status(self.status).map(|()| self.value_len)
+    }
+}
+
+/// Promote request whose value is read from a user buffer.
+///
+/// `value_ptr`/`value_len` describe memory owned by the caller; it must
+/// stay valid until the ioctl returns.
+///
+/// Use: Issued by user space (protocol version 2) to promote values up to
+/// `MAX_EXT_VALUE_SIZE`.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromoteExtRequest {
+    /// Common ioctl header (command must be PROMOTE_EXT, version 2 or newer).
+    pub header: IoctlHeader,
+    /// Tenant the entry is charged to.
+    pub tenant: u16,
+    /// Entry key to insert.
+    pub key: Key,
+    /// User address of the value bytes.
+    pub value_ptr: u64,
+    /// Length of the value in bytes.
+    pub value_len: u32,
+    /// Reserved for future flags; must be zero.
+    pub reserved: u32,
+    /// Version to associate with the entry.
+    pub version: Version,
+    /// Absolute expiration timestamp for the entry.
+    pub ttl: Ttl,
+}
+
+impl PromoteExtRequest {
+    /// Builds a promote request reading the value from `value`, in the
+    /// default tenant.
+    ///
+    /// # Errors
+    /// `ValueTooLong` when `value` exceeds `MAX_EXT_VALUE_SIZE`.
+    pub fn new(key: Key, value: &[u8], version: Version, ttl: Ttl) -> HkvResult<Self> {
+        if value.len() > MAX_EXT_VALUE_SIZE {
+            return Err(HkvError::ValueTooLong);
+        }
+        Ok(PromoteExtRequest {
+            header: IoctlHeader::new(IoctlCommand::PromoteExt),
+            tenant: DEFAULT_TENANT,
+            key,
+            value_ptr: value.as_ptr() as u64,
+            value_len: value.len() as u32,
+            reserved: 0,
+            version,
+            ttl,
+        })
+    }
+
+    /// Addresses the request to `tenant`.
+    pub fn with_tenant(mut self, tenant: u16) -> Self {
+        self.tenant = tenant;
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -549,7 +1235,9 @@ mod tests {
         let key = Key::new(b"alpha").unwrap();
         let request = ReadRequest::new(key.clone());
         assert_eq!(request.header, IoctlHeader::new(IoctlCommand::Read));
+        assert_eq!(request.tenant, DEFAULT_TENANT);
         assert_eq!(request.key, key);
+        assert_eq!(request.with_tenant(7).tenant, 7);
     }
 
     #[test]
@@ -561,9 +1249,86 @@ mod tests {
         assert_eq!(response.value, value);
     }
 
+    #[test]
+    fn test_status_decoding() {
+        assert_eq!(decode_status(STATUS_OK), Ok(()));
+        assert_eq!(decode_status(2), Err(HkvError::NotFound));
+        assert_eq!(decode_status(999), Err(HkvError::ProtocolViolation));
+        assert_eq!(status_code(&Ok::<_, HkvError>(7)), STATUS_OK);
+        assert_eq!(status_code::<()>(&Err(HkvError::Busy)), 20);
+
+        let value = Value::new(b"v").unwrap();
+        assert_eq!(
+            ReadResponse::new(STATUS_OK, value.clone()).result(),
+            Ok(&value)
+        );
+        assert_eq!(
+            ReadResponse::new(HkvError::StaleVersion.code(), value).result(),
+            Err(HkvError::StaleVersion)
+        );
+        assert_eq!(
+            PromoteResponse::new(HkvError::CapacityExceeded.code()).result(),
+            Err(HkvError::CapacityExceeded)
+        );
+        assert_eq!(ReadExtResponse::new(STATUS_OK, 42).result(), Ok(42));
+        let stats = StatsResponse::new(HkvError::InvalidInput.code(), CacheStats::default());
+        assert_eq!(stats.result(), Err(HkvError::InvalidInput));
+    }
+
+    #[test]
+    fn test_header_with_version() {
+        let header = IoctlHeader::with_version(IoctlCommand::Read, MIN_PROTOCOL_VERSION);
+        assert_eq!(header.version, MIN_PROTOCOL_VERSION);
+        assert_eq!(header.command, IoctlCommand::Read.as_u8());
+        assert_eq!(header.magic, IOCTL_MAGIC);
+    }
+
+    #[test]
+    fn test_ext_requests_point_at_user_buffers() {
+        let mut buf = vec![0u8; 4096];
+        let request = ReadExtRequest::new(Key::new(b"k").unwrap(), &mut buf).with_tenant(3);
+        assert_eq!(request.header, IoctlHeader::new(IoctlCommand::ReadExt));
+        assert_eq!(request.buf_ptr, buf.as_ptr() as u64);
+        assert_eq!(request.buf_len, 4096);
+        assert_eq!(request.tenant, 3);
+
+        let value = vec![7u8; 8 * 1024];
+        let request = PromoteExtRequest::new(
+            Key::new(b"k").unwrap(),
+            &value,
+            Version::new(1),
+            Ttl::INFINITE,
+        )
+        .unwrap();
+        assert_eq!(request.value_ptr, value.as_ptr() as u64);
+        assert_eq!(request.value_len, 8 * 1024);
+
+        let too_long = vec![0u8; MAX_EXT_VALUE_SIZE + 1];
+        assert_eq!(
+            PromoteExtRequest::new(
+                Key::new(b"k").unwrap(),
+                &too_long,
+                Version::ZERO,
+                Ttl::INFINITE
+            ),
+            Err(HkvError::ValueTooLong)
+        );
+
+        let response = ReadExtResponse::new(STATUS_OK, 5000);
+        assert!(!response.copied(4096));
+        assert!(response.copied(8192));
+    }
+
+    #[test]
+    fn test_ext_struct_sizes() {
+        assert_eq!(std::mem::size_of::<ReadExtRequest>(), 280);
+        assert_eq!(std::mem::size_of::<ReadExtResponse>(), 12);
+        assert_eq!(std::mem::size_of::<PromoteExtRequest>(), 296);
+    }
+
     #[test]
     fn test_read_struct_sizes() {
-        assert_eq!(std::mem::size_of::<ReadRequest>(), 262);
+        assert_eq!(std::mem::size_of::<ReadRequest>(), 264);
         assert_eq!(std::mem::size_of::<ReadResponse>(), 1032);
     }
 
@@ -589,31 +1354,117 @@ mod tests {
 
     #[test]
     fn test_promote_struct_sizes() {
-        assert_eq!(std::mem::size_of::<PromoteRequest>(), 1304);
+        assert_eq!(std::mem::size_of::<PromoteRequest>(), 1312);
         assert_eq!(std::mem::size_of::<PromoteResponse>(), 8);
     }
 
     #[test]
-    fn test_batch_promote_entry_size() {
-        assert_eq!(std::mem::size_of::<BatchPromoteEntry>(), 1304);
+    fn test_batch_promote_builder_scatter_list() {
+        let mut batch = BatchPromoteBuilder::new().with_tenant(4);
+        let large = vec![9u8; 8 * 1024];
+        assert_eq!(
+            batch.push(b"alpha", b"beta", Version::new(3), Ttl::INFINITE),
+            Ok(0)
+        );
+        assert_eq!(batch.push(b"blob", &large, Version::new(5), Ttl(7)), Ok(1));
+        assert_eq!(batch.len(), 2);
+
+        let (request, memory) = batch.prepare();
+        assert_eq!(request.header, IoctlHeader::new(IoctlCommand::BatchPromote));
+        assert_eq!((request.tenant, request.count), (4, 2));
+        let descriptors = memory.descriptors(&request).unwrap();
+        assert_eq!(memory.key(&descriptors[0]).unwrap(), b"alpha");
+        assert_eq!(memory.value(&descriptors[0]).unwrap(), b"beta");
+        assert_eq!(memory.key(&descriptors[1]).unwrap(), b"blob");
+        assert_eq!(memory.value(&descriptors[1]).unwrap(), &large[..]);
+        assert_eq!(descriptors[1].version, Version::new(5));
+        assert_eq!(descriptors[1].ttl, Ttl(7));
+    }
+
+    #[test]
+    fn test_batch_promote_builder_limits() {
+        let mut batch = BatchPromoteBuilder::new();
+        assert_eq!(
+            batch.push(&[0; MAX_KEY_SIZE + 1], b"v", Version::ZERO, Ttl::INFINITE),
+            Err(HkvError::KeyTooLong)
+        );
+        assert_eq!(
+            batch.push(
+                b"k",
+                &vec![0; MAX_EXT_VALUE_SIZE + 1],
+                Version::ZERO,
+                Ttl::INFINITE
+            ),
+            Err(HkvError::ValueTooLong)
+        );
+        for _ in 0..MAX_BATCH_SIZE {
+            batch
+                .push(b"k", b"v", Version::ZERO, Ttl::INFINITE)
+                .unwrap();
+        }
+        assert_eq!(
+            batch.push(b"k", b"v", Version::ZERO, Ttl::INFINITE),
+            Err(HkvError::CapacityExceeded)
+        );
     }
 
     #[test]
-    fn test_batch_promote_response_new() {
-        let response = BatchPromoteResponse::new(10);
+    fn test_batch_promote_statuses() {
+        let mut batch = BatchPromoteBuilder::new();
+        for key in [b"a", b"b", b"c"] {
+            batch.push(key, b"v", Version::ZERO, Ttl::INFINITE).unwrap();
+        }
+        let (_, mut memory) = batch.prepare();
+        memory.set_status(0, Ok(()));
+        memory.set_status(1, Err(HkvError::StaleVersion));
         assert_eq!(
-            response.header,
-            IoctlHeader::new(IoctlCommand::BatchPromote)
+            batch.statuses(),
+            &[STATUS_OK, 6, HkvError::InternalError.code()]
         );
-        assert_eq!(response.count, 10);
-        assert_eq!(response.reserved, 0);
-        assert_eq!(response.results.len(), BATCH_RESULT_BYTES);
+        assert!(batch.succeeded(0));
+        assert_eq!(batch.status(1), Err(HkvError::StaleVersion));
+        assert_eq!(batch.status(2), Err(HkvError::InternalError));
+        assert_eq!(batch.status(3), Err(HkvError::InvalidInput));
+        batch.prepare().1.set_status(0, Err(HkvError::Busy));
+        assert_eq!(batch.status(0), Err(HkvError::Busy));
+
+        // Preparing again forgets the previous call's outcomes.
+        batch.prepare();
+        assert!(!batch.succeeded(0));
+    }
+
+    #[test]
+    fn test_batch_promote_memory_rejects_foreign_pointers() {
+        let mut batch = BatchPromoteBuilder::new();
+        batch
+            .push(b"k", b"v", Version::ZERO, Ttl::INFINITE)
+            .unwrap();
+        let (request, memory) = batch.prepare();
+        let descriptor = memory.descriptors(&request).unwrap()[0];
+
+        let mut moved = request;
+        moved.entries_ptr += 8;
+        assert_eq!(memory.descriptors(&moved), Err(HkvError::InvalidInput));
+        let mut overlong = request;
+        overlong.count = 2;
+        assert_eq!(memory.descriptors(&overlong), Err(HkvError::InvalidInput));
+
+        let outside = BatchPromoteDescriptor {
+            value_len: 64,
+            ..descriptor
+        };
+        assert_eq!(memory.value(&outside), Err(HkvError::InvalidInput));
+        let before = BatchPromoteDescriptor {
+            key_ptr: descriptor.key_ptr - 1,
+            ..descriptor
+        };
+        assert_eq!(memory.key(&before), Err(HkvError::InvalidInput));
     }
 
     #[test]
     fn test_batch_promote_struct_sizes() {
-        assert_eq!(std::mem::size_of::<BatchPromoteRequest>(), 1_304_008);
-        assert_eq!(std::mem::size_of::<BatchPromoteResponse>(), 134);
+        assert_eq!(std::mem::size_of::<BatchPromoteRequest>(), 24);
+        assert_eq!(std::mem::size_of::<BatchPromoteDescriptor>(), 40);
     }
 
     #[test]
@@ -635,7 +1486,7 @@ mod tests {
 
     #[test]
     fn test_demote_invalidate_sizes() {
-        assert_eq!(std::mem::size_of::<DemoteRequest>(), 262);
+        assert_eq!(std::mem::size_of::<DemoteRequest>(), 264);
         assert_eq!(std::mem::size_of::<InvalidateRequest>(), 272);
     }
 
@@ -643,6 +1494,8 @@ mod tests {
     fn test_stats_request_new() {
         let request = StatsRequest::new();
         assert_eq!(request.header, IoctlHeader::new(IoctlCommand::Stats));
+        assert_eq!(request.tenant, ALL_TENANTS);
+        assert_eq!(StatsRequest::for_tenant(3).tenant, 3);
     }
 
     #[test]
@@ -661,6 +1514,9 @@ mod tests {
             entry_count: 11,
             lock_contentions: 12,
             rcu_grace_periods: 13,
+            tenant: 14,
+            tenant_count: 15,
+            budget_bytes: 16,
         };
         let response = StatsResponse::new(STATUS_OK, stats);
         assert_eq!(response.header, IoctlHeader::new(IoctlCommand::Stats));
@@ -671,9 +1527,9 @@ mod tests {
 
     #[test]
     fn test_stats_struct_sizes() {
-        assert_eq!(std::mem::size_of::<CacheStats>(), 104);
-        assert_eq!(std::mem::size_of::<StatsRequest>(), 4);
-        assert_eq!(std::mem::size_of::<StatsResponse>(), 112);
+        assert_eq!(std::mem::size_of::<CacheStats>(), 120);
+        assert_eq!(std::mem::size_of::<StatsRequest>(), 8);
+        assert_eq!(std::mem::size_of::<StatsResponse>(), 128);
     }
 
     #[test]
@@ -684,7 +1540,42 @@ mod tests {
         assert_eq!(request.max_entries, 100);
         assert_eq!(request.high_watermark, 80);
         assert_eq!(request.low_watermark, 70);
+        assert_eq!(request.eviction_policy, POLICY_KEEP);
         assert_eq!(request.reserved, 0);
+
+        let request = request
+            .with_eviction(EvictionPolicyId::Slru)
+            .with_admission(AdmissionPolicyId::TinyLfu)
+            .with_hotness(HotnessEstimatorId::Sampling)
+            .with_tenant_policy(TenantBudgetPolicyId::Proportional);
+        assert_eq!(request.eviction_policy, EvictionPolicyId::Slru.as_u8());
+        assert_eq!(request.admission_policy, AdmissionPolicyId::TinyLfu.as_u8());
+        assert_eq!(
+            request.hotness_estimator,
+            HotnessEstimatorId::Sampling.as_u8()
+        );
+        assert_eq!(
+            request.tenant_policy,
+            TenantBudgetPolicyId::Proportional.as_u8()
+        );
+        assert_eq!(request.consistency_mode, POLICY_KEEP);
+
+        let request = request.with_consistency(ConsistencyModeId::BoundedStaleness, 500);
+        assert_eq!(
+            request.consistency_mode,
+            ConsistencyModeId::BoundedStaleness.as_u8()
+        );
+        assert_eq!(
+            (request.consistency_tenant, request.max_stale_ms),
+            (ALL_TENANTS, 500)
+        );
+        let request = request.with_tenant_consistency(7, ConsistencyModeId::AsyncRefresh, 0);
+        assert_eq!((request.consistency_tenant, request.max_stale_ms), (7, 0));
+        let request = request.with_inherited_consistency(7);
+        assert_eq!(
+            (request.consistency_mode, request.consistency_tenant),
+            (CONSISTENCY_INHERIT, 7)
+        );
     }
 
     #[test]
@@ -693,9 +1584,30 @@ mod tests {
         assert_eq!(request.header, IoctlHeader::new(IoctlCommand::Flush));
     }
 
+    #[test]
+    fn test_tenant_config_request_new() {
+        let request = TenantConfigRequest::new(4)
+            .with_quota(1024)
+            .with_min_guarantee(256)
+            .with_weight(3)
+            .with_priority(9);
+        assert_eq!(request.header, IoctlHeader::new(IoctlCommand::TenantConfig));
+        assert_eq!(request.tenant, 4);
+        assert_eq!(
+            (request.quota_bytes, request.min_guarantee_bytes),
+            (1024, 256)
+        );
+        assert_eq!(
+            (request.weight, request.priority, request.reserved),
+            (3, 9, 0)
+        );
+        assert_eq!(TenantConfigRequest::new(5).weight, 1);
+    }
+
     #[test]
     fn test_config_flush_sizes() {
         assert_eq!(std::mem::size_of::<ConfigRequest>(), 40);
         assert_eq!(std::mem::size_of::<FlushRequest>(), 4);
+        assert_eq!(std::mem::size_of::<TenantConfigRequest>(), 32);
     }
 }
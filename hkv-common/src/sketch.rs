@@ -0,0 +1,174 @@
+//! # Count-Min Sketch
+//!
+//! Approximate per-key access counts by key hash. The engine's admission
+//! filter, the server's hot-key tracker, and the kernel cache's hotness
+//! estimators all count with this one sketch.
+//!
+//! ## Design Principles
+//!
+//! 1. **Hashes In, Counts Out**: The sketch takes precomputed 64-bit hashes
+//!    and never stores keys, so callers pick their own hasher and a candidate
+//!    can be compared against a victim before either is resident.
+//! 2. **Never Undercounts**: A key maps to one counter per row and its
+//!    estimate is the row minimum, so collisions can only inflate estimates.
+//! 3. **Shared by Reference**: Counters are relaxed atomics, so concurrent
+//!    recorders share one sketch through `&self`; an increment racing with a
+//!    halving may be lost, which only lowers an estimate.
+//! 4. **Aging by Halving**: `halve` divides every counter by two. With a
+//!    sample size set the sketch halves itself after that many increments;
+//!    otherwise the caller decays it on its own schedule.
+
+use core::fmt;
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// Multipliers deriving one counter per sketch row from a key hash.
+const ROW_SEEDS: [u64; 4] = [
+    0xc3a5_c85c_97cb_3127,
+    0xb492_b66f_be98_f273,
+    0x9ae1_6a3b_2f90_404f,
+    0xcbf2_9ce4_8422_2325,
+];
+
+/// Count-Min Sketch over atomic counters.
+pub struct CountMinSketch {
+    /// `ROW_SEEDS.len()` rows of `mask + 1` counters, row-major.
+    counters: Vec<AtomicU32>,
+    /// Counters per row minus one; the width is a power of two.
+    mask: usize,
+    /// Increments since the last halving.
+    additions: AtomicU64,
+    /// Increments that trigger the next halving; zero never self-ages.
+    sample_size: u64,
+}
+
+impl CountMinSketch {
+    /// Creates a sketch with `width` counters per row, rounded up to a power
+    /// of two (at least 16).
+    pub fn new(width: usize) -> Self {
+        let width = width.clamp(16, 1 << 30).next_power_of_two();
+        CountMinSketch {
+            counters: (0..width * ROW_SEEDS.len())
+                .map(|_| AtomicU32::new(0))
+                .collect(),
+            mask: width - 1,
+            additions: AtomicU64::new(0),
+            sample_size: 0,
+        }
+    }
+
+    /// Makes the sketch halve itself after every `sample_size` increments.
+    pub fn with_sample_size(mut self, sample_size: u64) -> Self {
+        self.sample_size = sample_size;
+        self
+    }
+
+    /// Returns the number of counters per row.
+    pub fn width(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Records one access and returns the key's new estimate.
+    pub fn increment(&self, hash: u64) -> u64 {
+        let estimate = (0..ROW_SEEDS.len())
+            .map(|row| {
+                let counter = &self.counters[self.index(row, hash)];
+                let previous = counter
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                        count.checked_add(1)
+                    })
+                    .unwrap_or(u32::MAX - 1);
+                u64::from(previous) + 1
+            })
+            .min()
+            .unwrap_or(0);
+        if self.sample_size > 0
+            && self.additions.fetch_add(1, Ordering::Relaxed) + 1 == self.sample_size
+        {
+            self.halve();
+        }
+        estimate
+    }
+
+    /// Returns the estimated access count for `hash`.
+    pub fn estimate(&self, hash: u64) -> u64 {
+        (0..ROW_SEEDS.len())
+            .map(|row| u64::from(self.counters[self.index(row, hash)].load(Ordering::Relaxed)))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Halves every counter so older accesses weigh less.
+    pub fn halve(&self) {
+        for counter in &self.counters {
+            counter.store(counter.load(Ordering::Relaxed) >> 1, Ordering::Relaxed);
+        }
+        let _ = self
+            .additions
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |additions| {
+                Some(additions / 2)
+            });
+    }
+
+    /// Maps `hash` to its counter in `row`.
+    ///
+    /// Multiplying by an odd seed and taking the high bits keeps rows
+    /// independent of the low hash bits callers often use for sharding.
+    fn index(&self, row: usize, hash: u64) -> usize {
+        let mixed = hash.wrapping_mul(ROW_SEEDS[row]);
+        row * (self.mask + 1) + ((mixed >> 32) as usize & self.mask)
+    }
+}
+
+impl fmt::Debug for CountMinSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CountMinSketch")
+            .field("width", &self.width())
+            .field("additions", &self.additions.load(Ordering::Relaxed))
+            .field("sample_size", &self.sample_size)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_accesses_per_hash() {
+        let sketch = CountMinSketch::new(64);
+        assert_eq!(sketch.estimate(42), 0);
+        for expected in 1..=5 {
+            assert_eq!(sketch.increment(42), expected);
+        }
+        assert_eq!(sketch.estimate(42), 5);
+        assert!(sketch.estimate(7) < 5);
+        assert_eq!(sketch.width(), 64);
+    }
+
+    #[test]
+    fn halving_ages_counts() {
+        let sketch = CountMinSketch::new(16);
+        for _ in 0..8 {
+            sketch.increment(1);
+        }
+        sketch.halve();
+        assert_eq!(sketch.estimate(1), 4);
+    }
+
+    #[test]
+    fn sample_size_halves_automatically() {
+        let sketch = CountMinSketch::new(16).with_sample_size(10);
+        for _ in 0..9 {
+            sketch.increment(1);
+        }
+        assert_eq!(sketch.estimate(1), 9);
+        sketch.increment(1);
+        assert_eq!(sketch.estimate(1), 5);
+
+        // The count restarts from half a sample, so five more trigger it again.
+        for _ in 0..5 {
+            sketch.increment(1);
+        }
+        assert_eq!(sketch.estimate(1), 5);
+    }
+}
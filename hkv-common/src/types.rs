@@ -46,12 +46,15 @@
 //! +---------+------------+--------------+
 //! Note: includes 4B padding between value and metadata.
 //! ```
+//!
+//! `crate::layout` pins these layouts with const assertions.
 
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::error::{HkvError, HkvResult};
+use crate::layout::check_private_fields;
 
 /// Maximum key size in bytes (256 bytes)
 pub const MAX_KEY_SIZE: usize = 256;
@@ -59,6 +62,13 @@ pub const MAX_KEY_SIZE: usize = 256;
 /// Maximum value size in bytes (1 KB)
 pub const MAX_VALUE_SIZE: usize = 1024;
 
+/// Maximum value size for the user-pointer commands (32 KB)
+///
+/// `CMD_READ_EXT` and `CMD_PROMOTE_EXT` copy values through a user buffer
+/// instead of the inline `Value`, so the kernel can cache values past
+/// `MAX_VALUE_SIZE` (session blobs, serialized objects) up to this size.
+pub const MAX_EXT_VALUE_SIZE: usize = 32 * 1024;
+
 /// Key type with bounded size
 ///
 /// Keys are limited to 256 bytes to:
@@ -74,6 +84,10 @@ pub struct Key {
     data: [u8; MAX_KEY_SIZE],
 }
 
+// The fields are private, so their ABI offsets are checked here rather
+// than in `layout`.
+check_private_fields!(Key { len, data });
+
 // Compare only initialized bytes (length-prefixed buffer pattern).
 impl PartialEq for Key {
     fn eq(&self, other: &Self) -> bool {
@@ -166,6 +180,8 @@ pub struct Value {
     data: [u8; MAX_VALUE_SIZE],
 }
 
+check_private_fields!(Value { len, data });
+
 // Compare only initialized bytes (length-prefixed buffer pattern).
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
@@ -0,0 +1,23 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use hkv_common::layout::c_header;
+
+const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/hybridkv_abi.h");
+
+#[test]
+fn checked_in_header_matches_the_rust_layouts() {
+    let generated = c_header();
+    if env::var_os("HKV_BLESS_ABI").is_some() {
+        fs::create_dir_all(Path::new(HEADER_PATH).parent().unwrap()).unwrap();
+        fs::write(HEADER_PATH, &generated).unwrap();
+        return;
+    }
+
+    let checked_in = fs::read_to_string(HEADER_PATH).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{HEADER_PATH} is stale; rerun with HKV_BLESS_ABI=1 to regenerate it"
+    );
+}
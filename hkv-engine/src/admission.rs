@@ -0,0 +1,100 @@
+//! # TinyLFU Admission
+//!
+//! Estimate recent key popularity so a full engine can refuse newcomers that
+//! are colder than the entry they would evict.
+//!
+//! ## Usage
+//!
+//! - Enable with `MemoryEngine::with_admission_filter(expected_keys)`; it only
+//!   takes effect together with a byte budget.
+//! - `MemoryEngine::admission_rejections` counts refused writes.
+//!
+//! ## Design Principles
+//!
+//! 1. **Shared Count-Min Sketch**: Counting is delegated to
+//!    `hkv_common::CountMinSketch`, the sketch the server's hot-key tracker
+//!    and the kernel cache use as well.
+//! 2. **Aging by Halving**: After `10 × width` increments every counter is
+//!    halved, so the sketch tracks recent popularity instead of all-time hits.
+//! 3. **Victim Comparison**: A newcomer is admitted only when its estimate is
+//!    strictly higher than the eviction victim's, which keeps one-hit scans
+//!    from flushing a hot working set (TinyLFU, Einziger et al.).
+//! 4. **Saturating Estimates**: Estimates are capped at 15, as TinyLFU's 4-bit
+//!    counters are, so a long-hot victim cannot outlive every newcomer.
+
+use std::fmt;
+
+use hkv_common::CountMinSketch;
+
+/// Largest frequency an estimate reports.
+const MAX_COUNT: u64 = 15;
+
+/// Increments per sketch counter before counters are halved.
+const SAMPLE_FACTOR: u64 = 10;
+
+/// Approximate per-key access counts with periodic aging.
+pub struct FrequencySketch {
+    sketch: CountMinSketch,
+}
+
+impl FrequencySketch {
+    /// Creates a sketch sized for roughly `expected_keys` distinct keys.
+    pub fn with_capacity(expected_keys: usize) -> Self {
+        let sketch = CountMinSketch::new(expected_keys);
+        let sample_size = sketch.width() as u64 * SAMPLE_FACTOR;
+        FrequencySketch {
+            sketch: sketch.with_sample_size(sample_size),
+        }
+    }
+
+    /// Records one access to the key with hash `hash`.
+    pub fn increment(&self, hash: u64) {
+        self.sketch.increment(hash);
+    }
+
+    /// Returns the estimated recent access count (0..=15) for `hash`.
+    pub fn frequency(&self, hash: u64) -> u8 {
+        self.sketch.estimate(hash).min(MAX_COUNT) as u8
+    }
+}
+
+impl fmt::Debug for FrequencySketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrequencySketch")
+            .field(&self.sketch)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_accesses_and_saturates() {
+        let sketch = FrequencySketch::with_capacity(64);
+        assert_eq!(sketch.frequency(42), 0);
+        for _ in 0..5 {
+            sketch.increment(42);
+        }
+        assert_eq!(sketch.frequency(42), 5);
+        for _ in 0..20 {
+            sketch.increment(42);
+        }
+        assert_eq!(sketch.frequency(42), 15);
+        assert!(sketch.frequency(7) < 15);
+    }
+
+    #[test]
+    fn ages_after_a_full_sample() {
+        let sketch = FrequencySketch::with_capacity(16);
+        for _ in 0..8 {
+            sketch.increment(1);
+        }
+        // 16 counters per row age after 160 increments in total.
+        for key in 0..152u64 {
+            sketch.increment((key + 2) << 40);
+        }
+        assert_eq!(sketch.frequency(1), 4);
+    }
+}
@@ -0,0 +1,472 @@
+//! # Append-Only File
+//!
+//! Log every mutating engine operation so the in-memory state can be rebuilt
+//! after a restart.
+//!
+//! ## Usage
+//!
+//! - Attach a log with `MemoryEngine::with_aof(AofConfig { .. })`; the file is
+//!   replayed first, then new writes are appended.
+//! - Call `MemoryEngine::rewrite_aof` (or `spawn_aof_rewrite` for the
+//!   `BGREWRITEAOF` flavor) to compact the log from the live shard contents.
+//!
+//! ## Design Principles
+//!
+//! 1. **Redis-Compatible Records**: Each record is a RESP2 array of bulk
+//!    strings (`SET`, `DEL`, `PEXPIREAT`), so the file is binary-safe and can
+//!    be inspected with standard Redis tooling.
+//! 2. **Idempotent Expiry**: TTLs are logged as absolute Unix milliseconds so a
+//!    replay never extends a key's lifetime.
+//! 3. **Log Under Lock**: Records are appended while the owning shard lock is
+//!    held, keeping per-key log order identical to apply order.
+//! 4. **Non-Blocking Rewrite**: A rewrite snapshots one shard at a time and
+//!    buffers concurrent writes to shards it has already visited, then swaps
+//!    the compacted file in atomically via `rename`.
+//! 5. **Truncation Tolerant**: A torn final record (crash mid-write) is
+//!    discarded on load instead of failing startup.
+//!
+//! ## File Layout
+//!
+//! ```text
+//! *3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n
+//! *3\r\n$9\r\nPEXPIREAT\r\n$3\r\nkey\r\n$13\r\n1700000000000\r\n
+//! *2\r\n$3\r\nDEL\r\n$3\r\nkey\r\n
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Interval used by the `EverySec` fsync policy.
+const FSYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Durability policy for appended records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// `fsync` after every record (safest, slowest).
+    Always,
+    /// `fsync` at most once per second (Redis default).
+    EverySec,
+    /// Leave flushing to the operating system.
+    No,
+}
+
+impl FsyncPolicy {
+    /// Parses the Redis `appendfsync` spelling (`always`, `everysec`, `no`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "always" => Some(Self::Always),
+            "everysec" => Some(Self::EverySec),
+            "no" => Some(Self::No),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration for the append-only file.
+#[derive(Debug, Clone)]
+pub struct AofConfig {
+    /// Path of the log file; created if missing.
+    pub path: PathBuf,
+    /// Durability policy for appended records.
+    pub fsync: FsyncPolicy,
+}
+
+/// Append-only log attached to a `MemoryEngine`.
+///
+/// The mutex serializes appends from all shards; records are small and the
+/// critical section is a single `write` call, so contention stays low.
+#[derive(Debug)]
+pub(crate) struct Aof {
+    config: AofConfig,
+    state: Mutex<AofState>,
+    /// Set while a rewrite is running to reject concurrent rewrites.
+    rewriting: AtomicBool,
+}
+
+#[derive(Debug)]
+struct AofState {
+    file: File,
+    /// Reusable encode buffer to avoid per-record allocations.
+    scratch: Vec<u8>,
+    last_fsync: Instant,
+    /// True when records were written since the last `fsync`.
+    unsynced: bool,
+    rewrite: Option<RewriteState>,
+}
+
+impl AofState {
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.last_fsync = Instant::now();
+        self.unsynced = false;
+        Ok(())
+    }
+}
+
+/// Bookkeeping for an in-progress rewrite.
+#[derive(Debug)]
+struct RewriteState {
+    /// Records for shards that were already snapshotted.
+    buffer: Vec<u8>,
+    /// Per-shard flag: true once the shard has been written to the new file.
+    done: Vec<bool>,
+}
+
+impl Aof {
+    /// Opens (or creates) the log for appending.
+    pub(crate) fn open(config: AofConfig) -> io::Result<Self> {
+        let file = open_append(&config.path)?;
+        Ok(Aof {
+            config,
+            state: Mutex::new(AofState {
+                file,
+                scratch: Vec::with_capacity(256),
+                last_fsync: Instant::now(),
+                unsynced: false,
+                rewrite: None,
+            }),
+            rewriting: AtomicBool::new(false),
+        })
+    }
+
+    /// Appends one command record written on behalf of `shard`.
+    pub(crate) fn append(&self, shard: usize, args: &[&[u8]]) -> io::Result<()> {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        state.scratch.clear();
+        encode_command(args, &mut state.scratch);
+        state.file.write_all(&state.scratch)?;
+
+        if let Some(rewrite) = state.rewrite.as_mut()
+            && rewrite.done[shard]
+        {
+            rewrite.buffer.extend_from_slice(&state.scratch);
+        }
+
+        state.unsynced = true;
+        match self.config.fsync {
+            FsyncPolicy::Always => state.sync()?,
+            FsyncPolicy::EverySec => {
+                if state.last_fsync.elapsed() >= FSYNC_INTERVAL {
+                    state.sync()?;
+                }
+            }
+            FsyncPolicy::No => {}
+        }
+        Ok(())
+    }
+
+    /// Forces buffered records to stable storage.
+    pub(crate) fn sync(&self) -> io::Result<()> {
+        self.state.lock().sync()
+    }
+
+    /// Flushes records the `EverySec` policy left unsynced.
+    ///
+    /// `append` only syncs when a later write arrives, so a periodic caller
+    /// is what bounds the loss window once writes stop. A no-op for the
+    /// other policies and when nothing was written since the last sync.
+    pub(crate) fn sync_pending(&self) -> io::Result<()> {
+        if self.config.fsync != FsyncPolicy::EverySec {
+            return Ok(());
+        }
+        let mut state = self.state.lock();
+        if state.unsynced {
+            state.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Returns true when appended records have not been synced yet.
+    #[cfg(test)]
+    pub(crate) fn has_unsynced(&self) -> bool {
+        self.state.lock().unsynced
+    }
+
+    /// Claims the rewrite slot. Returns false if a rewrite is already running.
+    pub(crate) fn try_claim_rewrite(&self) -> bool {
+        self.rewriting
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Returns true while a rewrite is running.
+    pub(crate) fn rewrite_in_progress(&self) -> bool {
+        self.rewriting.load(Ordering::Acquire)
+    }
+
+    /// Starts buffering writes for shards as they are snapshotted.
+    pub(crate) fn begin_rewrite(&self, shard_count: usize) {
+        self.state.lock().rewrite = Some(RewriteState {
+            buffer: Vec::new(),
+            done: vec![false; shard_count],
+        });
+    }
+
+    /// Marks a shard as snapshotted; later writes to it are buffered.
+    ///
+    /// Must be called while the shard lock used for the snapshot is held.
+    pub(crate) fn mark_shard_rewritten(&self, shard: usize) {
+        if let Some(rewrite) = self.state.lock().rewrite.as_mut() {
+            rewrite.done[shard] = true;
+        }
+    }
+
+    /// Path of the temporary file used while rewriting.
+    pub(crate) fn rewrite_path(&self) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(".rewrite");
+        PathBuf::from(path)
+    }
+
+    /// Appends buffered writes to the new file and atomically swaps it in.
+    pub(crate) fn finish_rewrite(&self, mut temp: File) -> io::Result<()> {
+        let mut state = self.state.lock();
+        let rewrite = state.rewrite.take();
+        if let Some(rewrite) = rewrite {
+            temp.write_all(&rewrite.buffer)?;
+        }
+        temp.sync_all()?;
+        drop(temp);
+
+        std::fs::rename(self.rewrite_path(), &self.config.path)?;
+        state.file = open_append(&self.config.path)?;
+        state.last_fsync = Instant::now();
+        state.unsynced = false;
+        self.rewriting.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    /// Abandons a failed rewrite and keeps appending to the old file.
+    pub(crate) fn abort_rewrite(&self) {
+        self.state.lock().rewrite = None;
+        let _ = std::fs::remove_file(self.rewrite_path());
+        self.rewriting.store(false, Ordering::Release);
+    }
+}
+
+/// Encodes a command as a RESP2 array of bulk strings.
+pub(crate) fn encode_command(args: &[&[u8]], out: &mut Vec<u8>) {
+    out.push(b'*');
+    out.extend_from_slice(args.len().to_string().as_bytes());
+    out.extend_from_slice(b"\r\n");
+    for arg in args {
+        out.push(b'$');
+        out.extend_from_slice(arg.len().to_string().as_bytes());
+        out.extend_from_slice(b"\r\n");
+        out.extend_from_slice(arg);
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+/// Reads every complete record in `path` and hands it to `apply`.
+///
+/// A torn final record is truncated away so later appends start on a record
+/// boundary. Returns the number of records applied; a missing file counts as
+/// an empty log.
+pub(crate) fn replay<F>(path: &Path, mut apply: F) -> io::Result<usize>
+where
+    F: FnMut(Vec<Vec<u8>>) -> io::Result<()>,
+{
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut offset = 0u64;
+    let mut applied = 0;
+
+    loop {
+        match read_record(&mut reader, &mut line)? {
+            Record::Command(args, len) => {
+                apply(args)?;
+                offset += len;
+                applied += 1;
+            }
+            Record::Eof => break,
+            Record::Truncated => {
+                OpenOptions::new().write(true).open(path)?.set_len(offset)?;
+                break;
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Outcome of reading one record from the log.
+enum Record {
+    /// A complete command and its encoded length in bytes.
+    Command(Vec<Vec<u8>>, u64),
+    /// Clean end of file on a record boundary.
+    Eof,
+    /// The file ends in the middle of a record.
+    Truncated,
+}
+
+fn read_record<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<Record> {
+    let mut consumed = 0u64;
+
+    match read_line(reader, line)? {
+        Line::Complete(len) => consumed += len,
+        Line::Eof => return Ok(Record::Eof),
+        Line::Partial => return Ok(Record::Truncated),
+    }
+    if line.first() != Some(&b'*') {
+        return Err(invalid_data("expected array header"));
+    }
+    let count = parse_len(&line[1..])?;
+
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        match read_line(reader, line)? {
+            Line::Complete(len) => consumed += len,
+            Line::Eof | Line::Partial => return Ok(Record::Truncated),
+        }
+        if line.first() != Some(&b'$') {
+            return Err(invalid_data("expected bulk header"));
+        }
+        let len = parse_len(&line[1..])?;
+
+        let mut data = vec![0u8; len + 2];
+        match reader.read_exact(&mut data) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(Record::Truncated);
+            }
+            Err(err) => return Err(err),
+        }
+        if data[len..] != *b"\r\n" {
+            return Err(invalid_data("missing bulk terminator"));
+        }
+        data.truncate(len);
+        consumed += (len + 2) as u64;
+        args.push(data);
+    }
+
+    Ok(Record::Command(args, consumed))
+}
+
+enum Line {
+    /// A CRLF-terminated line; carries the raw byte length including CRLF.
+    Complete(u64),
+    Eof,
+    Partial,
+}
+
+fn read_line<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<Line> {
+    buf.clear();
+    let bytes = reader.read_until(b'\n', buf)?;
+    if bytes == 0 {
+        return Ok(Line::Eof);
+    }
+    if buf.len() < 2 || !buf.ends_with(b"\r\n") {
+        return Ok(Line::Partial);
+    }
+    buf.truncate(buf.len() - 2);
+    Ok(Line::Complete(bytes as u64))
+}
+
+fn parse_len(data: &[u8]) -> io::Result<usize> {
+    if data.is_empty() || !data.iter().all(u8::is_ascii_digit) {
+        return Err(invalid_data("invalid length"));
+    }
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| invalid_data("invalid length"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("aof: {message}"))
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("hkv-aof-{name}-{}-{nanos}", std::process::id()))
+    }
+
+    #[test]
+    fn parses_fsync_policy() {
+        assert_eq!(FsyncPolicy::parse("always"), Some(FsyncPolicy::Always));
+        assert_eq!(FsyncPolicy::parse("EverySec"), Some(FsyncPolicy::EverySec));
+        assert_eq!(FsyncPolicy::parse("no"), Some(FsyncPolicy::No));
+        assert_eq!(FsyncPolicy::parse("sometimes"), None);
+    }
+
+    #[test]
+    fn append_then_replay_roundtrip() {
+        let path = temp_path("roundtrip");
+        let aof = Aof::open(AofConfig {
+            path: path.clone(),
+            fsync: FsyncPolicy::Always,
+        })
+        .unwrap();
+        aof.append(0, &[b"SET", b"k", b"bin\r\nary"]).unwrap();
+        aof.append(0, &[b"DEL", b"k"]).unwrap();
+        drop(aof);
+
+        let mut seen = Vec::new();
+        let applied = replay(&path, |args| {
+            seen.push(args);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(applied, 2);
+        assert_eq!(
+            seen[0],
+            vec![b"SET".to_vec(), b"k".to_vec(), b"bin\r\nary".to_vec()]
+        );
+        assert_eq!(seen[1], vec![b"DEL".to_vec(), b"k".to_vec()]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn replay_truncates_torn_tail() {
+        let path = temp_path("torn");
+        let mut bytes = Vec::new();
+        encode_command(&[b"SET", b"a", b"1"], &mut bytes);
+        let good_len = bytes.len() as u64;
+        bytes.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$1\r\nb");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let applied = replay(&path, |_| Ok(())).unwrap();
+        assert_eq!(applied, 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), good_len);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn replay_rejects_corrupt_records() {
+        let path = temp_path("corrupt");
+        std::fs::write(&path, b"garbage\r\n").unwrap();
+        let err = replay(&path, |_| Ok(())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn replay_missing_file_is_empty() {
+        let path = temp_path("missing");
+        assert_eq!(replay(&path, |_| Ok(())).unwrap(), 0);
+    }
+}
@@ -0,0 +1,46 @@
+//! # Wall-Clock Conversion
+//!
+//! The engine tracks expirations with `Instant` (monotonic) so TTL checks are
+//! immune to clock adjustments. Anything written to disk must survive a
+//! restart, so persisted expirations use absolute Unix milliseconds instead.
+//! These helpers translate between the two representations.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Returns the current wall-clock time in Unix milliseconds.
+pub(crate) fn unix_millis_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64
+}
+
+/// Returns the current wall-clock time in Unix nanoseconds.
+///
+/// Seeds the per-shard version clocks, so versions issued after a restart
+/// start above those of earlier runs.
+pub(crate) fn unix_nanos_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_nanos() as u64
+}
+
+/// Converts a monotonic deadline into absolute Unix milliseconds.
+///
+/// Deadlines in the past map to the current time so they stay expired.
+pub(crate) fn deadline_to_unix_millis(deadline: Instant, now: Instant) -> u64 {
+    let remaining = deadline.saturating_duration_since(now);
+    unix_millis_now().saturating_add(remaining.as_millis() as u64)
+}
+
+/// Converts absolute Unix milliseconds back into a monotonic deadline.
+///
+/// Returns `None` when the timestamp is already in the past.
+pub(crate) fn unix_millis_to_deadline(unix_ms: u64, now: Instant) -> Option<Instant> {
+    let current = unix_millis_now();
+    if unix_ms <= current {
+        return None;
+    }
+    Some(now + Duration::from_millis(unix_ms - current))
+}
@@ -9,11 +9,13 @@
 //!    avoid dynamic dispatch overhead.
 //! 4. **Explicit TTL**: Expose expiration via a dedicated method to keep the
 //!    hot read path minimal.
+//! 5. **Per-Type Extensions**: Collection commands live in extension traits
+//!    (e.g. `ListEngine`) so string-only engines stay small.
 
 use std::sync::Arc;
 use std::time::Duration;
 
-use hkv_common::HkvResult;
+use hkv_common::{HkvResult, Version};
 
 /// TTL query result for Redis-style semantics.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,6 +28,19 @@ pub enum TtlStatus {
     ExpiresIn(Duration),
 }
 
+/// Result of a compare-and-set (`CAS`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CasOutcome {
+    /// The write was applied; carries the key's new version.
+    Stored(Version),
+    /// The key had moved on; carries its current version
+    /// (`Version::ZERO` when missing).
+    Mismatch {
+        /// Version the key holds right now.
+        current: Version,
+    },
+}
+
 /// Strategy pattern: defines the engine behavior surface for the server.
 ///
 /// Keys and values are treated as bulk strings (binary-safe) for Phase 1.
@@ -33,11 +48,25 @@ pub trait KVEngine: Send + Sync {
     /// Returns the value for a key, or `None` if missing or expired.
     fn get(&self, key: &[u8]) -> HkvResult<Option<Arc<[u8]>>>;
 
+    /// Returns the value and the version of the last write to the key.
+    ///
+    /// Versions grow on every `set`/`delete`/`expire` of a key (and never
+    /// repeat across delete and re-create), so a cached copy is stale exactly
+    /// when its version is lower than the current one.
+    fn get_with_version(&self, key: &[u8]) -> HkvResult<Option<(Arc<[u8]>, Version)>>;
+
     /// Inserts or replaces a key with the provided value.
     ///
     /// Takes ownership to avoid extra copies on the hot path.
     fn set(&self, key: Vec<u8>, value: Vec<u8>) -> HkvResult<()>;
 
+    /// Writes `value` only if the key's current version equals `expected`.
+    ///
+    /// Use `Version::ZERO` to create a key that must not exist yet. Gives
+    /// callers optimistic, lock-free read-modify-write on top of
+    /// `get_with_version`.
+    fn cas(&self, key: Vec<u8>, expected: Version, value: Vec<u8>) -> HkvResult<CasOutcome>;
+
     /// Removes a key. Returns true if the key existed and was removed.
     fn delete(&self, key: &[u8]) -> HkvResult<bool>;
 
@@ -47,3 +76,190 @@ pub trait KVEngine: Send + Sync {
     /// Returns the TTL state for a key.
     fn ttl(&self, key: &[u8]) -> HkvResult<TtlStatus>;
 }
+
+/// List commands (Redis `L*`/`R*` family).
+///
+/// Indices follow Redis semantics: negative values count from the tail and
+/// ranges are inclusive. Commands against a non-list key return
+/// `HkvError::WrongType`.
+pub trait ListEngine: KVEngine {
+    /// Prepends values (each pushed to the head in order). Returns the new length.
+    fn lpush(&self, key: &[u8], values: Vec<Vec<u8>>) -> HkvResult<usize>;
+
+    /// Appends values to the tail. Returns the new length.
+    fn rpush(&self, key: &[u8], values: Vec<Vec<u8>>) -> HkvResult<usize>;
+
+    /// Pops up to `count` values from the head; `None` if the key is missing.
+    fn lpop(&self, key: &[u8], count: usize) -> HkvResult<Option<Vec<Arc<[u8]>>>>;
+
+    /// Pops up to `count` values from the tail; `None` if the key is missing.
+    fn rpop(&self, key: &[u8], count: usize) -> HkvResult<Option<Vec<Arc<[u8]>>>>;
+
+    /// Returns the values in the inclusive range `[start, stop]`.
+    fn lrange(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<Vec<Arc<[u8]>>>;
+
+    /// Returns the list length (0 for missing keys).
+    fn llen(&self, key: &[u8]) -> HkvResult<usize>;
+
+    /// Returns the value at `index`, or `None` when out of range.
+    fn lindex(&self, key: &[u8], index: i64) -> HkvResult<Option<Arc<[u8]>>>;
+
+    /// Keeps only the inclusive range `[start, stop]`; an empty result deletes the key.
+    fn ltrim(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<()>;
+}
+
+/// A hash field and its value.
+pub type FieldValue = (Arc<[u8]>, Arc<[u8]>);
+
+/// One `HSCAN` page: the next cursor (`0` when done) and the fields found.
+pub type ScanPage = (u64, Vec<FieldValue>);
+
+/// Hash commands (Redis `H*` family).
+///
+/// Commands against a non-hash key return `HkvError::WrongType`.
+pub trait HashEngine: KVEngine {
+    /// Sets field/value pairs. Returns the number of newly added fields.
+    fn hset(&self, key: &[u8], pairs: Vec<(Vec<u8>, Vec<u8>)>) -> HkvResult<usize>;
+
+    /// Returns the value of `field`, or `None` if the key or field is missing.
+    fn hget(&self, key: &[u8], field: &[u8]) -> HkvResult<Option<Arc<[u8]>>>;
+
+    /// Removes fields. Returns how many existed; an emptied hash deletes the key.
+    fn hdel(&self, key: &[u8], fields: &[Vec<u8>]) -> HkvResult<usize>;
+
+    /// Returns every field/value pair in unspecified order.
+    fn hgetall(&self, key: &[u8]) -> HkvResult<Vec<FieldValue>>;
+
+    /// Adds `delta` to an integer field (missing fields start at 0).
+    ///
+    /// Returns `InvalidInput` if the field is not an integer or would overflow.
+    fn hincrby(&self, key: &[u8], field: &[u8], delta: i64) -> HkvResult<i64>;
+
+    /// Incrementally iterates fields (`HSCAN`).
+    ///
+    /// Start with cursor `0`; the returned cursor is `0` once the scan is
+    /// complete. `count` is a batch-size hint and `pattern` a glob filter.
+    fn hscan(
+        &self,
+        key: &[u8],
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> HkvResult<ScanPage>;
+}
+
+/// Set commands (Redis `S*` family), including multi-key set algebra.
+///
+/// Commands against a non-set key return `HkvError::WrongType`; missing keys
+/// behave as empty sets. The `*store` variants overwrite `dest` whatever its
+/// type and delete it when the result is empty.
+pub trait SetEngine: KVEngine {
+    /// Adds members. Returns how many were not already present.
+    fn sadd(&self, key: &[u8], members: Vec<Vec<u8>>) -> HkvResult<usize>;
+
+    /// Removes members. Returns how many were present.
+    fn srem(&self, key: &[u8], members: &[Vec<u8>]) -> HkvResult<usize>;
+
+    /// Returns every member in unspecified order.
+    fn smembers(&self, key: &[u8]) -> HkvResult<Vec<Arc<[u8]>>>;
+
+    /// Returns true when `member` is in the set.
+    fn sismember(&self, key: &[u8], member: &[u8]) -> HkvResult<bool>;
+
+    /// Returns the number of members (0 for missing keys).
+    fn scard(&self, key: &[u8]) -> HkvResult<usize>;
+
+    /// Returns the members present in every set.
+    fn sinter(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>>;
+
+    /// Returns the members present in any set.
+    fn sunion(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>>;
+
+    /// Returns the members of the first set absent from all others.
+    fn sdiff(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>>;
+
+    /// Stores `sinter(keys)` at `dest`. Returns the resulting size.
+    fn sinterstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize>;
+
+    /// Stores `sunion(keys)` at `dest`. Returns the resulting size.
+    fn sunionstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize>;
+
+    /// Stores `sdiff(keys)` at `dest`. Returns the resulting size.
+    fn sdiffstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize>;
+}
+
+/// A sorted-set member and its score.
+pub type ScoredMember = (Arc<[u8]>, f64);
+
+/// One end of a `ZRANGEBYSCORE` interval (`1.5` or `(1.5` in Redis syntax).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreBound {
+    /// The endpoint itself is in range.
+    Inclusive(f64),
+    /// The endpoint itself is out of range.
+    Exclusive(f64),
+}
+
+impl ScoreBound {
+    /// Returns true when `score` satisfies this bound used as a minimum.
+    pub fn admits_min(self, score: f64) -> bool {
+        match self {
+            Self::Inclusive(bound) => score >= bound,
+            Self::Exclusive(bound) => score > bound,
+        }
+    }
+
+    /// Returns true when `score` satisfies this bound used as a maximum.
+    pub fn admits_max(self, score: f64) -> bool {
+        match self {
+            Self::Inclusive(bound) => score <= bound,
+            Self::Exclusive(bound) => score < bound,
+        }
+    }
+}
+
+/// Sorted set commands (Redis `Z*` family).
+///
+/// Members are ordered by score, ties broken by member bytes. Commands against
+/// a non-zset key return `HkvError::WrongType`; NaN scores are rejected with
+/// `HkvError::InvalidInput`.
+pub trait ZSetEngine: KVEngine {
+    /// Adds members or updates their scores. Returns how many were new.
+    fn zadd(&self, key: &[u8], entries: Vec<(f64, Vec<u8>)>) -> HkvResult<usize>;
+
+    /// Removes members. Returns how many were present.
+    fn zrem(&self, key: &[u8], members: &[Vec<u8>]) -> HkvResult<usize>;
+
+    /// Returns the score of `member`, or `None` if the key or member is missing.
+    fn zscore(&self, key: &[u8], member: &[u8]) -> HkvResult<Option<f64>>;
+
+    /// Returns the 0-based rank of `member` in ascending score order.
+    fn zrank(&self, key: &[u8], member: &[u8]) -> HkvResult<Option<usize>>;
+
+    /// Returns the number of members (0 for missing keys).
+    fn zcard(&self, key: &[u8]) -> HkvResult<usize>;
+
+    /// Returns members with ranks in the inclusive range `[start, stop]`.
+    ///
+    /// Negative indices count from the highest score, as in Redis.
+    fn zrange(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<Vec<ScoredMember>>;
+
+    /// Returns members with scores in `[min, max]`, in ascending order.
+    ///
+    /// `limit` is an optional `(offset, count)` applied to the matches.
+    fn zrangebyscore(
+        &self,
+        key: &[u8],
+        min: ScoreBound,
+        max: ScoreBound,
+        limit: Option<(usize, usize)>,
+    ) -> HkvResult<Vec<ScoredMember>>;
+
+    /// Adds `delta` to the score of `member` (missing members start at 0).
+    ///
+    /// Returns `InvalidInput` if the result would be NaN.
+    fn zincrby(&self, key: &[u8], delta: f64, member: &[u8]) -> HkvResult<f64>;
+
+    /// Removes and returns up to `count` members with the lowest scores.
+    fn zpopmin(&self, key: &[u8], count: usize) -> HkvResult<Vec<ScoredMember>>;
+}
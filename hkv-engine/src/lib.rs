@@ -1,6 +1,22 @@
+pub mod admission;
+pub mod aof;
+mod clock;
 pub mod engine;
+mod glob;
 pub mod memory;
+mod skiplist;
+pub mod snapshot;
+mod value;
 
-pub use engine::KVEngine;
+pub use admission::FrequencySketch;
+pub use aof::{AofConfig, FsyncPolicy};
+pub use engine::ListEngine;
+pub use engine::SetEngine;
 pub use engine::TtlStatus;
+pub use engine::{CasOutcome, KVEngine};
+pub use engine::{FieldValue, HashEngine, ScanPage};
+pub use engine::{ScoreBound, ScoredMember, ZSetEngine};
+pub use hkv_common::eviction::{EvictionPolicy, EvictionPolicyKind};
 pub use memory::MemoryEngine;
+pub use snapshot::{SaveRule, SnapshotConfig};
+pub use value::ValueType;
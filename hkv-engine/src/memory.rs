@@ -1,29 +1,53 @@
 //! # In-Memory Engine
 //!
 //! Provide the in-memory backend with sharded locking, TTL-aware
-//! lookups, and byte-based LRU eviction for predictable latency.
+//! lookups, and byte-based eviction for predictable latency.
 //!
 //! ## Usage
 //!
 //! - Use `MemoryEngine::new()` for a default sharded engine with unlimited
 //!   capacity (Phase 1 baseline).
 //! - Use `MemoryEngine::with_shard_count_and_capacity` to enforce a byte limit
-//!   and trigger LRU eviction.
+//!   and trigger eviction; `with_eviction_policy` picks the victim policy
+//!   (LRU by default).
+//! - Add `with_admission_filter` so a full engine only lets in new keys that
+//!   are more popular than the victim they would displace.
 //! - Use `start_expirer` to enable active TTL cleanup in the background.
+//! - Use `with_aof` to replay and then append to an append-only file, and
+//!   `start_aof_syncer` so the `everysec` policy also syncs once writes stop.
+//! - Use `with_snapshot` plus `save_snapshot`/`start_snapshotter` for
+//!   point-in-time binary backups.
 //!
 //! ## Design Principles
 //!
 //! 1. **Sharded Locks**: Per-shard locks reduce contention under concurrency.
-//! 2. **Per-Shard LRU**: Each shard maintains its own LRU list; eviction
+//! 2. **Per-Shard Policy**: Each shard owns its own `EvictionPolicy`; eviction
 //!    round-robins across shards for a scalable but non-global ordering.
-//! 3. **Byte-Based LRU**: Evict by total bytes to enforce memory limits.
-//! 4. **Arc-backed Buffers**: Values are `Arc<[u8]>` to avoid extra copies.
+//! 3. **Byte-Based Eviction**: Evict by total bytes to enforce memory limits.
+//! 4. **Arc-backed Buffers**: Strings and collection elements are `Arc<[u8]>`
+//!    to avoid extra copies.
 //! 5. **TTL Fast Path**: Expiration is checked on access for O(1) reads.
 //! 6. **Strategy Pattern**: Implements `KVEngine` to keep callers decoupled.
+//! 7. **Optional Durability**: Mutations are logged to the AOF under the shard
+//!    lock, so a disabled AOF costs a single branch per write.
+//! 8. **Typed Values**: A node holds a `StoredValue`; commands against the
+//!    wrong type fail with `WrongType`, and empty collections are removed.
+//! 9. **Per-Key Versions**: Every write stamps the node with the next value of
+//!    a per-shard clock. A key always maps to the same shard, so its versions
+//!    only grow, even across delete and re-create, without tombstones.
+//!    Clocks start at the wall-clock time in nanoseconds and are raised to
+//!    the snapshot's high-water mark on load, so versions keep growing
+//!    across restarts and never fall below ones the kernel tier still holds.
+//! 10. **Frequency-Gated Admission**: With a TinyLFU sketch enabled, a `set`
+//!     of a new key that would force an eviction fails with
+//!     `CapacityExceeded` unless the key is seen more often than the victim
+//!     the eviction would take. That victim comes from the shard eviction
+//!     starts at, so a cold key never displaces a hot one in another shard.
+//!     Rejected writes are not logged.
 //!
 //! ## Structure Overview
 //!
-//! The engine wires shards, locks, and LRU nodes together as follows:
+//! The engine wires shards, locks, and nodes together as follows:
 //!
 //! ```text
 //! MemoryEngine
@@ -33,46 +57,63 @@
 //!                     ├── map: HashMap<Arc<[u8]>, usize>
 //!                     ├── nodes: Vec<Option<Node>>
 //!                     ├── free: Vec<usize>
-//!                     └── head/tail: LRU indices
-//!                           └── Node { key, value, expires_at, size, prev, next }
+//!                     └── policy: Box<dyn EvictionPolicy> (tracks slots)
+//!                           └── Node { key, value: StoredValue, version, expires_at, size }
 //! ```
 
+use std::fs::File;
 use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
 use std::sync::{
     Arc,
-    atomic::{AtomicBool, AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
 };
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
 use ahash::RandomState;
 use hashbrown::HashMap;
-use parking_lot::RwLock;
+use parking_lot::{RwLock, RwLockWriteGuard};
 
-use hkv_common::{HkvError, HkvResult};
+use hkv_common::eviction::{EvictionPolicy, EvictionPolicyKind};
+use hkv_common::{HkvError, HkvResult, Version};
 
-use crate::engine::{KVEngine, TtlStatus};
+use crate::admission::FrequencySketch;
+use crate::aof::{self, Aof, AofConfig};
+use crate::clock;
+use crate::engine::{
+    CasOutcome, HashEngine, KVEngine, ListEngine, SetEngine, TtlStatus, ZSetEngine,
+};
+use crate::snapshot::{self, Snapshot, SnapshotConfig, SnapshotEntry};
+use crate::value::{Collection, StoredValue, ValueType};
+
+/// A string's value, version, and TTL, as `get_with_ttl` reads them.
+type StringEntry = (Arc<[u8]>, Version, TtlStatus);
+
+mod hash;
+mod list;
+mod set;
+mod zset;
 
 /// Default shards = CPU count * multiplier to reduce lock contention.
 const DEFAULT_SHARD_MULTIPLIER: usize = 4;
 
 /// Internal node representing a single key/value entry.
 ///
-/// Uses an index-based intrusive list (pattern) for O(1) LRU updates without
-/// heap pointers, keeping the layout cache-friendly and safe.
+/// Eviction order lives in the shard's policy, keyed by the node's slot, so
+/// nodes stay small regardless of the policy in use.
 #[derive(Debug)]
 struct Node {
     // Shared key buffer; map stores the same Arc to avoid duplicate allocations.
     key: Arc<[u8]>,
-    // Shared value buffer for zero-copy reads across callers.
-    value: Arc<[u8]>,
+    // Typed value; string buffers are shared for zero-copy reads.
+    value: StoredValue,
+    // Version of the last write to this key.
+    version: Version,
     // Absolute expiration timestamp.
     expires_at: Option<Instant>,
     // Byte size for eviction accounting (key + value).
     size: usize,
-    // Intrusive LRU pointers (index-based to keep nodes packed).
-    prev: Option<usize>,
-    next: Option<usize>,
 }
 
 impl Node {
@@ -90,14 +131,14 @@ impl Node {
 /// Per-shard storage container for the in-memory engine.
 ///
 /// This struct keeps the hot path tightly packed: a hash map for lookups and a
-/// dense node arena for LRU ordering. The arena stores indices for LRU links,
-/// avoiding pointers and keeping data cache-friendly.
+/// dense node arena whose slot indices the eviction policy tracks, avoiding
+/// pointers and keeping data cache-friendly.
 ///
 /// Design notes:
 /// - The map key is `Arc<[u8]>` to share the key buffer with the node without
 ///   copying; this is a zero-cost abstraction because `Arc` is ref-counted.
-/// - LRU links use indices instead of pointers to avoid unsafe code and keep
-///   the layout stable for the compiler.
+/// - Policies see slot indices instead of pointers to avoid unsafe code and
+///   keep the layout stable for the compiler.
 /// - `free` is a simple slot recycler to reduce allocations on churn.
 #[derive(Debug)]
 struct ShardInner {
@@ -107,93 +148,100 @@ struct ShardInner {
     nodes: Vec<Option<Node>>,
     /// Free-list for recycling node slots.
     free: Vec<usize>,
-    /// LRU head (oldest) and tail (most recent).
-    head: Option<usize>,
-    tail: Option<usize>,
+    /// Eviction order over live slots.
+    policy: Box<dyn EvictionPolicy>,
+    /// Access frequencies for admission; `None` admits every write.
+    admission: Option<FrequencySketch>,
+    /// Last version handed out by `next_version`.
+    version_clock: Version,
 }
 
 impl ShardInner {
-    /// Creates a new shard with empty LRU state and a local hash map.
+    /// Creates a new shard with an empty policy and a local hash map.
     ///
     /// Sharing the `RandomState` seed across shards keeps hash distribution
     /// consistent without introducing shared mutability.
-    fn new(hash_state: RandomState) -> Self {
+    fn new(
+        hash_state: RandomState,
+        policy: Box<dyn EvictionPolicy>,
+        version_clock: Version,
+    ) -> Self {
         ShardInner {
             map: HashMap::with_hasher(hash_state),
             nodes: Vec::new(),
             free: Vec::new(),
-            head: None,
-            tail: None,
+            policy,
+            admission: None,
+            version_clock,
         }
     }
 
-    /// Detaches `idx` from the LRU list.
-    ///
-    /// Call this before re-linking or removing the node.
-    fn lru_remove(&mut self, idx: usize) {
-        let (prev, next) = {
-            let node = self.nodes[idx].as_ref().expect("node exists");
-            (node.prev, node.next)
-        };
+    /// Hashes a key with the shard's map hasher.
+    fn key_hash(&self, key: &[u8]) -> u64 {
+        self.map.hasher().hash_one(key)
+    }
 
-        if let Some(prev_idx) = prev {
-            if let Some(prev_node) = self.nodes[prev_idx].as_mut() {
-                prev_node.next = next;
+    /// Replaces the eviction policy and registers every live slot with it.
+    ///
+    /// The previous ordering is lost; slots are registered in arena order.
+    fn set_policy(&mut self, mut policy: Box<dyn EvictionPolicy>) {
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if let Some(node) = node {
+                policy.on_insert(idx, self.key_hash(&node.key));
             }
-        } else {
-            self.head = next;
         }
+        self.policy = policy;
+    }
 
-        if let Some(next_idx) = next {
-            if let Some(next_node) = self.nodes[next_idx].as_mut() {
-                next_node.prev = prev;
-            }
-        } else {
-            self.tail = prev;
-        }
+    /// Advances the shard's version clock and returns the new version.
+    ///
+    /// Called once per write while holding the shard write lock.
+    fn next_version(&mut self) -> Version {
+        self.version_clock.increment()
+    }
 
-        if let Some(node) = self.nodes[idx].as_mut() {
-            node.prev = None;
-            node.next = None;
-        }
+    /// Ensures later versions are greater than `floor`.
+    fn raise_version_clock(&mut self, floor: Version) {
+        self.version_clock = self.version_clock.max(floor);
     }
 
-    /// Appends `idx` to the LRU tail (most recently used).
-    ///
-    /// This keeps updates O(1) without heap pointers.
-    fn lru_push_back(&mut self, idx: usize) {
-        let tail = self.tail;
-        if let Some(node) = self.nodes[idx].as_mut() {
-            node.prev = tail;
-            node.next = None;
+    /// Reports an access to the eviction policy and the admission sketch.
+    fn touch(&mut self, idx: usize) {
+        self.policy.on_access(idx);
+        if let Some(sketch) = self.admission.as_ref()
+            && let Some(node) = self.nodes[idx].as_ref()
+        {
+            sketch.increment(self.key_hash(&node.key));
         }
+    }
 
-        if let Some(tail_idx) = tail {
-            if let Some(tail_node) = self.nodes[tail_idx].as_mut() {
-                tail_node.next = Some(idx);
-            }
-        } else {
-            self.head = Some(idx);
+    /// Counts an access to a key that may not be resident.
+    ///
+    /// Misses count too, so a key requested often enough earns admission.
+    fn record_access(&mut self, key: &[u8]) {
+        if let Some(sketch) = self.admission.as_ref() {
+            sketch.increment(self.key_hash(key));
         }
+    }
 
-        self.tail = Some(idx);
+    /// Returns the sketch estimate for `key`, or `None` without a sketch.
+    fn frequency(&self, key: &[u8]) -> Option<u8> {
+        let sketch = self.admission.as_ref()?;
+        Some(sketch.frequency(self.key_hash(key)))
     }
 
-    /// Marks a node as recently used by moving it to the tail.
-    ///
-    /// Skips relinking if the node is already the tail.
-    fn touch(&mut self, idx: usize) {
-        if self.tail == Some(idx) {
-            return;
-        }
-        self.lru_remove(idx);
-        self.lru_push_back(idx);
+    /// Returns the estimate for the policy's current victim, or `None` when
+    /// the shard has nothing to evict.
+    fn victim_frequency(&self) -> Option<u8> {
+        let victim = self.nodes[self.policy.victim()?].as_ref()?;
+        self.frequency(&victim.key)
     }
 
-    /// Inserts a new node and returns its slot index.
+    /// Inserts a new node stamped with the next version and returns its slot.
     ///
     /// Reuses a free slot if available to reduce allocations under churn.
-    fn insert_new(&mut self, key: Arc<[u8]>, value: Arc<[u8]>, size: usize) -> usize {
+    fn insert_new(&mut self, key: Arc<[u8]>, value: StoredValue, size: usize) -> usize {
+        let version = self.next_version();
         let idx = self.free.pop().unwrap_or_else(|| {
             self.nodes.push(None);
             self.nodes.len() - 1
@@ -202,38 +250,40 @@ impl ShardInner {
         self.nodes[idx] = Some(Node {
             key: Arc::clone(&key),
             value,
+            version,
             expires_at: None,
             size,
-            prev: None,
-            next: None,
         });
-        self.lru_push_back(idx);
+        self.policy.on_insert(idx, self.key_hash(&key));
         self.map.insert(key, idx);
         idx
     }
 
     /// Removes a node by index and returns its byte size.
     ///
-    /// This updates the map, LRU links, and free list.
+    /// This updates the map, the policy, and the free list.
     fn remove_idx(&mut self, idx: usize) -> Option<usize> {
-        let node = self.nodes[idx].as_ref()?;
-        let key = Arc::clone(&node.key);
-        let size = node.size;
-
-        // Detach before clearing the slot so LRU pointers stay valid.
-        self.lru_remove(idx);
-        self.nodes[idx] = None;
-        self.map.remove(key.as_ref());
-        self.free.push(idx);
+        let size = self.release(idx)?;
+        self.policy.on_remove(idx);
         Some(size)
     }
 
-    /// Removes and returns the least-recently used node size.
+    /// Removes the policy's victim and returns its byte size.
     ///
     /// Used by the eviction logic when over capacity.
-    fn pop_lru(&mut self) -> Option<usize> {
-        let idx = self.head?;
-        self.remove_idx(idx)
+    fn evict_victim(&mut self) -> Option<usize> {
+        let idx = self.policy.victim()?;
+        let size = self.release(idx)?;
+        self.policy.on_evict(idx);
+        Some(size)
+    }
+
+    /// Clears slot `idx` from the arena and map, leaving the policy as is.
+    fn release(&mut self, idx: usize) -> Option<usize> {
+        let node = self.nodes[idx].take()?;
+        self.map.remove(node.key.as_ref());
+        self.free.push(idx);
+        Some(node.size)
     }
 }
 
@@ -249,7 +299,8 @@ struct Shard {
 /// Sharded in-memory implementation of `KVEngine` for Phase 1.
 ///
 /// This engine favors predictable latency and cache locality over feature
-/// richness; it only supports string keys/values for now.
+/// richness; keys hold strings, lists (`ListEngine`), hashes (`HashEngine`),
+/// sets (`SetEngine`), or sorted sets (`ZSetEngine`).
 #[derive(Debug)]
 pub struct MemoryEngine {
     /// Per-shard storage.
@@ -264,18 +315,78 @@ pub struct MemoryEngine {
     used_bytes: AtomicUsize,
     /// Round-robin cursor for eviction across shards.
     eviction_cursor: AtomicUsize,
+    /// Policy every shard uses to pick eviction victims.
+    eviction_policy: EvictionPolicyKind,
+    /// New keys refused by the admission filter.
+    admission_rejections: AtomicU64,
+    /// Append-only log; `None` keeps the engine purely in memory.
+    aof: Option<Aof>,
+    /// Snapshot target and save schedule; `None` disables snapshots.
+    snapshot: Option<SnapshotConfig>,
+    /// Writes since the last successful snapshot (Redis "dirty" counter).
+    changes: AtomicU64,
+    /// Unix seconds of the last successful snapshot (or engine creation).
+    last_save: AtomicU64,
+    /// Set while a snapshot is being written.
+    saving: AtomicBool,
 }
 
-/// Handle for the background expiration sweeper.
+/// A write-locked shard plus the (type-checked) slot of one collection key.
+struct CollectionSlot<'a> {
+    shard_idx: usize,
+    inner: RwLockWriteGuard<'a, ShardInner>,
+    /// Slot of the live entry; `None` when the key is missing.
+    idx: Option<usize>,
+}
+
+impl CollectionSlot<'_> {
+    /// Borrows the existing collection, if any.
+    fn collection<C: Collection>(&self) -> Option<&C> {
+        let node = self.inner.nodes[self.idx?].as_ref()?;
+        C::from_value(&node.value)
+    }
+}
+
+/// Write guards for several shards, held for one multi-key command.
+///
+/// Built only by `MemoryEngine::lock_shards`, which acquires them in
+/// ascending shard index order.
+struct ShardGuards<'a> {
+    /// Sorted by shard index, without duplicates.
+    guards: Vec<(usize, RwLockWriteGuard<'a, ShardInner>)>,
+}
+
+impl ShardGuards<'_> {
+    fn position(&self, shard_idx: usize) -> usize {
+        self.guards
+            .binary_search_by_key(&shard_idx, |(idx, _)| *idx)
+            .expect("shard was locked by lock_shards")
+    }
+
+    fn get(&self, shard_idx: usize) -> &ShardInner {
+        &self.guards[self.position(shard_idx)].1
+    }
+
+    fn get_mut(&mut self, shard_idx: usize) -> &mut ShardInner {
+        let pos = self.position(shard_idx);
+        &mut self.guards[pos].1
+    }
+}
+
+/// Handle for a periodic background thread (expirer, snapshotter, or AOF
+/// syncer).
 ///
 /// Call `stop` to signal shutdown and join the thread.
-pub struct ExpirationHandle {
+pub struct BackgroundHandle {
     stop: Arc<AtomicBool>,
     join: Option<JoinHandle<()>>,
 }
 
-impl ExpirationHandle {
-    /// Stops the sweeper and waits for the thread to finish.
+/// Handle for the background expiration sweeper.
+pub type ExpirationHandle = BackgroundHandle;
+
+impl BackgroundHandle {
+    /// Stops the thread and waits for it to finish.
     ///
     /// Use this in tests or shutdown hooks to avoid leaking threads.
     pub fn stop(mut self) {
@@ -291,11 +402,16 @@ impl MemoryEngine {
     ///
     /// Uses an effectively unbounded capacity to keep Phase 1 simple.
     pub fn new() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+
+    /// Creates an engine with the default shard count and a byte capacity.
+    pub fn with_capacity(max_bytes: usize) -> Self {
         let threads = std::thread::available_parallelism()
             .map(|count| count.get())
             .unwrap_or(1);
         let shard_count = threads.saturating_mul(DEFAULT_SHARD_MULTIPLIER);
-        Self::with_shard_count(shard_count)
+        Self::with_shard_count_and_capacity(shard_count, max_bytes)
     }
 
     /// Creates a new engine with a caller-provided shard count.
@@ -311,10 +427,15 @@ impl MemoryEngine {
     pub fn with_shard_count_and_capacity(shards: usize, max_bytes: usize) -> Self {
         let shard_count = normalize_shard_count(shards);
         let hash_state = RandomState::new();
+        let version_seed = Version::new(clock::unix_nanos_now());
         let mut shard_vec = Vec::with_capacity(shard_count);
         for _ in 0..shard_count {
             shard_vec.push(Shard {
-                inner: RwLock::new(ShardInner::new(hash_state.clone())),
+                inner: RwLock::new(ShardInner::new(
+                    hash_state.clone(),
+                    EvictionPolicyKind::default().build(),
+                    version_seed,
+                )),
             });
         }
 
@@ -325,237 +446,888 @@ impl MemoryEngine {
             max_bytes,
             used_bytes: AtomicUsize::new(0),
             eviction_cursor: AtomicUsize::new(0),
+            eviction_policy: EvictionPolicyKind::default(),
+            admission_rejections: AtomicU64::new(0),
+            aof: None,
+            snapshot: None,
+            changes: AtomicU64::new(0),
+            last_save: AtomicU64::new(clock::unix_millis_now() / 1000),
+            saving: AtomicBool::new(false),
         }
     }
 
-    /// Removes expired entries across all shards.
+    /// Selects the eviction policy for every shard.
     ///
-    /// This is an O(n) scan and is intended for a periodic background sweep.
-    pub fn purge_expired(&self, now: Instant) -> usize {
-        let mut removed = 0;
+    /// Call this before loading data: entries already present are re-registered
+    /// in slot order, so their recency and frequency history is lost.
+    pub fn with_eviction_policy(mut self, kind: EvictionPolicyKind) -> Self {
         for shard in &self.shards {
-            let mut inner = shard.inner.write();
-            let mut expired = Vec::new();
-            for &idx in inner.map.values() {
-                if let Some(node) = inner.nodes[idx].as_ref() {
-                    if node.is_expired(now) {
-                        expired.push(idx);
-                    }
-                }
-            }
+            shard.inner.write().set_policy(kind.build());
+        }
+        self.eviction_policy = kind;
+        self
+    }
 
-            for idx in expired {
-                if let Some(size) = inner.remove_idx(idx) {
-                    removed += 1;
-                    self.used_bytes.fetch_sub(size, Ordering::Relaxed);
-                }
-            }
+    /// Returns the active eviction policy.
+    pub fn eviction_policy(&self) -> EvictionPolicyKind {
+        self.eviction_policy
+    }
+
+    /// Enables TinyLFU admission, sized for about `expected_keys` keys.
+    ///
+    /// Only matters with a byte budget: a new key whose insertion would force
+    /// an eviction is stored only if the sketch has seen it more often than
+    /// the victim. Explicit `cas` writes and collection commands are always
+    /// admitted.
+    pub fn with_admission_filter(self, expected_keys: usize) -> Self {
+        let per_shard = expected_keys.div_ceil(self.shards.len());
+        for shard in &self.shards {
+            shard.inner.write().admission = Some(FrequencySketch::with_capacity(per_shard));
         }
-        removed
+        self
     }
 
-    /// Starts a background thread that periodically removes expired entries.
+    /// Returns how many `set`s of new keys the admission filter dropped.
+    pub fn admission_rejections(&self) -> u64 {
+        self.admission_rejections.load(Ordering::Relaxed)
+    }
+
+    /// Attaches a snapshot target and save schedule.
     ///
-    /// The returned handle must be stopped to avoid leaking the thread.
-    pub fn start_expirer(self: &Arc<Self>, interval: Duration) -> ExpirationHandle {
-        let interval = if interval.is_zero() {
-            Duration::from_millis(1)
-        } else {
-            interval
+    /// This does not load the file; call `load_snapshot` when the snapshot
+    /// should be the startup source (i.e. no AOF is attached).
+    pub fn with_snapshot(mut self, config: SnapshotConfig) -> Self {
+        self.snapshot = Some(config);
+        self
+    }
+
+    /// Loads the configured snapshot into the engine.
+    ///
+    /// Returns the number of entries restored; a missing file restores none.
+    /// Entries that expired while the server was down are skipped.
+    pub fn load_snapshot(&self) -> io::Result<usize> {
+        let config = self.snapshot_config()?;
+        let snapshot = match snapshot::read(&config.path) {
+            Ok(snapshot) => snapshot,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
         };
+        for shard in &self.shards {
+            shard
+                .inner
+                .write()
+                .raise_version_clock(snapshot.max_version);
+        }
 
-        let stop = Arc::new(AtomicBool::new(false));
-        let stop_thread = Arc::clone(&stop);
-        let engine = Arc::clone(self);
+        let now = Instant::now();
+        let mut restored = 0;
+        for entry in snapshot.entries {
+            let deadline = match entry.expires_at_ms {
+                Some(unix_ms) => match clock::unix_millis_to_deadline(unix_ms, now) {
+                    Some(deadline) => Some(deadline),
+                    None => continue,
+                },
+                None => None,
+            };
+            let to_io = |err: HkvError| io::Error::other(err.to_string());
+            match self.store(Arc::clone(&entry.key), entry.value) {
+                Ok(()) => {}
+                Err(HkvError::CapacityExceeded) => continue,
+                Err(err) => return Err(to_io(err)),
+            }
+            if let Some(deadline) = deadline {
+                self.expire_at(&entry.key, deadline).map_err(to_io)?;
+            }
+            restored += 1;
+        }
+
+        self.changes.store(0, Ordering::Relaxed);
+        self.last_save
+            .store(clock::unix_millis_now() / 1000, Ordering::Relaxed);
+        Ok(restored)
+    }
+
+    /// Writes a point-in-time snapshot to the configured path (`SAVE`).
+    ///
+    /// Fails if another snapshot is already being written.
+    pub fn save_snapshot(&self) -> io::Result<()> {
+        let config = self.snapshot_config()?;
+        if !self.try_claim_save() {
+            return Err(io::Error::other("background save already in progress"));
+        }
+        let result = self.write_snapshot(config);
+        self.saving.store(false, Ordering::Release);
+        result
+    }
+
+    /// Writes a snapshot on a background thread (`BGSAVE`).
+    ///
+    /// Returns `InvalidInput` when snapshots are not configured and `Busy`
+    /// when a save is already running.
+    pub fn spawn_snapshot(self: &Arc<Self>) -> HkvResult<()> {
+        if self.snapshot.is_none() {
+            return Err(HkvError::InvalidInput);
+        }
+        if !self.try_claim_save() {
+            return Err(HkvError::Busy);
+        }
 
-        let join = std::thread::spawn(move || {
-            while !stop_thread.load(Ordering::Acquire) {
-                std::thread::sleep(interval);
-                engine.purge_expired(Instant::now());
+        let engine = Arc::clone(self);
+        std::thread::spawn(move || {
+            if let Some(config) = engine.snapshot.as_ref() {
+                let _ = engine.write_snapshot(config);
             }
+            engine.saving.store(false, Ordering::Release);
         });
+        Ok(())
+    }
 
-        ExpirationHandle {
-            stop,
-            join: Some(join),
-        }
+    /// Starts a thread that saves whenever a configured `SaveRule` is due.
+    ///
+    /// Rules are checked every `interval`; the handle must be stopped to
+    /// avoid leaking the thread.
+    pub fn start_snapshotter(self: &Arc<Self>, interval: Duration) -> BackgroundHandle {
+        let engine = Arc::clone(self);
+        spawn_periodic(interval, move || {
+            let config = match engine.snapshot.as_ref() {
+                Some(config) => config,
+                None => return,
+            };
+            let changes = engine.changes_since_save();
+            let elapsed = (clock::unix_millis_now() / 1000).saturating_sub(engine.last_save());
+            let due = config
+                .save_rules
+                .iter()
+                .any(|rule| rule.is_due(changes, elapsed));
+            if due && engine.try_claim_save() {
+                let _ = engine.write_snapshot(config);
+                engine.saving.store(false, Ordering::Release);
+            }
+        })
     }
 
-    /// Hashes a key to its owning shard index.
+    /// Returns true while a snapshot is being written.
+    pub fn snapshot_in_progress(&self) -> bool {
+        self.saving.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of writes since the last successful snapshot.
+    pub fn changes_since_save(&self) -> u64 {
+        self.changes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the Unix time (seconds) of the last successful snapshot.
     ///
-    /// Uses the same hash state as the shard map to keep distribution uniform.
-    fn shard_index(&self, key: &[u8]) -> usize {
-        let mut hasher = self.hash_state.build_hasher();
-        hasher.write(key);
-        (hasher.finish() as usize) & self.shard_mask
+    /// Before the first save this is the engine creation time (`LASTSAVE`).
+    pub fn last_save(&self) -> u64 {
+        self.last_save.load(Ordering::Relaxed)
     }
 
-    /// Returns the shard responsible for a given key.
-    fn shard_for(&self, key: &[u8]) -> &Shard {
-        &self.shards[self.shard_index(key)]
+    fn snapshot_config(&self) -> io::Result<&SnapshotConfig> {
+        self.snapshot
+            .as_ref()
+            .ok_or_else(|| io::Error::other("snapshots are not configured"))
     }
 
-    /// Calculates entry size for eviction accounting.
+    fn try_claim_save(&self) -> bool {
+        self.saving
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Collects a point-in-time view of every shard and writes it out.
     ///
-    /// This ignores allocator overhead to keep the computation zero-cost.
-    fn entry_size(key_len: usize, value_len: usize) -> usize {
-        key_len + value_len
+    /// The caller must have claimed the `saving` flag.
+    fn write_snapshot(&self, config: &SnapshotConfig) -> io::Result<()> {
+        let changes_at_start = self.changes_since_save();
+        let snapshot = self.collect_snapshot();
+        let now_ms = clock::unix_millis_now();
+        snapshot::write(&config.path, now_ms, &snapshot)?;
+
+        // Writes that raced with the save stay counted for the next one.
+        self.changes.fetch_sub(changes_at_start, Ordering::Relaxed);
+        self.last_save.store(now_ms / 1000, Ordering::Relaxed);
+        Ok(())
     }
 
-    /// Evicts entries until within the configured byte budget.
+    /// Copies every live entry while all shard read locks are held.
     ///
-    /// Scans shards in round-robin order to avoid concentrating evictions.
-    fn evict_if_needed(&self) {
-        if self.max_bytes == usize::MAX {
-            return;
+    /// Locks are taken in shard index order; strings and elements are `Arc`s,
+    /// so the copy is a refcount bump per buffer. The version high-water mark
+    /// is read under the same locks.
+    fn collect_snapshot(&self) -> Snapshot {
+        let guards: Vec<_> = self.shards.iter().map(|shard| shard.inner.read()).collect();
+        let now = Instant::now();
+        let mut entries = Vec::new();
+        let mut max_version = Version::ZERO;
+        for inner in &guards {
+            max_version = max_version.max(inner.version_clock);
+            for node in inner.nodes.iter().flatten() {
+                if node.is_expired(now) {
+                    continue;
+                }
+                entries.push(SnapshotEntry {
+                    key: Arc::clone(&node.key),
+                    value: node.value.clone(),
+                    expires_at_ms: node
+                        .expires_at
+                        .map(|deadline| clock::deadline_to_unix_millis(deadline, now)),
+                });
+            }
         }
+        Snapshot {
+            max_version,
+            entries,
+        }
+    }
 
-        loop {
-            let used = self.used_bytes.load(Ordering::Relaxed);
-            if used <= self.max_bytes {
-                break;
-            }
+    /// Replays the append-only file at `config.path`, then logs new writes to it.
+    ///
+    /// Replay runs before the log is attached, so it does not re-append the
+    /// records it applies. A torn final record is discarded.
+    pub fn with_aof(mut self, config: AofConfig) -> io::Result<Self> {
+        aof::replay(&config.path, |args| self.replay_command(&args))?;
+        self.aof = Some(Aof::open(config)?);
+        Ok(self)
+    }
 
-            let start = self.eviction_cursor.fetch_add(1, Ordering::Relaxed);
-            let mut evicted = false;
+    /// Forces pending AOF records to stable storage.
+    ///
+    /// A no-op when the AOF is disabled.
+    pub fn sync_aof(&self) -> io::Result<()> {
+        match self.aof.as_ref() {
+            Some(aof) => aof.sync(),
+            None => Ok(()),
+        }
+    }
 
-            for offset in 0..self.shards.len() {
-                let idx = (start + offset) & self.shard_mask;
-                if let Some(size) = self.evict_one_from_shard(idx) {
-                    self.used_bytes.fetch_sub(size, Ordering::Relaxed);
-                    evicted = true;
-                    break;
-                }
+    /// Starts a thread that syncs records the `everysec` policy left pending.
+    ///
+    /// Without it the last writes before the engine goes idle stay unsynced
+    /// until the next write. The handle must be stopped to avoid leaking the
+    /// thread.
+    pub fn start_aof_syncer(self: &Arc<Self>, interval: Duration) -> BackgroundHandle {
+        let engine = Arc::clone(self);
+        spawn_periodic(interval, move || {
+            if let Some(aof) = engine.aof.as_ref() {
+                let _ = aof.sync_pending();
             }
+        })
+    }
 
-            if !evicted {
-                break;
-            }
+    /// Returns true while an AOF rewrite is running.
+    pub fn aof_rewrite_in_progress(&self) -> bool {
+        self.aof
+            .as_ref()
+            .map(Aof::rewrite_in_progress)
+            .unwrap_or(false)
+    }
+
+    /// Compacts the AOF by rewriting it from the live shard contents.
+    ///
+    /// Writers are only blocked while their own shard is being snapshotted.
+    pub fn rewrite_aof(&self) -> io::Result<()> {
+        let aof = self
+            .aof
+            .as_ref()
+            .ok_or_else(|| io::Error::other("append-only file is disabled"))?;
+        if !aof.try_claim_rewrite() {
+            return Err(io::Error::other("aof rewrite already in progress"));
         }
+        self.run_aof_rewrite(aof)
     }
 
-    /// Evicts a single LRU entry from a shard.
+    /// Starts an AOF rewrite on a background thread (`BGREWRITEAOF`).
     ///
-    /// Returns the reclaimed byte size for global accounting.
-    fn evict_one_from_shard(&self, shard_index: usize) -> Option<usize> {
-        let shard = &self.shards[shard_index];
-        let mut inner = shard.inner.write();
-        inner.pop_lru()
+    /// Returns `InvalidInput` when the AOF is disabled and `Busy` when a
+    /// rewrite is already running.
+    pub fn spawn_aof_rewrite(self: &Arc<Self>) -> HkvResult<()> {
+        let aof = self.aof.as_ref().ok_or(HkvError::InvalidInput)?;
+        if !aof.try_claim_rewrite() {
+            return Err(HkvError::Busy);
+        }
+
+        let engine = Arc::clone(self);
+        std::thread::spawn(move || {
+            if let Some(aof) = engine.aof.as_ref() {
+                let _ = engine.run_aof_rewrite(aof);
+            }
+        });
+        Ok(())
     }
-}
 
-impl KVEngine for MemoryEngine {
-    /// Looks up a key, updates LRU, and returns its value if present.
+    /// Writes every live entry to a fresh log and swaps it in.
     ///
-    /// Expired entries are removed on access to keep memory usage stable.
-    fn get(&self, key: &[u8]) -> HkvResult<Option<Arc<[u8]>>> {
-        let shard = self.shard_for(key);
-        let now = Instant::now();
-        let mut inner = shard.inner.write();
+    /// The caller must have claimed the rewrite slot.
+    fn run_aof_rewrite(&self, aof: &Aof) -> io::Result<()> {
+        let result = self.write_aof_snapshot(aof);
+        if result.is_err() {
+            aof.abort_rewrite();
+        }
+        result
+    }
 
-        let idx = match inner.map.get(key) {
-            Some(&idx) => idx,
-            None => return Ok(None),
-        };
+    fn write_aof_snapshot(&self, aof: &Aof) -> io::Result<()> {
+        let mut temp = File::create(aof.rewrite_path())?;
+        aof.begin_rewrite(self.shards.len());
+
+        let mut buf = Vec::new();
+        for (shard_idx, shard) in self.shards.iter().enumerate() {
+            buf.clear();
+            {
+                let inner = shard.inner.read();
+                let now = Instant::now();
+                for node in inner.nodes.iter().flatten() {
+                    if node.is_expired(now) {
+                        continue;
+                    }
+                    rebuild_commands(&node.key, &node.value, |args| {
+                        aof::encode_command(args, &mut buf);
+                        Ok::<(), io::Error>(())
+                    })?;
+                    if let Some(deadline) = node.expires_at {
+                        let unix_ms = clock::deadline_to_unix_millis(deadline, now);
+                        let unix_ms = unix_ms.to_string();
+                        aof::encode_command(
+                            &[b"PEXPIREAT", &node.key, unix_ms.as_bytes()],
+                            &mut buf,
+                        );
+                    }
+                }
+                // Mark while the read lock is held so no write slips between
+                // the snapshot and the start of buffering for this shard.
+                aof.mark_shard_rewritten(shard_idx);
+            }
+            temp.write_all(&buf)?;
+        }
 
-        let expired = match inner.nodes[idx].as_ref() {
-            Some(node) => node.is_expired(now),
-            None => return Ok(None),
-        };
+        aof.finish_rewrite(temp)
+    }
 
-        if expired {
-            if let Some(size) = inner.remove_idx(idx) {
-                self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+    /// Applies one replayed AOF record.
+    fn replay_command(&self, args: &[Vec<u8>]) -> io::Result<()> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "aof: invalid record");
+        let cmd = args.first().ok_or_else(invalid)?.to_ascii_uppercase();
+
+        let result = match (cmd.as_slice(), args.len()) {
+            (b"SET", 3) => self.set(args[1].clone(), args[2].clone()),
+            (b"DEL", 2) => self.delete(&args[1]).map(|_| ()),
+            (b"LPUSH", 3..) => self.lpush(&args[1], args[2..].to_vec()).map(|_| ()),
+            (b"RPUSH", 3..) => self.rpush(&args[1], args[2..].to_vec()).map(|_| ()),
+            (b"LPOP", 3) => {
+                let count = parse_arg(&args[2]).ok_or_else(invalid)?;
+                self.lpop(&args[1], count).map(|_| ())
             }
-            return Ok(None);
+            (b"RPOP", 3) => {
+                let count = parse_arg(&args[2]).ok_or_else(invalid)?;
+                self.rpop(&args[1], count).map(|_| ())
+            }
+            (b"HSET", len) if len >= 4 && len.is_multiple_of(2) => {
+                let pairs = args[2..]
+                    .chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect();
+                self.hset(&args[1], pairs).map(|_| ())
+            }
+            (b"HDEL", 3..) => self.hdel(&args[1], &args[2..]).map(|_| ()),
+            (b"SADD", 3..) => self.sadd(&args[1], args[2..].to_vec()).map(|_| ()),
+            (b"SREM", 3..) => self.srem(&args[1], &args[2..]).map(|_| ()),
+            (b"ZADD", len) if len >= 4 && len.is_multiple_of(2) => {
+                let mut entries = Vec::with_capacity(len / 2 - 1);
+                for pair in args[2..].chunks(2) {
+                    let score = parse_arg(&pair[0]).ok_or_else(invalid)?;
+                    entries.push((score, pair[1].clone()));
+                }
+                self.zadd(&args[1], entries).map(|_| ())
+            }
+            (b"ZREM", 3..) => self.zrem(&args[1], &args[2..]).map(|_| ()),
+            (b"ZPOPMIN", 3) => {
+                let count = parse_arg(&args[2]).ok_or_else(invalid)?;
+                self.zpopmin(&args[1], count).map(|_| ())
+            }
+            (b"LTRIM", 4) => {
+                let start = parse_arg(&args[2]).ok_or_else(invalid)?;
+                let stop = parse_arg(&args[3]).ok_or_else(invalid)?;
+                self.ltrim(&args[1], start, stop)
+            }
+            (b"PEXPIREAT", 3) => {
+                let unix_ms: u64 = parse_arg(&args[2]).ok_or_else(invalid)?;
+                match clock::unix_millis_to_deadline(unix_ms, Instant::now()) {
+                    Some(deadline) => self.expire_at(&args[1], deadline).map(|_| ()),
+                    None => self.delete(&args[1]).map(|_| ()),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+
+        // A key the admission filter refuses stays missing, as it did when
+        // the write was first made (rejections are never logged).
+        match result {
+            Ok(()) | Err(HkvError::NotFound) | Err(HkvError::CapacityExceeded) => Ok(()),
+            Err(err) => Err(io::Error::other(err.to_string())),
         }
+    }
 
-        let value = inner.nodes[idx]
-            .as_ref()
-            .map(|node| Arc::clone(&node.value));
-        inner.touch(idx);
-        Ok(value)
+    /// Appends a record to the AOF on behalf of `shard_idx`.
+    ///
+    /// Call while holding the shard lock so log order matches apply order.
+    fn log(&self, shard_idx: usize, args: &[&[u8]]) -> HkvResult<()> {
+        match self.aof.as_ref() {
+            Some(aof) => aof
+                .append(shard_idx, args)
+                .map_err(|_| HkvError::InternalError),
+            None => Ok(()),
+        }
     }
 
-    /// Inserts or replaces a key/value pair and updates LRU ordering.
+    /// Replaces whatever `key` holds with `value`, clearing any TTL.
     ///
-    /// This resets TTL to `None` and triggers eviction when over budget.
-    fn set(&self, key: Vec<u8>, value: Vec<u8>) -> HkvResult<()> {
-        let shard = self.shard_for(&key);
-        let mut inner = shard.inner.write();
-        let key_arc: Arc<[u8]> = Arc::from(key);
-        let value_arc: Arc<[u8]> = Arc::from(value);
-        let new_size = Self::entry_size(key_arc.len(), value_arc.len());
-
-        if let Some(&idx) = inner.map.get(key_arc.as_ref()) {
-            let remove = inner.nodes[idx]
-                .as_ref()
-                .map(|node| node.is_expired(Instant::now()));
-            if remove.unwrap_or(false) {
-                if let Some(size) = inner.remove_idx(idx) {
-                    self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+    /// Used by `SET` and snapshot loading; non-string values are logged as
+    /// `DEL` followed by the commands that rebuild them.
+    fn store(&self, key: Arc<[u8]>, value: StoredValue) -> HkvResult<()> {
+        self.store_if(key, value, None, None).map(|_| ())
+    }
+
+    /// `store`, but only when the key's current version equals `expected`
+    /// (`Version::ZERO` for a missing key). `None` stores unconditionally.
+    ///
+    /// The check and the write happen under one shard lock. `deadline`
+    /// becomes the new entry's TTL, logged with it as `PEXPIREAT`.
+    fn store_if(
+        &self,
+        key: Arc<[u8]>,
+        value: StoredValue,
+        expected: Option<Version>,
+        deadline: Option<Instant>,
+    ) -> HkvResult<CasOutcome> {
+        let shard_idx = self.shard_index(&key);
+        let now = Instant::now();
+        let mut inner = self.shards[shard_idx].inner.write();
+        let existing = self.live_slot(&mut inner, &key, now);
+
+        if let Some(expected) = expected {
+            let current = existing
+                .and_then(|idx| inner.nodes[idx].as_ref())
+                .map_or(Version::ZERO, |node| node.version);
+            if current != expected {
+                return Ok(CasOutcome::Mismatch { current });
+            }
+        }
+
+        let new_size = Self::entry_size(key.len(), value.payload_len());
+        let mut victim_shard = None;
+        if existing.is_none() && expected.is_none() && inner.admission.is_some() {
+            inner.record_access(&key);
+            let used = self.used_bytes.load(Ordering::Relaxed);
+            if used.saturating_add(new_size) > self.max_bytes {
+                let candidate = inner.frequency(&key).unwrap_or(0);
+                if let Some((idx, victim)) = self.admission_victim(shard_idx, &inner) {
+                    if candidate <= victim {
+                        self.admission_rejections.fetch_add(1, Ordering::Relaxed);
+                        return Err(HkvError::CapacityExceeded);
+                    }
+                    victim_shard = Some(idx);
                 }
             }
         }
 
-        if let Some(&idx) = inner.map.get(key_arc.as_ref()) {
-            if let Some(node) = inner.nodes[idx].as_mut() {
-                let old_size = node.size;
-                node.value = value_arc;
-                node.size = new_size;
-                node.expires_at = None;
-                inner.touch(idx);
+        if self.aof.is_some() {
+            if !matches!(value, StoredValue::String(_)) {
+                self.log(shard_idx, &[b"DEL", &key])?;
+            }
+            rebuild_commands(&key, &value, |args| self.log(shard_idx, args))?;
+            if let Some(deadline) = deadline {
+                let unix_ms = clock::deadline_to_unix_millis(deadline, now).to_string();
+                self.log(shard_idx, &[b"PEXPIREAT", &key, unix_ms.as_bytes()])?;
+            }
+        }
 
-                if new_size > old_size {
-                    self.used_bytes
-                        .fetch_add(new_size - old_size, Ordering::Relaxed);
-                } else if old_size > new_size {
-                    self.used_bytes
-                        .fetch_sub(old_size - new_size, Ordering::Relaxed);
+        let version = match existing {
+            Some(idx) => {
+                let version = inner.next_version();
+                if let Some(node) = inner.nodes[idx].as_mut() {
+                    let old_size = node.size;
+                    node.value = value;
+                    node.version = version;
+                    node.size = new_size;
+                    node.expires_at = deadline;
+                    inner.touch(idx);
+                    self.adjust_used_bytes(old_size, new_size);
                 }
+                version
             }
-        } else {
-            inner.insert_new(Arc::clone(&key_arc), value_arc, new_size);
-            self.used_bytes.fetch_add(new_size, Ordering::Relaxed);
-        }
+            None => {
+                let idx = inner.insert_new(key, value, new_size);
+                if let Some(node) = inner.nodes[idx].as_mut() {
+                    node.expires_at = deadline;
+                }
+                self.used_bytes.fetch_add(new_size, Ordering::Relaxed);
+                inner.version_clock
+            }
+        };
+        self.changes.fetch_add(1, Ordering::Relaxed);
 
         drop(inner);
-        self.evict_if_needed();
-        Ok(())
+        // Evict the victim admission was weighed against, then continue as usual.
+        self.evict_from(victim_shard);
+        Ok(CasOutcome::Stored(version))
     }
 
-    /// Deletes a key and returns whether a live entry was removed.
+    /// Finds the shard the next eviction should come from and its victim's
+    /// frequency estimate.
     ///
-    /// Expired entries are treated as missing to match Redis semantics.
-    fn delete(&self, key: &[u8]) -> HkvResult<bool> {
-        let shard = self.shard_for(key);
-        let now = Instant::now();
-        let mut inner = shard.inner.write();
-
-        let idx = match inner.map.get(key) {
-            Some(&idx) => idx,
-            None => return Ok(false),
-        };
+    /// Starts at the writer's own shard (already locked as `own`) and walks
+    /// the others with `try_read`, skipping contended shards, so the lock
+    /// order used elsewhere is never violated. `None` means nothing can be
+    /// evicted and the write is admitted.
+    fn admission_victim(&self, own_idx: usize, own: &ShardInner) -> Option<(usize, u8)> {
+        if let Some(frequency) = own.victim_frequency() {
+            return Some((own_idx, frequency));
+        }
+        (1..self.shards.len()).find_map(|offset| {
+            let idx = (own_idx + offset) & self.shard_mask;
+            let frequency = self.shards[idx].inner.try_read()?.victim_frequency()?;
+            Some((idx, frequency))
+        })
+    }
 
+    /// Returns the slot of the live entry for `key`.
+    ///
+    /// Expired entries are removed on the way, matching the read path.
+    fn live_slot(&self, inner: &mut ShardInner, key: &[u8], now: Instant) -> Option<usize> {
+        let idx = *inner.map.get(key)?;
         let expired = inner.nodes[idx]
             .as_ref()
             .map(|node| node.is_expired(now))
             .unwrap_or(false);
+        if expired {
+            if let Some(size) = inner.remove_idx(idx) {
+                self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+            }
+            return None;
+        }
+        Some(idx)
+    }
 
-        if let Some(size) = inner.remove_idx(idx) {
-            self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+    /// Reads the collection stored at `key`, reporting the access to the policy.
+    ///
+    /// Returns `Ok(None)` for missing keys and `WrongType` for other types.
+    fn read_collection<C: Collection, R>(
+        &self,
+        key: &[u8],
+        read: impl FnOnce(&C) -> R,
+    ) -> HkvResult<Option<R>> {
+        let shard = self.shard_for(key);
+        let mut inner = shard.inner.write();
+        let idx = match self.live_slot(&mut inner, key, Instant::now()) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+
+        let node = inner.nodes[idx].as_ref().ok_or(HkvError::InternalError)?;
+        let collection = C::from_value(&node.value).ok_or(HkvError::WrongType)?;
+        let result = read(collection);
+        inner.touch(idx);
+        Ok(Some(result))
+    }
+
+    /// Mutates the collection stored at `key` under the shard write lock.
+    ///
+    /// Missing keys are created empty when `create` is set and skipped
+    /// (`Ok(None)`) otherwise. `log_args` is appended to the AOF once the type
+    /// check passes and before `apply` runs.
+    fn update_collection<C: Collection, R>(
+        &self,
+        key: &[u8],
+        create: bool,
+        log_args: &[&[u8]],
+        apply: impl FnOnce(&mut C) -> R,
+    ) -> HkvResult<Option<R>> {
+        let slot = self.lock_collection::<C>(key)?;
+        if slot.idx.is_none() && !create {
+            return Ok(None);
         }
 
-        Ok(!expired)
+        self.log(slot.shard_idx, log_args)?;
+        self.commit_collection(slot, key, apply).map(Some)
     }
 
-    /// Sets a TTL for an existing key.
+    /// Write-locks the shard owning `key` and type-checks its live value.
     ///
-    /// Missing or expired keys return `HkvError::NotFound`.
-    fn expire(&self, key: &[u8], ttl: Duration) -> HkvResult<()> {
+    /// Commands whose AOF record depends on the current contents (e.g.
+    /// `HINCRBY`) inspect the slot, log, then call `commit_collection`.
+    fn lock_collection<C: Collection>(&self, key: &[u8]) -> HkvResult<CollectionSlot<'_>> {
+        let shard_idx = self.shard_index(key);
+        let mut inner = self.shards[shard_idx].inner.write();
+        let idx = self.live_slot(&mut inner, key, Instant::now());
+
+        if let Some(idx) = idx {
+            let node = inner.nodes[idx].as_ref().ok_or(HkvError::InternalError)?;
+            if node.value.value_type() != C::TYPE {
+                return Err(HkvError::WrongType);
+            }
+        }
+
+        Ok(CollectionSlot {
+            shard_idx,
+            inner,
+            idx,
+        })
+    }
+
+    /// Applies `apply` to a locked slot, creating the collection if missing.
+    ///
+    /// Updates byte accounting and eviction policy; collections left empty are
+    /// removed so `EXISTS`-style checks see them as missing.
+    fn commit_collection<C: Collection, R>(
+        &self,
+        slot: CollectionSlot<'_>,
+        key: &[u8],
+        apply: impl FnOnce(&mut C) -> R,
+    ) -> HkvResult<R> {
+        let CollectionSlot { mut inner, idx, .. } = slot;
+        let idx = match idx {
+            Some(idx) => idx,
+            None => {
+                let size = Self::entry_size(key.len(), 0);
+                self.used_bytes.fetch_add(size, Ordering::Relaxed);
+                inner.insert_new(Arc::from(key), C::default().into_value(), size)
+            }
+        };
+
+        let version = inner.next_version();
+        let node = inner.nodes[idx].as_mut().ok_or(HkvError::InternalError)?;
+        let collection = C::from_value_mut(&mut node.value).ok_or(HkvError::WrongType)?;
+        let result = apply(collection);
+        if collection.is_empty() {
+            if let Some(size) = inner.remove_idx(idx) {
+                self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+            }
+        } else {
+            let old_size = node.size;
+            let new_size = Self::entry_size(node.key.len(), node.value.payload_len());
+            node.version = version;
+            node.size = new_size;
+            inner.touch(idx);
+            self.adjust_used_bytes(old_size, new_size);
+        }
+        self.changes.fetch_add(1, Ordering::Relaxed);
+
+        drop(inner);
+        self.evict_if_needed();
+        Ok(result)
+    }
+
+    /// Write-locks every shard owning one of `keys`.
+    ///
+    /// Locks are always taken in ascending shard index order (the same order
+    /// `collect_snapshot` uses), and single-key paths never hold more than
+    /// one shard lock, so two multi-key commands with overlapping shards
+    /// cannot deadlock.
+    fn lock_shards(&self, keys: &[&[u8]]) -> ShardGuards<'_> {
+        let mut indices: Vec<usize> = keys.iter().map(|key| self.shard_index(key)).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        ShardGuards {
+            guards: indices
+                .into_iter()
+                .map(|idx| (idx, self.shards[idx].inner.write()))
+                .collect(),
+        }
+    }
+
+    /// Returns the type of the value stored at `key` (`TYPE`).
+    pub fn key_type(&self, key: &[u8]) -> Option<ValueType> {
+        let shard = self.shard_for(key);
+        let mut inner = shard.inner.write();
+        let idx = self.live_slot(&mut inner, key, Instant::now())?;
+        inner.nodes[idx]
+            .as_ref()
+            .map(|node| node.value.value_type())
+    }
+
+    /// Returns the version of the value stored at `key`, whatever its type.
+    ///
+    /// `None` for missing or expired keys.
+    pub fn key_version(&self, key: &[u8]) -> Option<Version> {
+        let shard = self.shard_for(key);
+        let mut inner = shard.inner.write();
+        let idx = self.live_slot(&mut inner, key, Instant::now())?;
+        inner.nodes[idx].as_ref().map(|node| node.version)
+    }
+
+    /// Returns a string's value, version, and TTL from one read of its shard.
+    ///
+    /// Copying a key elsewhere (the kernel cache) needs all three to describe
+    /// the same write; separate `get_with_version` and `ttl` calls could
+    /// straddle an `EXPIRE`. Counts as an access, like `get`.
+    pub fn get_with_ttl(&self, key: &[u8]) -> HkvResult<Option<StringEntry>> {
         let shard = self.shard_for(key);
         let now = Instant::now();
         let mut inner = shard.inner.write();
+        let Some(idx) = self.live_slot(&mut inner, key, now) else {
+            inner.record_access(key);
+            return Ok(None);
+        };
+
+        let entry = match inner.nodes[idx].as_ref() {
+            Some(Node {
+                value: StoredValue::String(value),
+                version,
+                expires_at,
+                ..
+            }) => {
+                let ttl = match expires_at {
+                    None => TtlStatus::NoExpiry,
+                    Some(deadline) => TtlStatus::ExpiresIn(deadline.saturating_duration_since(now)),
+                };
+                (Arc::clone(value), *version, ttl)
+            }
+            Some(_) => return Err(HkvError::WrongType),
+            None => return Ok(None),
+        };
+        inner.touch(idx);
+        Ok(Some(entry))
+    }
+
+    /// `set` that also returns the version stamped on the new value.
+    ///
+    /// Callers mirroring writes elsewhere (the kernel cache) use the version
+    /// to order their updates.
+    pub fn set_versioned(&self, key: Vec<u8>, value: Vec<u8>) -> HkvResult<Version> {
+        self.set_with_deadline(key, value, None)
+    }
+
+    /// `set_versioned` that also gives the new value a TTL (`SET ... EX`).
+    ///
+    /// The value and its deadline are stored under one shard lock and
+    /// stamped with one version, so no reader sees the value without its TTL.
+    pub fn set_ex_versioned(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl: Duration,
+    ) -> HkvResult<Version> {
+        self.set_with_deadline(key, value, Some(Instant::now() + ttl))
+    }
+
+    fn set_with_deadline(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        deadline: Option<Instant>,
+    ) -> HkvResult<Version> {
+        let value = StoredValue::String(Arc::from(value));
+        match self.store_if(Arc::from(key), value, None, deadline)? {
+            CasOutcome::Stored(version) => Ok(version),
+            CasOutcome::Mismatch { current } => Ok(current),
+        }
+    }
+
+    /// `delete` that returns the version the removal advanced the shard
+    /// clock to, or `None` when no live entry was removed.
+    pub fn delete_versioned(&self, key: &[u8]) -> HkvResult<Option<Version>> {
+        let shard_idx = self.shard_index(key);
+        let now = Instant::now();
+        let mut inner = self.shards[shard_idx].inner.write();
+
+        let idx = match inner.map.get(key) {
+            Some(&idx) => idx,
+            None => return Ok(None),
+        };
+
+        let expired = inner.nodes[idx]
+            .as_ref()
+            .map(|node| node.is_expired(now))
+            .unwrap_or(false);
+
+        self.log(shard_idx, &[b"DEL", key])?;
+
+        if let Some(size) = inner.remove_idx(idx) {
+            self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+        }
+        if expired {
+            return Ok(None);
+        }
+        self.changes.fetch_add(1, Ordering::Relaxed);
+        Ok(Some(inner.next_version()))
+    }
+
+    /// `expire` that also returns the key's new version.
+    ///
+    /// Missing or expired keys return `HkvError::NotFound`.
+    pub fn expire_versioned(&self, key: &[u8], ttl: Duration) -> HkvResult<Version> {
+        self.expire_at(key, Instant::now() + ttl)
+    }
+
+    /// Applies a size change of one entry to the global byte counter.
+    fn adjust_used_bytes(&self, old_size: usize, new_size: usize) {
+        if new_size > old_size {
+            self.used_bytes
+                .fetch_add(new_size - old_size, Ordering::Relaxed);
+        } else if old_size > new_size {
+            self.used_bytes
+                .fetch_sub(old_size - new_size, Ordering::Relaxed);
+        }
+    }
+
+    /// Removes expired entries across all shards.
+    ///
+    /// This is an O(n) scan and is intended for a periodic background sweep.
+    pub fn purge_expired(&self, now: Instant) -> usize {
+        let mut removed = 0;
+        for shard in &self.shards {
+            let mut inner = shard.inner.write();
+            let mut expired = Vec::new();
+            for &idx in inner.map.values() {
+                if let Some(node) = inner.nodes[idx].as_ref()
+                    && node.is_expired(now)
+                {
+                    expired.push(idx);
+                }
+            }
+
+            for idx in expired {
+                if let Some(size) = inner.remove_idx(idx) {
+                    removed += 1;
+                    self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Starts a background thread that periodically removes expired entries.
+    ///
+    /// The returned handle must be stopped to avoid leaking the thread.
+    pub fn start_expirer(self: &Arc<Self>, interval: Duration) -> ExpirationHandle {
+        let engine = Arc::clone(self);
+        spawn_periodic(interval, move || {
+            engine.purge_expired(Instant::now());
+        })
+    }
+
+    /// Hashes a key to its owning shard index.
+    ///
+    /// Uses the same hash state as the shard map to keep distribution uniform.
+    fn shard_index(&self, key: &[u8]) -> usize {
+        let mut hasher = self.hash_state.build_hasher();
+        hasher.write(key);
+        (hasher.finish() as usize) & self.shard_mask
+    }
+
+    /// Returns the shard responsible for a given key.
+    fn shard_for(&self, key: &[u8]) -> &Shard {
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// Sets an absolute expiration deadline on an existing key.
+    ///
+    /// Logged as `PEXPIREAT` so replay never extends the key's lifetime.
+    fn expire_at(&self, key: &[u8], deadline: Instant) -> HkvResult<Version> {
+        let shard_idx = self.shard_index(key);
+        let now = Instant::now();
+        let mut inner = self.shards[shard_idx].inner.write();
 
         let idx = match inner.map.get(key) {
             Some(&idx) => idx,
@@ -574,11 +1346,176 @@ impl KVEngine for MemoryEngine {
             return Err(HkvError::NotFound);
         }
 
+        if self.aof.is_some() {
+            let unix_ms = clock::deadline_to_unix_millis(deadline, now).to_string();
+            self.log(shard_idx, &[b"PEXPIREAT", key, unix_ms.as_bytes()])?;
+        }
+
+        let version = inner.next_version();
         if let Some(node) = inner.nodes[idx].as_mut() {
-            node.expires_at = Some(now + ttl);
+            node.expires_at = Some(deadline);
+            node.version = version;
         }
+        self.changes.fetch_add(1, Ordering::Relaxed);
 
-        Ok(())
+        Ok(version)
+    }
+
+    /// Calculates entry size for eviction accounting.
+    ///
+    /// `value_len` is the string length or the summed collection element
+    /// lengths. This ignores allocator overhead to keep the computation
+    /// zero-cost.
+    fn entry_size(key_len: usize, value_len: usize) -> usize {
+        key_len + value_len
+    }
+
+    /// Evicts entries until within the configured byte budget.
+    ///
+    /// Scans shards in round-robin order to avoid concentrating evictions.
+    fn evict_if_needed(&self) {
+        self.evict_from(None);
+    }
+
+    /// `evict_if_needed`, taking the first victim from `first` when given.
+    fn evict_from(&self, mut first: Option<usize>) {
+        if self.max_bytes == usize::MAX {
+            return;
+        }
+
+        loop {
+            let used = self.used_bytes.load(Ordering::Relaxed);
+            if used <= self.max_bytes {
+                break;
+            }
+
+            let start = match first.take() {
+                Some(idx) => idx,
+                None => self.eviction_cursor.fetch_add(1, Ordering::Relaxed),
+            };
+            let mut evicted = false;
+
+            for offset in 0..self.shards.len() {
+                let idx = (start + offset) & self.shard_mask;
+                if let Some(size) = self.evict_one_from_shard(idx) {
+                    self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+                    evicted = true;
+                    break;
+                }
+            }
+
+            if !evicted {
+                break;
+            }
+        }
+    }
+
+    /// Evicts the policy's victim from a shard.
+    ///
+    /// Returns the reclaimed byte size for global accounting.
+    fn evict_one_from_shard(&self, shard_index: usize) -> Option<usize> {
+        let shard = &self.shards[shard_index];
+        let mut inner = shard.inner.write();
+        // Evictions are logged as DEL so a replay does not resurrect them.
+        if let Some(aof) = self.aof.as_ref()
+            && let Some(idx) = inner.policy.victim()
+            && let Some(node) = inner.nodes[idx].as_ref()
+        {
+            let _ = aof.append(shard_index, &[b"DEL", &node.key]);
+        }
+        inner.evict_victim()
+    }
+}
+
+impl Default for MemoryEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KVEngine for MemoryEngine {
+    /// Looks up a key, records the access, and returns its value if present.
+    ///
+    /// Expired entries are removed on access to keep memory usage stable.
+    /// Keys holding a non-string value return `HkvError::WrongType`.
+    fn get(&self, key: &[u8]) -> HkvResult<Option<Arc<[u8]>>> {
+        Ok(self.get_with_version(key)?.map(|(value, _)| value))
+    }
+
+    /// Like `get`, but also returns the version of the last write to the key.
+    fn get_with_version(&self, key: &[u8]) -> HkvResult<Option<(Arc<[u8]>, Version)>> {
+        let shard = self.shard_for(key);
+        let now = Instant::now();
+        let mut inner = shard.inner.write();
+
+        let idx = match inner.map.get(key) {
+            Some(&idx) => idx,
+            None => {
+                inner.record_access(key);
+                return Ok(None);
+            }
+        };
+
+        let expired = match inner.nodes[idx].as_ref() {
+            Some(node) => node.is_expired(now),
+            None => return Ok(None),
+        };
+
+        if expired {
+            if let Some(size) = inner.remove_idx(idx) {
+                self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+            }
+            inner.record_access(key);
+            return Ok(None);
+        }
+
+        let versioned = match inner.nodes[idx].as_ref() {
+            Some(Node {
+                value: StoredValue::String(value),
+                version,
+                ..
+            }) => (Arc::clone(value), *version),
+            Some(_) => return Err(HkvError::WrongType),
+            None => return Ok(None),
+        };
+        inner.touch(idx);
+        Ok(Some(versioned))
+    }
+
+    /// Inserts or replaces a key/value pair and records the access.
+    ///
+    /// Overwrites values of any type, resets TTL to `None`, and triggers
+    /// eviction when over budget. Returns `CapacityExceeded` when the
+    /// admission filter refuses a new key.
+    fn set(&self, key: Vec<u8>, value: Vec<u8>) -> HkvResult<()> {
+        self.store(Arc::from(key), StoredValue::String(Arc::from(value)))
+    }
+
+    /// Sets a string only if the key's version still equals `expected`.
+    ///
+    /// Like `set`, a successful write replaces any type and clears the TTL; it
+    /// is logged to the AOF as a plain `SET`.
+    fn cas(&self, key: Vec<u8>, expected: Version, value: Vec<u8>) -> HkvResult<CasOutcome> {
+        self.store_if(
+            Arc::from(key),
+            StoredValue::String(Arc::from(value)),
+            Some(expected),
+            None,
+        )
+    }
+
+    /// Deletes a key and returns whether a live entry was removed.
+    ///
+    /// Expired entries are treated as missing to match Redis semantics.
+    fn delete(&self, key: &[u8]) -> HkvResult<bool> {
+        self.delete_versioned(key).map(|version| version.is_some())
+    }
+
+    /// Sets a TTL for an existing key.
+    ///
+    /// Missing or expired keys return `HkvError::NotFound`.
+    fn expire(&self, key: &[u8], ttl: Duration) -> HkvResult<()> {
+        self.expire_versioned(key, ttl).map(|_| ())
     }
 
     /// Returns TTL state for a key (missing, no-expiry, or remaining time).
@@ -622,6 +1559,86 @@ impl KVEngine for MemoryEngine {
     }
 }
 
+/// Runs `task` every `interval` on a dedicated thread until stopped.
+///
+/// A zero interval is clamped to 1ms to avoid a busy loop.
+fn spawn_periodic<F>(interval: Duration, mut task: F) -> BackgroundHandle
+where
+    F: FnMut() + Send + 'static,
+{
+    let interval = if interval.is_zero() {
+        Duration::from_millis(1)
+    } else {
+        interval
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_thread = Arc::clone(&stop);
+    let join = std::thread::spawn(move || {
+        while !stop_thread.load(Ordering::Acquire) {
+            std::thread::sleep(interval);
+            task();
+        }
+    });
+
+    BackgroundHandle {
+        stop,
+        join: Some(join),
+    }
+}
+
+/// Emits the commands that recreate `value` at a missing `key` (AOF rewrite
+/// form). Stops at and returns the first error from `emit`.
+fn rebuild_commands<E>(
+    key: &[u8],
+    value: &StoredValue,
+    mut emit: impl FnMut(&[&[u8]]) -> Result<(), E>,
+) -> Result<(), E> {
+    match value {
+        StoredValue::String(value) => emit(&[b"SET", key, value]),
+        StoredValue::List(list) => {
+            let mut args: Vec<&[u8]> = Vec::with_capacity(list.len() + 2);
+            args.push(b"RPUSH");
+            args.push(key);
+            args.extend(list.iter().map(|item| &item[..]));
+            emit(&args)
+        }
+        StoredValue::Set(set) => {
+            let mut args: Vec<&[u8]> = Vec::with_capacity(set.len() + 2);
+            args.push(b"SADD");
+            args.push(key);
+            args.extend(set.iter().map(|member| &member[..]));
+            emit(&args)
+        }
+        StoredValue::Hash(hash) => {
+            let mut args: Vec<&[u8]> = Vec::with_capacity(hash.len() * 2 + 2);
+            args.push(b"HSET");
+            args.push(key);
+            for (field, value) in hash.iter() {
+                args.push(field);
+                args.push(value);
+            }
+            emit(&args)
+        }
+        StoredValue::ZSet(zset) => {
+            let scores: Vec<String> = zset.iter().map(|(_, score)| score.to_string()).collect();
+            let mut args: Vec<&[u8]> = Vec::with_capacity(zset.len() * 2 + 2);
+            args.push(b"ZADD");
+            args.push(key);
+            for ((member, _), score) in zset.iter().zip(&scores) {
+                args.push(score.as_bytes());
+                args.push(member);
+            }
+            emit(&args)
+        }
+    }
+}
+
+/// Parses a decimal AOF argument.
+fn parse_arg<T: std::str::FromStr>(arg: &[u8]) -> Option<T> {
+    std::str::from_utf8(arg).ok()?.parse().ok()
+}
+
 /// Normalizes shard counts to a power of two for fast masking.
 ///
 /// This keeps shard selection branch-free and avoids modulo operations.
@@ -633,6 +1650,7 @@ fn normalize_shard_count(count: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::snapshot::SaveRule;
 
     #[test]
     fn set_get_roundtrip() {
@@ -642,6 +1660,163 @@ mod tests {
         assert_eq!(&*value, b"value");
     }
 
+    #[test]
+    fn versions_grow_on_every_write() {
+        let engine = MemoryEngine::with_shard_count(4);
+        assert!(engine.get_with_version(b"k").unwrap().is_none());
+
+        engine.set(b"k".to_vec(), b"a".to_vec()).unwrap();
+        let (value, v1) = engine.get_with_version(b"k").unwrap().unwrap();
+        assert_eq!(&*value, b"a");
+        assert!(v1 > Version::ZERO);
+
+        // Reads do not bump the version.
+        engine.get(b"k").unwrap();
+        assert_eq!(engine.key_version(b"k"), Some(v1));
+
+        engine.expire(b"k", Duration::from_secs(60)).unwrap();
+        let v2 = engine.key_version(b"k").unwrap();
+        assert!(v2 > v1);
+
+        engine.set(b"k".to_vec(), b"b".to_vec()).unwrap();
+        let v3 = engine.key_version(b"k").unwrap();
+        assert!(v3 > v2);
+
+        // Re-creating a deleted key never reuses an older version.
+        assert!(engine.delete(b"k").unwrap());
+        assert_eq!(engine.key_version(b"k"), None);
+        engine.set(b"k".to_vec(), b"c".to_vec()).unwrap();
+        assert!(engine.key_version(b"k").unwrap() > v3);
+    }
+
+    #[test]
+    fn versioned_writes_report_the_new_version() {
+        let engine = MemoryEngine::with_shard_count(4);
+        let v1 = engine.set_versioned(b"k".to_vec(), b"a".to_vec()).unwrap();
+        assert_eq!(engine.key_version(b"k"), Some(v1));
+
+        let v2 = engine
+            .expire_versioned(b"k", Duration::from_secs(60))
+            .unwrap();
+        assert!(v2 > v1);
+        assert_eq!(engine.key_version(b"k"), Some(v2));
+
+        let v3 = engine.delete_versioned(b"k").unwrap().unwrap();
+        assert!(v3 > v2);
+        assert_eq!(engine.delete_versioned(b"k").unwrap(), None);
+        assert_eq!(
+            engine.expire_versioned(b"k", Duration::from_secs(1)),
+            Err(HkvError::NotFound)
+        );
+    }
+
+    #[test]
+    fn set_ex_stores_value_and_ttl_under_one_version() {
+        let engine = MemoryEngine::with_shard_count(2);
+        let v1 = engine
+            .set_ex_versioned(b"k".to_vec(), b"a".to_vec(), Duration::from_secs(60))
+            .unwrap();
+        let (value, version, ttl) = engine.get_with_ttl(b"k").unwrap().unwrap();
+        assert_eq!((&value[..], version), (&b"a"[..], v1));
+        assert!(matches!(ttl, TtlStatus::ExpiresIn(left) if left <= Duration::from_secs(60)));
+
+        // Overwriting an existing key replaces its TTL too.
+        let v2 = engine
+            .set_ex_versioned(b"k".to_vec(), b"b".to_vec(), Duration::ZERO)
+            .unwrap();
+        assert!(v2 > v1);
+        assert_eq!(engine.get(b"k").unwrap(), None);
+
+        engine.set_versioned(b"k".to_vec(), b"c".to_vec()).unwrap();
+        assert_eq!(engine.ttl(b"k").unwrap(), TtlStatus::NoExpiry);
+    }
+
+    #[test]
+    fn get_with_ttl_reads_value_version_and_expiry_together() {
+        let engine = MemoryEngine::with_shard_count(2);
+        assert_eq!(engine.get_with_ttl(b"k").unwrap(), None);
+
+        let v1 = engine.set_versioned(b"k".to_vec(), b"a".to_vec()).unwrap();
+        let (value, version, ttl) = engine.get_with_ttl(b"k").unwrap().unwrap();
+        assert_eq!(
+            (&value[..], version, ttl),
+            (&b"a"[..], v1, TtlStatus::NoExpiry)
+        );
+
+        let v2 = engine
+            .expire_versioned(b"k", Duration::from_secs(60))
+            .unwrap();
+        let (_, version, ttl) = engine.get_with_ttl(b"k").unwrap().unwrap();
+        assert_eq!(version, v2);
+        assert!(matches!(ttl, TtlStatus::ExpiresIn(left) if left <= Duration::from_secs(60)));
+
+        engine.lpush(b"list", vec![b"x".to_vec()]).unwrap();
+        assert_eq!(engine.get_with_ttl(b"list"), Err(HkvError::WrongType));
+    }
+
+    #[test]
+    fn cas_writes_only_on_matching_version() {
+        let engine = MemoryEngine::with_shard_count(2);
+        let created = match engine.cas(b"k".to_vec(), Version::ZERO, b"a".to_vec()) {
+            Ok(CasOutcome::Stored(version)) => version,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(engine.key_version(b"k"), Some(created));
+
+        assert_eq!(
+            engine.cas(b"k".to_vec(), Version::ZERO, b"b".to_vec()),
+            Ok(CasOutcome::Mismatch { current: created })
+        );
+        let updated = match engine.cas(b"k".to_vec(), created, b"b".to_vec()) {
+            Ok(CasOutcome::Stored(version)) => version,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert!(updated > created);
+        assert_eq!(&*engine.get(b"k").unwrap().unwrap(), b"b");
+
+        // A CAS over a collection replaces it, like SET.
+        engine.sadd(b"set", vec![b"m".to_vec()]).unwrap();
+        let set_version = engine.key_version(b"set").unwrap();
+        assert!(matches!(
+            engine.cas(b"set".to_vec(), set_version, b"v".to_vec()),
+            Ok(CasOutcome::Stored(_))
+        ));
+        assert_eq!(engine.key_type(b"set"), Some(ValueType::String));
+    }
+
+    #[test]
+    fn cas_is_logged_as_set() {
+        let path = temp_path("cas");
+        {
+            let engine = MemoryEngine::with_shard_count(1)
+                .with_aof(aof_config(&path))
+                .unwrap();
+            engine
+                .cas(b"k".to_vec(), Version::ZERO, b"v1".to_vec())
+                .unwrap();
+            // A rejected CAS must not reach the log.
+            engine
+                .cas(b"k".to_vec(), Version::ZERO, b"v2".to_vec())
+                .unwrap();
+        }
+
+        let replayed = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        assert_eq!(&*replayed.get(b"k").unwrap().unwrap(), b"v1");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn collection_writes_bump_versions() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.rpush(b"list", vec![b"a".to_vec()]).unwrap();
+        let before = engine.key_version(b"list").unwrap();
+        engine.rpush(b"list", vec![b"b".to_vec()]).unwrap();
+        assert!(engine.key_version(b"list").unwrap() > before);
+        assert_eq!(engine.get_with_version(b"list"), Err(HkvError::WrongType));
+    }
+
     #[test]
     fn delete_removes_key() {
         let engine = MemoryEngine::with_shard_count(2);
@@ -697,6 +1872,134 @@ mod tests {
         assert!(engine.get(b"c").unwrap().is_some());
     }
 
+    #[test]
+    fn every_eviction_policy_enforces_the_budget() {
+        for kind in EvictionPolicyKind::ALL {
+            let engine =
+                MemoryEngine::with_shard_count_and_capacity(2, 100).with_eviction_policy(kind);
+            assert_eq!(engine.eviction_policy(), kind);
+            for i in 0..50u32 {
+                let key = format!("key:{i:02}").into_bytes();
+                engine.set(key.clone(), b"0123456789".to_vec()).unwrap();
+                engine.get(&key).unwrap();
+            }
+            assert!(engine.used_bytes.load(Ordering::Relaxed) <= 100, "{kind:?}");
+            let resident = (0..50u32)
+                .filter(|i| {
+                    let key = format!("key:{i:02}").into_bytes();
+                    engine.get(&key).unwrap().is_some()
+                })
+                .count();
+            assert_eq!(resident, 6, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn scan_resistant_policies_keep_reused_keys() {
+        let survives_scan = |kind: EvictionPolicyKind| {
+            let engine =
+                MemoryEngine::with_shard_count_and_capacity(1, 40).with_eviction_policy(kind);
+            engine.set(b"hot".to_vec(), b"1234567".to_vec()).unwrap();
+            engine.get(b"hot").unwrap();
+            for i in 0..6 {
+                engine
+                    .set(format!("s{i}").into_bytes(), b"12345678".to_vec())
+                    .unwrap();
+            }
+            engine.get(b"hot").unwrap().is_some()
+        };
+        assert!(!survives_scan(EvictionPolicyKind::Lru));
+        assert!(!survives_scan(EvictionPolicyKind::Fifo));
+        assert!(survives_scan(EvictionPolicyKind::Lfu));
+        assert!(survives_scan(EvictionPolicyKind::Slru));
+    }
+
+    #[test]
+    fn admission_filter_keeps_hot_keys_through_scans() {
+        let run_scan = |engine: &MemoryEngine| {
+            engine.set(b"hot".to_vec(), b"1234567".to_vec()).unwrap();
+            for _ in 0..5 {
+                engine.get(b"hot").unwrap();
+            }
+            let rejected = (0..10)
+                .filter(|i| {
+                    let key = format!("s{i}").into_bytes();
+                    engine.set(key, b"12345678".to_vec()) == Err(HkvError::CapacityExceeded)
+                })
+                .count();
+            (engine.get(b"hot").unwrap().is_some(), rejected)
+        };
+
+        let plain = MemoryEngine::with_shard_count_and_capacity(1, 40);
+        assert_eq!(run_scan(&plain), (false, 0));
+        assert_eq!(plain.admission_rejections(), 0);
+
+        let filtered = MemoryEngine::with_shard_count_and_capacity(1, 40).with_admission_filter(64);
+        assert_eq!(run_scan(&filtered), (true, 7));
+        assert_eq!(filtered.admission_rejections(), 7);
+        assert!(filtered.get(b"s9").unwrap().is_none());
+        assert!(filtered.used_bytes.load(Ordering::Relaxed) <= 40);
+    }
+
+    #[test]
+    fn admission_filter_admits_keys_requested_while_missing() {
+        let engine = MemoryEngine::with_shard_count_and_capacity(1, 20).with_admission_filter(64);
+        engine.set(b"a".to_vec(), b"123456789".to_vec()).unwrap();
+        engine.get(b"a").unwrap();
+        engine.set(b"b".to_vec(), b"123456789".to_vec()).unwrap();
+        assert_eq!(
+            engine.set(b"c".to_vec(), b"123456789".to_vec()),
+            Err(HkvError::CapacityExceeded)
+        );
+        assert!(engine.get(b"c").unwrap().is_none());
+
+        // Repeated misses raise the estimate above the victim's.
+        for _ in 0..3 {
+            engine.get(b"c").unwrap();
+        }
+        engine.set(b"c".to_vec(), b"123456789".to_vec()).unwrap();
+        assert!(engine.get(b"c").unwrap().is_some());
+        assert_eq!(engine.admission_rejections(), 1);
+    }
+
+    #[test]
+    fn admission_weighs_victims_from_other_shards() {
+        let engine = MemoryEngine::with_shard_count_and_capacity(4, 20).with_admission_filter(256);
+        engine.set(b"hot".to_vec(), b"1234567".to_vec()).unwrap();
+        for _ in 0..5 {
+            engine.get(b"hot").unwrap();
+        }
+
+        // Most keys land in a shard with nothing to evict; they must still be
+        // weighed against the victims eviction would take elsewhere.
+        let mut rejected = 0;
+        for i in 0..50 {
+            match engine.set(format!("k{i:02}").into_bytes(), b"1234567".to_vec()) {
+                Ok(()) => {}
+                Err(HkvError::CapacityExceeded) => rejected += 1,
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+            assert!(engine.get(b"hot").unwrap().is_some(), "evicted by k{i:02}");
+        }
+        assert_eq!(rejected, 49);
+        assert_eq!(engine.admission_rejections(), 49);
+        assert!(engine.used_bytes.load(Ordering::Relaxed) <= 20);
+    }
+
+    #[test]
+    fn switching_policy_keeps_existing_entries() {
+        let engine = MemoryEngine::with_shard_count_and_capacity(1, 20);
+        engine.set(b"a".to_vec(), b"1234".to_vec()).unwrap();
+        engine.set(b"b".to_vec(), b"1234".to_vec()).unwrap();
+        let engine = engine.with_eviction_policy(EvictionPolicyKind::Fifo);
+        engine.get(b"a").unwrap();
+        engine.set(b"c".to_vec(), b"1234567890".to_vec()).unwrap();
+
+        assert!(engine.get(b"a").unwrap().is_none());
+        assert!(engine.get(b"b").unwrap().is_some());
+        assert!(engine.get(b"c").unwrap().is_some());
+    }
+
     #[test]
     fn ttl_reports_missing_or_expiry() {
         let engine = MemoryEngine::with_shard_count(2);
@@ -709,4 +2012,445 @@ mod tests {
         std::thread::sleep(Duration::from_millis(5));
         assert_eq!(engine.ttl(b"alpha").unwrap(), TtlStatus::Missing);
     }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("hkv-mem-{name}-{}-{nanos}", std::process::id()))
+    }
+
+    fn aof_config(path: &std::path::Path) -> AofConfig {
+        AofConfig {
+            path: path.to_path_buf(),
+            fsync: crate::aof::FsyncPolicy::Always,
+        }
+    }
+
+    #[test]
+    fn aof_replay_restores_writes() {
+        let path = temp_path("replay");
+        {
+            let engine = MemoryEngine::with_shard_count(4)
+                .with_aof(aof_config(&path))
+                .unwrap();
+            engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+            engine.set(b"beta".to_vec(), b"2".to_vec()).unwrap();
+            engine.set(b"alpha".to_vec(), b"3".to_vec()).unwrap();
+            engine.delete(b"beta").unwrap();
+            engine.set(b"gamma".to_vec(), b"4".to_vec()).unwrap();
+            engine.expire(b"gamma", Duration::from_secs(60)).unwrap();
+            engine
+                .set_ex_versioned(b"delta".to_vec(), b"5".to_vec(), Duration::from_secs(60))
+                .unwrap();
+        }
+
+        let engine = MemoryEngine::with_shard_count(2)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        assert_eq!(&*engine.get(b"alpha").unwrap().unwrap(), b"3");
+        assert!(engine.get(b"beta").unwrap().is_none());
+        assert!(matches!(
+            engine.ttl(b"gamma").unwrap(),
+            TtlStatus::ExpiresIn(_)
+        ));
+        assert_eq!(&*engine.get(b"delta").unwrap().unwrap(), b"5");
+        assert!(matches!(
+            engine.ttl(b"delta").unwrap(),
+            TtlStatus::ExpiresIn(_)
+        ));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn versions_keep_growing_across_aof_reload() {
+        let path = temp_path("aof-versions");
+        let before = {
+            let engine = MemoryEngine::with_shard_count(1)
+                .with_aof(aof_config(&path))
+                .unwrap();
+            for round in 0..100 {
+                engine
+                    .set(b"alpha".to_vec(), round.to_string().into_bytes())
+                    .unwrap();
+            }
+            engine.get_with_version(b"alpha").unwrap().unwrap().1
+        };
+
+        let engine = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        let (_, replayed) = engine.get_with_version(b"alpha").unwrap().unwrap();
+        assert!(replayed > before);
+        engine.set(b"alpha".to_vec(), b"new".to_vec()).unwrap();
+        assert!(engine.get_with_version(b"alpha").unwrap().unwrap().1 > replayed);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn aof_syncer_flushes_idle_everysec_writes() {
+        let path = temp_path("syncer");
+        let engine = Arc::new(
+            MemoryEngine::with_shard_count(1)
+                .with_aof(AofConfig {
+                    path: path.clone(),
+                    fsync: crate::aof::FsyncPolicy::EverySec,
+                })
+                .unwrap(),
+        );
+        engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+        engine.set(b"beta".to_vec(), b"2".to_vec()).unwrap();
+        let aof = engine.aof.as_ref().unwrap();
+        assert!(aof.has_unsynced());
+
+        let syncer = engine.start_aof_syncer(Duration::from_millis(5));
+        std::thread::sleep(Duration::from_millis(50));
+        syncer.stop();
+        assert!(!engine.aof.as_ref().unwrap().has_unsynced());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn aof_replay_drops_keys_expired_while_down() {
+        let path = temp_path("expired");
+        {
+            let engine = MemoryEngine::with_shard_count(1)
+                .with_aof(aof_config(&path))
+                .unwrap();
+            engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+            engine.expire(b"alpha", Duration::from_millis(1)).unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(5));
+
+        let engine = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        assert_eq!(engine.ttl(b"alpha").unwrap(), TtlStatus::Missing);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn aof_rewrite_compacts_log() {
+        let path = temp_path("rewrite");
+        let engine = MemoryEngine::with_shard_count(4)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        for round in 0..50 {
+            let value = format!("v{round}").into_bytes();
+            engine.set(b"alpha".to_vec(), value).unwrap();
+        }
+        engine.set(b"beta".to_vec(), b"gone".to_vec()).unwrap();
+        engine.delete(b"beta").unwrap();
+        let before = std::fs::metadata(&path).unwrap().len();
+
+        engine.rewrite_aof().unwrap();
+        let after = std::fs::metadata(&path).unwrap().len();
+        assert!(
+            after < before,
+            "rewrite did not shrink: {after} >= {before}"
+        );
+
+        // Writes after the rewrite land in the new file.
+        engine.set(b"delta".to_vec(), b"5".to_vec()).unwrap();
+        drop(engine);
+
+        let engine = MemoryEngine::with_shard_count(4)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        assert_eq!(&*engine.get(b"alpha").unwrap().unwrap(), b"v49");
+        assert!(engine.get(b"beta").unwrap().is_none());
+        assert_eq!(&*engine.get(b"delta").unwrap().unwrap(), b"5");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn aof_replay_and_rewrite_restore_lists() {
+        let path = temp_path("lists");
+        {
+            let engine = MemoryEngine::with_shard_count(2)
+                .with_aof(aof_config(&path))
+                .unwrap();
+            let values =
+                |items: &[&str]| items.iter().map(|item| item.as_bytes().to_vec()).collect();
+            engine
+                .rpush(b"queue", values(&["a", "b", "c", "d"]))
+                .unwrap();
+            engine.lpush(b"queue", values(&["z"])).unwrap();
+            engine.lpop(b"queue", 1).unwrap();
+            engine.rpop(b"queue", 1).unwrap();
+            engine.ltrim(b"queue", 0, 1).unwrap();
+            engine.rpush(b"gone", values(&["x"])).unwrap();
+            engine.lpop(b"gone", 1).unwrap();
+        }
+
+        let engine = MemoryEngine::with_shard_count(2)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        let expected: Vec<Arc<[u8]>> = vec![Arc::from(&b"a"[..]), Arc::from(&b"b"[..])];
+        assert_eq!(engine.lrange(b"queue", 0, -1).unwrap(), expected);
+        assert!(engine.key_type(b"gone").is_none());
+
+        engine.rewrite_aof().unwrap();
+        drop(engine);
+        let engine = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&path))
+            .unwrap();
+        assert_eq!(engine.lrange(b"queue", 0, -1).unwrap(), expected);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn aof_and_snapshot_restore_hashes() {
+        let aof_path = temp_path("hashes");
+        let snap_path = temp_path("snapshot-hashes");
+        {
+            let engine = MemoryEngine::with_shard_count(2)
+                .with_aof(aof_config(&aof_path))
+                .unwrap()
+                .with_snapshot(snapshot_config(&snap_path));
+            let pair =
+                |field: &str, value: &str| (field.as_bytes().to_vec(), value.as_bytes().to_vec());
+            engine
+                .hset(b"user", vec![pair("name", "ann"), pair("tmp", "x")])
+                .unwrap();
+            engine.hincrby(b"user", b"visits", 3).unwrap();
+            engine.hincrby(b"user", b"visits", 4).unwrap();
+            engine.hdel(b"user", &[b"tmp".to_vec()]).unwrap();
+            engine.save_snapshot().unwrap();
+        }
+
+        let from_aof = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&aof_path))
+            .unwrap();
+        let from_snapshot =
+            MemoryEngine::with_shard_count(1).with_snapshot(snapshot_config(&snap_path));
+        from_snapshot.load_snapshot().unwrap();
+
+        for engine in [&from_aof, &from_snapshot] {
+            assert_eq!(&*engine.hget(b"user", b"visits").unwrap().unwrap(), b"7");
+            assert_eq!(&*engine.hget(b"user", b"name").unwrap().unwrap(), b"ann");
+            assert!(engine.hget(b"user", b"tmp").unwrap().is_none());
+        }
+        let _ = std::fs::remove_file(aof_path);
+        let _ = std::fs::remove_file(snap_path);
+    }
+
+    #[test]
+    fn aof_and_snapshot_restore_sets() {
+        let aof_path = temp_path("sets");
+        let snap_path = temp_path("snapshot-sets");
+        let members = |items: &[&str]| -> Vec<Vec<u8>> {
+            items.iter().map(|item| item.as_bytes().to_vec()).collect()
+        };
+        {
+            let engine = MemoryEngine::with_shard_count(4)
+                .with_aof(aof_config(&aof_path))
+                .unwrap()
+                .with_snapshot(snapshot_config(&snap_path));
+            engine.sadd(b"a", members(&["1", "2", "3"])).unwrap();
+            engine.sadd(b"b", members(&["2", "3", "4"])).unwrap();
+            engine.srem(b"a", &members(&["1"])).unwrap();
+            engine.sinterstore(b"both", &members(&["a", "b"])).unwrap();
+            engine.save_snapshot().unwrap();
+        }
+
+        let from_aof = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&aof_path))
+            .unwrap();
+        let from_snapshot =
+            MemoryEngine::with_shard_count(1).with_snapshot(snapshot_config(&snap_path));
+        from_snapshot.load_snapshot().unwrap();
+
+        for engine in [&from_aof, &from_snapshot] {
+            assert_eq!(engine.key_type(b"both"), Some(ValueType::Set));
+            assert_eq!(engine.scard(b"both").unwrap(), 2);
+            assert!(!engine.sismember(b"a", b"1").unwrap());
+            assert!(engine.sismember(b"b", b"4").unwrap());
+        }
+        let _ = std::fs::remove_file(aof_path);
+        let _ = std::fs::remove_file(snap_path);
+    }
+
+    #[test]
+    fn aof_rewrite_and_snapshot_restore_sorted_sets() {
+        let aof_path = temp_path("zsets");
+        let snap_path = temp_path("snapshot-zsets");
+        {
+            let engine = MemoryEngine::with_shard_count(2)
+                .with_aof(aof_config(&aof_path))
+                .unwrap()
+                .with_snapshot(snapshot_config(&snap_path));
+            let entries = vec![
+                (0.1, b"a".to_vec()),
+                (2.0, b"b".to_vec()),
+                (3.0, b"c".to_vec()),
+            ];
+            engine.zadd(b"board", entries).unwrap();
+            engine.zincrby(b"board", 0.2, b"a").unwrap();
+            engine.zpopmin(b"board", 1).unwrap();
+            engine
+                .zadd(b"board", vec![(f64::NEG_INFINITY, b"d".to_vec())])
+                .unwrap();
+            engine.save_snapshot().unwrap();
+            engine.rewrite_aof().unwrap();
+        }
+
+        let from_aof = MemoryEngine::with_shard_count(1)
+            .with_aof(aof_config(&aof_path))
+            .unwrap();
+        let from_snapshot =
+            MemoryEngine::with_shard_count(1).with_snapshot(snapshot_config(&snap_path));
+        from_snapshot.load_snapshot().unwrap();
+
+        for engine in [&from_aof, &from_snapshot] {
+            let all = engine.zrange(b"board", 0, -1).unwrap();
+            let names: Vec<&[u8]> = all.iter().map(|(member, _)| &member[..]).collect();
+            assert_eq!(names, vec![&b"d"[..], b"b", b"c"]);
+            assert_eq!(engine.zscore(b"board", b"a").unwrap(), None);
+            assert_eq!(
+                engine.zscore(b"board", b"d").unwrap(),
+                Some(f64::NEG_INFINITY)
+            );
+        }
+        let _ = std::fs::remove_file(aof_path);
+        let _ = std::fs::remove_file(snap_path);
+    }
+
+    #[test]
+    fn snapshot_restores_lists() {
+        let path = temp_path("snapshot-lists");
+        let engine = MemoryEngine::with_shard_count(2).with_snapshot(snapshot_config(&path));
+        engine
+            .rpush(b"queue", vec![b"a".to_vec(), b"b".to_vec()])
+            .unwrap();
+        engine.expire(b"queue", Duration::from_secs(60)).unwrap();
+        engine.save_snapshot().unwrap();
+
+        let restored = MemoryEngine::with_shard_count(2).with_snapshot(snapshot_config(&path));
+        assert_eq!(restored.load_snapshot().unwrap(), 1);
+        assert_eq!(restored.key_type(b"queue"), Some(ValueType::List));
+        assert_eq!(restored.llen(b"queue").unwrap(), 2);
+        assert!(matches!(
+            restored.ttl(b"queue").unwrap(),
+            TtlStatus::ExpiresIn(_)
+        ));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn spawn_aof_rewrite_requires_aof() {
+        let engine = Arc::new(MemoryEngine::with_shard_count(1));
+        assert_eq!(engine.spawn_aof_rewrite(), Err(HkvError::InvalidInput));
+        assert!(engine.rewrite_aof().is_err());
+    }
+
+    fn snapshot_config(path: &std::path::Path) -> SnapshotConfig {
+        SnapshotConfig {
+            path: path.to_path_buf(),
+            save_rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn snapshot_save_and_load_roundtrip() {
+        let path = temp_path("snapshot");
+        let engine = MemoryEngine::with_shard_count(4).with_snapshot(snapshot_config(&path));
+        engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+        engine.set(b"beta".to_vec(), b"2".to_vec()).unwrap();
+        engine.expire(b"beta", Duration::from_secs(60)).unwrap();
+        engine.set(b"gone".to_vec(), b"3".to_vec()).unwrap();
+        engine.delete(b"gone").unwrap();
+        assert_eq!(engine.changes_since_save(), 5);
+
+        engine.save_snapshot().unwrap();
+        assert_eq!(engine.changes_since_save(), 0);
+
+        let restored = MemoryEngine::with_shard_count(2).with_snapshot(snapshot_config(&path));
+        assert_eq!(restored.load_snapshot().unwrap(), 2);
+        assert_eq!(&*restored.get(b"alpha").unwrap().unwrap(), b"1");
+        assert!(matches!(
+            restored.ttl(b"beta").unwrap(),
+            TtlStatus::ExpiresIn(_)
+        ));
+        assert!(restored.get(b"gone").unwrap().is_none());
+        assert_eq!(restored.changes_since_save(), 0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn versions_keep_growing_across_snapshot_reload() {
+        let path = temp_path("snapshot-versions");
+        let engine = MemoryEngine::with_shard_count(2).with_snapshot(snapshot_config(&path));
+        // Pretend earlier runs issued versions far ahead of the wall clock.
+        let high = Version::new(u64::MAX / 2);
+        for shard in &engine.shards {
+            shard.inner.write().raise_version_clock(high);
+        }
+        engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+        let (_, saved) = engine.get_with_version(b"alpha").unwrap().unwrap();
+        assert!(saved > high);
+        engine.save_snapshot().unwrap();
+
+        let restored = MemoryEngine::with_shard_count(2).with_snapshot(snapshot_config(&path));
+        restored.load_snapshot().unwrap();
+        let (_, loaded) = restored.get_with_version(b"alpha").unwrap().unwrap();
+        assert!(loaded > saved);
+        restored.set(b"beta".to_vec(), b"2".to_vec()).unwrap();
+        let (_, fresh) = restored.get_with_version(b"beta").unwrap().unwrap();
+        assert!(fresh > saved);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn snapshot_load_skips_expired_entries() {
+        let path = temp_path("snapshot-expired");
+        let engine = MemoryEngine::with_shard_count(1).with_snapshot(snapshot_config(&path));
+        engine.set(b"short".to_vec(), b"1".to_vec()).unwrap();
+        engine.expire(b"short", Duration::from_millis(20)).unwrap();
+        engine.save_snapshot().unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+
+        let restored = MemoryEngine::with_shard_count(1).with_snapshot(snapshot_config(&path));
+        assert_eq!(restored.load_snapshot().unwrap(), 0);
+        assert!(restored.get(b"short").unwrap().is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn snapshotter_saves_when_rule_is_due() {
+        let path = temp_path("snapshotter");
+        let engine = Arc::new(
+            MemoryEngine::with_shard_count(1).with_snapshot(SnapshotConfig {
+                path: path.clone(),
+                save_rules: vec![SaveRule {
+                    seconds: 0,
+                    changes: 2,
+                }],
+            }),
+        );
+        let handle = engine.start_snapshotter(Duration::from_millis(5));
+
+        engine.set(b"alpha".to_vec(), b"1".to_vec()).unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(!path.exists());
+
+        engine.set(b"beta".to_vec(), b"2".to_vec()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while engine.changes_since_save() != 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        handle.stop();
+
+        assert!(path.exists());
+        assert_eq!(engine.changes_since_save(), 0);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn spawn_snapshot_requires_config() {
+        let engine = Arc::new(MemoryEngine::with_shard_count(1));
+        assert_eq!(engine.spawn_snapshot(), Err(HkvError::InvalidInput));
+        assert!(engine.save_snapshot().is_err());
+    }
 }
@@ -0,0 +1,197 @@
+//! # Hash Commands
+//!
+//! `HashEngine` for `MemoryEngine`, built on the shared collection helpers.
+//! `HINCRBY` is logged to the AOF as the resulting `HSET`, so replay never
+//! depends on integer parsing succeeding twice.
+
+use std::sync::Arc;
+
+use hkv_common::{HkvError, HkvResult};
+
+use super::MemoryEngine;
+use crate::engine::{FieldValue, HashEngine, ScanPage};
+use crate::value::HashValue;
+
+impl HashEngine for MemoryEngine {
+    fn hset(&self, key: &[u8], pairs: Vec<(Vec<u8>, Vec<u8>)>) -> HkvResult<usize> {
+        let pairs: Vec<FieldValue> = pairs
+            .into_iter()
+            .map(|(field, value)| (Arc::from(field), Arc::from(value)))
+            .collect();
+        let mut args: Vec<&[u8]> = Vec::with_capacity(pairs.len() * 2 + 2);
+        args.push(b"HSET");
+        args.push(key);
+        for (field, value) in &pairs {
+            args.push(field);
+            args.push(value);
+        }
+
+        let added = self.update_collection(key, true, &args, |hash: &mut HashValue| {
+            pairs
+                .iter()
+                .filter(|(field, value)| hash.insert(Arc::clone(field), Arc::clone(value)))
+                .count()
+        })?;
+        Ok(added.unwrap_or(0))
+    }
+
+    fn hget(&self, key: &[u8], field: &[u8]) -> HkvResult<Option<Arc<[u8]>>> {
+        let value = self.read_collection(key, |hash: &HashValue| hash.get(field).cloned())?;
+        Ok(value.flatten())
+    }
+
+    fn hdel(&self, key: &[u8], fields: &[Vec<u8>]) -> HkvResult<usize> {
+        let mut args: Vec<&[u8]> = Vec::with_capacity(fields.len() + 2);
+        args.push(b"HDEL");
+        args.push(key);
+        args.extend(fields.iter().map(Vec::as_slice));
+
+        let removed = self.update_collection(key, false, &args, |hash: &mut HashValue| {
+            fields.iter().filter(|field| hash.remove(field)).count()
+        })?;
+        Ok(removed.unwrap_or(0))
+    }
+
+    fn hgetall(&self, key: &[u8]) -> HkvResult<Vec<FieldValue>> {
+        let pairs = self.read_collection(key, |hash: &HashValue| {
+            hash.iter()
+                .map(|(field, value)| (Arc::clone(field), Arc::clone(value)))
+                .collect()
+        })?;
+        Ok(pairs.unwrap_or_default())
+    }
+
+    fn hincrby(&self, key: &[u8], field: &[u8], delta: i64) -> HkvResult<i64> {
+        let slot = self.lock_collection::<HashValue>(key)?;
+        let current = match slot
+            .collection::<HashValue>()
+            .and_then(|hash| hash.get(field))
+        {
+            Some(value) => std::str::from_utf8(value)
+                .ok()
+                .and_then(|text| text.parse::<i64>().ok())
+                .ok_or(HkvError::InvalidInput)?,
+            None => 0,
+        };
+        let updated = current.checked_add(delta).ok_or(HkvError::InvalidInput)?;
+        let text = updated.to_string();
+
+        self.log(slot.shard_idx, &[b"HSET", key, field, text.as_bytes()])?;
+        self.commit_collection(slot, key, |hash: &mut HashValue| {
+            hash.insert(Arc::from(field), Arc::from(text.as_bytes()));
+        })?;
+        Ok(updated)
+    }
+
+    fn hscan(
+        &self,
+        key: &[u8],
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> HkvResult<ScanPage> {
+        let page =
+            self.read_collection(key, |hash: &HashValue| hash.scan(cursor, count, pattern))?;
+        Ok(page.unwrap_or((0, Vec::new())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::KVEngine;
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        items
+            .iter()
+            .map(|(field, value)| (field.as_bytes().to_vec(), value.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn set_get_and_delete_fields() {
+        let engine = MemoryEngine::with_shard_count(1);
+        let added = engine
+            .hset(b"user:1", pairs(&[("name", "ann"), ("age", "30")]))
+            .unwrap();
+        assert_eq!(added, 2);
+        assert_eq!(engine.hset(b"user:1", pairs(&[("age", "31")])).unwrap(), 0);
+        assert_eq!(&*engine.hget(b"user:1", b"age").unwrap().unwrap(), b"31");
+        assert!(engine.hget(b"user:1", b"missing").unwrap().is_none());
+
+        let mut all = engine.hgetall(b"user:1").unwrap();
+        all.sort();
+        assert_eq!(all.len(), 2);
+        assert_eq!(&*all[0].0, b"age");
+
+        let fields = vec![b"name".to_vec(), b"nope".to_vec()];
+        assert_eq!(engine.hdel(b"user:1", &fields).unwrap(), 1);
+        assert_eq!(engine.hdel(b"user:1", &[b"age".to_vec()]).unwrap(), 1);
+        assert!(engine.key_type(b"user:1").is_none());
+        assert_eq!(
+            engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn hincrby_validates_integers() {
+        let engine = MemoryEngine::with_shard_count(1);
+        assert_eq!(engine.hincrby(b"stats", b"visits", 5).unwrap(), 5);
+        assert_eq!(engine.hincrby(b"stats", b"visits", -7).unwrap(), -2);
+
+        engine.hset(b"stats", pairs(&[("name", "x")])).unwrap();
+        assert_eq!(
+            engine.hincrby(b"stats", b"name", 1),
+            Err(HkvError::InvalidInput)
+        );
+        engine
+            .hset(b"stats", pairs(&[("big", &i64::MAX.to_string())]))
+            .unwrap();
+        assert_eq!(
+            engine.hincrby(b"stats", b"big", 1),
+            Err(HkvError::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn wrong_type_is_rejected() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.set(b"text".to_vec(), b"v".to_vec()).unwrap();
+        assert_eq!(
+            engine.hset(b"text", pairs(&[("a", "b")])),
+            Err(HkvError::WrongType)
+        );
+        assert_eq!(engine.hincrby(b"text", b"a", 1), Err(HkvError::WrongType));
+        engine.hset(b"hash", pairs(&[("a", "b")])).unwrap();
+        assert_eq!(engine.get(b"hash"), Err(HkvError::WrongType));
+    }
+
+    #[test]
+    fn hscan_pages_through_all_fields() {
+        let engine = MemoryEngine::with_shard_count(1);
+        let items: Vec<(String, String)> =
+            (0..50).map(|i| (format!("f{i}"), i.to_string())).collect();
+        let borrowed: Vec<(&str, &str)> = items
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+            .collect();
+        engine.hset(b"big", pairs(&borrowed)).unwrap();
+
+        let mut seen = 0;
+        let mut cursor = 0;
+        loop {
+            let (next, batch) = engine.hscan(b"big", cursor, None, 10).unwrap();
+            seen += batch.len();
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen, 50);
+        assert_eq!(
+            engine.hscan(b"missing", 0, None, 10).unwrap(),
+            (0, Vec::new())
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! # List Commands
+//!
+//! `ListEngine` for `MemoryEngine`. Every command goes through the shared
+//! `read_collection`/`update_collection` helpers, so TTL handling, type
+//! checks, AOF logging, and byte accounting stay in one place.
+
+use std::sync::Arc;
+
+use hkv_common::HkvResult;
+
+use super::MemoryEngine;
+use crate::engine::ListEngine;
+use crate::value::ListValue;
+
+/// Which end of the list a push or pop works on.
+#[derive(Clone, Copy)]
+enum End {
+    Head,
+    Tail,
+}
+
+impl MemoryEngine {
+    fn push(&self, key: &[u8], values: Vec<Vec<u8>>, end: End) -> HkvResult<usize> {
+        let cmd: &[u8] = match end {
+            End::Head => b"LPUSH",
+            End::Tail => b"RPUSH",
+        };
+        let items: Vec<Arc<[u8]>> = values.into_iter().map(Arc::from).collect();
+        let mut args: Vec<&[u8]> = Vec::with_capacity(items.len() + 2);
+        args.push(cmd);
+        args.push(key);
+        args.extend(items.iter().map(|item| &item[..]));
+
+        let len = self.update_collection(key, true, &args, |list: &mut ListValue| {
+            for item in &items {
+                match end {
+                    End::Head => list.push_front(Arc::clone(item)),
+                    End::Tail => list.push_back(Arc::clone(item)),
+                }
+            }
+            list.len()
+        })?;
+        Ok(len.unwrap_or(0))
+    }
+
+    fn pop(&self, key: &[u8], count: usize, end: End) -> HkvResult<Option<Vec<Arc<[u8]>>>> {
+        if count == 0 {
+            return self.read_collection(key, |_: &ListValue| Vec::new());
+        }
+
+        let cmd: &[u8] = match end {
+            End::Head => b"LPOP",
+            End::Tail => b"RPOP",
+        };
+        let count_arg = count.to_string();
+        let args: [&[u8]; 3] = [cmd, key, count_arg.as_bytes()];
+
+        self.update_collection(key, false, &args, |list: &mut ListValue| {
+            let mut popped = Vec::with_capacity(count.min(list.len()));
+            while popped.len() < count {
+                let item = match end {
+                    End::Head => list.pop_front(),
+                    End::Tail => list.pop_back(),
+                };
+                match item {
+                    Some(item) => popped.push(item),
+                    None => break,
+                }
+            }
+            popped
+        })
+    }
+}
+
+impl ListEngine for MemoryEngine {
+    fn lpush(&self, key: &[u8], values: Vec<Vec<u8>>) -> HkvResult<usize> {
+        self.push(key, values, End::Head)
+    }
+
+    fn rpush(&self, key: &[u8], values: Vec<Vec<u8>>) -> HkvResult<usize> {
+        self.push(key, values, End::Tail)
+    }
+
+    fn lpop(&self, key: &[u8], count: usize) -> HkvResult<Option<Vec<Arc<[u8]>>>> {
+        self.pop(key, count, End::Head)
+    }
+
+    fn rpop(&self, key: &[u8], count: usize) -> HkvResult<Option<Vec<Arc<[u8]>>>> {
+        self.pop(key, count, End::Tail)
+    }
+
+    fn lrange(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<Vec<Arc<[u8]>>> {
+        let items = self.read_collection(key, |list: &ListValue| list.range(start, stop))?;
+        Ok(items.unwrap_or_default())
+    }
+
+    fn llen(&self, key: &[u8]) -> HkvResult<usize> {
+        let len = self.read_collection(key, |list: &ListValue| list.len())?;
+        Ok(len.unwrap_or(0))
+    }
+
+    fn lindex(&self, key: &[u8], index: i64) -> HkvResult<Option<Arc<[u8]>>> {
+        let item = self.read_collection(key, |list: &ListValue| list.get(index).cloned())?;
+        Ok(item.flatten())
+    }
+
+    fn ltrim(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<()> {
+        let start_arg = start.to_string();
+        let stop_arg = stop.to_string();
+        let args: [&[u8]; 4] = [b"LTRIM", key, start_arg.as_bytes(), stop_arg.as_bytes()];
+        self.update_collection(key, false, &args, |list: &mut ListValue| {
+            list.trim(start, stop)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{KVEngine, TtlStatus};
+    use hkv_common::HkvError;
+    use std::time::Duration;
+
+    fn items(values: &[Arc<[u8]>]) -> Vec<&[u8]> {
+        values.iter().map(|value| &value[..]).collect()
+    }
+
+    fn bytes(values: &[&str]) -> Vec<Vec<u8>> {
+        values
+            .iter()
+            .map(|value| value.as_bytes().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn push_pop_and_range() {
+        let engine = MemoryEngine::with_shard_count(1);
+        assert_eq!(engine.rpush(b"list", bytes(&["b", "c"])).unwrap(), 2);
+        assert_eq!(engine.lpush(b"list", bytes(&["a", "z"])).unwrap(), 4);
+
+        let all = engine.lrange(b"list", 0, -1).unwrap();
+        assert_eq!(items(&all), vec![&b"z"[..], b"a", b"b", b"c"]);
+        assert_eq!(engine.llen(b"list").unwrap(), 4);
+        assert_eq!(&*engine.lindex(b"list", -1).unwrap().unwrap(), b"c");
+        assert!(engine.lindex(b"list", 9).unwrap().is_none());
+
+        let popped = engine.lpop(b"list", 1).unwrap().unwrap();
+        assert_eq!(items(&popped), vec![&b"z"[..]]);
+        let popped = engine.rpop(b"list", 2).unwrap().unwrap();
+        assert_eq!(items(&popped), vec![&b"c"[..], b"b"]);
+        assert!(engine.lpop(b"missing", 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_list_deletes_key() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.rpush(b"list", bytes(&["a", "b", "c"])).unwrap();
+        engine.ltrim(b"list", 1, 1).unwrap();
+        assert_eq!(
+            items(&engine.lrange(b"list", 0, -1).unwrap()),
+            vec![&b"b"[..]]
+        );
+
+        engine.rpop(b"list", 5).unwrap();
+        assert!(engine.key_type(b"list").is_none());
+        assert_eq!(engine.ttl(b"list").unwrap(), TtlStatus::Missing);
+        assert_eq!(
+            engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn wrong_type_is_rejected() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.set(b"text".to_vec(), b"v".to_vec()).unwrap();
+        engine.rpush(b"list", bytes(&["a"])).unwrap();
+
+        assert_eq!(
+            engine.lpush(b"text", bytes(&["a"])),
+            Err(HkvError::WrongType)
+        );
+        assert_eq!(engine.llen(b"text"), Err(HkvError::WrongType));
+        assert_eq!(engine.get(b"list"), Err(HkvError::WrongType));
+
+        // SET overwrites any type, as in Redis.
+        engine.set(b"list".to_vec(), b"v".to_vec()).unwrap();
+        assert_eq!(&*engine.get(b"list").unwrap().unwrap(), b"v");
+    }
+
+    #[test]
+    fn list_elements_count_toward_eviction() {
+        // Each list is 1 byte of key + 8 bytes of elements.
+        let engine = MemoryEngine::with_shard_count_and_capacity(1, 20);
+        engine.rpush(b"a", bytes(&["1234", "5678"])).unwrap();
+        engine.rpush(b"b", bytes(&["1234", "5678"])).unwrap();
+        assert_eq!(engine.llen(b"a").unwrap(), 2);
+
+        engine.rpush(b"b", bytes(&["90123"])).unwrap();
+        assert_eq!(engine.llen(b"a").unwrap(), 0);
+        assert_eq!(engine.llen(b"b").unwrap(), 3);
+        assert_eq!(
+            engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed),
+            14
+        );
+    }
+
+    #[test]
+    fn push_preserves_ttl() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.rpush(b"list", bytes(&["a"])).unwrap();
+        engine.expire(b"list", Duration::from_secs(60)).unwrap();
+        engine.rpush(b"list", bytes(&["b"])).unwrap();
+        assert!(matches!(
+            engine.ttl(b"list").unwrap(),
+            TtlStatus::ExpiresIn(_)
+        ));
+    }
+}
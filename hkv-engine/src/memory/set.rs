@@ -0,0 +1,438 @@
+//! # Set Commands
+//!
+//! `SetEngine` for `MemoryEngine`. Single-key commands use the shared
+//! collection helpers; set algebra locks every involved shard through
+//! `lock_shards` so the read of all sources and the write of `dest` happen
+//! atomically. `*STORE` results are logged as `DEL` + `SADD` of the computed
+//! members, because replaying the original command during an AOF rewrite
+//! could observe source shards at a different point in time.
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use hashbrown::HashSet;
+use hkv_common::{HkvError, HkvResult, Version};
+
+use super::MemoryEngine;
+use crate::engine::SetEngine;
+use crate::value::{Collection, SetValue};
+
+/// Set algebra operation.
+#[derive(Clone, Copy)]
+enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+/// Set algebra result and, when stored, the version stamped on `dest`.
+type Evaluated = (Vec<Arc<[u8]>>, Option<Version>);
+
+impl MemoryEngine {
+    /// `sinterstore` that also returns the version stamped on `dest`.
+    ///
+    /// The version is the new set's, or the delete's when the result is
+    /// empty; callers mirroring `dest` elsewhere (the kernel cache) use it to
+    /// drop stale copies.
+    pub fn sinterstore_versioned(
+        &self,
+        dest: &[u8],
+        keys: &[Vec<u8>],
+    ) -> HkvResult<(usize, Version)> {
+        self.store_set_algebra(SetOp::Inter, dest, keys)
+    }
+
+    /// `sunionstore` that also returns the version stamped on `dest`.
+    pub fn sunionstore_versioned(
+        &self,
+        dest: &[u8],
+        keys: &[Vec<u8>],
+    ) -> HkvResult<(usize, Version)> {
+        self.store_set_algebra(SetOp::Union, dest, keys)
+    }
+
+    /// `sdiffstore` that also returns the version stamped on `dest`.
+    pub fn sdiffstore_versioned(
+        &self,
+        dest: &[u8],
+        keys: &[Vec<u8>],
+    ) -> HkvResult<(usize, Version)> {
+        self.store_set_algebra(SetOp::Diff, dest, keys)
+    }
+
+    /// Stores `op` over `keys` at `dest`; returns the size and `dest`'s version.
+    fn store_set_algebra(
+        &self,
+        op: SetOp,
+        dest: &[u8],
+        keys: &[Vec<u8>],
+    ) -> HkvResult<(usize, Version)> {
+        let (members, version) = self.set_algebra(op, keys, Some(dest))?;
+        Ok((members.len(), version.unwrap_or(Version::ZERO)))
+    }
+
+    /// Evaluates `op` over `keys`, optionally storing the result at `dest`.
+    ///
+    /// Returns the members and, when storing, the version stamped on `dest`.
+    fn set_algebra(
+        &self,
+        op: SetOp,
+        keys: &[Vec<u8>],
+        dest: Option<&[u8]>,
+    ) -> HkvResult<Evaluated> {
+        if keys.is_empty() {
+            return Err(HkvError::InvalidInput);
+        }
+
+        let mut locked: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+        locked.extend(dest);
+        let mut guards = self.lock_shards(&locked);
+        let now = Instant::now();
+
+        // Pass 1: drop expired sources, type-check, and record the access.
+        let mut slots = Vec::with_capacity(keys.len());
+        for key in keys {
+            let shard_idx = self.shard_index(key);
+            let inner = guards.get_mut(shard_idx);
+            let idx = self.live_slot(inner, key, now);
+            if let Some(idx) = idx {
+                let node = inner.nodes[idx].as_ref().ok_or(HkvError::InternalError)?;
+                if SetValue::from_value(&node.value).is_none() {
+                    return Err(HkvError::WrongType);
+                }
+                inner.touch(idx);
+            }
+            slots.push(idx.map(|idx| (shard_idx, idx)));
+        }
+
+        // Pass 2: compute the result from shared borrows of every source.
+        let sets: Vec<Option<&SetValue>> = slots
+            .iter()
+            .map(|slot| {
+                let (shard_idx, idx) = (*slot)?;
+                let node = guards.get(shard_idx).nodes[idx].as_ref()?;
+                SetValue::from_value(&node.value)
+            })
+            .collect();
+        let result = evaluate(op, &sets);
+
+        let mut version = None;
+        if let Some(dest) = dest {
+            let shard_idx = self.shard_index(dest);
+            self.log(shard_idx, &[b"DEL", dest])?;
+            if !result.is_empty() {
+                let mut args: Vec<&[u8]> = Vec::with_capacity(result.len() + 2);
+                args.push(b"SADD");
+                args.push(dest);
+                args.extend(result.iter().map(|member| &member[..]));
+                self.log(shard_idx, &args)?;
+            }
+
+            let inner = guards.get_mut(shard_idx);
+            if let Some(idx) = self.live_slot(inner, dest, now)
+                && let Some(size) = inner.remove_idx(idx)
+            {
+                self.used_bytes.fetch_sub(size, Ordering::Relaxed);
+            }
+            if !result.is_empty() {
+                let mut set = SetValue::default();
+                for member in &result {
+                    set.insert(Arc::clone(member));
+                }
+                let value = set.into_value();
+                let size = Self::entry_size(dest.len(), value.payload_len());
+                let idx = inner.insert_new(Arc::from(dest), value, size);
+                version = inner.nodes[idx].as_ref().map(|node| node.version);
+                self.used_bytes.fetch_add(size, Ordering::Relaxed);
+            } else {
+                // Deleting `dest` still advances its version.
+                version = Some(inner.next_version());
+            }
+            self.changes.fetch_add(1, Ordering::Relaxed);
+
+            drop(guards);
+            self.evict_if_needed();
+        }
+
+        Ok((result, version))
+    }
+}
+
+/// Applies `op` to the sources; `None` entries are missing (empty) sets.
+fn evaluate(op: SetOp, sets: &[Option<&SetValue>]) -> Vec<Arc<[u8]>> {
+    match op {
+        SetOp::Inter => {
+            let mut present: Vec<&SetValue> = match sets.iter().copied().collect() {
+                Some(present) => present,
+                None => return Vec::new(),
+            };
+            present.sort_by_key(|set| set.len());
+            let (smallest, rest) = match present.split_first() {
+                Some(split) => split,
+                None => return Vec::new(),
+            };
+            smallest
+                .iter()
+                .filter(|member| rest.iter().all(|set| set.contains(member)))
+                .cloned()
+                .collect()
+        }
+        SetOp::Union => {
+            let mut seen: HashSet<&[u8]> = HashSet::new();
+            sets.iter()
+                .flatten()
+                .flat_map(|set| set.iter())
+                .filter(|member| seen.insert(&member[..]))
+                .cloned()
+                .collect()
+        }
+        SetOp::Diff => {
+            let first = match sets.first().copied().flatten() {
+                Some(first) => first,
+                None => return Vec::new(),
+            };
+            first
+                .iter()
+                .filter(|member| sets[1..].iter().flatten().all(|set| !set.contains(member)))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+impl SetEngine for MemoryEngine {
+    fn sadd(&self, key: &[u8], members: Vec<Vec<u8>>) -> HkvResult<usize> {
+        let members: Vec<Arc<[u8]>> = members.into_iter().map(Arc::from).collect();
+        let mut args: Vec<&[u8]> = Vec::with_capacity(members.len() + 2);
+        args.push(b"SADD");
+        args.push(key);
+        args.extend(members.iter().map(|member| &member[..]));
+
+        let added = self.update_collection(key, true, &args, |set: &mut SetValue| {
+            members
+                .iter()
+                .filter(|member| set.insert(Arc::clone(member)))
+                .count()
+        })?;
+        Ok(added.unwrap_or(0))
+    }
+
+    fn srem(&self, key: &[u8], members: &[Vec<u8>]) -> HkvResult<usize> {
+        let mut args: Vec<&[u8]> = Vec::with_capacity(members.len() + 2);
+        args.push(b"SREM");
+        args.push(key);
+        args.extend(members.iter().map(Vec::as_slice));
+
+        let removed = self.update_collection(key, false, &args, |set: &mut SetValue| {
+            members.iter().filter(|member| set.remove(member)).count()
+        })?;
+        Ok(removed.unwrap_or(0))
+    }
+
+    fn smembers(&self, key: &[u8]) -> HkvResult<Vec<Arc<[u8]>>> {
+        let members = self.read_collection(key, |set: &SetValue| set.iter().cloned().collect())?;
+        Ok(members.unwrap_or_default())
+    }
+
+    fn sismember(&self, key: &[u8], member: &[u8]) -> HkvResult<bool> {
+        let found = self.read_collection(key, |set: &SetValue| set.contains(member))?;
+        Ok(found.unwrap_or(false))
+    }
+
+    fn scard(&self, key: &[u8]) -> HkvResult<usize> {
+        let len = self.read_collection(key, |set: &SetValue| set.len())?;
+        Ok(len.unwrap_or(0))
+    }
+
+    fn sinter(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>> {
+        self.set_algebra(SetOp::Inter, keys, None)
+            .map(|(members, _)| members)
+    }
+
+    fn sunion(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>> {
+        self.set_algebra(SetOp::Union, keys, None)
+            .map(|(members, _)| members)
+    }
+
+    fn sdiff(&self, keys: &[Vec<u8>]) -> HkvResult<Vec<Arc<[u8]>>> {
+        self.set_algebra(SetOp::Diff, keys, None)
+            .map(|(members, _)| members)
+    }
+
+    fn sinterstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize> {
+        self.sinterstore_versioned(dest, keys).map(|(len, _)| len)
+    }
+
+    fn sunionstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize> {
+        self.sunionstore_versioned(dest, keys).map(|(len, _)| len)
+    }
+
+    fn sdiffstore(&self, dest: &[u8], keys: &[Vec<u8>]) -> HkvResult<usize> {
+        self.sdiffstore_versioned(dest, keys).map(|(len, _)| len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::KVEngine;
+    use crate::value::ValueType;
+    use std::thread;
+
+    fn bytes(values: &[&str]) -> Vec<Vec<u8>> {
+        values
+            .iter()
+            .map(|value| value.as_bytes().to_vec())
+            .collect()
+    }
+
+    fn sorted(values: Vec<Arc<[u8]>>) -> Vec<Vec<u8>> {
+        let mut values: Vec<Vec<u8>> = values.iter().map(|value| value.to_vec()).collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn add_remove_and_membership() {
+        let engine = MemoryEngine::with_shard_count(1);
+        assert_eq!(engine.sadd(b"s", bytes(&["a", "b", "a"])).unwrap(), 2);
+        assert_eq!(engine.sadd(b"s", bytes(&["b", "c"])).unwrap(), 1);
+        assert_eq!(engine.scard(b"s").unwrap(), 3);
+        assert!(engine.sismember(b"s", b"c").unwrap());
+        assert!(!engine.sismember(b"missing", b"c").unwrap());
+        assert_eq!(
+            sorted(engine.smembers(b"s").unwrap()),
+            bytes(&["a", "b", "c"])
+        );
+
+        assert_eq!(engine.srem(b"s", &bytes(&["a", "x"])).unwrap(), 1);
+        assert_eq!(engine.srem(b"s", &bytes(&["b", "c"])).unwrap(), 2);
+        assert!(engine.key_type(b"s").is_none());
+        assert_eq!(
+            engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn algebra_across_shards() {
+        let engine = MemoryEngine::with_shard_count(8);
+        engine.sadd(b"a", bytes(&["1", "2", "3", "4"])).unwrap();
+        engine.sadd(b"b", bytes(&["3", "4", "5"])).unwrap();
+        engine.sadd(b"c", bytes(&["4", "6"])).unwrap();
+
+        let keys = bytes(&["a", "b", "c"]);
+        assert_eq!(sorted(engine.sinter(&keys).unwrap()), bytes(&["4"]));
+        assert_eq!(
+            sorted(engine.sunion(&keys).unwrap()),
+            bytes(&["1", "2", "3", "4", "5", "6"])
+        );
+        assert_eq!(sorted(engine.sdiff(&keys).unwrap()), bytes(&["1", "2"]));
+
+        // Missing keys behave as empty sets.
+        assert!(engine.sinter(&bytes(&["a", "nope"])).unwrap().is_empty());
+        assert_eq!(engine.sunion(&bytes(&["nope", "c"])).unwrap().len(), 2);
+        assert!(engine.sdiff(&bytes(&["nope", "a"])).unwrap().is_empty());
+        assert_eq!(engine.sinter(&[]), Err(HkvError::InvalidInput));
+    }
+
+    #[test]
+    fn store_replaces_destination() {
+        let engine = MemoryEngine::with_shard_count(4);
+        engine.sadd(b"a", bytes(&["1", "2"])).unwrap();
+        engine.sadd(b"b", bytes(&["2", "3"])).unwrap();
+        engine.set(b"dest".to_vec(), b"text".to_vec()).unwrap();
+
+        assert_eq!(engine.sunionstore(b"dest", &bytes(&["a", "b"])).unwrap(), 3);
+        assert_eq!(engine.key_type(b"dest"), Some(ValueType::Set));
+
+        // The destination may also be one of the sources.
+        assert_eq!(engine.sdiffstore(b"a", &bytes(&["a", "b"])).unwrap(), 1);
+        assert_eq!(sorted(engine.smembers(b"a").unwrap()), bytes(&["1"]));
+
+        // An empty result deletes the destination.
+        assert_eq!(engine.sinterstore(b"dest", &bytes(&["a", "b"])).unwrap(), 0);
+        assert!(engine.key_type(b"dest").is_none());
+
+        let used = engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(used, (1 + 1) + (1 + 2));
+    }
+
+    #[test]
+    fn versioned_store_reports_the_destination_version() {
+        let engine = MemoryEngine::with_shard_count(4);
+        engine.sadd(b"a", bytes(&["1", "2"])).unwrap();
+        engine.sadd(b"b", bytes(&["2", "3"])).unwrap();
+        let text = engine
+            .set_versioned(b"dest".to_vec(), b"text".to_vec())
+            .unwrap();
+
+        let (len, stored) = engine
+            .sunionstore_versioned(b"dest", &bytes(&["a", "b"]))
+            .unwrap();
+        assert_eq!(len, 3);
+        assert!(stored > text);
+        assert_eq!(engine.key_version(b"dest"), Some(stored));
+
+        let (len, diffed) = engine
+            .sdiffstore_versioned(b"dest", &bytes(&["a", "b"]))
+            .unwrap();
+        assert_eq!(len, 1);
+        assert!(diffed > stored);
+
+        // An empty result deletes `dest` and still reports a newer version.
+        let (len, deleted) = engine
+            .sinterstore_versioned(b"dest", &bytes(&["a", "missing"]))
+            .unwrap();
+        assert_eq!(len, 0);
+        assert!(deleted > diffed);
+        assert_eq!(engine.key_version(b"dest"), None);
+    }
+
+    #[test]
+    fn wrong_type_is_rejected() {
+        let engine = MemoryEngine::with_shard_count(2);
+        engine.set(b"text".to_vec(), b"v".to_vec()).unwrap();
+        engine.sadd(b"set", bytes(&["a"])).unwrap();
+
+        assert_eq!(
+            engine.sadd(b"text", bytes(&["a"])),
+            Err(HkvError::WrongType)
+        );
+        assert_eq!(
+            engine.sunion(&bytes(&["set", "text"])),
+            Err(HkvError::WrongType)
+        );
+        assert_eq!(engine.get(b"set"), Err(HkvError::WrongType));
+    }
+
+    #[test]
+    fn concurrent_store_in_opposite_orders_does_not_deadlock() {
+        let engine = Arc::new(MemoryEngine::with_shard_count(16));
+        for key in ["a", "b", "c", "d"] {
+            engine.sadd(key.as_bytes(), bytes(&["x", key])).unwrap();
+        }
+
+        let handles: Vec<_> = (0..4)
+            .map(|thread_idx| {
+                let engine = Arc::clone(&engine);
+                thread::spawn(move || {
+                    let mut keys = bytes(&["a", "b", "c", "d"]);
+                    if thread_idx % 2 == 1 {
+                        keys.reverse();
+                    }
+                    let dest = format!("dest{thread_idx}");
+                    for _ in 0..500 {
+                        engine.sunionstore(dest.as_bytes(), &keys).unwrap();
+                        engine.sinter(&keys).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(engine.scard(b"dest0").unwrap(), 5);
+    }
+}
@@ -0,0 +1,236 @@
+//! # Sorted Set Commands
+//!
+//! `ZSetEngine` for `MemoryEngine`, built on the shared collection helpers.
+//! Scores are written to the AOF with `f64`'s shortest round-trip formatting,
+//! and `ZINCRBY` is logged as the resulting `ZADD` so replay is exact.
+
+use std::sync::Arc;
+
+use hkv_common::{HkvError, HkvResult};
+
+use super::MemoryEngine;
+use crate::engine::{ScoreBound, ScoredMember, ZSetEngine};
+use crate::value::ZSetValue;
+
+impl ZSetEngine for MemoryEngine {
+    fn zadd(&self, key: &[u8], entries: Vec<(f64, Vec<u8>)>) -> HkvResult<usize> {
+        if entries.iter().any(|(score, _)| score.is_nan()) {
+            return Err(HkvError::InvalidInput);
+        }
+        let entries: Vec<(f64, Arc<[u8]>)> = entries
+            .into_iter()
+            .map(|(score, member)| (score, Arc::from(member)))
+            .collect();
+        let scores: Vec<String> = entries.iter().map(|(score, _)| score.to_string()).collect();
+        let mut args: Vec<&[u8]> = Vec::with_capacity(entries.len() * 2 + 2);
+        args.push(b"ZADD");
+        args.push(key);
+        for ((_, member), score) in entries.iter().zip(&scores) {
+            args.push(score.as_bytes());
+            args.push(member);
+        }
+
+        let added = self.update_collection(key, true, &args, |zset: &mut ZSetValue| {
+            entries
+                .iter()
+                .filter(|(score, member)| zset.insert(Arc::clone(member), *score))
+                .count()
+        })?;
+        Ok(added.unwrap_or(0))
+    }
+
+    fn zrem(&self, key: &[u8], members: &[Vec<u8>]) -> HkvResult<usize> {
+        let mut args: Vec<&[u8]> = Vec::with_capacity(members.len() + 2);
+        args.push(b"ZREM");
+        args.push(key);
+        args.extend(members.iter().map(Vec::as_slice));
+
+        let removed = self.update_collection(key, false, &args, |zset: &mut ZSetValue| {
+            members.iter().filter(|member| zset.remove(member)).count()
+        })?;
+        Ok(removed.unwrap_or(0))
+    }
+
+    fn zscore(&self, key: &[u8], member: &[u8]) -> HkvResult<Option<f64>> {
+        let score = self.read_collection(key, |zset: &ZSetValue| zset.score(member))?;
+        Ok(score.flatten())
+    }
+
+    fn zrank(&self, key: &[u8], member: &[u8]) -> HkvResult<Option<usize>> {
+        let rank = self.read_collection(key, |zset: &ZSetValue| zset.rank(member))?;
+        Ok(rank.flatten())
+    }
+
+    fn zcard(&self, key: &[u8]) -> HkvResult<usize> {
+        let len = self.read_collection(key, |zset: &ZSetValue| zset.len())?;
+        Ok(len.unwrap_or(0))
+    }
+
+    fn zrange(&self, key: &[u8], start: i64, stop: i64) -> HkvResult<Vec<ScoredMember>> {
+        let items = self.read_collection(key, |zset: &ZSetValue| zset.range(start, stop))?;
+        Ok(items.unwrap_or_default())
+    }
+
+    fn zrangebyscore(
+        &self,
+        key: &[u8],
+        min: ScoreBound,
+        max: ScoreBound,
+        limit: Option<(usize, usize)>,
+    ) -> HkvResult<Vec<ScoredMember>> {
+        let (offset, count) = match limit {
+            Some((offset, count)) => (offset, Some(count)),
+            None => (0, None),
+        };
+        let items = self.read_collection(key, |zset: &ZSetValue| {
+            zset.range_by_score(min, max, offset, count)
+        })?;
+        Ok(items.unwrap_or_default())
+    }
+
+    fn zincrby(&self, key: &[u8], delta: f64, member: &[u8]) -> HkvResult<f64> {
+        let slot = self.lock_collection::<ZSetValue>(key)?;
+        let current = slot
+            .collection::<ZSetValue>()
+            .and_then(|zset| zset.score(member))
+            .unwrap_or(0.0);
+        let updated = current + delta;
+        if updated.is_nan() {
+            return Err(HkvError::InvalidInput);
+        }
+        let text = updated.to_string();
+
+        self.log(slot.shard_idx, &[b"ZADD", key, text.as_bytes(), member])?;
+        self.commit_collection(slot, key, |zset: &mut ZSetValue| {
+            zset.insert(Arc::from(member), updated);
+        })?;
+        Ok(updated)
+    }
+
+    fn zpopmin(&self, key: &[u8], count: usize) -> HkvResult<Vec<ScoredMember>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let count_arg = count.to_string();
+        let args: [&[u8]; 3] = [b"ZPOPMIN", key, count_arg.as_bytes()];
+        let popped = self.update_collection(key, false, &args, |zset: &mut ZSetValue| {
+            zset.pop_min(count)
+        })?;
+        Ok(popped.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::KVEngine;
+    use crate::value::ValueType;
+
+    fn entries(items: &[(f64, &str)]) -> Vec<(f64, Vec<u8>)> {
+        items
+            .iter()
+            .map(|(score, member)| (*score, member.as_bytes().to_vec()))
+            .collect()
+    }
+
+    fn members(items: &[ScoredMember]) -> Vec<&[u8]> {
+        items.iter().map(|(member, _)| &member[..]).collect()
+    }
+
+    #[test]
+    fn add_score_rank_and_range() {
+        let engine = MemoryEngine::with_shard_count(1);
+        let added = engine
+            .zadd(
+                b"board",
+                entries(&[(30.0, "carl"), (10.0, "ann"), (20.0, "bob")]),
+            )
+            .unwrap();
+        assert_eq!(added, 3);
+        assert_eq!(engine.zadd(b"board", entries(&[(5.0, "carl")])).unwrap(), 0);
+        assert_eq!(engine.key_type(b"board"), Some(ValueType::ZSet));
+        assert_eq!(engine.zcard(b"board").unwrap(), 3);
+        assert_eq!(engine.zscore(b"board", b"carl").unwrap(), Some(5.0));
+        assert_eq!(engine.zrank(b"board", b"carl").unwrap(), Some(0));
+        assert_eq!(engine.zrank(b"board", b"bob").unwrap(), Some(2));
+        assert_eq!(engine.zrank(b"board", b"nobody").unwrap(), None);
+
+        let all = engine.zrange(b"board", 0, -1).unwrap();
+        assert_eq!(members(&all), vec![&b"carl"[..], b"ann", b"bob"]);
+        let tail = engine.zrange(b"board", -1, -1).unwrap();
+        assert_eq!(tail, vec![(Arc::from(&b"bob"[..]), 20.0)]);
+        assert!(engine.zrange(b"missing", 0, -1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn range_by_score_honors_bounds_and_limit() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine
+            .zadd(
+                b"window",
+                entries(&[(1.0, "a"), (2.0, "b"), (3.0, "c"), (4.0, "d")]),
+            )
+            .unwrap();
+
+        let inclusive = engine
+            .zrangebyscore(
+                b"window",
+                ScoreBound::Inclusive(2.0),
+                ScoreBound::Inclusive(3.0),
+                None,
+            )
+            .unwrap();
+        assert_eq!(members(&inclusive), vec![&b"b"[..], b"c"]);
+
+        let exclusive = engine
+            .zrangebyscore(
+                b"window",
+                ScoreBound::Exclusive(1.0),
+                ScoreBound::Inclusive(f64::INFINITY),
+                Some((1, 2)),
+            )
+            .unwrap();
+        assert_eq!(members(&exclusive), vec![&b"c"[..], b"d"]);
+    }
+
+    #[test]
+    fn zincrby_and_zpopmin() {
+        let engine = MemoryEngine::with_shard_count(1);
+        assert_eq!(engine.zincrby(b"z", 2.5, b"a").unwrap(), 2.5);
+        assert_eq!(engine.zincrby(b"z", -1.0, b"a").unwrap(), 1.5);
+        engine
+            .zadd(b"z", entries(&[(f64::INFINITY, "top")]))
+            .unwrap();
+        assert_eq!(
+            engine.zincrby(b"z", f64::NEG_INFINITY, b"top"),
+            Err(HkvError::InvalidInput)
+        );
+        assert_eq!(
+            engine.zadd(b"z", entries(&[(f64::NAN, "bad")])),
+            Err(HkvError::InvalidInput)
+        );
+
+        let popped = engine.zpopmin(b"z", 1).unwrap();
+        assert_eq!(popped, vec![(Arc::from(&b"a"[..]), 1.5)]);
+        assert_eq!(engine.zpopmin(b"z", 10).unwrap().len(), 1);
+        assert!(engine.key_type(b"z").is_none());
+        assert_eq!(
+            engine.used_bytes.load(std::sync::atomic::Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn wrong_type_is_rejected() {
+        let engine = MemoryEngine::with_shard_count(1);
+        engine.set(b"text".to_vec(), b"v".to_vec()).unwrap();
+        assert_eq!(
+            engine.zadd(b"text", entries(&[(1.0, "a")])),
+            Err(HkvError::WrongType)
+        );
+        assert_eq!(engine.zincrby(b"text", 1.0, b"a"), Err(HkvError::WrongType));
+        engine.zadd(b"zset", entries(&[(1.0, "a")])).unwrap();
+        assert_eq!(engine.get(b"zset"), Err(HkvError::WrongType));
+    }
+}
@@ -0,0 +1,406 @@
+//! # Skiplist Index
+//!
+//! Score-ordered index behind sorted sets. Entries are ordered by
+//! `(score, member)`, and every forward link records how many level-0 nodes
+//! it skips, so rank lookups and rank-based range starts are O(log n).
+//!
+//! ## Design Principles
+//!
+//! 1. **Arena Storage**: Nodes live in a `Vec` and link by index, mirroring
+//!    the node arena in `memory.rs`; freed slots are reused and cloning the list
+//!    is a plain vector copy.
+//! 2. **Spans for Ranks**: Each level stores the distance to its successor,
+//!    as in Redis' `zskiplist`, so `ZRANK`/`ZRANGE` never walk from the head.
+//! 3. **Callers Own Uniqueness**: The list does not look members up by name;
+//!    `ZSetValue` keeps a member → score map and passes the current score to
+//!    `remove`/`rank`.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use ahash::RandomState;
+
+/// Maximum tower height; 32 levels cover 4^32 entries at p = 1/4.
+const MAX_LEVEL: usize = 32;
+
+/// Sentinel index for "no successor".
+const NIL: usize = usize::MAX;
+
+/// Index of the head sentinel node.
+const HEAD: usize = 0;
+
+#[derive(Clone, Copy)]
+struct Link {
+    next: usize,
+    /// Level-0 nodes between this node and `next` (inclusive of `next`).
+    span: usize,
+}
+
+#[derive(Clone)]
+struct SkipNode {
+    score: f64,
+    member: Arc<[u8]>,
+    links: Vec<Link>,
+}
+
+/// Ordered `(score, member)` index with rank support.
+#[derive(Clone)]
+pub(crate) struct SkipList {
+    /// Node arena; slot 0 is the head sentinel.
+    nodes: Vec<SkipNode>,
+    free: Vec<usize>,
+    /// Number of levels currently in use (at least 1).
+    level: usize,
+    len: usize,
+    /// xorshift state for tower heights, seeded per instance.
+    rng: u64,
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        let head = SkipNode {
+            score: f64::NEG_INFINITY,
+            member: Arc::from(&[][..]),
+            links: vec![Link { next: NIL, span: 0 }; MAX_LEVEL],
+        };
+        Self {
+            nodes: vec![head],
+            free: Vec::new(),
+            level: 1,
+            len: 0,
+            rng: seed(),
+        }
+    }
+}
+
+/// Draws a non-zero xorshift seed that differs between instances.
+///
+/// A shared fixed seed would give every sorted set the same tower heights,
+/// so inputs tuned against one would degrade all of them.
+fn seed() -> u64 {
+    RandomState::new().hash_one(0x9E37_79B9_7F4A_7C15u64) | 1
+}
+
+impl SkipList {
+    /// Returns the number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Inserts `(score, member)`; the pair must not already be present.
+    pub(crate) fn insert(&mut self, score: f64, member: Arc<[u8]>) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0usize; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i + 1 == self.level { 0 } else { rank[i + 1] };
+            loop {
+                let link = self.nodes[x].links[i];
+                if link.next != NIL && self.precedes(link.next, score, &member) {
+                    rank[i] += link.span;
+                    x = link.next;
+                } else {
+                    break;
+                }
+            }
+            update[i] = x;
+        }
+
+        let height = self.random_level();
+        if height > self.level {
+            for i in self.level..height {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].links[i].span = self.len;
+            }
+            self.level = height;
+        }
+
+        let node = self.alloc(score, member, height);
+        for i in 0..height {
+            let prev = update[i];
+            let prev_link = self.nodes[prev].links[i];
+            let before = rank[0] - rank[i];
+            self.nodes[node].links[i] = Link {
+                next: prev_link.next,
+                span: prev_link.span - before,
+            };
+            self.nodes[prev].links[i] = Link {
+                next: node,
+                span: before + 1,
+            };
+        }
+        for (i, &prev) in update.iter().enumerate().take(self.level).skip(height) {
+            self.nodes[prev].links[i].span += 1;
+        }
+        self.len += 1;
+    }
+
+    /// Removes `(score, member)`. Returns true when it was present.
+    pub(crate) fn remove(&mut self, score: f64, member: &[u8]) -> bool {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            loop {
+                let next = self.nodes[x].links[i].next;
+                if next != NIL && self.precedes(next, score, member) {
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+            update[i] = x;
+        }
+
+        let target = self.nodes[update[0]].links[0].next;
+        if target == NIL || self.compare(target, score, member) != Ordering::Equal {
+            return false;
+        }
+
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.nodes[prev].links[i].next == target {
+                let removed = self.nodes[target].links[i];
+                let link = &mut self.nodes[prev].links[i];
+                link.span += removed.span;
+                link.span -= 1;
+                link.next = removed.next;
+            } else {
+                self.nodes[prev].links[i].span -= 1;
+            }
+        }
+        while self.level > 1 && self.nodes[HEAD].links[self.level - 1].next == NIL {
+            self.level -= 1;
+        }
+
+        let node = &mut self.nodes[target];
+        node.member = Arc::from(&[][..]);
+        node.links.clear();
+        self.free.push(target);
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the 0-based rank of `(score, member)` if present.
+    pub(crate) fn rank(&self, score: f64, member: &[u8]) -> Option<usize> {
+        let mut x = HEAD;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let link = self.nodes[x].links[i];
+                if link.next != NIL && self.compare(link.next, score, member) != Ordering::Greater {
+                    traversed += link.span;
+                    x = link.next;
+                } else {
+                    break;
+                }
+            }
+            if x != HEAD && self.compare(x, score, member) == Ordering::Equal {
+                return Some(traversed - 1);
+            }
+        }
+        None
+    }
+
+    /// Iterates entries in order, starting at 0-based `rank`.
+    pub(crate) fn iter_from_rank(&self, rank: usize) -> Iter<'_> {
+        if rank >= self.len {
+            return Iter {
+                list: self,
+                next: NIL,
+            };
+        }
+
+        let target = rank + 1;
+        let mut x = HEAD;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let link = self.nodes[x].links[i];
+                if link.next != NIL && traversed + link.span <= target {
+                    traversed += link.span;
+                    x = link.next;
+                } else {
+                    break;
+                }
+            }
+            if traversed == target {
+                break;
+            }
+        }
+        Iter {
+            list: self,
+            next: x,
+        }
+    }
+
+    /// Iterates entries in order, starting at the first score for which
+    /// `below` returns false.
+    pub(crate) fn iter_from_score(&self, below: impl Fn(f64) -> bool) -> Iter<'_> {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            loop {
+                let next = self.nodes[x].links[i].next;
+                if next != NIL && below(self.nodes[next].score) {
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+        }
+        Iter {
+            list: self,
+            next: self.nodes[x].links[0].next,
+        }
+    }
+
+    /// Iterates all entries in `(score, member)` order.
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        Iter {
+            list: self,
+            next: self.nodes[HEAD].links[0].next,
+        }
+    }
+
+    /// Orders node `idx` against `(score, member)`.
+    fn compare(&self, idx: usize, score: f64, member: &[u8]) -> Ordering {
+        let node = &self.nodes[idx];
+        node.score
+            .partial_cmp(&score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| node.member[..].cmp(member))
+    }
+
+    fn precedes(&self, idx: usize, score: f64, member: &[u8]) -> bool {
+        self.compare(idx, score, member) == Ordering::Less
+    }
+
+    fn alloc(&mut self, score: f64, member: Arc<[u8]>, height: usize) -> usize {
+        let node = SkipNode {
+            score,
+            member,
+            links: vec![Link { next: NIL, span: 0 }; height],
+        };
+        match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Draws a tower height with P(level > k) = 4^-k.
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            if self.rng & 3 != 0 {
+                break;
+            }
+            level += 1;
+        }
+        level
+    }
+}
+
+impl fmt::Debug for SkipList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// In-order iterator over `(member, score)` entries.
+pub(crate) struct Iter<'a> {
+    list: &'a SkipList,
+    next: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Arc<[u8]>, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == NIL {
+            return None;
+        }
+        let node = &self.list.nodes[self.next];
+        self.next = node.links[0].next;
+        Some((&node.member, node.score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members(iter: Iter<'_>) -> Vec<Vec<u8>> {
+        iter.map(|(member, _)| member.to_vec()).collect()
+    }
+
+    #[test]
+    fn orders_by_score_then_member() {
+        let mut list = SkipList::default();
+        list.insert(2.0, Arc::from(&b"b"[..]));
+        list.insert(1.0, Arc::from(&b"z"[..]));
+        list.insert(2.0, Arc::from(&b"a"[..]));
+        assert_eq!(
+            members(list.iter()),
+            vec![b"z".to_vec(), b"a".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(list.rank(2.0, b"b"), Some(2));
+        assert_eq!(list.rank(2.0, b"missing"), None);
+    }
+
+    #[test]
+    fn ranks_stay_consistent_through_churn() {
+        let mut list = SkipList::default();
+        for i in 0..1000u32 {
+            // Interleave scores so inserts land all over the list.
+            let score = f64::from((i * 7919) % 1000);
+            list.insert(score, Arc::from(i.to_be_bytes().to_vec()));
+        }
+        for i in (0..1000u32).step_by(3) {
+            let score = f64::from((i * 7919) % 1000);
+            assert!(list.remove(score, &i.to_be_bytes()));
+        }
+        assert!(!list.remove(0.5, b"nope"));
+
+        let ordered: Vec<(Vec<u8>, f64)> = list
+            .iter()
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect();
+        assert_eq!(ordered.len(), list.len());
+        assert!(ordered.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        for (rank, (member, score)) in ordered.iter().enumerate() {
+            assert_eq!(list.rank(*score, member), Some(rank));
+            let (at_rank, _) = list.iter_from_rank(rank).next().unwrap();
+            assert_eq!(&at_rank[..], &member[..]);
+        }
+        assert!(list.iter_from_rank(list.len()).next().is_none());
+    }
+
+    #[test]
+    fn instances_get_their_own_seed() {
+        let seeds: Vec<u64> = (0..4).map(|_| SkipList::default().rng).collect();
+        assert!(seeds.iter().all(|seed| *seed != 0));
+        assert!(seeds.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn seeks_by_score() {
+        let mut list = SkipList::default();
+        for (score, member) in [(1.0, "a"), (2.0, "b"), (3.0, "c")] {
+            list.insert(score, Arc::from(member.as_bytes()));
+        }
+        let from_two = members(list.iter_from_score(|score| score < 2.0));
+        assert_eq!(from_two, vec![b"b".to_vec(), b"c".to_vec()]);
+        let after_two = members(list.iter_from_score(|score| score <= 2.0));
+        assert_eq!(after_two, vec![b"c".to_vec()]);
+        assert!(list.iter_from_score(|_| true).next().is_none());
+    }
+}
@@ -0,0 +1,507 @@
+//! # Point-in-Time Snapshots
+//!
+//! Serialize the whole keyspace into a compact, versioned, checksummed binary
+//! file (RDB-like) and rebuild the shards from it on startup.
+//!
+//! ## Usage
+//!
+//! - Attach a target with `MemoryEngine::with_snapshot(SnapshotConfig { .. })`.
+//! - `save_snapshot` blocks (`SAVE`), `spawn_snapshot` runs on a background
+//!   thread (`BGSAVE`), and `start_snapshotter` applies the
+//!   "save after N changes in M seconds" rules periodically.
+//! - `load_snapshot` restores the configured file into an empty engine.
+//!
+//! ## Design Principles
+//!
+//! 1. **Point-in-Time**: All shard read locks are taken (in index order) while
+//!    entries are collected, so the file reflects a single instant. Values are
+//!    `Arc`-backed, making the collection pass a cheap pointer copy.
+//! 2. **Absolute Expiry**: Deadlines are stored as Unix milliseconds so keys
+//!    that expire while the server is down are dropped on load.
+//! 3. **Crash Safety**: Files are written to `<path>.tmp`, fsynced, then renamed
+//!    over the target so a crash never leaves a half-written snapshot.
+//! 4. **Fail Loudly on Corruption**: A CRC32 over the whole file is verified
+//!    before any entry is applied.
+//! 5. **Version High-Water Mark**: The header records the highest key version
+//!    handed out, so a restored engine never reissues a version the kernel
+//!    cache tier may still hold.
+//!
+//! ## File Layout
+//!
+//! ```text
+//! +-------------+------------+---------------+----------------+-------------+
+//! | magic:8B    | version:2B | created_ms:8B | max_version:8B | entries ... |
+//! +-------------+------------+---------------+----------------+-------------+
+//! | end:1B (0xFF)            | crc32:4B (over everything before)           |
+//! +--------------------------+---------------------------------------------+
+//!
+//! Entry:
+//! +--------+---------------+-----------+-----+---------------------+
+//! | type:1B| expire_ms:8B  | key_len:4B| key | payload (per type)  |
+//! +--------+---------------+-----------+-----+---------------------+
+//!
+//! Payloads:
+//!   string: value_len:4B value
+//!   list:   count:4B (item_len:4B item)*count
+//!   hash:   count:4B (field_len:4B field value_len:4B value)*count
+//!   set:    count:4B (member_len:4B member)*count
+//!   zset:   count:4B (member_len:4B member score:8B)*count   (f64 bits)
+//! ```
+//!
+//! All integers are little-endian; `expire_ms == 0` means no expiration.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use hkv_common::Version;
+
+use crate::value::{HashValue, ListValue, SetValue, StoredValue, ZSetValue};
+
+/// File magic identifying a HybridKV snapshot.
+const MAGIC: &[u8; 8] = b"HKVSNAP\0";
+
+/// Current snapshot format version.
+///
+/// `read` rejects any other version instead of misreading the file.
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// Type tag for a string entry.
+const TYPE_STRING: u8 = 0x01;
+
+/// Type tag for a list entry.
+const TYPE_LIST: u8 = 0x02;
+
+/// Type tag for a hash entry.
+const TYPE_HASH: u8 = 0x03;
+
+/// Type tag for a set entry.
+const TYPE_SET: u8 = 0x04;
+
+/// Type tag for a sorted set entry.
+const TYPE_ZSET: u8 = 0x05;
+
+/// Marker terminating the entry stream.
+const TYPE_END: u8 = 0xFF;
+
+/// One "save after `changes` writes within `seconds`" rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveRule {
+    /// Minimum seconds since the last successful save.
+    pub seconds: u64,
+    /// Minimum number of writes since the last successful save.
+    pub changes: u64,
+}
+
+impl SaveRule {
+    /// Parses the Redis `save` directive format, e.g. `"900 1 300 10"`.
+    ///
+    /// Returns `None` on odd token counts or non-numeric tokens. An empty
+    /// string yields an empty rule list (scheduling disabled).
+    pub fn parse_list(spec: &str) -> Option<Vec<SaveRule>> {
+        let tokens: Vec<&str> = spec.split_whitespace().collect();
+        if !tokens.len().is_multiple_of(2) {
+            return None;
+        }
+        tokens
+            .chunks(2)
+            .map(|pair| {
+                Some(SaveRule {
+                    seconds: pair[0].parse().ok()?,
+                    changes: pair[1].parse().ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns true when the rule fires for the given counters.
+    pub fn is_due(&self, changes: u64, seconds_since_save: u64) -> bool {
+        changes >= self.changes && seconds_since_save >= self.seconds
+    }
+}
+
+/// Configuration for snapshot persistence.
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Snapshot file path (Redis `dbfilename`).
+    pub path: PathBuf,
+    /// Rules evaluated by the background snapshotter.
+    pub save_rules: Vec<SaveRule>,
+}
+
+/// A single keyspace entry captured for a snapshot.
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotEntry {
+    pub(crate) key: Arc<[u8]>,
+    pub(crate) value: StoredValue,
+    /// Absolute expiration in Unix milliseconds.
+    pub(crate) expires_at_ms: Option<u64>,
+}
+
+/// Contents of a snapshot file.
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    /// Highest key version handed out when the snapshot was taken.
+    pub(crate) max_version: Version,
+    pub(crate) entries: Vec<SnapshotEntry>,
+}
+
+/// Writes `snapshot` to `path` atomically (temp file + fsync + rename).
+pub(crate) fn write(path: &Path, created_ms: u64, snapshot: &Snapshot) -> io::Result<()> {
+    let entries = &snapshot.entries;
+    let mut buf = Vec::with_capacity(64 + entries.len() * 32);
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    buf.extend_from_slice(&created_ms.to_le_bytes());
+    buf.extend_from_slice(&snapshot.max_version.get().to_le_bytes());
+
+    for entry in entries {
+        let tag = match entry.value {
+            StoredValue::String(_) => TYPE_STRING,
+            StoredValue::List(_) => TYPE_LIST,
+            StoredValue::Hash(_) => TYPE_HASH,
+            StoredValue::Set(_) => TYPE_SET,
+            StoredValue::ZSet(_) => TYPE_ZSET,
+        };
+        buf.push(tag);
+        buf.extend_from_slice(&entry.expires_at_ms.unwrap_or(0).to_le_bytes());
+        put_bytes(&mut buf, &entry.key)?;
+        match &entry.value {
+            StoredValue::String(value) => put_bytes(&mut buf, value)?,
+            StoredValue::List(list) => {
+                put_len(&mut buf, list.len())?;
+                for item in list.iter() {
+                    put_bytes(&mut buf, item)?;
+                }
+            }
+            StoredValue::Hash(hash) => {
+                put_len(&mut buf, hash.len())?;
+                for (field, value) in hash.iter() {
+                    put_bytes(&mut buf, field)?;
+                    put_bytes(&mut buf, value)?;
+                }
+            }
+            StoredValue::Set(set) => {
+                put_len(&mut buf, set.len())?;
+                for member in set.iter() {
+                    put_bytes(&mut buf, member)?;
+                }
+            }
+            StoredValue::ZSet(zset) => {
+                put_len(&mut buf, zset.len())?;
+                for (member, score) in zset.iter() {
+                    put_bytes(&mut buf, member)?;
+                    buf.extend_from_slice(&score.to_bits().to_le_bytes());
+                }
+            }
+        }
+    }
+
+    buf.push(TYPE_END);
+    let checksum = crc32(&buf);
+    buf.extend_from_slice(&checksum.to_le_bytes());
+
+    let temp_path = temp_path(path);
+    let mut file = File::create(&temp_path)?;
+    file.write_all(&buf)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temp_path, path)
+}
+
+/// Reads and verifies a snapshot file.
+pub(crate) fn read(path: &Path) -> io::Result<Snapshot> {
+    let data = std::fs::read(path)?;
+    if data.len() < MAGIC.len() + 2 + 8 + 1 + 4 {
+        return Err(invalid_data("file too short"));
+    }
+
+    let (body, trailer) = data.split_at(data.len() - 4);
+    let expected = u32::from_le_bytes(trailer.try_into().expect("4-byte trailer"));
+    if crc32(body) != expected {
+        return Err(invalid_data("checksum mismatch"));
+    }
+
+    let mut cursor = Cursor { data: body, pos: 0 };
+    if cursor.take(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("bad magic"));
+    }
+    let version = u16::from_le_bytes(cursor.array()?);
+    if version != SNAPSHOT_VERSION {
+        return Err(invalid_data("unsupported version"));
+    }
+    let _created_ms = u64::from_le_bytes(cursor.array()?);
+    let max_version = Version::new(u64::from_le_bytes(cursor.array()?));
+
+    let mut entries = Vec::new();
+    loop {
+        let tag = cursor.u8()?;
+        if tag == TYPE_END {
+            break;
+        }
+        let expires_at_ms = u64::from_le_bytes(cursor.array()?);
+        let key = Arc::from(cursor.bytes()?);
+        let value = match tag {
+            TYPE_STRING => StoredValue::String(Arc::from(cursor.bytes()?)),
+            TYPE_LIST => {
+                let mut list = ListValue::default();
+                for _ in 0..cursor.len()? {
+                    list.push_back(Arc::from(cursor.bytes()?));
+                }
+                StoredValue::List(list)
+            }
+            TYPE_HASH => {
+                let mut hash = HashValue::default();
+                for _ in 0..cursor.len()? {
+                    let field = Arc::from(cursor.bytes()?);
+                    hash.insert(field, Arc::from(cursor.bytes()?));
+                }
+                StoredValue::Hash(hash)
+            }
+            TYPE_SET => {
+                let mut set = SetValue::default();
+                for _ in 0..cursor.len()? {
+                    set.insert(Arc::from(cursor.bytes()?));
+                }
+                StoredValue::Set(set)
+            }
+            TYPE_ZSET => {
+                let mut zset = ZSetValue::default();
+                for _ in 0..cursor.len()? {
+                    let member = Arc::from(cursor.bytes()?);
+                    let score = f64::from_bits(u64::from_le_bytes(cursor.array()?));
+                    if score.is_nan() {
+                        return Err(invalid_data("NaN sorted set score"));
+                    }
+                    zset.insert(member, score);
+                }
+                StoredValue::ZSet(zset)
+            }
+            _ => return Err(invalid_data("unknown entry type")),
+        };
+        entries.push(SnapshotEntry {
+            key,
+            value,
+            expires_at_ms: (expires_at_ms != 0).then_some(expires_at_ms),
+        });
+    }
+
+    if cursor.pos != body.len() {
+        return Err(invalid_data("trailing bytes"));
+    }
+    Ok(Snapshot {
+        max_version,
+        entries,
+    })
+}
+
+/// Bounds-checked reader over the verified snapshot body.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid_data("unexpected end of file"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("exact length"))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> io::Result<usize> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+}
+
+fn put_len(buf: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid_data("length exceeds u32"))?;
+    buf.extend_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn put_bytes(buf: &mut Vec<u8>, data: &[u8]) -> io::Result<()> {
+    put_len(buf, data.len())?;
+    buf.extend_from_slice(data);
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    PathBuf::from(temp)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("snapshot: {message}"))
+}
+
+/// CRC-32 (IEEE 802.3) lookup table, built at compile time.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE) checksum of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("hkv-snap-{name}-{}-{nanos}", std::process::id()))
+    }
+
+    fn entry(key: &[u8], value: &[u8], expires_at_ms: Option<u64>) -> SnapshotEntry {
+        SnapshotEntry {
+            key: Arc::from(key),
+            value: StoredValue::String(Arc::from(value)),
+            expires_at_ms,
+        }
+    }
+
+    fn list_entry(key: &[u8], items: &[&[u8]]) -> SnapshotEntry {
+        let mut list = ListValue::default();
+        for item in items {
+            list.push_back(Arc::from(*item));
+        }
+        SnapshotEntry {
+            key: Arc::from(key),
+            value: StoredValue::List(list),
+            expires_at_ms: None,
+        }
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn parses_save_rules() {
+        let rules = SaveRule::parse_list("900 1 300 10").unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                SaveRule {
+                    seconds: 900,
+                    changes: 1
+                },
+                SaveRule {
+                    seconds: 300,
+                    changes: 10
+                },
+            ]
+        );
+        assert_eq!(SaveRule::parse_list("").unwrap(), Vec::new());
+        assert!(SaveRule::parse_list("900").is_none());
+        assert!(SaveRule::parse_list("a b").is_none());
+        assert!(rules[1].is_due(10, 300));
+        assert!(!rules[1].is_due(9, 3000));
+    }
+
+    #[test]
+    fn write_then_read_roundtrip() {
+        let path = temp_file("roundtrip");
+        let entries = vec![
+            entry(b"alpha", b"1", None),
+            entry(b"beta", b"bin\0ary", Some(1_700_000_000_000)),
+            entry(b"", b"", None),
+            list_entry(b"queue", &[b"a", b"", b"ccc"]),
+        ];
+        let snapshot = Snapshot {
+            max_version: Version::new(77),
+            entries,
+        };
+        write(&path, 42, &snapshot).unwrap();
+        // `StoredValue` is not `Eq`; the derived `Debug` output covers every field.
+        assert_eq!(
+            format!("{:?}", read(&path).unwrap()),
+            format!("{snapshot:?}")
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn read_rejects_corruption() {
+        let path = temp_file("corrupt");
+        let snapshot = Snapshot {
+            max_version: Version::ZERO,
+            entries: vec![entry(b"alpha", b"1", None)],
+        };
+        write(&path, 42, &snapshot).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        let mid = data.len() / 2;
+        data[mid] ^= 0xFF;
+        std::fs::write(&path, &data).unwrap();
+
+        let err = read(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn read_rejects_unknown_versions() {
+        let path = temp_file("versions");
+        let snapshot = Snapshot {
+            max_version: Version::new(9),
+            entries: vec![entry(b"alpha", b"1", None)],
+        };
+        write(&path, 42, &snapshot).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        let version = SNAPSHOT_VERSION + 1;
+        data[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&version.to_le_bytes());
+        let body = data.len() - 4;
+        let crc = crc32(&data[..body]);
+        data[body..].copy_from_slice(&crc.to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+
+        assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,722 @@
+//! # Typed Values
+//!
+//! Each key holds exactly one value type. Strings stay a single shared
+//! buffer; collection types wrap their elements together with a running byte
+//! count so eviction accounting stays O(1) per mutation.
+//!
+//! ## Design Principles
+//!
+//! 1. **Closed Set of Types**: `StoredValue` is an enum, so every storage path
+//!    (snapshot, AOF rewrite, eviction) is forced to handle each type.
+//! 2. **Incremental Accounting**: Collections track their payload bytes as
+//!    they change instead of rescanning on every write.
+//! 3. **Redis Index Semantics**: Negative indices count from the tail and
+//!    out-of-range bounds are clamped rather than rejected.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use ahash::RandomState;
+use hashbrown::{HashMap, HashSet};
+
+use crate::engine::{ScanPage, ScoreBound, ScoredMember};
+use crate::glob::glob_match;
+use crate::skiplist::SkipList;
+
+/// Bytes charged per sorted-set score.
+const SCORE_BYTES: usize = std::mem::size_of::<f64>();
+
+/// Value type reported by `TYPE` and checked for `WRONGTYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// Binary-safe string.
+    String,
+    /// Doubly ended list of strings.
+    List,
+    /// Field/value map.
+    Hash,
+    /// Unordered set of unique strings.
+    Set,
+    /// Members ordered by a floating-point score.
+    ZSet,
+}
+
+impl ValueType {
+    /// Returns the Redis type name.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::List => "list",
+            Self::Hash => "hash",
+            Self::Set => "set",
+            Self::ZSet => "zset",
+        }
+    }
+}
+
+/// Value stored in an engine node.
+#[derive(Debug, Clone)]
+pub(crate) enum StoredValue {
+    String(Arc<[u8]>),
+    List(ListValue),
+    Hash(HashValue),
+    Set(SetValue),
+    ZSet(ZSetValue),
+}
+
+impl StoredValue {
+    /// Returns the type tag of the value.
+    pub(crate) fn value_type(&self) -> ValueType {
+        match self {
+            Self::String(_) => ValueType::String,
+            Self::List(_) => ValueType::List,
+            Self::Hash(_) => ValueType::Hash,
+            Self::Set(_) => ValueType::Set,
+            Self::ZSet(_) => ValueType::ZSet,
+        }
+    }
+
+    /// Returns the payload bytes used for eviction accounting.
+    pub(crate) fn payload_len(&self) -> usize {
+        match self {
+            Self::String(value) => value.len(),
+            Self::List(list) => list.bytes,
+            Self::Hash(hash) => hash.bytes,
+            Self::Set(set) => set.bytes,
+            Self::ZSet(zset) => zset.bytes,
+        }
+    }
+}
+
+/// Collection types that can be created on first write and removed when empty.
+pub(crate) trait Collection: Default {
+    /// Type tag used for `WRONGTYPE` checks.
+    const TYPE: ValueType;
+
+    /// Borrows the collection if `value` holds this type.
+    fn from_value(value: &StoredValue) -> Option<&Self>;
+
+    /// Mutably borrows the collection if `value` holds this type.
+    fn from_value_mut(value: &mut StoredValue) -> Option<&mut Self>;
+
+    /// Wraps the collection into a stored value.
+    fn into_value(self) -> StoredValue;
+
+    /// Returns true when the collection has no elements.
+    fn is_empty(&self) -> bool;
+}
+
+/// List payload: a deque of shared element buffers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ListValue {
+    items: VecDeque<Arc<[u8]>>,
+    /// Sum of element lengths.
+    bytes: usize,
+}
+
+impl ListValue {
+    /// Returns the number of elements.
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Iterates elements from head to tail.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Arc<[u8]>> {
+        self.items.iter()
+    }
+
+    /// Inserts an element at the head.
+    pub(crate) fn push_front(&mut self, item: Arc<[u8]>) {
+        self.bytes += item.len();
+        self.items.push_front(item);
+    }
+
+    /// Inserts an element at the tail.
+    pub(crate) fn push_back(&mut self, item: Arc<[u8]>) {
+        self.bytes += item.len();
+        self.items.push_back(item);
+    }
+
+    /// Removes and returns the head element.
+    pub(crate) fn pop_front(&mut self) -> Option<Arc<[u8]>> {
+        let item = self.items.pop_front()?;
+        self.bytes -= item.len();
+        Some(item)
+    }
+
+    /// Removes and returns the tail element.
+    pub(crate) fn pop_back(&mut self) -> Option<Arc<[u8]>> {
+        let item = self.items.pop_back()?;
+        self.bytes -= item.len();
+        Some(item)
+    }
+
+    /// Returns the element at a Redis-style index.
+    pub(crate) fn get(&self, index: i64) -> Option<&Arc<[u8]>> {
+        let len = self.items.len() as i64;
+        let index = if index < 0 { len + index } else { index };
+        if index < 0 {
+            return None;
+        }
+        self.items.get(index as usize)
+    }
+
+    /// Returns the elements in the inclusive Redis-style range.
+    pub(crate) fn range(&self, start: i64, stop: i64) -> Vec<Arc<[u8]>> {
+        match normalize_range(start, stop, self.items.len()) {
+            Some((start, stop)) => self.items.range(start..=stop).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Keeps only the elements in the inclusive Redis-style range.
+    pub(crate) fn trim(&mut self, start: i64, stop: i64) {
+        match normalize_range(start, stop, self.items.len()) {
+            Some((start, stop)) => {
+                self.items.truncate(stop + 1);
+                self.items.drain(..start);
+                self.bytes = self.items.iter().map(|item| item.len()).sum();
+            }
+            None => {
+                self.items.clear();
+                self.bytes = 0;
+            }
+        }
+    }
+}
+
+impl Collection for ListValue {
+    const TYPE: ValueType = ValueType::List;
+
+    fn from_value(value: &StoredValue) -> Option<&Self> {
+        match value {
+            StoredValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut StoredValue) -> Option<&mut Self> {
+        match value {
+            StoredValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> StoredValue {
+        StoredValue::List(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Hash payload: field -> value map with shared buffers.
+///
+/// `order` indexes the same fields by their hash so `HSCAN` can resume from
+/// a cursor without rehashing or sorting the whole map.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HashValue {
+    map: HashMap<Arc<[u8]>, Arc<[u8]>, RandomState>,
+    /// Field hash -> fields with that hash (more than one only on collision).
+    order: BTreeMap<u64, Vec<Arc<[u8]>>>,
+    /// Sum of field and value lengths.
+    bytes: usize,
+}
+
+impl HashValue {
+    /// Returns the number of fields.
+    pub(crate) fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the value of `field`.
+    pub(crate) fn get(&self, field: &[u8]) -> Option<&Arc<[u8]>> {
+        self.map.get(field)
+    }
+
+    /// Iterates field/value pairs in unspecified order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Arc<[u8]>, &Arc<[u8]>)> {
+        self.map.iter()
+    }
+
+    /// Sets `field` to `value`. Returns true when the field is new.
+    pub(crate) fn insert(&mut self, field: Arc<[u8]>, value: Arc<[u8]>) -> bool {
+        let added = field.len() + value.len();
+        match self.map.insert(Arc::clone(&field), value) {
+            Some(old) => {
+                self.bytes = self.bytes + added - field.len() - old.len();
+                false
+            }
+            None => {
+                self.bytes += added;
+                let hash = self.field_hash(&field);
+                self.order.entry(hash).or_default().push(field);
+                true
+            }
+        }
+    }
+
+    /// Removes `field`. Returns true when it existed.
+    pub(crate) fn remove(&mut self, field: &[u8]) -> bool {
+        match self.map.remove_entry(field) {
+            Some((field, value)) => {
+                self.bytes -= field.len() + value.len();
+                let hash = self.field_hash(&field);
+                if let Some(bucket) = self.order.get_mut(&hash) {
+                    bucket.retain(|other| *other != field);
+                    if bucket.is_empty() {
+                        self.order.remove(&hash);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns up to `count` pairs at or after `cursor`, plus the next cursor.
+    ///
+    /// The cursor is a position in the hash space of field names, so (as in
+    /// Redis) every field present for the whole scan is returned at least
+    /// once regardless of concurrent inserts or removals. `0` starts and ends
+    /// a scan. `pattern` filters the returned batch, not the cursor walk.
+    /// A page costs O(log n + count).
+    pub(crate) fn scan(&self, cursor: u64, count: usize, pattern: Option<&[u8]>) -> ScanPage {
+        let count = count.max(1);
+        let mut walked = 0;
+        let mut batch = Vec::new();
+        for (hash, fields) in self.order.range(cursor..) {
+            // Never split fields that share a hash across two batches.
+            if walked >= count {
+                return (*hash, batch);
+            }
+            walked += fields.len();
+            for field in fields {
+                if pattern.is_none_or(|pattern| glob_match(pattern, field)) {
+                    let value = &self.map[field];
+                    batch.push((Arc::clone(field), Arc::clone(value)));
+                }
+            }
+        }
+        (0, batch)
+    }
+
+    fn field_hash(&self, field: &[u8]) -> u64 {
+        self.map.hasher().hash_one(field)
+    }
+}
+
+impl Collection for HashValue {
+    const TYPE: ValueType = ValueType::Hash;
+
+    fn from_value(value: &StoredValue) -> Option<&Self> {
+        match value {
+            StoredValue::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut StoredValue) -> Option<&mut Self> {
+        match value {
+            StoredValue::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> StoredValue {
+        StoredValue::Hash(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Set payload: unique shared member buffers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SetValue {
+    members: HashSet<Arc<[u8]>, RandomState>,
+    /// Sum of member lengths.
+    bytes: usize,
+}
+
+impl SetValue {
+    /// Returns the number of members.
+    pub(crate) fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true when `member` is present.
+    pub(crate) fn contains(&self, member: &[u8]) -> bool {
+        self.members.contains(member)
+    }
+
+    /// Iterates members in unspecified order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Arc<[u8]>> {
+        self.members.iter()
+    }
+
+    /// Adds `member`. Returns true when it was not already present.
+    pub(crate) fn insert(&mut self, member: Arc<[u8]>) -> bool {
+        let len = member.len();
+        let added = self.members.insert(member);
+        if added {
+            self.bytes += len;
+        }
+        added
+    }
+
+    /// Removes `member`. Returns true when it was present.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.members.take(member) {
+            Some(member) => {
+                self.bytes -= member.len();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Collection for SetValue {
+    const TYPE: ValueType = ValueType::Set;
+
+    fn from_value(value: &StoredValue) -> Option<&Self> {
+        match value {
+            StoredValue::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut StoredValue) -> Option<&mut Self> {
+        match value {
+            StoredValue::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> StoredValue {
+        StoredValue::Set(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Sorted set payload: a member → score map plus a `(score, member)` index.
+#[derive(Clone, Default)]
+pub(crate) struct ZSetValue {
+    scores: HashMap<Arc<[u8]>, f64, RandomState>,
+    index: SkipList,
+    /// Sum of member lengths plus 8 bytes per score.
+    bytes: usize,
+}
+
+impl ZSetValue {
+    /// Returns the number of members.
+    pub(crate) fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns the score of `member`.
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Iterates members in score order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Arc<[u8]>, f64)> {
+        self.index.iter()
+    }
+
+    /// Sets the score of `member`. Returns true when it was not already present.
+    pub(crate) fn insert(&mut self, member: Arc<[u8]>, score: f64) -> bool {
+        match self.scores.get_mut(&member) {
+            Some(current) => {
+                if *current != score {
+                    self.index.remove(*current, &member);
+                    *current = score;
+                    self.index.insert(score, member);
+                }
+                false
+            }
+            None => {
+                self.bytes += member.len() + SCORE_BYTES;
+                self.scores.insert(Arc::clone(&member), score);
+                self.index.insert(score, member);
+                true
+            }
+        }
+    }
+
+    /// Removes `member`. Returns true when it was present.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove_entry(member) {
+            Some((member, score)) => {
+                self.index.remove(score, &member);
+                self.bytes -= member.len() + SCORE_BYTES;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the 0-based rank of `member` in ascending score order.
+    pub(crate) fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = self.score(member)?;
+        self.index.rank(score, member)
+    }
+
+    /// Returns members with ranks in `[start, stop]` (Redis index semantics).
+    pub(crate) fn range(&self, start: i64, stop: i64) -> Vec<ScoredMember> {
+        match normalize_range(start, stop, self.len()) {
+            Some((start, stop)) => self
+                .index
+                .iter_from_rank(start)
+                .take(stop - start + 1)
+                .map(|(member, score)| (Arc::clone(member), score))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns members with scores between `min` and `max`, skipping
+    /// `offset` matches and returning at most `count` when given.
+    pub(crate) fn range_by_score(
+        &self,
+        min: ScoreBound,
+        max: ScoreBound,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<ScoredMember> {
+        self.index
+            .iter_from_score(|score| !min.admits_min(score))
+            .take_while(|&(_, score)| max.admits_max(score))
+            .skip(offset)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(member, score)| (Arc::clone(member), score))
+            .collect()
+    }
+
+    /// Removes and returns up to `count` lowest-scored members.
+    pub(crate) fn pop_min(&mut self, count: usize) -> Vec<ScoredMember> {
+        let popped: Vec<ScoredMember> = self
+            .index
+            .iter()
+            .take(count)
+            .map(|(member, score)| (Arc::clone(member), score))
+            .collect();
+        for (member, _) in &popped {
+            self.remove(member);
+        }
+        popped
+    }
+}
+
+impl fmt::Debug for ZSetValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZSetValue")
+            .field("index", &self.index)
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+impl Collection for ZSetValue {
+    const TYPE: ValueType = ValueType::ZSet;
+
+    fn from_value(value: &StoredValue) -> Option<&Self> {
+        match value {
+            StoredValue::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut StoredValue) -> Option<&mut Self> {
+        match value {
+            StoredValue::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> StoredValue {
+        StoredValue::ZSet(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+/// Resolves an inclusive `[start, stop]` range against `len` elements.
+///
+/// Negative indices count from the end; returns `None` for empty ranges.
+pub(crate) fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&[u8]]) -> ListValue {
+        let mut list = ListValue::default();
+        for item in items {
+            list.push_back(Arc::from(*item));
+        }
+        list
+    }
+
+    #[test]
+    fn normalizes_redis_ranges() {
+        assert_eq!(normalize_range(0, -1, 5), Some((0, 4)));
+        assert_eq!(normalize_range(-2, -1, 5), Some((3, 4)));
+        assert_eq!(normalize_range(-100, 100, 5), Some((0, 4)));
+        assert_eq!(normalize_range(3, 1, 5), None);
+        assert_eq!(normalize_range(5, 10, 5), None);
+        assert_eq!(normalize_range(0, -1, 0), None);
+    }
+
+    #[test]
+    fn list_tracks_bytes_through_mutations() {
+        let mut value = list(&[b"a", b"bb", b"ccc", b"dddd"]);
+        assert_eq!(value.bytes, 10);
+        value.push_front(Arc::from(&b"zz"[..]));
+        assert_eq!(value.bytes, 12);
+        assert_eq!(value.pop_back().as_deref(), Some(&b"dddd"[..]));
+        assert_eq!(value.bytes, 8);
+
+        value.trim(1, 2);
+        assert_eq!(
+            value.range(0, -1),
+            vec![Arc::from(&b"a"[..]), Arc::from(&b"bb"[..])]
+        );
+        assert_eq!(value.bytes, 3);
+
+        value.trim(5, 10);
+        assert!(value.is_empty());
+        assert_eq!(value.bytes, 0);
+    }
+
+    #[test]
+    fn hash_tracks_bytes_on_overwrite_and_remove() {
+        let mut hash = HashValue::default();
+        assert!(hash.insert(Arc::from(&b"name"[..]), Arc::from(&b"ann"[..])));
+        assert!(!hash.insert(Arc::from(&b"name"[..]), Arc::from(&b"beatrice"[..])));
+        assert_eq!(hash.bytes, 12);
+        assert!(hash.remove(b"name"));
+        assert!(!hash.remove(b"name"));
+        assert_eq!(hash.bytes, 0);
+        assert!(hash.order.is_empty());
+    }
+
+    #[test]
+    fn hash_scan_resumes_after_the_cursor_field_is_removed() {
+        let mut hash = HashValue::default();
+        for i in 0..20 {
+            let field = format!("field:{i}");
+            hash.insert(Arc::from(field.as_bytes()), Arc::from(&b"v"[..]));
+        }
+        let (cursor, first) = hash.scan(0, 5, None);
+        assert_eq!(first.len(), 5);
+
+        // Drop the field the cursor points at; the scan still finds the rest.
+        let (_, resumed) = hash.scan(cursor, 1, None);
+        assert!(hash.remove(&resumed[0].0));
+        let mut seen = first.len();
+        let mut cursor = cursor;
+        while cursor != 0 {
+            let (next, batch) = hash.scan(cursor, 5, None);
+            seen += batch.len();
+            cursor = next;
+        }
+        assert_eq!(seen, 19);
+    }
+
+    #[test]
+    fn hash_scan_visits_every_field_once() {
+        let mut hash = HashValue::default();
+        for i in 0..100 {
+            let field = format!("field:{i}");
+            hash.insert(Arc::from(field.as_bytes()), Arc::from(&b"v"[..]));
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, batch) = hash.scan(cursor, 7, None);
+            assert!(batch.len() <= 8);
+            seen.extend(batch.into_iter().map(|(field, _)| field));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 100);
+
+        let (_, batch) = hash.scan(0, 1000, Some(b"field:1?"));
+        assert_eq!(batch.len(), 10);
+    }
+
+    #[test]
+    fn list_get_supports_negative_indices() {
+        let value = list(&[b"a", b"b", b"c"]);
+        assert_eq!(value.get(0).map(|item| &**item), Some(&b"a"[..]));
+        assert_eq!(value.get(-1).map(|item| &**item), Some(&b"c"[..]));
+        assert!(value.get(3).is_none());
+        assert!(value.get(-4).is_none());
+    }
+
+    #[test]
+    fn zset_updates_scores_and_ranges() {
+        let mut zset = ZSetValue::default();
+        assert!(zset.insert(Arc::from(&b"a"[..]), 3.0));
+        assert!(zset.insert(Arc::from(&b"b"[..]), 1.0));
+        assert!(zset.insert(Arc::from(&b"c"[..]), 2.0));
+        assert!(!zset.insert(Arc::from(&b"a"[..]), 0.5));
+        assert_eq!(zset.bytes, 3 * (1 + SCORE_BYTES));
+        assert_eq!(zset.rank(b"a"), Some(0));
+        assert_eq!(zset.rank(b"c"), Some(2));
+
+        let names = |items: Vec<ScoredMember>| -> Vec<Vec<u8>> {
+            items
+                .into_iter()
+                .map(|(member, _)| member.to_vec())
+                .collect()
+        };
+        assert_eq!(
+            names(zset.range(-2, -1)),
+            vec![b"b".to_vec(), b"c".to_vec()]
+        );
+        let by_score = zset.range_by_score(
+            ScoreBound::Exclusive(0.5),
+            ScoreBound::Inclusive(f64::INFINITY),
+            0,
+            Some(1),
+        );
+        assert_eq!(names(by_score), vec![b"b".to_vec()]);
+
+        assert_eq!(names(zset.pop_min(2)), vec![b"a".to_vec(), b"b".to_vec()]);
+        assert!(zset.remove(b"c"));
+        assert!(zset.is_empty());
+        assert_eq!(zset.bytes, 0);
+    }
+}
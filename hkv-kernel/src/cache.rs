@@ -0,0 +1,1632 @@
+//! # Kernel Cache Data Plane
+//!
+//! User-space model of the `/dev/hybridkv` cache: per-tenant indexes of
+//! immutable entries, versioned promotion and invalidation, watermark
+//! eviction, tenant budgets, and the `CacheStats` counters.
+//!
+//! ## Design Principles
+//!
+//! 1. **Read-Mostly Index**: Readers take a shard read lock and clone an
+//!    `Arc`; writers publish a new entry instead of mutating one in place,
+//!    mirroring RCU publish/retire in the module.
+//! 2. **Version-Based Staleness**: An invalidation keeps the entry as a
+//!    tombstone carrying the invalidating version. Reads miss on it and
+//!    promotions older than it are rejected with `StaleVersion`, so a
+//!    promotion racing a write can never resurrect the old value. Keys
+//!    invalidated while uncached (or whose tombstone is evicted) keep a
+//!    key-only tombstone in the tenant's bounded tombstone table.
+//! 3. **Watermark Eviction**: Crossing the high watermark (percent of
+//!    `max_bytes` or `max_entries`) evicts until usage drops to the low
+//!    watermark; stale and expired entries go first, then the victims the
+//!    active eviction policy picks (LRU by default).
+//! 4. **Policies Off the Read Path**: Reads report to the policy plane with
+//!    `try_lock` and skip the report under contention, so a busy policy
+//!    never blocks a lookup. New keys that would cross the high watermark
+//!    must pass the admission policy.
+//! 5. **Tenant Isolation**: Every tenant is a separate eviction domain with
+//!    an optional hard quota, checked and charged in one atomic step as an
+//!    entry is published. Under global pressure, memory is reclaimed
+//!    first from tenants above their budget, then from tenants above their
+//!    minimum guarantee, and only then from anyone.
+//! 6. **Validate Like the Kernel**: Every request is checked for magic,
+//!    protocol version, command, and in-bounds lengths before any key is
+//!    hashed or copied.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hkv_common::{
+    ALL_TENANTS, BatchPromoteDescriptor, BatchPromoteMemory, BatchPromoteRequest, CacheStats,
+    ConfigRequest, ConsistencyModeId, DEFAULT_TENANT, DemoteRequest, EntryFlags, EntryMetadata,
+    FlushRequest, HkvError, HkvResult, IOCTL_MAGIC, InvalidateRequest, IoctlCommand, IoctlHeader,
+    Key, MAX_EXT_VALUE_SIZE, MAX_KEY_SIZE, MAX_VALUE_SIZE, PromoteExtRequest, PromoteRequest,
+    ReadExtRequest, ReadRequest, ReadRequestV1, RefreshRequest, StatsRequest, TenantConfigRequest,
+    Ttl, Value, Version,
+};
+
+use crate::consistency::{Consistency, ConsistencyChange, RefreshQueue};
+use crate::domain::{Domain, Slot, entry_size, lock_write};
+use crate::policy::{Candidate, PolicyIds, PolicyRegistry, PolicySet, migrate_eviction};
+
+/// Victims requested from an eviction policy per round.
+const EVICTION_BATCH: usize = 32;
+
+/// Maximum number of registered tenants, including the default one.
+pub const MAX_TENANTS: usize = 256;
+
+/// Default memory budget (64 MiB).
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Default entry limit.
+pub const DEFAULT_MAX_ENTRIES: u64 = 65_536;
+
+/// Default high watermark (percent).
+pub const DEFAULT_HIGH_WATERMARK: u32 = 90;
+
+/// Default low watermark (percent).
+pub const DEFAULT_LOW_WATERMARK: u32 = 70;
+
+/// One entry to insert, borrowed from whichever request carried it.
+#[derive(Clone, Copy)]
+struct Promotion<'a> {
+    key: &'a Key,
+    value: &'a [u8],
+    version: Version,
+    ttl: Ttl,
+}
+
+/// Capacity limits applied by `CMD_CONFIG`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Limits {
+    max_bytes: u64,
+    max_entries: u64,
+    high_watermark: u32,
+    low_watermark: u32,
+}
+
+impl Limits {
+    /// Validates a config request into limits.
+    ///
+    /// Both limits must be non-zero and `0 < low <= high <= 100`.
+    fn from_request(request: &ConfigRequest) -> HkvResult<Self> {
+        if request.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        if request.max_bytes == 0
+            || request.max_entries == 0
+            || request.low_watermark == 0
+            || request.low_watermark > request.high_watermark
+            || request.high_watermark > 100
+        {
+            return Err(HkvError::InvalidInput);
+        }
+        Ok(Limits {
+            max_bytes: request.max_bytes,
+            max_entries: request.max_entries,
+            high_watermark: request.high_watermark,
+            low_watermark: request.low_watermark,
+        })
+    }
+
+    fn bytes_at(&self, percent: u32) -> u64 {
+        self.max_bytes / 100 * percent as u64 + self.max_bytes % 100 * percent as u64 / 100
+    }
+
+    fn entries_at(&self, percent: u32) -> u64 {
+        self.max_entries / 100 * percent as u64 + self.max_entries % 100 * percent as u64 / 100
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            high_watermark: DEFAULT_HIGH_WATERMARK,
+            low_watermark: DEFAULT_LOW_WATERMARK,
+        }
+    }
+}
+
+/// User-space implementation of the kernel cache data plane.
+///
+/// Each method handles one `IoctlCommand` against its `hkv_common::protocol`
+/// request; `KernelCache::ioctl` dispatches raw command numbers.
+pub struct KernelCache {
+    /// Tenant domains; `DEFAULT_TENANT` always exists.
+    tenants: RwLock<HashMap<u16, Arc<Domain>>>,
+    hash_state: RandomState,
+    limits: RwLock<Limits>,
+    used_bytes: AtomicU64,
+    entry_count: AtomicU64,
+    lock_contentions: AtomicU64,
+    rcu_grace_periods: AtomicU64,
+    registry: PolicyRegistry,
+    /// Lock order: `policies`, then `tenants`, then a domain's eviction
+    /// policy, then shard locks.
+    policies: Mutex<PolicySet>,
+    /// Cache-wide consistency mode; tenants may override it.
+    consistency: RwLock<Consistency>,
+    /// Refreshes queued by async-refresh reads; taken after shard locks.
+    refreshes: Mutex<RefreshQueue>,
+}
+
+impl KernelCache {
+    /// Creates an empty cache with the default limits, watermarks, and
+    /// built-in policies.
+    pub fn new() -> Self {
+        Self::with_registry(PolicyRegistry::new())
+    }
+
+    /// Creates an empty cache whose `CMD_CONFIG` can select any policy in
+    /// `registry`; starts with the default policies.
+    pub fn with_registry(registry: PolicyRegistry) -> Self {
+        let ids = PolicyIds::default();
+        let policies = registry
+            .build(ids)
+            .expect("registry is missing a default policy");
+        let default_domain = Domain::new(
+            DEFAULT_TENANT,
+            registry
+                .build_eviction(ids.eviction)
+                .expect("registry is missing the default eviction policy"),
+        );
+        KernelCache {
+            tenants: RwLock::new(HashMap::from([(DEFAULT_TENANT, Arc::new(default_domain))])),
+            hash_state: RandomState::new(),
+            limits: RwLock::new(Limits::default()),
+            used_bytes: AtomicU64::new(0),
+            entry_count: AtomicU64::new(0),
+            lock_contentions: AtomicU64::new(0),
+            rcu_grace_periods: AtomicU64::new(0),
+            registry,
+            policies: Mutex::new(policies),
+            consistency: RwLock::new(Consistency::default()),
+            refreshes: Mutex::new(RefreshQueue::default()),
+        }
+    }
+
+    /// Creates an empty cache configured by `request`.
+    pub fn with_config(request: &ConfigRequest) -> HkvResult<Self> {
+        let cache = Self::new();
+        cache.config(request)?;
+        Ok(cache)
+    }
+
+    /// `CMD_READ`: returns the tenant's cached value for an unexpired
+    /// entry.
+    ///
+    /// Misses and expired entries return `NotFound`. Stale (invalidated)
+    /// entries follow the tenant's consistency mode: `NotFound` under strict
+    /// invalidation, otherwise the stale value while it is young enough,
+    /// counted as a hit and a `stale_hit`. Entries larger than the inline
+    /// `Value` buffer are `ValueTooLong` (and a miss) for this command;
+    /// `read_ext` serves them. Unregistered tenants are `InvalidInput`.
+    ///
+    /// A version 1 header belongs to the `ReadRequestV1` layout (`read_v1`)
+    /// and is `VersionMismatch` here.
+    pub fn read(&self, request: &ReadRequest) -> HkvResult<Value> {
+        check_header(&request.header, IoctlCommand::Read)?;
+        if request.header.version == ReadRequestV1::VERSION {
+            return Err(HkvError::VersionMismatch);
+        }
+        self.read_inline(request.tenant, &request.key)
+    }
+
+    /// Version 1 `CMD_READ`: `read` in the default tenant.
+    ///
+    /// Any other header version is `VersionMismatch`.
+    pub fn read_v1(&self, request: &ReadRequestV1) -> HkvResult<Value> {
+        check_header(&request.header, IoctlCommand::Read)?;
+        if request.header.version != ReadRequestV1::VERSION {
+            return Err(HkvError::VersionMismatch);
+        }
+        self.read_inline(DEFAULT_TENANT, &request.key)
+    }
+
+    /// Shared body of `read` and `read_v1`.
+    fn read_inline(&self, tenant: u16, key: &Key) -> HkvResult<Value> {
+        let domain = self.domain(tenant)?;
+        let slot = self.lookup(&domain, key)?;
+        if slot.value.len() > MAX_VALUE_SIZE {
+            domain.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Err(HkvError::ValueTooLong);
+        }
+        record_hit(&domain, &slot);
+        Value::new(&slot.value)
+    }
+
+    /// `CMD_READ_EXT`: copies the tenant's cached value into `user`, the
+    /// memory behind `request.buf_ptr`, and returns its length.
+    ///
+    /// Misses behave as in `read`. When the value does not fit, nothing is
+    /// copied and the length is still returned so the caller can retry;
+    /// every call counts as one lookup. `user` not matching the request's
+    /// pointer and length is `InvalidInput` (the module's `EFAULT`).
+    pub fn read_ext(&self, request: &ReadExtRequest, user: &mut [u8]) -> HkvResult<usize> {
+        check_header(&request.header, IoctlCommand::ReadExt)?;
+        if request.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        check_user_buf(request.buf_ptr, request.buf_len, user)?;
+        let domain = self.domain(request.tenant)?;
+        let slot = self.lookup(&domain, &request.key)?;
+        record_hit(&domain, &slot);
+        if let Some(dst) = user.get_mut(..slot.value.len()) {
+            dst.copy_from_slice(&slot.value);
+        }
+        Ok(slot.value.len())
+    }
+
+    /// `CMD_PROMOTE`: inserts or replaces one entry of the request's tenant.
+    ///
+    /// Fails with `StaleVersion` when the cached entry (or the write that
+    /// invalidated it) is newer, and `CapacityExceeded` when eviction cannot
+    /// make room within the tenant's quota or the cache limits.
+    pub fn promote(&self, request: &PromoteRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::Promote)?;
+        let domain = self.domain(request.tenant)?;
+        self.promote_entry(
+            &domain,
+            Promotion {
+                key: &request.key,
+                value: request.value.as_bytes(),
+                version: request.version,
+                ttl: request.ttl,
+            },
+        )
+    }
+
+    /// `CMD_PROMOTE_EXT`: inserts or replaces one entry whose value is
+    /// `user`, the memory behind `request.value_ptr`.
+    ///
+    /// Accepts values up to `MAX_EXT_VALUE_SIZE`; otherwise behaves as
+    /// `promote`. `user` not matching the request's pointer and length is
+    /// `InvalidInput` (the module's `EFAULT`).
+    pub fn promote_ext(&self, request: &PromoteExtRequest, user: &[u8]) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::PromoteExt)?;
+        if request.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        if request.value_len as usize > MAX_EXT_VALUE_SIZE {
+            return Err(HkvError::ValueTooLong);
+        }
+        check_user_buf(request.value_ptr, request.value_len, user)?;
+        let domain = self.domain(request.tenant)?;
+        self.promote_entry(
+            &domain,
+            Promotion {
+                key: &request.key,
+                value: user,
+                version: request.version,
+                ttl: request.ttl,
+            },
+        )
+    }
+
+    /// `CMD_BATCH_PROMOTE`: promotes each descriptor independently.
+    ///
+    /// `user` is the memory behind the request's pointers. Whole-request
+    /// errors (bad header, count above `MAX_BATCH_SIZE`, arrays outside
+    /// `user`, unregistered tenant) fail the call; per-entry failures are
+    /// written to that entry's status.
+    pub fn batch_promote(
+        &self,
+        request: &BatchPromoteRequest,
+        user: &mut BatchPromoteMemory<'_>,
+    ) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::BatchPromote)?;
+        let descriptors = user.descriptors(request)?;
+        let domain = self.domain(request.tenant)?;
+
+        for (index, descriptor) in descriptors.iter().enumerate() {
+            let result = self.promote_descriptor(&domain, descriptor, user);
+            user.set_status(index, result);
+        }
+        Ok(())
+    }
+
+    /// `CMD_DEMOTE`: removes a key; succeeds even when it was not cached.
+    pub fn demote(&self, request: &DemoteRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::Demote)?;
+        check_key(&request.key)?;
+        let domain = self.domain(request.tenant)?;
+        let hash = self.hash(request.tenant, &request.key);
+        let removed = domain
+            .write_shard(hash, &self.lock_contentions)
+            .remove(&request.key);
+        if let Some(slot) = removed {
+            domain.lock_eviction().on_remove(&request.key);
+            self.retire(&domain, &slot);
+            domain.counters.demotions.fetch_add(1, Ordering::Relaxed);
+            self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// `CMD_INVALIDATE`: marks a cached entry older than `version` stale.
+    ///
+    /// The entry stays as a tombstone until evicted, demoted, or replaced by
+    /// a promotion at `version` or newer. An uncached key gets a key-only
+    /// tombstone instead, so a promotion read before the write but issued
+    /// after this call is still rejected.
+    pub fn invalidate(&self, request: &InvalidateRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::Invalidate)?;
+        check_key(&request.key)?;
+        let domain = self.domain(request.tenant)?;
+        let hash = self.hash(request.tenant, &request.key);
+
+        let mut shard = domain.write_shard(hash, &self.lock_contentions);
+        let Some(slot) = shard.get(&request.key) else {
+            domain.add_tombstone(&request.key, request.version);
+            return Ok(());
+        };
+        if slot.floor() >= request.version {
+            return Ok(());
+        }
+
+        let mut metadata = slot.metadata;
+        metadata.flags.set(EntryFlags::INVALIDATED);
+        let tombstone = Slot {
+            metadata,
+            value: slot.value.clone(),
+            invalidated_by: request.version,
+            invalidated_at: now_nanos(),
+        };
+        shard.insert(request.key.clone(), Arc::new(tombstone));
+        drop(shard);
+        domain
+            .counters
+            .invalidations
+            .fetch_add(1, Ordering::Relaxed);
+        self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// `CMD_STATS`: returns cache-wide stats, or one tenant's when the
+    /// request names a tenant.
+    pub fn stats(&self, request: &StatsRequest) -> HkvResult<CacheStats> {
+        check_header(&request.header, IoctlCommand::Stats)?;
+        if request.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        if request.tenant == ALL_TENANTS {
+            return Ok(self.snapshot());
+        }
+        self.tenant_snapshot(request.tenant)
+    }
+
+    /// `CMD_CONFIG`: replaces limits and watermarks, evicting if now over.
+    ///
+    /// Non-zero policy IDs swap in a fresh policy from the registry; every
+    /// tenant's new eviction policy inherits the old one's victim order. A
+    /// non-zero consistency mode replaces the cache-wide mode or, for a
+    /// registered `consistency_tenant`, that tenant's override;
+    /// `CONSISTENCY_INHERIT` drops the override. An unknown ID or tenant
+    /// fails with `InvalidInput` and changes nothing.
+    pub fn config(&self, request: &ConfigRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::Config)?;
+        let limits = Limits::from_request(request)?;
+        let change = Consistency::from_request(request)?;
+        let tenant = match change {
+            Some(_) if request.consistency_tenant != ALL_TENANTS => {
+                Some(self.domain(request.consistency_tenant)?)
+            }
+            _ => None,
+        };
+        let mut policies = self.lock_policies();
+        if let Some(id) = policies.apply(&self.registry, request)? {
+            for domain in self.domains() {
+                let mut new = self.registry.build_eviction(id)?;
+                let mut eviction = domain.lock_eviction();
+                migrate_eviction(eviction.as_mut(), new.as_mut());
+                *eviction = new;
+            }
+        }
+        drop(policies);
+        match (change, tenant) {
+            (Some(ConsistencyChange::Set(consistency)), Some(domain)) => {
+                domain.set_consistency(Some(consistency));
+            }
+            (Some(ConsistencyChange::Inherit), Some(domain)) => domain.set_consistency(None),
+            (Some(ConsistencyChange::Set(consistency)), None) => {
+                *self
+                    .consistency
+                    .write()
+                    .unwrap_or_else(|err| err.into_inner()) = consistency;
+            }
+            // `from_request` only accepts `Inherit` for a single tenant.
+            (Some(ConsistencyChange::Inherit), None) | (None, _) => {}
+        }
+        *self.limits.write().unwrap_or_else(|err| err.into_inner()) = limits;
+        self.enforce_watermarks(&limits);
+        Ok(())
+    }
+
+    /// `CMD_FLUSH`: drops every entry of every tenant (one grace period for
+    /// the whole sweep).
+    pub fn flush(&self, request: &FlushRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::Flush)?;
+        for domain in self.domains() {
+            let mut eviction = domain.lock_eviction();
+            for shard in domain.shards() {
+                let drained: Vec<_> = lock_write(shard, &self.lock_contentions).drain().collect();
+                for (key, slot) in drained {
+                    eviction.on_remove(&key);
+                    self.retire(&domain, &slot);
+                }
+            }
+        }
+        self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// `CMD_TENANT_CONFIG`: registers a tenant or replaces its settings.
+    ///
+    /// Requires `min_guarantee <= quota` when a quota is set. A lowered quota
+    /// takes effect immediately by evicting from the tenant's own domain.
+    pub fn tenant_config(&self, request: &TenantConfigRequest) -> HkvResult<()> {
+        check_header(&request.header, IoctlCommand::TenantConfig)?;
+        if request.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        if request.tenant == ALL_TENANTS
+            || (request.quota_bytes != 0 && request.min_guarantee_bytes > request.quota_bytes)
+        {
+            return Err(HkvError::InvalidInput);
+        }
+
+        let policies = self.lock_policies();
+        let mut tenants = self.tenants.write().unwrap_or_else(|err| err.into_inner());
+        let domain = match tenants.get(&request.tenant) {
+            Some(domain) => Arc::clone(domain),
+            None => {
+                if tenants.len() >= MAX_TENANTS {
+                    return Err(HkvError::CapacityExceeded);
+                }
+                let eviction = self.registry.build_eviction(policies.ids().eviction)?;
+                let domain = Arc::new(Domain::new(request.tenant, eviction));
+                tenants.insert(request.tenant, Arc::clone(&domain));
+                domain
+            }
+        };
+        domain.configure(request);
+        drop(tenants);
+        drop(policies);
+
+        if request.quota_bytes != 0 {
+            self.evict_domain(&domain, request.quota_bytes, None);
+        }
+        Ok(())
+    }
+
+    /// Returns the cache-wide statistics without a request header.
+    pub fn snapshot(&self) -> CacheStats {
+        let domains = self.domains();
+        let mut stats = CacheStats {
+            used_bytes: self.used_bytes.load(Ordering::Relaxed),
+            max_bytes: self.limits().max_bytes,
+            entry_count: self.entry_count.load(Ordering::Relaxed),
+            lock_contentions: self.lock_contentions.load(Ordering::Relaxed),
+            rcu_grace_periods: self.rcu_grace_periods.load(Ordering::Relaxed),
+            tenant: u32::from(ALL_TENANTS),
+            tenant_count: domains.len() as u32,
+            budget_bytes: self.limits().max_bytes,
+            ..CacheStats::default()
+        };
+        for domain in &domains {
+            let counters = &domain.counters;
+            stats.lookups += counters.lookups.load(Ordering::Relaxed);
+            stats.hits += counters.hits.load(Ordering::Relaxed);
+            stats.misses += counters.misses.load(Ordering::Relaxed);
+            stats.stale_hits += counters.stale_hits.load(Ordering::Relaxed);
+            stats.promotions += counters.promotions.load(Ordering::Relaxed);
+            stats.demotions += counters.demotions.load(Ordering::Relaxed);
+            stats.evictions += counters.evictions.load(Ordering::Relaxed);
+            stats.invalidations += counters.invalidations.load(Ordering::Relaxed);
+        }
+        stats
+    }
+
+    /// Returns one tenant's statistics.
+    ///
+    /// `max_bytes` is the tenant's hard cap (its quota, or the cache limit)
+    /// and `budget_bytes` what the tenant budget policy grants it right now;
+    /// lock and RCU counters are cache-wide.
+    pub fn tenant_snapshot(&self, tenant: u16) -> HkvResult<CacheStats> {
+        let domain = self.domain(tenant)?;
+        let limits = self.limits();
+        let budget_bytes = self
+            .budgets(&limits)
+            .into_iter()
+            .find(|(candidate, _)| Arc::ptr_eq(candidate, &domain))
+            .map(|(_, budget)| budget)
+            .unwrap_or(0);
+        let counters = &domain.counters;
+        Ok(CacheStats {
+            lookups: counters.lookups.load(Ordering::Relaxed),
+            hits: counters.hits.load(Ordering::Relaxed),
+            misses: counters.misses.load(Ordering::Relaxed),
+            stale_hits: counters.stale_hits.load(Ordering::Relaxed),
+            promotions: counters.promotions.load(Ordering::Relaxed),
+            demotions: counters.demotions.load(Ordering::Relaxed),
+            evictions: counters.evictions.load(Ordering::Relaxed),
+            invalidations: counters.invalidations.load(Ordering::Relaxed),
+            used_bytes: domain.used_bytes.load(Ordering::Relaxed),
+            max_bytes: domain.share().cap(limits.max_bytes),
+            entry_count: domain.entry_count.load(Ordering::Relaxed),
+            lock_contentions: self.lock_contentions.load(Ordering::Relaxed),
+            rcu_grace_periods: self.rcu_grace_periods.load(Ordering::Relaxed),
+            tenant: u32::from(tenant),
+            tenant_count: self.domains().len() as u32,
+            budget_bytes,
+        })
+    }
+
+    /// Removes and returns the refreshes queued by async-refresh reads,
+    /// oldest first (the module delivers them to user space).
+    pub fn take_refresh_requests(&self) -> Vec<RefreshRequest> {
+        self.lock_refreshes().drain()
+    }
+
+    /// Returns how many refreshes were dropped because the queue was full.
+    pub fn dropped_refreshes(&self) -> u64 {
+        self.lock_refreshes().dropped()
+    }
+
+    /// Returns the IDs of the active policies.
+    pub fn policy_ids(&self) -> PolicyIds {
+        self.lock_policies().ids()
+    }
+
+    /// Returns the registered tenant IDs in ascending order.
+    pub fn tenants(&self) -> Vec<u16> {
+        let mut tenants: Vec<u16> = self
+            .tenants
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .keys()
+            .copied()
+            .collect();
+        tenants.sort_unstable();
+        tenants
+    }
+
+    /// Returns the default tenant's cached version of `key`, including
+    /// stale tombstones.
+    pub fn version_of(&self, key: &Key) -> Option<Version> {
+        check_key(key).ok()?;
+        let domain = self.domain(DEFAULT_TENANT).ok()?;
+        let hash = self.hash(DEFAULT_TENANT, key);
+        domain
+            .read_shard(hash)
+            .get(key)
+            .map(|slot| slot.metadata.version)
+    }
+
+    /// Finds an unexpired entry a read may serve, counting the lookup and
+    /// any miss.
+    ///
+    /// A stale entry is returned only when the tenant's consistency mode
+    /// serves it; async refresh also queues a refresh of the key.
+    fn lookup(&self, domain: &Arc<Domain>, key: &Key) -> HkvResult<Arc<Slot>> {
+        check_key(key)?;
+        let counters = &domain.counters;
+        counters.lookups.fetch_add(1, Ordering::Relaxed);
+
+        let hash = self.hash(domain.tenant, key);
+        let slot = domain.read_shard(hash).get(key).cloned();
+        self.observe(domain, hash, slot.as_ref().map(|_| key));
+        let Some(slot) = slot else {
+            counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Err(HkvError::NotFound);
+        };
+        let now = now_nanos();
+        if slot.is_expired(now) {
+            counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Err(HkvError::NotFound);
+        }
+        if slot.is_stale() {
+            let consistency = self.consistency_of(domain);
+            if !consistency.serves(&slot, now) {
+                counters.misses.fetch_add(1, Ordering::Relaxed);
+                return Err(HkvError::NotFound);
+            }
+            if consistency.mode == ConsistencyModeId::AsyncRefresh {
+                self.lock_refreshes().push(RefreshRequest {
+                    tenant: domain.tenant,
+                    key: key.clone(),
+                    version: slot.invalidated_by,
+                });
+            }
+        }
+        Ok(slot)
+    }
+
+    /// Copies one batch entry in from user memory and promotes it.
+    fn promote_descriptor(
+        &self,
+        domain: &Arc<Domain>,
+        descriptor: &BatchPromoteDescriptor,
+        user: &BatchPromoteMemory<'_>,
+    ) -> HkvResult<()> {
+        if descriptor.reserved != 0 {
+            return Err(HkvError::ProtocolViolation);
+        }
+        let key = Key::new(user.key(descriptor)?)?;
+        self.promote_entry(
+            domain,
+            Promotion {
+                key: &key,
+                value: user.value(descriptor)?,
+                version: descriptor.version,
+                ttl: descriptor.ttl,
+            },
+        )
+    }
+
+    /// Validates and inserts one entry, making room first when needed.
+    fn promote_entry(&self, domain: &Arc<Domain>, entry: Promotion<'_>) -> HkvResult<()> {
+        check_key(entry.key)?;
+        if entry.value.len() > MAX_EXT_VALUE_SIZE {
+            return Err(HkvError::ValueTooLong);
+        }
+
+        let size = entry_size(entry.key.len(), entry.value.len());
+        let limits = self.limits();
+        let quota = domain.share().quota;
+        if size > limits.max_bytes || (quota != 0 && size > quota) {
+            return Err(HkvError::CapacityExceeded);
+        }
+        let hash = self.hash(domain.tenant, entry.key);
+        let existing = domain.read_shard(hash).get(entry.key).cloned();
+        let floor = match existing.as_ref() {
+            Some(slot) => slot.floor(),
+            None => domain.tombstone_floor(entry.key),
+        };
+        if entry.version < floor {
+            return Err(HkvError::StaleVersion);
+        }
+        let (freed, slots) = existing
+            .as_ref()
+            .map(|slot| (slot.size(), 1))
+            .unwrap_or((0, 0));
+        let added = size - freed.min(size);
+        if existing.is_none() && self.under_pressure(&limits, size) {
+            let candidate = Candidate {
+                key_hash: hash,
+                size,
+            };
+            if !self.admit(domain, &limits, candidate) {
+                return Err(HkvError::CapacityExceeded);
+            }
+        }
+        if quota != 0 && domain.used_bytes.load(Ordering::Relaxed) + added > quota {
+            // The replaced entry stays resident until the swap, so it counts
+            // toward the target rather than being evicted for room.
+            self.evict_domain(domain, quota - added, Some(entry.key));
+            if domain.used_bytes.load(Ordering::Relaxed) + added > quota {
+                return Err(HkvError::CapacityExceeded);
+            }
+        }
+        if !self.fits(&limits, added, 1 - slots) {
+            let bytes_target = limits
+                .bytes_at(limits.low_watermark)
+                .min(limits.max_bytes - size);
+            let entries_target = limits
+                .entries_at(limits.low_watermark)
+                .min(limits.max_entries - 1);
+            self.evict_until(bytes_target, entries_target, Some((domain, entry.key)));
+            if !self.fits(&limits, added, 1 - slots) {
+                return Err(HkvError::CapacityExceeded);
+            }
+        }
+
+        let slot = Arc::new(Slot {
+            metadata: EntryMetadata::new(
+                entry.version,
+                entry.ttl,
+                entry.key.len() as u16,
+                entry.value.len() as u16,
+            ),
+            value: entry.value.into(),
+            invalidated_by: Version::ZERO,
+            invalidated_at: 0,
+        });
+
+        let mut shard = domain.write_shard(hash, &self.lock_contentions);
+        let (floor, freed) = match shard.get(entry.key) {
+            Some(current) => (current.floor(), current.size()),
+            None => (domain.tombstone_floor(entry.key), 0),
+        };
+        if entry.version < floor {
+            return Err(HkvError::StaleVersion);
+        }
+        // Charged under the shard lock, against the entry actually replaced.
+        if !domain.charge(size, freed, quota) {
+            return Err(HkvError::CapacityExceeded);
+        }
+        let replaced = shard.insert(entry.key.clone(), slot);
+        if replaced.is_none() {
+            domain.clear_tombstone(entry.key);
+        }
+        drop(shard);
+
+        let mut eviction = domain.lock_eviction();
+        match replaced {
+            Some(_) => eviction.on_access(entry.key),
+            None => eviction.on_insert(entry.key),
+        }
+        drop(eviction);
+        if let Some(old) = replaced {
+            self.retire(domain, &old);
+            self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        }
+        self.used_bytes.fetch_add(size, Ordering::Relaxed);
+        self.entry_count.fetch_add(1, Ordering::Relaxed);
+        domain.counters.promotions.fetch_add(1, Ordering::Relaxed);
+        self.enforce_watermarks(&limits);
+        Ok(())
+    }
+
+    /// Returns true when a new entry of `size` bytes would not fit or would
+    /// cross the high watermark.
+    fn under_pressure(&self, limits: &Limits, size: u64) -> bool {
+        !self.fits(limits, size, 1)
+            || self.used_bytes.load(Ordering::Relaxed) + size
+                > limits.bytes_at(limits.high_watermark)
+            || self.entry_count.load(Ordering::Relaxed) + 1
+                > limits.entries_at(limits.high_watermark)
+    }
+
+    /// Asks the admission policy whether `candidate` may displace the next
+    /// eviction victim: from the tenant furthest over budget, or from the
+    /// candidate's own tenant when nobody is over.
+    fn admit(&self, domain: &Arc<Domain>, limits: &Limits, candidate: Candidate) -> bool {
+        let victim_domain = self
+            .budgets(limits)
+            .into_iter()
+            .filter(|(other, budget)| other.used_bytes.load(Ordering::Relaxed) > *budget)
+            .max_by_key(|(other, budget)| other.used_bytes.load(Ordering::Relaxed) - budget)
+            .map(|(other, _)| other)
+            .unwrap_or_else(|| Arc::clone(domain));
+
+        let victim = {
+            let eviction = victim_domain.lock_eviction();
+            eviction.peek_victim().and_then(|key| {
+                let hash = self.hash(victim_domain.tenant, key);
+                let size = victim_domain
+                    .read_shard(hash)
+                    .get(key)
+                    .map(|slot| slot.size())?;
+                Some(Candidate {
+                    key_hash: hash,
+                    size,
+                })
+            })
+        };
+        let policies = self.lock_policies();
+        policies
+            .admission
+            .admit(candidate, victim, policies.hotness.as_ref())
+    }
+
+    /// Reports a lookup to the policy plane unless it is busy.
+    fn observe(&self, domain: &Domain, hash: u64, hit: Option<&Key>) {
+        if let Ok(mut policies) = self.policies.try_lock() {
+            policies.hotness.record(hash);
+        }
+        if let Some(key) = hit
+            && let Some(mut eviction) = domain.try_lock_eviction()
+        {
+            eviction.on_access(key);
+        }
+    }
+
+    /// Returns true when adding `bytes` and `entries` stays within limits.
+    fn fits(&self, limits: &Limits, bytes: u64, entries: u64) -> bool {
+        self.used_bytes.load(Ordering::Relaxed) + bytes <= limits.max_bytes
+            && self.entry_count.load(Ordering::Relaxed) + entries <= limits.max_entries
+    }
+
+    /// Evicts down to the low watermark once usage crosses the high one.
+    fn enforce_watermarks(&self, limits: &Limits) {
+        let used = self.used_bytes.load(Ordering::Relaxed);
+        let entries = self.entry_count.load(Ordering::Relaxed);
+        if used > limits.bytes_at(limits.high_watermark)
+            || entries > limits.entries_at(limits.high_watermark)
+        {
+            self.evict_until(
+                limits.bytes_at(limits.low_watermark),
+                limits.entries_at(limits.low_watermark),
+                None,
+            );
+        }
+    }
+
+    /// Evicts across tenants until both global gauges are at or below the
+    /// targets.
+    ///
+    /// Tenants are drained in passes: down to their budget (largest overage
+    /// first); then the promoting tenant, which pays for its own growth once
+    /// within budget; then others down to their minimum guarantee; then
+    /// anyone. `keep` is never evicted (the entry being promoted).
+    fn evict_until(
+        &self,
+        bytes_target: u64,
+        entries_target: u64,
+        keep: Option<(&Arc<Domain>, &Key)>,
+    ) {
+        let over = || {
+            self.used_bytes.load(Ordering::Relaxed) > bytes_target
+                || self.entry_count.load(Ordering::Relaxed) > entries_target
+        };
+        if !over() {
+            return;
+        }
+
+        let used = |domain: &Domain| domain.used_bytes.load(Ordering::Relaxed);
+        let mut passes = self.budgets(&self.limits());
+        passes.sort_by_key(|(domain, budget)| {
+            std::cmp::Reverse(used(domain).saturating_sub(*budget))
+        });
+        let mut by_guarantee: Vec<_> = passes
+            .iter()
+            .map(|(domain, _)| (Arc::clone(domain), domain.share().min_guarantee))
+            .collect();
+        by_guarantee
+            .sort_by_key(|(domain, floor)| std::cmp::Reverse(used(domain).saturating_sub(*floor)));
+        passes.extend(keep.map(|(owner, _)| (Arc::clone(owner), 0)));
+        passes.extend(by_guarantee.iter().cloned());
+        passes.extend(by_guarantee.into_iter().map(|(domain, _)| (domain, 0)));
+
+        let mut evicted = false;
+        for (domain, floor) in passes {
+            if !over() {
+                break;
+            }
+            let keep = keep
+                .filter(|(owner, _)| Arc::ptr_eq(owner, &domain))
+                .map(|(_, key)| key);
+            evicted |= self.evict_from(&domain, keep, || over() && used(&domain) > floor);
+        }
+        if evicted {
+            self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Evicts from one tenant until it uses at most `bytes_target`.
+    fn evict_domain(&self, domain: &Domain, bytes_target: u64, keep: Option<&Key>) {
+        let over = || domain.used_bytes.load(Ordering::Relaxed) > bytes_target;
+        if self.evict_from(domain, keep, over) {
+            self.rcu_grace_periods.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Evicts from `domain` while `over` holds; returns true if anything was
+    /// evicted.
+    ///
+    /// Order: stale and expired entries first, then the domain's eviction
+    /// policy victims, then (for keys the policy lost track of in a race)
+    /// any entry. `keep` is never evicted.
+    fn evict_from(&self, domain: &Domain, keep: Option<&Key>, over: impl Fn() -> bool) -> bool {
+        if !over() {
+            return false;
+        }
+
+        let now = now_nanos();
+        let mut evicted = false;
+        let dead = self.scan(domain, |key, slot| {
+            keep != Some(key) && (slot.is_stale() || slot.is_expired(now))
+        });
+        for key in dead {
+            if !over() {
+                break;
+            }
+            evicted |= self.evict(domain, &key);
+        }
+
+        let mut kept = false;
+        while over() {
+            let victims = domain.lock_eviction().select_victims(EVICTION_BATCH);
+            if victims.is_empty() {
+                break;
+            }
+            for key in victims {
+                if keep == Some(&key) {
+                    kept = true;
+                    continue;
+                }
+                if !over() {
+                    break;
+                }
+                evicted |= self.evict(domain, &key);
+            }
+        }
+        if kept && let Some(key) = keep {
+            domain.lock_eviction().on_insert(key);
+        }
+
+        if over() {
+            for key in self.scan(domain, |key, _| keep != Some(key)) {
+                if !over() {
+                    break;
+                }
+                evicted |= self.evict(domain, &key);
+            }
+        }
+        evicted
+    }
+
+    /// Removes `key` as an eviction; returns false if it was already gone.
+    fn evict(&self, domain: &Domain, key: &Key) -> bool {
+        let hash = self.hash(domain.tenant, key);
+        let mut shard = domain.write_shard(hash, &self.lock_contentions);
+        let Some(slot) = shard.remove(key) else {
+            return false;
+        };
+        if slot.is_stale() {
+            domain.add_tombstone(key, slot.invalidated_by);
+        }
+        drop(shard);
+        domain.lock_eviction().on_remove(key);
+        self.retire(domain, &slot);
+        domain.counters.evictions.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Returns the keys of every entry of `domain` matching `filter`.
+    fn scan(&self, domain: &Domain, filter: impl Fn(&Key, &Slot) -> bool) -> Vec<Key> {
+        let mut keys = Vec::new();
+        for shard in domain.shards() {
+            let shard = shard.read().unwrap_or_else(|err| err.into_inner());
+            keys.extend(
+                shard
+                    .iter()
+                    .filter(|(key, slot)| filter(key, slot))
+                    .map(|(key, _)| key.clone()),
+            );
+        }
+        keys
+    }
+
+    /// Returns every domain paired with its current byte budget.
+    fn budgets(&self, limits: &Limits) -> Vec<(Arc<Domain>, u64)> {
+        let policies = self.lock_policies();
+        let domains = self.domains();
+        let shares: Vec<_> = domains.iter().map(|domain| domain.share()).collect();
+        let budgets = policies.tenant.allocate(limits.max_bytes, &shares);
+        domains.into_iter().zip(budgets).collect()
+    }
+
+    /// Releases a removed or replaced slot's accounting.
+    fn retire(&self, domain: &Domain, slot: &Slot) {
+        domain.retire(slot);
+        self.used_bytes.fetch_sub(slot.size(), Ordering::Relaxed);
+        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn domain(&self, tenant: u16) -> HkvResult<Arc<Domain>> {
+        self.tenants
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&tenant)
+            .cloned()
+            .ok_or(HkvError::InvalidInput)
+    }
+
+    fn domains(&self) -> Vec<Arc<Domain>> {
+        self.tenants
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn limits(&self) -> Limits {
+        *self.limits.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn lock_policies(&self) -> MutexGuard<'_, PolicySet> {
+        self.policies.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn lock_refreshes(&self) -> MutexGuard<'_, RefreshQueue> {
+        self.refreshes.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns the tenant's consistency override, or the cache-wide mode.
+    fn consistency_of(&self, domain: &Domain) -> Consistency {
+        domain.consistency().unwrap_or_else(|| {
+            *self
+                .consistency
+                .read()
+                .unwrap_or_else(|err| err.into_inner())
+        })
+    }
+
+    /// Hashes a tenant-qualified key, for shard selection and hotness.
+    fn hash(&self, tenant: u16, key: &Key) -> u64 {
+        self.hash_state.hash_one((tenant, key))
+    }
+}
+
+impl Default for KernelCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Checks magic, protocol version, command, and the reserved byte.
+pub(crate) fn check_header(header: &IoctlHeader, command: IoctlCommand) -> HkvResult<()> {
+    if header.magic != IOCTL_MAGIC || header.command != command.as_u8() || header.reserved != 0 {
+        return Err(HkvError::ProtocolViolation);
+    }
+    if !command.supports_version(header.version) {
+        return Err(HkvError::VersionMismatch);
+    }
+    Ok(())
+}
+
+/// Checks that `user` is the memory a request's pointer and length name,
+/// the simulation's stand-in for `access_ok` before `copy_*_user`.
+fn check_user_buf(ptr: u64, len: u32, user: &[u8]) -> HkvResult<()> {
+    if user.as_ptr() as u64 != ptr || user.len() != len as usize {
+        return Err(HkvError::InvalidInput);
+    }
+    Ok(())
+}
+
+/// Rejects empty keys and lengths past the fixed buffer before hashing.
+fn check_key(key: &Key) -> HkvResult<()> {
+    if key.len() > MAX_KEY_SIZE {
+        return Err(HkvError::KeyTooLong);
+    }
+    if key.is_empty() {
+        return Err(HkvError::InvalidInput);
+    }
+    Ok(())
+}
+
+/// Counts a served lookup, and a stale hit when the value was stale.
+fn record_hit(domain: &Domain, slot: &Slot) {
+    domain.counters.hits.fetch_add(1, Ordering::Relaxed);
+    if slot.is_stale() {
+        domain.counters.stale_hits.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Wall-clock nanoseconds, the clock `Ttl::from_duration` uses.
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_nanos() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::MAX_TOMBSTONES;
+    use hkv_common::{BatchPromoteBuilder, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+
+    fn key(name: &str) -> Key {
+        Key::new(name.as_bytes()).unwrap()
+    }
+
+    fn promote(cache: &KernelCache, name: &str, value: &str, version: u64) -> HkvResult<()> {
+        cache.promote(&PromoteRequest::new(
+            key(name),
+            Value::new(value.as_bytes()).unwrap(),
+            Version::new(version),
+            Ttl::INFINITE,
+        ))
+    }
+
+    fn read(cache: &KernelCache, name: &str) -> HkvResult<Vec<u8>> {
+        cache
+            .read(&ReadRequest::new(key(name)))
+            .map(|value| value.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn promote_read_demote_round_trip() {
+        let cache = KernelCache::new();
+        assert_eq!(read(&cache, "k"), Err(HkvError::NotFound));
+        promote(&cache, "k", "v1", 1).unwrap();
+        assert_eq!(read(&cache, "k").unwrap(), b"v1");
+
+        cache.demote(&DemoteRequest::new(key("k"))).unwrap();
+        cache.demote(&DemoteRequest::new(key("k"))).unwrap();
+        assert_eq!(read(&cache, "k"), Err(HkvError::NotFound));
+
+        let stats = cache.snapshot();
+        assert_eq!((stats.lookups, stats.hits, stats.misses), (3, 1, 2));
+        assert_eq!((stats.promotions, stats.demotions), (1, 1));
+        assert_eq!((stats.used_bytes, stats.entry_count), (0, 0));
+    }
+
+    #[test]
+    fn version_one_reads_serve_the_default_tenant() {
+        let cache = KernelCache::new();
+        promote(&cache, "k", "v1", 1).unwrap();
+        let value = cache.read_v1(&ReadRequestV1::new(key("k"))).unwrap();
+        assert_eq!(value.as_bytes(), b"v1");
+        assert_eq!(
+            cache.read_v1(&ReadRequestV1::new(key("missing"))),
+            Err(HkvError::NotFound)
+        );
+        assert_eq!(cache.snapshot().hits, 1);
+    }
+
+    #[test]
+    fn invalidation_leaves_a_tombstone_that_rejects_older_promotions() {
+        let cache = KernelCache::new();
+        promote(&cache, "k", "v1", 1).unwrap();
+        cache
+            .invalidate(&InvalidateRequest::new(key("k"), Version::new(3)))
+            .unwrap();
+        assert_eq!(read(&cache, "k"), Err(HkvError::NotFound));
+        // Strict invalidation never serves the tombstone.
+        assert_eq!(cache.snapshot().stale_hits, 0);
+
+        // A promotion that read the value before the write lost the race.
+        assert_eq!(promote(&cache, "k", "v1", 2), Err(HkvError::StaleVersion));
+        promote(&cache, "k", "v3", 3).unwrap();
+        assert_eq!(read(&cache, "k").unwrap(), b"v3");
+
+        // Older invalidations are no-ops.
+        cache
+            .invalidate(&InvalidateRequest::new(key("k"), Version::new(2)))
+            .unwrap();
+        assert_eq!(read(&cache, "k").unwrap(), b"v3");
+        assert_eq!(cache.snapshot().invalidations, 1);
+    }
+
+    #[test]
+    fn invalidating_an_uncached_key_rejects_older_promotions() {
+        let cache = KernelCache::new();
+        cache
+            .invalidate(&InvalidateRequest::new(key("k"), Version::new(2)))
+            .unwrap();
+
+        // Read at v1 before the write, issued after its invalidation.
+        assert_eq!(promote(&cache, "k", "v1", 1), Err(HkvError::StaleVersion));
+        assert_eq!(read(&cache, "k"), Err(HkvError::NotFound));
+        promote(&cache, "k", "v2", 2).unwrap();
+        assert_eq!(read(&cache, "k").unwrap(), b"v2");
+        assert_eq!(cache.snapshot().entry_count, 1);
+    }
+
+    #[test]
+    fn evicting_a_tombstone_keeps_its_floor() {
+        let cache = KernelCache::with_config(&ConfigRequest::new(u64::MAX, 10, 100, 10)).unwrap();
+        promote(&cache, "k", "v1", 1).unwrap();
+        cache
+            .invalidate(&InvalidateRequest::new(key("k"), Version::new(3)))
+            .unwrap();
+        for i in 0..10 {
+            promote(&cache, &format!("f{i}"), "v", 1).unwrap();
+        }
+        assert!(cache.snapshot().evictions > 0);
+
+        assert_eq!(promote(&cache, "k", "v2", 2), Err(HkvError::StaleVersion));
+        promote(&cache, "k", "v3", 3).unwrap();
+    }
+
+    #[test]
+    fn tombstones_are_bounded_oldest_first() {
+        let cache = KernelCache::new();
+        for i in 0..=MAX_TOMBSTONES {
+            cache
+                .invalidate(&InvalidateRequest::new(
+                    key(&format!("k{i}")),
+                    Version::new(5),
+                ))
+                .unwrap();
+        }
+        promote(&cache, "k0", "v", 1).unwrap();
+        assert_eq!(
+            promote(&cache, &format!("k{MAX_TOMBSTONES}"), "v", 1),
+            Err(HkvError::StaleVersion)
+        );
+    }
+
+    #[test]
+    fn expired_entries_miss() {
+        let cache = KernelCache::new();
+        let request = PromoteRequest::new(
+            key("k"),
+            Value::new(b"v").unwrap(),
+            Version::new(1),
+            Ttl::from_nanos(1),
+        );
+        cache.promote(&request).unwrap();
+        assert_eq!(read(&cache, "k"), Err(HkvError::NotFound));
+    }
+
+    #[test]
+    fn watermarks_evict_least_recently_read_entries() {
+        let cache = KernelCache::with_config(&ConfigRequest::new(u64::MAX, 10, 80, 50)).unwrap();
+        for i in 0..8 {
+            promote(&cache, &format!("k{i}"), "v", 1).unwrap();
+        }
+        read(&cache, "k0").unwrap();
+        assert_eq!(cache.snapshot().evictions, 0);
+
+        // The ninth entry crosses 80% of 10 and drains to 50%.
+        promote(&cache, "k8", "v", 1).unwrap();
+        let stats = cache.snapshot();
+        assert_eq!(stats.entry_count, 5);
+        assert_eq!(stats.evictions, 4);
+        assert!(read(&cache, "k0").is_ok());
+        assert!(read(&cache, "k8").is_ok());
+        assert_eq!(read(&cache, "k1"), Err(HkvError::NotFound));
+    }
+
+    #[test]
+    fn byte_budget_is_a_hard_limit() {
+        let entry = entry_size(2, 10);
+        let cache =
+            KernelCache::with_config(&ConfigRequest::new(entry * 2, 100, 100, 100)).unwrap();
+        promote(&cache, "k1", "0123456789", 1).unwrap();
+        promote(&cache, "k2", "0123456789", 1).unwrap();
+        promote(&cache, "k3", "0123456789", 1).unwrap();
+        let stats = cache.snapshot();
+        assert_eq!(stats.used_bytes, entry * 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(read(&cache, "k1"), Err(HkvError::NotFound));
+
+        let big = "x".repeat(MAX_VALUE_SIZE);
+        let tiny = KernelCache::with_config(&ConfigRequest::new(64, 100, 100, 100)).unwrap();
+        assert_eq!(
+            promote(&tiny, "k", &big, 1),
+            Err(HkvError::CapacityExceeded)
+        );
+    }
+
+    #[test]
+    fn batch_promote_reports_per_entry_results() {
+        let cache = KernelCache::new();
+        promote(&cache, "b", "new", 9).unwrap();
+        let blob = vec![3u8; 16 * 1024];
+        let mut batch = BatchPromoteBuilder::new();
+        batch
+            .push(b"a", b"1", Version::new(1), Ttl::INFINITE)
+            .unwrap();
+        batch
+            .push(b"b", b"old", Version::new(2), Ttl::INFINITE)
+            .unwrap();
+        batch
+            .push(b"", b"x", Version::new(1), Ttl::INFINITE)
+            .unwrap();
+        batch
+            .push(b"blob", &blob, Version::new(1), Ttl::INFINITE)
+            .unwrap();
+
+        let (request, mut user) = batch.prepare();
+        cache.batch_promote(&request, &mut user).unwrap();
+        assert!(batch.succeeded(0));
+        assert_eq!(batch.status(1), Err(HkvError::StaleVersion));
+        assert_eq!(batch.status(2), Err(HkvError::InvalidInput));
+        assert!(batch.succeeded(3));
+        assert_eq!(read(&cache, "b").unwrap(), b"new");
+
+        let mut buf = vec![0u8; blob.len()];
+        let request = ReadExtRequest::new(key("blob"), &mut buf);
+        assert_eq!(cache.read_ext(&request, &mut buf), Ok(blob.len()));
+        assert_eq!(buf, blob);
+    }
+
+    #[test]
+    fn batch_promote_rejects_requests_outside_user_memory() {
+        let cache = KernelCache::new();
+        let mut batch = BatchPromoteBuilder::new();
+        batch
+            .push(b"a", b"1", Version::new(1), Ttl::INFINITE)
+            .unwrap();
+
+        let (mut request, mut user) = batch.prepare();
+        request.statuses_ptr = 0;
+        assert_eq!(
+            cache.batch_promote(&request, &mut user),
+            Err(HkvError::InvalidInput)
+        );
+        let (mut request, mut user) = batch.prepare();
+        request.header.version = 1;
+        assert_eq!(
+            cache.batch_promote(&request, &mut user),
+            Err(HkvError::VersionMismatch)
+        );
+        assert_eq!(read(&cache, "a"), Err(HkvError::NotFound));
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let cache = KernelCache::new();
+        let mut request = ReadRequest::new(key("k"));
+        request.header.magic = 0;
+        assert_eq!(cache.read(&request), Err(HkvError::ProtocolViolation));
+
+        let mut request = ReadRequest::new(key("k"));
+        request.header.version = PROTOCOL_VERSION + 1;
+        assert_eq!(cache.read(&request), Err(HkvError::VersionMismatch));
+
+        let request = ReadRequest::new(Key::new(b"").unwrap());
+        assert_eq!(cache.read(&request), Err(HkvError::InvalidInput));
+
+        assert_eq!(
+            cache.config(&ConfigRequest::new(1024, 10, 50, 60)),
+            Err(HkvError::InvalidInput)
+        );
+        assert_eq!(cache.snapshot().lookups, 0);
+
+        // A version 1 read must use the version 1 layout, and vice versa.
+        let mut request = ReadRequest::new(key("k"));
+        request.header.version = ReadRequestV1::VERSION;
+        assert_eq!(cache.read(&request), Err(HkvError::VersionMismatch));
+        let mut request = ReadRequestV1::new(key("k"));
+        request.header.version = PROTOCOL_VERSION;
+        assert_eq!(cache.read_v1(&request), Err(HkvError::VersionMismatch));
+        let mut buf = [0u8; 8];
+        let mut request = ReadExtRequest::new(key("k"), &mut buf);
+        request.header.version = MIN_PROTOCOL_VERSION;
+        assert_eq!(
+            cache.read_ext(&request, &mut buf),
+            Err(HkvError::VersionMismatch)
+        );
+
+        // The user memory must be the buffer the request points at.
+        let request = ReadExtRequest::new(key("k"), &mut buf);
+        let mut other = [0u8; 8];
+        assert_eq!(
+            cache.read_ext(&request, &mut other),
+            Err(HkvError::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn extended_values_round_trip_through_user_buffers() {
+        let cache = KernelCache::new();
+        let blob: Vec<u8> = (0..6 * 1024).map(|i| i as u8).collect();
+        let request =
+            PromoteExtRequest::new(key("session"), &blob, Version::new(1), Ttl::INFINITE).unwrap();
+        cache.promote_ext(&request, &blob).unwrap();
+        assert_eq!(
+            cache.snapshot().used_bytes,
+            entry_size("session".len(), blob.len())
+        );
+
+        // Too small: nothing copied, length reported for the retry.
+        let mut small = vec![0u8; 4096];
+        let request = ReadExtRequest::new(key("session"), &mut small);
+        assert_eq!(cache.read_ext(&request, &mut small), Ok(blob.len()));
+        assert!(small.iter().all(|&b| b == 0));
+
+        let mut buf = vec![0u8; 8192];
+        let request = ReadExtRequest::new(key("session"), &mut buf);
+        assert_eq!(cache.read_ext(&request, &mut buf), Ok(blob.len()));
+        assert_eq!(&buf[..blob.len()], &blob[..]);
+
+        // The inline command cannot carry it.
+        assert_eq!(read(&cache, "session"), Err(HkvError::ValueTooLong));
+
+        // Small values are served by both commands.
+        promote(&cache, "small", "v", 1).unwrap();
+        let request = ReadExtRequest::new(key("small"), &mut buf);
+        assert_eq!(cache.read_ext(&request, &mut buf), Ok(1));
+        assert_eq!(&buf[..1], b"v");
+
+        let too_long = vec![0u8; MAX_EXT_VALUE_SIZE + 1];
+        let mut request =
+            PromoteExtRequest::new(key("big"), &too_long[..8], Version::new(1), Ttl::INFINITE)
+                .unwrap();
+        request.value_ptr = too_long.as_ptr() as u64;
+        request.value_len = too_long.len() as u32;
+        assert_eq!(
+            cache.promote_ext(&request, &too_long),
+            Err(HkvError::ValueTooLong)
+        );
+    }
+
+    #[test]
+    fn config_swaps_the_eviction_policy_at_runtime() {
+        use hkv_common::EvictionPolicyId;
+
+        let limits = ConfigRequest::new(u64::MAX, 10, 80, 50);
+        let cache = KernelCache::with_config(&limits).unwrap();
+        for i in 0..8 {
+            promote(&cache, &format!("k{i}"), "v", 1).unwrap();
+        }
+        cache
+            .config(&limits.with_eviction(EvictionPolicyId::Fifo))
+            .unwrap();
+        assert_eq!(cache.policy_ids().eviction, EvictionPolicyId::Fifo.as_u8());
+
+        // Under FIFO a read no longer saves k0.
+        read(&cache, "k0").unwrap();
+        promote(&cache, "k8", "v", 1).unwrap();
+        assert_eq!(cache.snapshot().entry_count, 5);
+        assert_eq!(read(&cache, "k0"), Err(HkvError::NotFound));
+        assert!(read(&cache, "k4").is_ok());
+
+        let mut unknown = limits;
+        unknown.eviction_policy = 200;
+        assert_eq!(cache.config(&unknown), Err(HkvError::InvalidInput));
+        assert_eq!(cache.policy_ids().eviction, EvictionPolicyId::Fifo.as_u8());
+    }
+
+    #[test]
+    fn admission_policy_gates_new_keys_under_pressure() {
+        use hkv_common::AdmissionPolicyId;
+
+        let limits =
+            ConfigRequest::new(u64::MAX, 4, 100, 50).with_admission(AdmissionPolicyId::TinyLfu);
+        let cache = KernelCache::with_config(&limits).unwrap();
+        for i in 0..4 {
+            promote(&cache, &format!("k{i}"), "v", 1).unwrap();
+            read(&cache, &format!("k{i}")).unwrap();
+        }
+
+        // A never-read key is not hotter than the LRU victim.
+        assert_eq!(
+            promote(&cache, "cold", "v", 1),
+            Err(HkvError::CapacityExceeded)
+        );
+        // Misses count towards hotness, so a key read often enough gets in.
+        for _ in 0..3 {
+            let _ = read(&cache, "hot");
+        }
+        promote(&cache, "hot", "v", 1).unwrap();
+        assert!(read(&cache, "hot").is_ok());
+        // Replacing a resident key skips admission.
+        promote(&cache, "hot", "v2", 2).unwrap();
+    }
+
+    fn register(cache: &KernelCache, request: TenantConfigRequest) {
+        cache.tenant_config(&request).unwrap();
+    }
+
+    fn promote_as(cache: &KernelCache, tenant: u16, name: &str) -> HkvResult<()> {
+        cache.promote(
+            &PromoteRequest::new(
+                key(name),
+                Value::new(b"0123456789").unwrap(),
+                Version::new(1),
+                Ttl::INFINITE,
+            )
+            .with_tenant(tenant),
+        )
+    }
+
+    #[test]
+    fn tenants_have_separate_key_spaces_and_stats() {
+        let cache = KernelCache::new();
+        register(&cache, TenantConfigRequest::new(1));
+        promote(&cache, "k", "default", 1).unwrap();
+        let request = PromoteRequest::new(
+            key("k"),
+            Value::new(b"tenant-1").unwrap(),
+            Version::new(1),
+            Ttl::INFINITE,
+        );
+        cache.promote(&request.with_tenant(1)).unwrap();
+
+        let read_as = |tenant| cache.read(&ReadRequest::new(key("k")).with_tenant(tenant));
+        assert_eq!(read_as(0).unwrap().as_bytes(), b"default");
+        assert_eq!(read_as(1).unwrap().as_bytes(), b"tenant-1");
+        assert_eq!(read_as(2), Err(HkvError::InvalidInput));
+
+        cache
+            .demote(&DemoteRequest::new(key("k")).with_tenant(1))
+            .unwrap();
+        assert_eq!(read_as(1), Err(HkvError::NotFound));
+        assert!(read_as(0).is_ok());
+
+        let tenant = cache.stats(&StatsRequest::for_tenant(1)).unwrap();
+        assert_eq!((tenant.tenant, tenant.tenant_count), (1, 2));
+        assert_eq!((tenant.lookups, tenant.hits, tenant.misses), (2, 1, 1));
+        assert_eq!((tenant.entry_count, tenant.demotions), (0, 1));
+        let total = cache.stats(&StatsRequest::new()).unwrap();
+        assert_eq!(
+            (total.tenant, total.lookups, total.entry_count),
+            (u32::from(ALL_TENANTS), 4, 1)
+        );
+        assert_eq!(cache.tenants(), vec![0, 1]);
+
+        let invalid = TenantConfigRequest::new(3)
+            .with_quota(10)
+            .with_min_guarantee(20);
+        assert_eq!(cache.tenant_config(&invalid), Err(HkvError::InvalidInput));
+        assert_eq!(
+            cache.tenant_config(&TenantConfigRequest::new(ALL_TENANTS)),
+            Err(HkvError::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn hard_quota_evicts_only_within_the_tenant() {
+        let entry = entry_size(2, 10);
+        let cache = KernelCache::new();
+        register(&cache, TenantConfigRequest::new(1).with_quota(entry * 3));
+        for i in 0..4 {
+            promote_as(&cache, 0, &format!("d{i}")).unwrap();
+        }
+        for i in 0..6 {
+            promote_as(&cache, 1, &format!("t{i}")).unwrap();
+        }
+
+        let tenant = cache.tenant_snapshot(1).unwrap();
+        assert_eq!(
+            (tenant.used_bytes, tenant.max_bytes),
+            (entry * 3, entry * 3)
+        );
+        assert_eq!(tenant.evictions, 3);
+        let default = cache.tenant_snapshot(0).unwrap();
+        assert_eq!((default.entry_count, default.evictions), (4, 0));
+
+        // Replacing a resident key at a full quota reuses its own bytes, so
+        // nothing else is evicted.
+        promote_as(&cache, 1, "t5").unwrap();
+        let tenant = cache.tenant_snapshot(1).unwrap();
+        assert_eq!((tenant.entry_count, tenant.evictions), (3, 3));
+
+        // Lowering the quota applies immediately.
+        register(&cache, TenantConfigRequest::new(1).with_quota(entry));
+        assert_eq!(cache.tenant_snapshot(1).unwrap().entry_count, 1);
+    }
+
+    #[test]
+    fn global_pressure_reclaims_from_tenants_over_their_budget() {
+        let entry = entry_size(2, 10);
+        let cache =
+            KernelCache::with_config(&ConfigRequest::new(entry * 10, 100, 100, 100)).unwrap();
+        register(
+            &cache,
+            TenantConfigRequest::new(DEFAULT_TENANT).with_weight(0),
+        );
+        register(&cache, TenantConfigRequest::new(1));
+        register(
+            &cache,
+            TenantConfigRequest::new(2).with_min_guarantee(entry * 2),
+        );
+
+        // Alone, tenant 1 may use the whole cache.
+        for i in 0..10 {
+            promote_as(&cache, 1, &format!("a{i}")).unwrap();
+        }
+        assert_eq!(cache.tenant_snapshot(1).unwrap().entry_count, 10);
+
+        // Budgets: tenant 2 gets its 2-entry guarantee plus half of the
+        // remaining 8. Its promotions take memory back from tenant 1 only
+        // down to tenant 1's budget, then it evicts its own entries.
+        for i in 0..8 {
+            promote_as(&cache, 2, &format!("b{i}")).unwrap();
+        }
+        let first = cache.tenant_snapshot(1).unwrap();
+        let second = cache.tenant_snapshot(2).unwrap();
+        assert_eq!(first.budget_bytes, entry * 4);
+        assert_eq!(second.budget_bytes, entry * 6);
+        assert_eq!(first.used_bytes, entry * 4);
+        assert_eq!(second.used_bytes, entry * 6);
+        assert_eq!((first.evictions, second.evictions), (6, 2));
+        assert_eq!(cache.snapshot().used_bytes, entry * 10);
+    }
+
+    #[test]
+    fn flush_clears_everything() {
+        let cache = KernelCache::new();
+        for i in 0..10 {
+            promote(&cache, &format!("k{i}"), "v", 1).unwrap();
+        }
+        cache.flush(&FlushRequest::new()).unwrap();
+        let stats = cache.snapshot();
+        assert_eq!((stats.entry_count, stats.used_bytes), (0, 0));
+        assert!(stats.rcu_grace_periods >= 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! # Consistency Modes
+//!
+//! Decide what a read of a stale (invalidated) entry returns, and queue the
+//! refresh requests the async-refresh mode sends to user space.
+//!
+//! ## Design Principles
+//!
+//! 1. **Cache Default, Tenant Override**: `CMD_CONFIG` sets the mode for the
+//!    whole cache or for one tenant; a tenant without an override (or whose
+//!    override was dropped with `CONSISTENCY_INHERIT`) follows the cache.
+//! 2. **Age From Invalidation**: A stale value's age is the time since the
+//!    write that invalidated it, not since it was promoted.
+//! 3. **Bounded Queue**: Refreshes are deduplicated per key and capped, so a
+//!    hot stale key or a stalled consumer cannot grow kernel memory; a
+//!    dropped refresh is re-queued by the next stale read. User space
+//!    drains the queue and re-promotes the keys from the engine.
+
+use std::collections::{HashSet, VecDeque};
+
+use hkv_common::{
+    ALL_TENANTS, CONSISTENCY_INHERIT, ConfigRequest, ConsistencyModeId, HkvError, HkvResult, Key,
+    POLICY_KEEP, RefreshRequest,
+};
+
+use crate::domain::Slot;
+
+/// Maximum refresh requests waiting for user space.
+pub const MAX_PENDING_REFRESHES: usize = 1024;
+
+/// A stale-read mode and its stale-age bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Consistency {
+    pub(crate) mode: ConsistencyModeId,
+    /// Oldest servable stale value, in nanoseconds since invalidation
+    /// (0 = unbounded, async refresh only).
+    pub(crate) max_stale_nanos: u64,
+}
+
+impl Default for Consistency {
+    fn default() -> Self {
+        Consistency {
+            mode: ConsistencyModeId::Strict,
+            max_stale_nanos: 0,
+        }
+    }
+}
+
+/// What a config request does to a consistency mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConsistencyChange {
+    /// Install this mode.
+    Set(Consistency),
+    /// Drop the tenant's override.
+    Inherit,
+}
+
+impl Consistency {
+    /// Validates the consistency fields of a config request.
+    ///
+    /// Returns `None` for `POLICY_KEEP`. Bounded staleness needs a non-zero
+    /// `max_stale_ms`; `CONSISTENCY_INHERIT` needs a single tenant.
+    pub(crate) fn from_request(request: &ConfigRequest) -> HkvResult<Option<ConsistencyChange>> {
+        match request.consistency_mode {
+            POLICY_KEEP => return Ok(None),
+            CONSISTENCY_INHERIT if request.consistency_tenant == ALL_TENANTS => {
+                return Err(HkvError::InvalidInput);
+            }
+            CONSISTENCY_INHERIT => return Ok(Some(ConsistencyChange::Inherit)),
+            _ => {}
+        }
+        let mode =
+            ConsistencyModeId::from_u8(request.consistency_mode).ok_or(HkvError::InvalidInput)?;
+        if mode == ConsistencyModeId::BoundedStaleness && request.max_stale_ms == 0 {
+            return Err(HkvError::InvalidInput);
+        }
+        Ok(Some(ConsistencyChange::Set(Consistency {
+            mode,
+            max_stale_nanos: u64::from(request.max_stale_ms) * 1_000_000,
+        })))
+    }
+
+    /// Returns true when a read may return `slot`'s stale value at `now`.
+    pub(crate) fn serves(&self, slot: &Slot, now_nanos: u64) -> bool {
+        let age = now_nanos.saturating_sub(slot.invalidated_at);
+        match self.mode {
+            ConsistencyModeId::Strict => false,
+            ConsistencyModeId::BoundedStaleness => age <= self.max_stale_nanos,
+            ConsistencyModeId::AsyncRefresh => {
+                self.max_stale_nanos == 0 || age <= self.max_stale_nanos
+            }
+        }
+    }
+}
+
+/// Pending refreshes, oldest first, at most one per key.
+#[derive(Default)]
+pub(crate) struct RefreshQueue {
+    pending: VecDeque<RefreshRequest>,
+    queued: HashSet<(u16, Key)>,
+    dropped: u64,
+}
+
+impl RefreshQueue {
+    /// Queues a refresh unless the key is already pending or the queue is
+    /// full; returns true when queued.
+    pub(crate) fn push(&mut self, request: RefreshRequest) -> bool {
+        let id = (request.tenant, request.key.clone());
+        if self.queued.contains(&id) {
+            return false;
+        }
+        if self.pending.len() >= MAX_PENDING_REFRESHES {
+            self.dropped += 1;
+            return false;
+        }
+        self.queued.insert(id);
+        self.pending.push_back(request);
+        true
+    }
+
+    /// Removes and returns every pending refresh.
+    pub(crate) fn drain(&mut self) -> Vec<RefreshRequest> {
+        self.queued.clear();
+        self.pending.drain(..).collect()
+    }
+
+    /// Returns how many refreshes were dropped because the queue was full.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::Version;
+
+    fn refresh(key: &[u8]) -> RefreshRequest {
+        RefreshRequest {
+            tenant: 0,
+            key: Key::new(key).unwrap(),
+            version: Version::new(2),
+        }
+    }
+
+    #[test]
+    fn from_request_validates_and_keeps() {
+        let request = ConfigRequest::new(1024, 16, 90, 70);
+        assert_eq!(Consistency::from_request(&request), Ok(None));
+
+        let bounded = request.with_consistency(ConsistencyModeId::BoundedStaleness, 0);
+        assert_eq!(
+            Consistency::from_request(&bounded),
+            Err(HkvError::InvalidInput)
+        );
+        let bounded = request.with_consistency(ConsistencyModeId::BoundedStaleness, 5);
+        assert_eq!(
+            Consistency::from_request(&bounded),
+            Ok(Some(ConsistencyChange::Set(Consistency {
+                mode: ConsistencyModeId::BoundedStaleness,
+                max_stale_nanos: 5_000_000,
+            })))
+        );
+        assert_eq!(
+            Consistency::from_request(&request.with_inherited_consistency(3)),
+            Ok(Some(ConsistencyChange::Inherit))
+        );
+        assert_eq!(
+            Consistency::from_request(&request.with_inherited_consistency(ALL_TENANTS)),
+            Err(HkvError::InvalidInput)
+        );
+
+        let mut unknown = request;
+        unknown.consistency_mode = 99;
+        assert_eq!(
+            Consistency::from_request(&unknown),
+            Err(HkvError::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn refresh_queue_dedups_and_caps() {
+        let mut queue = RefreshQueue::default();
+        assert!(queue.push(refresh(b"k")));
+        assert!(!queue.push(refresh(b"k")));
+        for index in 1..MAX_PENDING_REFRESHES {
+            assert!(queue.push(refresh(format!("k{index}").as_bytes())));
+        }
+        assert!(!queue.push(refresh(b"overflow")));
+        assert_eq!(queue.dropped(), 1);
+
+        assert_eq!(queue.drain().len(), MAX_PENDING_REFRESHES);
+        assert!(queue.push(refresh(b"k")));
+    }
+}
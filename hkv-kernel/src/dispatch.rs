@@ -0,0 +1,247 @@
+//! # ioctl Dispatch
+//!
+//! Route a raw command number and its argument structs to `KernelCache`,
+//! the way `hybridkv_ioctl(fd, cmd, arg)` does in the module.
+//!
+//! ## Design Principles
+//!
+//! 1. **Command Decides**: The command number is decoded first; unknown
+//!    numbers fail with `UnsupportedCommand` before the argument is touched.
+//! 2. **Argument Must Match**: An argument of the wrong shape for the command
+//!    is `InvalidInput`, the analogue of a size mismatch in the encoded cmd.
+//!    A version 1 `CMD_READ` arrives as `IoctlArg::ReadV1`, the layout the
+//!    module picks after reading the header's version.
+//! 3. **Status In, Errno Out**: Commands with a response struct always write
+//!    it (with `STATUS_OK` or the error code) and also return the error, the
+//!    way the module fills the buffer and returns a negative errno;
+//!    `ioctl_errno` returns that errno (`HkvError::errno`) directly.
+
+use hkv_common::{
+    BatchPromoteMemory, BatchPromoteRequest, CacheStats, ConfigRequest, DemoteRequest,
+    FlushRequest, HkvError, HkvResult, InvalidateRequest, IoctlCommand, PromoteExtRequest,
+    PromoteRequest, PromoteResponse, ReadExtRequest, ReadExtResponse, ReadRequest, ReadRequestV1,
+    ReadResponse, STATUS_OK, StatsRequest, StatsResponse, TenantConfigRequest, Value, status_code,
+};
+
+use crate::cache::KernelCache;
+
+/// Argument buffers for one ioctl call.
+pub enum IoctlArg<'a> {
+    /// `CMD_READ` request and the response to fill.
+    Read(&'a ReadRequest, &'a mut ReadResponse),
+    /// Version 1 `CMD_READ` request and the response to fill.
+    ReadV1(&'a ReadRequestV1, &'a mut ReadResponse),
+    /// `CMD_PROMOTE` request and the response to fill.
+    Promote(&'a PromoteRequest, &'a mut PromoteResponse),
+    /// `CMD_BATCH_PROMOTE` request and the user memory behind its pointers
+    /// (descriptors, key/value bytes, and the status array to fill).
+    BatchPromote(&'a BatchPromoteRequest, BatchPromoteMemory<'a>),
+    /// `CMD_DEMOTE` request.
+    Demote(&'a DemoteRequest),
+    /// `CMD_INVALIDATE` request.
+    Invalidate(&'a InvalidateRequest),
+    /// `CMD_STATS` request and the response to fill.
+    Stats(&'a StatsRequest, &'a mut StatsResponse),
+    /// `CMD_CONFIG` request.
+    Config(&'a ConfigRequest),
+    /// `CMD_FLUSH` request.
+    Flush(&'a FlushRequest),
+    /// `CMD_TENANT_CONFIG` request.
+    TenantConfig(&'a TenantConfigRequest),
+    /// `CMD_READ_EXT` request, the response to fill, and the user memory
+    /// behind `buf_ptr`.
+    ReadExt(&'a ReadExtRequest, &'a mut ReadExtResponse, &'a mut [u8]),
+    /// `CMD_PROMOTE_EXT` request and the user memory behind `value_ptr`.
+    PromoteExt(&'a PromoteExtRequest, &'a [u8]),
+}
+
+impl IoctlArg<'_> {
+    /// Returns the command this argument belongs to.
+    pub fn command(&self) -> IoctlCommand {
+        match self {
+            IoctlArg::Read(..) | IoctlArg::ReadV1(..) => IoctlCommand::Read,
+            IoctlArg::Promote(..) => IoctlCommand::Promote,
+            IoctlArg::BatchPromote(..) => IoctlCommand::BatchPromote,
+            IoctlArg::Demote(_) => IoctlCommand::Demote,
+            IoctlArg::Invalidate(_) => IoctlCommand::Invalidate,
+            IoctlArg::Stats(..) => IoctlCommand::Stats,
+            IoctlArg::Config(_) => IoctlCommand::Config,
+            IoctlArg::Flush(_) => IoctlCommand::Flush,
+            IoctlArg::TenantConfig(_) => IoctlCommand::TenantConfig,
+            IoctlArg::ReadExt(..) => IoctlCommand::ReadExt,
+            IoctlArg::PromoteExt(..) => IoctlCommand::PromoteExt,
+        }
+    }
+}
+
+impl KernelCache {
+    /// Handles one ioctl: decodes `command`, checks `arg` matches, and runs it.
+    pub fn ioctl(&self, command: u8, arg: IoctlArg<'_>) -> HkvResult<()> {
+        let command = IoctlCommand::from_u8(command).ok_or(HkvError::UnsupportedCommand)?;
+        if arg.command() != command {
+            return Err(HkvError::InvalidInput);
+        }
+
+        match arg {
+            IoctlArg::Read(request, response) => fill_read(self.read(request), response),
+            IoctlArg::ReadV1(request, response) => fill_read(self.read_v1(request), response),
+            IoctlArg::Promote(request, response) => {
+                let result = self.promote(request);
+                *response = PromoteResponse::new(status_code(&result));
+                result
+            }
+            IoctlArg::BatchPromote(request, mut user) => self.batch_promote(request, &mut user),
+            IoctlArg::Demote(request) => self.demote(request),
+            IoctlArg::Invalidate(request) => self.invalidate(request),
+            IoctlArg::Stats(request, response) => {
+                let result = self.stats(request);
+                let stats = result.unwrap_or_else(|_| CacheStats::default());
+                *response = StatsResponse::new(status_code(&result), stats);
+                result.map(|_| ())
+            }
+            IoctlArg::Config(request) => self.config(request),
+            IoctlArg::Flush(request) => self.flush(request),
+            IoctlArg::TenantConfig(request) => self.tenant_config(request),
+            IoctlArg::ReadExt(request, response, user) => {
+                let result = self.read_ext(request, user);
+                *response = match &result {
+                    Ok(len) => ReadExtResponse::new(STATUS_OK, *len as u32),
+                    Err(err) => ReadExtResponse::new(err.code(), 0),
+                };
+                result.map(|_| ())
+            }
+            IoctlArg::PromoteExt(request, user) => self.promote_ext(request, user),
+        }
+    }
+
+    /// Handles one ioctl like `ioctl`, returning what the module's handler
+    /// returns: `0` on success or the error's negated errno.
+    pub fn ioctl_errno(&self, command: u8, arg: IoctlArg<'_>) -> i32 {
+        match self.ioctl(command, arg) {
+            Ok(()) => 0,
+            Err(err) => -err.errno(),
+        }
+    }
+}
+
+/// Writes a `CMD_READ` outcome to its response and passes the error on.
+fn fill_read(result: HkvResult<Value>, response: &mut ReadResponse) -> HkvResult<()> {
+    *response = match &result {
+        Ok(value) => ReadResponse::new(STATUS_OK, value.clone()),
+        Err(err) => ReadResponse::new(err.code(), Value::new(&[])?),
+    };
+    result.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::{
+        CMD_FLUSH, CMD_PROMOTE, CMD_PROMOTE_EXT, CMD_READ, CMD_READ_EXT, CMD_STATS, Key, Ttl,
+        Version, errno,
+    };
+
+    #[test]
+    fn dispatches_by_command_number() {
+        let cache = KernelCache::new();
+        let key = Key::new(b"k").unwrap();
+        let promote = PromoteRequest::new(
+            key.clone(),
+            Value::new(b"v").unwrap(),
+            Version::new(1),
+            Ttl::INFINITE,
+        );
+        let mut promoted = PromoteResponse::new(u16::MAX);
+        cache
+            .ioctl(CMD_PROMOTE, IoctlArg::Promote(&promote, &mut promoted))
+            .unwrap();
+        assert_eq!(promoted.status, STATUS_OK);
+
+        let request = ReadRequest::new(key);
+        let mut response = ReadResponse::new(u16::MAX, Value::new(&[]).unwrap());
+        cache
+            .ioctl(CMD_READ, IoctlArg::Read(&request, &mut response))
+            .unwrap();
+        assert_eq!(response.status, STATUS_OK);
+        assert_eq!(response.value.as_bytes(), b"v");
+
+        let request = ReadRequestV1::new(Key::new(b"k").unwrap());
+        let mut response = ReadResponse::new(u16::MAX, Value::new(&[]).unwrap());
+        cache
+            .ioctl(CMD_READ, IoctlArg::ReadV1(&request, &mut response))
+            .unwrap();
+        assert_eq!(response.value.as_bytes(), b"v");
+
+        let mut stats = StatsResponse::new(u16::MAX, CacheStats::default());
+        cache
+            .ioctl(CMD_STATS, IoctlArg::Stats(&StatsRequest::new(), &mut stats))
+            .unwrap();
+        assert_eq!((stats.status, stats.stats.hits), (STATUS_OK, 2));
+    }
+
+    #[test]
+    fn extended_commands_carry_user_memory() {
+        let cache = KernelCache::new();
+        let value = vec![b'x'; 2048];
+        let key = Key::new(b"blob").unwrap();
+        let promote =
+            PromoteExtRequest::new(key.clone(), &value, Version::new(1), Ttl::INFINITE).unwrap();
+        cache
+            .ioctl(CMD_PROMOTE_EXT, IoctlArg::PromoteExt(&promote, &value))
+            .unwrap();
+
+        let mut buf = vec![0u8; 4096];
+        let request = ReadExtRequest::new(key, &mut buf);
+        let mut response = ReadExtResponse::new(u16::MAX, 0);
+        cache
+            .ioctl(
+                CMD_READ_EXT,
+                IoctlArg::ReadExt(&request, &mut response, &mut buf),
+            )
+            .unwrap();
+        assert!(response.copied(request.buf_len));
+        assert_eq!(&buf[..response.value_len as usize], &value[..]);
+    }
+
+    #[test]
+    fn failures_fill_the_status_and_return_the_error() {
+        let cache = KernelCache::new();
+        let request = ReadRequest::new(Key::new(b"missing").unwrap());
+        let mut response = ReadResponse::new(STATUS_OK, Value::new(b"junk").unwrap());
+        assert_eq!(
+            cache.ioctl(CMD_READ, IoctlArg::Read(&request, &mut response)),
+            Err(HkvError::NotFound)
+        );
+        assert_eq!(response.status, HkvError::NotFound.code());
+        assert!(response.value.is_empty());
+
+        assert_eq!(
+            cache.ioctl(CMD_STATS, IoctlArg::Flush(&FlushRequest::new())),
+            Err(HkvError::InvalidInput)
+        );
+        assert_eq!(
+            cache.ioctl(200, IoctlArg::Flush(&FlushRequest::new())),
+            Err(HkvError::UnsupportedCommand)
+        );
+    }
+
+    #[test]
+    fn errno_returns_match_the_module() {
+        let cache = KernelCache::new();
+        let request = ReadRequest::new(Key::new(b"missing").unwrap());
+        let mut response = ReadResponse::new(STATUS_OK, Value::new(&[]).unwrap());
+        assert_eq!(
+            cache.ioctl_errno(CMD_READ, IoctlArg::Read(&request, &mut response)),
+            -errno::ENOENT
+        );
+        assert_eq!(response.result(), Err(HkvError::NotFound));
+        assert_eq!(
+            cache.ioctl_errno(200, IoctlArg::Flush(&FlushRequest::new())),
+            -errno::ENOTTY
+        );
+        assert_eq!(
+            cache.ioctl_errno(CMD_FLUSH, IoctlArg::Flush(&FlushRequest::new())),
+            0
+        );
+    }
+}
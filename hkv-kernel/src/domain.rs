@@ -0,0 +1,305 @@
+//! # Tenant Domains
+//!
+//! One tenant's partition of the kernel cache: its own index shards, gauges,
+//! counters, eviction policy, and budget settings.
+//!
+//! ## Design Principles
+//!
+//! 1. **Independent Eviction Domains**: Each tenant orders only its own keys,
+//!    so one tenant's access pattern never reorders another's victims.
+//! 2. **Separate Key Spaces**: The same key promoted by two tenants is two
+//!    entries, each charged to its own tenant.
+//! 3. **Settings, Not Budgets**: A domain stores its quota, guarantee,
+//!    weight, and priority; the byte budget is derived on demand by the
+//!    active tenant budget policy from every domain's settings and usage.
+//! 4. **Bounded Tombstones**: Invalidations of keys with no cached entry are
+//!    remembered as key-only tombstones in a FIFO of `MAX_TOMBSTONES`, so
+//!    they reject late promotions without growing with the key space.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use hkv_common::{EntryMetadata, Key, TenantConfigRequest, Version};
+
+use crate::consistency::Consistency;
+use crate::policy::{EvictionPolicy, TenantShare};
+
+/// Number of index shards per domain (one lock per shard, as per-bucket
+/// locks in the module).
+const SHARD_COUNT: usize = 16;
+
+/// Key-only tombstones kept per domain before the oldest is forgotten.
+pub(crate) const MAX_TOMBSTONES: usize = 4096;
+
+/// One published, immutable cache entry.
+///
+/// The key lives in the shard map; the value is sized to its payload so
+/// values past the inline `Value` buffer cost only their own bytes.
+pub(crate) struct Slot {
+    pub(crate) metadata: EntryMetadata,
+    /// Value bytes, shared with the tombstone an invalidation publishes.
+    pub(crate) value: Arc<[u8]>,
+    /// Version of the write that invalidated the entry (`ZERO` while fresh).
+    pub(crate) invalidated_by: Version,
+    /// Unix nanoseconds of the invalidation (0 while fresh).
+    pub(crate) invalidated_at: u64,
+}
+
+impl Slot {
+    /// Returns the accounted size: key and value bytes plus metadata.
+    pub(crate) fn size(&self) -> u64 {
+        entry_size(self.metadata.key_len as usize, self.value.len())
+    }
+
+    pub(crate) fn is_stale(&self) -> bool {
+        self.metadata.flags.is_invalidated()
+    }
+
+    pub(crate) fn is_expired(&self, now_nanos: u64) -> bool {
+        self.metadata.ttl.is_expired(now_nanos)
+    }
+
+    /// Oldest version a promotion may carry to replace this slot.
+    pub(crate) fn floor(&self) -> Version {
+        self.metadata.version.max(self.invalidated_by)
+    }
+}
+
+/// Accounted size of an entry: payload bytes plus metadata.
+pub(crate) fn entry_size(key_len: usize, value_len: usize) -> u64 {
+    (key_len + value_len + std::mem::size_of::<EntryMetadata>()) as u64
+}
+
+pub(crate) type Shard = RwLock<HashMap<Key, Arc<Slot>>>;
+
+/// Invalidation versions of keys that have no cached entry.
+///
+/// A FIFO bounded by `MAX_TOMBSTONES`. Each tombstone carries a sequence
+/// number so a queue slot left behind by `clear` never evicts a newer
+/// tombstone for the same key.
+#[derive(Default)]
+struct Tombstones {
+    floors: HashMap<Key, (Version, u64)>,
+    order: VecDeque<(Key, u64)>,
+    next_seq: u64,
+}
+
+impl Tombstones {
+    fn floor(&self, key: &Key) -> Version {
+        self.floors
+            .get(key)
+            .map_or(Version::ZERO, |&(version, _)| version)
+    }
+
+    fn insert(&mut self, key: &Key, version: Version) {
+        if let Some((floor, _)) = self.floors.get_mut(key) {
+            *floor = (*floor).max(version);
+            return;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.floors.insert(key.clone(), (version, seq));
+        self.order.push_back((key.clone(), seq));
+
+        while self.floors.len() > MAX_TOMBSTONES {
+            let Some((oldest, seq)) = self.order.pop_front() else {
+                break;
+            };
+            if self
+                .floors
+                .get(&oldest)
+                .is_some_and(|&(_, live)| live == seq)
+            {
+                self.floors.remove(&oldest);
+            }
+        }
+        if self.order.len() > 2 * MAX_TOMBSTONES {
+            let floors = &self.floors;
+            self.order
+                .retain(|(key, seq)| floors.get(key).is_some_and(|&(_, live)| live == *seq));
+        }
+    }
+
+    fn clear(&mut self, key: &Key) {
+        self.floors.remove(key);
+    }
+}
+
+/// Per-tenant counters reported through `CMD_STATS`.
+#[derive(Default)]
+pub(crate) struct DomainCounters {
+    pub(crate) lookups: AtomicU64,
+    pub(crate) hits: AtomicU64,
+    pub(crate) misses: AtomicU64,
+    pub(crate) stale_hits: AtomicU64,
+    pub(crate) promotions: AtomicU64,
+    pub(crate) demotions: AtomicU64,
+    pub(crate) evictions: AtomicU64,
+    pub(crate) invalidations: AtomicU64,
+}
+
+/// One tenant's partition of the cache.
+pub(crate) struct Domain {
+    pub(crate) tenant: u16,
+    shards: Box<[Shard]>,
+    pub(crate) used_bytes: AtomicU64,
+    pub(crate) entry_count: AtomicU64,
+    pub(crate) counters: DomainCounters,
+    /// Lock order: after `KernelCache::policies`, before any shard lock.
+    eviction: Mutex<Box<dyn EvictionPolicy>>,
+    settings: RwLock<TenantShare>,
+    /// Consistency override; `None` follows the cache-wide mode.
+    consistency: RwLock<Option<Consistency>>,
+    /// Lock order: after the key's shard lock.
+    tombstones: Mutex<Tombstones>,
+}
+
+impl Domain {
+    /// Creates an empty domain with weight 1 and no quota or guarantee.
+    pub(crate) fn new(tenant: u16, eviction: Box<dyn EvictionPolicy>) -> Self {
+        Domain {
+            tenant,
+            shards: (0..SHARD_COUNT).map(|_| RwLock::default()).collect(),
+            used_bytes: AtomicU64::new(0),
+            entry_count: AtomicU64::new(0),
+            counters: DomainCounters::default(),
+            eviction: Mutex::new(eviction),
+            settings: RwLock::new(TenantShare {
+                weight: 1,
+                ..TenantShare::default()
+            }),
+            consistency: RwLock::new(None),
+            tombstones: Mutex::new(Tombstones::default()),
+        }
+    }
+
+    /// Returns the domain's settings with `demand` set to current usage.
+    pub(crate) fn share(&self) -> TenantShare {
+        TenantShare {
+            demand: self.used_bytes.load(Ordering::Relaxed),
+            ..*self.settings.read().unwrap_or_else(|err| err.into_inner())
+        }
+    }
+
+    /// Replaces the domain's settings from a validated request.
+    pub(crate) fn configure(&self, request: &TenantConfigRequest) {
+        *self.settings.write().unwrap_or_else(|err| err.into_inner()) = TenantShare {
+            quota: request.quota_bytes,
+            min_guarantee: request.min_guarantee_bytes,
+            weight: request.weight,
+            priority: request.priority,
+            demand: 0,
+        };
+    }
+
+    /// Returns the tenant's consistency override, if any.
+    pub(crate) fn consistency(&self) -> Option<Consistency> {
+        *self
+            .consistency
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Overrides the cache-wide consistency mode for this tenant, or drops
+    /// the override with `None`.
+    pub(crate) fn set_consistency(&self, consistency: Option<Consistency>) {
+        *self
+            .consistency
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = consistency;
+    }
+
+    /// Returns the version an uncached `key` was last invalidated at
+    /// (`ZERO` without a tombstone).
+    pub(crate) fn tombstone_floor(&self, key: &Key) -> Version {
+        self.lock_tombstones().floor(key)
+    }
+
+    /// Remembers that uncached `key` was invalidated at `version`.
+    pub(crate) fn add_tombstone(&self, key: &Key, version: Version) {
+        self.lock_tombstones().insert(key, version);
+    }
+
+    /// Drops `key`'s tombstone once a cached entry carries its floor.
+    pub(crate) fn clear_tombstone(&self, key: &Key) {
+        self.lock_tombstones().clear(key);
+    }
+
+    fn lock_tombstones(&self) -> MutexGuard<'_, Tombstones> {
+        self.tombstones
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub(crate) fn lock_eviction(&self) -> MutexGuard<'_, Box<dyn EvictionPolicy>> {
+        self.eviction.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns the eviction policy unless another thread holds it.
+    pub(crate) fn try_lock_eviction(&self) -> Option<MutexGuard<'_, Box<dyn EvictionPolicy>>> {
+        self.eviction.try_lock().ok()
+    }
+
+    pub(crate) fn shards(&self) -> &[Shard] {
+        &self.shards
+    }
+
+    pub(crate) fn shard(&self, hash: u64) -> &Shard {
+        &self.shards[(hash as usize) % self.shards.len()]
+    }
+
+    pub(crate) fn read_shard(&self, hash: u64) -> RwLockReadGuard<'_, HashMap<Key, Arc<Slot>>> {
+        self.shard(hash)
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Takes a shard write lock, counting a contention when it is not free.
+    pub(crate) fn write_shard<'a>(
+        &'a self,
+        hash: u64,
+        contentions: &AtomicU64,
+    ) -> RwLockWriteGuard<'a, HashMap<Key, Arc<Slot>>> {
+        lock_write(self.shard(hash), contentions)
+    }
+
+    /// Charges an entry of `size` bytes that replaces one of `freed` bytes,
+    /// unless the domain would end up past `quota` (zero is unlimited).
+    ///
+    /// The quota check and the charge are one atomic update, so concurrent
+    /// promotions into different shards cannot both slip under the quota.
+    pub(crate) fn charge(&self, size: u64, freed: u64, quota: u64) -> bool {
+        let charged = self
+            .used_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let after = used + size;
+                (quota == 0 || after.saturating_sub(freed) <= quota).then_some(after)
+            })
+            .is_ok();
+        if charged {
+            self.entry_count.fetch_add(1, Ordering::Relaxed);
+        }
+        charged
+    }
+
+    /// Releases a removed or replaced slot's accounting in the domain.
+    pub(crate) fn retire(&self, slot: &Slot) {
+        self.used_bytes.fetch_sub(slot.size(), Ordering::Relaxed);
+        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Takes a shard write lock, counting a contention when it is not free.
+pub(crate) fn lock_write<'a>(
+    shard: &'a Shard,
+    contentions: &AtomicU64,
+) -> RwLockWriteGuard<'a, HashMap<Key, Arc<Slot>>> {
+    match shard.try_write() {
+        Ok(guard) => guard,
+        Err(_) => {
+            contentions.fetch_add(1, Ordering::Relaxed);
+            shard.write().unwrap_or_else(|err| err.into_inner())
+        }
+    }
+}
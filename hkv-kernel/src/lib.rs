@@ -1,14 +1,27 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! # HybridKV Kernel Cache (User-Space Simulation)
+//!
+//! Reference implementation of the `/dev/hybridkv` data plane, running in
+//! user space against the `#[repr(C)]` request structs of
+//! `hkv_common::protocol`.
+//!
+//! ## Design Principles
+//!
+//! 1. **Same ABI, No Kernel**: Requests and responses are the exact structs
+//!    the module exchanges over ioctl, so the simulation doubles as a test
+//!    backend on machines without the module.
+//! 2. **Behavioral Reference**: Versioning, staleness, eviction, and stats
+//!    semantics defined here are the contract the module must match.
+//! 3. **Mechanism Only**: What to promote and when is decided in user space;
+//!    this crate only stores, indexes, invalidates, and evicts.
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod cache;
+pub mod consistency;
+pub mod dispatch;
+mod domain;
+pub mod policy;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use cache::KernelCache;
+pub use dispatch::IoctlArg;
+pub use policy::{PolicyIds, PolicyRegistry};
+
+pub use hkv_common::RefreshRequest;
@@ -0,0 +1,409 @@
+//! # Policy Plane
+//!
+//! Pluggable eviction, admission, hotness and tenant budget policies for
+//! `KernelCache`, built from a registry keyed by the IDs carried in
+//! `ConfigRequest`.
+//!
+//! ## Design Principles
+//!
+//! 1. **Data Plane Stays Put**: The cache owns the entries; policies only see
+//!    keys, hashes and sizes, so swapping one never moves or copies data.
+//! 2. **Factories, Not Instances**: The registry stores constructors, so a
+//!    swap always starts the new policy from a clean state.
+//! 3. **All or Nothing**: A config request naming several policies builds all
+//!    of them before installing any, so an unknown ID changes nothing.
+//! 4. **Open for Extension**: Built-ins are pre-registered under their ABI
+//!    IDs; custom policies register under `POLICY_CUSTOM_BASE` and up.
+
+pub mod admission;
+pub mod eviction;
+pub mod hotness;
+pub mod tenant;
+
+use std::collections::HashMap;
+
+use hkv_common::eviction::EvictionPolicyKind;
+use hkv_common::{
+    AdmissionPolicyId, ConfigRequest, EvictionPolicyId, HkvError, HkvResult, HotnessEstimatorId,
+    POLICY_KEEP, TenantBudgetPolicyId,
+};
+
+pub use admission::{
+    AdmissionPolicy, AlwaysAdmit, Candidate, SizeAwareAdmission, ThresholdAdmission,
+    TinyLfuAdmission,
+};
+pub use eviction::{BuiltinPolicy, EvictionPolicy};
+pub use hotness::{CountMinSketch, HotnessEstimator, SamplingEstimator, TieredCounters};
+pub use tenant::{
+    HardQuotaBudget, PriorityBudget, ProportionalBudget, TenantBudgetPolicy, TenantShare,
+};
+
+/// Builds a fresh eviction policy.
+pub type EvictionFactory = Box<dyn Fn() -> Box<dyn EvictionPolicy> + Send + Sync>;
+/// Builds a fresh admission policy.
+pub type AdmissionFactory = Box<dyn Fn() -> Box<dyn AdmissionPolicy> + Send + Sync>;
+/// Builds a fresh hotness estimator.
+pub type HotnessFactory = Box<dyn Fn() -> Box<dyn HotnessEstimator> + Send + Sync>;
+/// Builds a fresh tenant budget policy.
+pub type TenantBudgetFactory = Box<dyn Fn() -> Box<dyn TenantBudgetPolicy> + Send + Sync>;
+
+/// IDs of the policies a cache is currently running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolicyIds {
+    /// Eviction policy ID.
+    pub eviction: u8,
+    /// Admission policy ID.
+    pub admission: u8,
+    /// Hotness estimator ID.
+    pub hotness: u8,
+    /// Tenant budget policy ID.
+    pub tenant: u8,
+}
+
+impl Default for PolicyIds {
+    fn default() -> Self {
+        PolicyIds {
+            eviction: EvictionPolicyId::Lru.as_u8(),
+            admission: AdmissionPolicyId::Always.as_u8(),
+            hotness: HotnessEstimatorId::CountMinSketch.as_u8(),
+            tenant: TenantBudgetPolicyId::Proportional.as_u8(),
+        }
+    }
+}
+
+/// Policy constructors by ID.
+pub struct PolicyRegistry {
+    eviction: HashMap<u8, EvictionFactory>,
+    admission: HashMap<u8, AdmissionFactory>,
+    hotness: HashMap<u8, HotnessFactory>,
+    tenant: HashMap<u8, TenantBudgetFactory>,
+}
+
+impl PolicyRegistry {
+    /// Creates a registry holding only the built-in policies.
+    pub fn new() -> Self {
+        let mut registry = PolicyRegistry {
+            eviction: HashMap::new(),
+            admission: HashMap::new(),
+            hotness: HashMap::new(),
+            tenant: HashMap::new(),
+        };
+
+        let builtin = |result: HkvResult<()>| result.expect("built-in policy IDs are non-zero");
+        for &id in EvictionPolicyId::ALL {
+            let kind = EvictionPolicyKind::from(id);
+            builtin(
+                registry.register_eviction(id.as_u8(), move || Box::new(BuiltinPolicy::new(kind))),
+            );
+        }
+
+        builtin(
+            registry
+                .register_admission(AdmissionPolicyId::Always.as_u8(), || Box::new(AlwaysAdmit)),
+        );
+        builtin(
+            registry.register_admission(AdmissionPolicyId::Threshold.as_u8(), || {
+                Box::new(ThresholdAdmission::default())
+            }),
+        );
+        builtin(
+            registry.register_admission(AdmissionPolicyId::TinyLfu.as_u8(), || {
+                Box::new(TinyLfuAdmission)
+            }),
+        );
+        builtin(
+            registry.register_admission(AdmissionPolicyId::SizeAware.as_u8(), || {
+                Box::new(SizeAwareAdmission)
+            }),
+        );
+
+        builtin(
+            registry.register_hotness(HotnessEstimatorId::CountMinSketch.as_u8(), || {
+                Box::new(CountMinSketch::default())
+            }),
+        );
+        builtin(
+            registry.register_hotness(HotnessEstimatorId::Sampling.as_u8(), || {
+                Box::new(SamplingEstimator::default())
+            }),
+        );
+        builtin(
+            registry.register_hotness(HotnessEstimatorId::TieredCounters.as_u8(), || {
+                Box::new(TieredCounters::default())
+            }),
+        );
+
+        builtin(
+            registry.register_tenant_budget(TenantBudgetPolicyId::HardQuota.as_u8(), || {
+                Box::new(HardQuotaBudget)
+            }),
+        );
+        builtin(
+            registry.register_tenant_budget(TenantBudgetPolicyId::Proportional.as_u8(), || {
+                Box::new(ProportionalBudget)
+            }),
+        );
+        builtin(
+            registry.register_tenant_budget(TenantBudgetPolicyId::PriorityBased.as_u8(), || {
+                Box::new(PriorityBudget)
+            }),
+        );
+
+        registry
+    }
+
+    /// Registers (or replaces) the eviction policy built for `id`.
+    pub fn register_eviction<F>(&mut self, id: u8, factory: F) -> HkvResult<()>
+    where
+        F: Fn() -> Box<dyn EvictionPolicy> + Send + Sync + 'static,
+    {
+        check_id(id)?;
+        self.eviction.insert(id, Box::new(factory));
+        Ok(())
+    }
+
+    /// Registers (or replaces) the admission policy built for `id`.
+    pub fn register_admission<F>(&mut self, id: u8, factory: F) -> HkvResult<()>
+    where
+        F: Fn() -> Box<dyn AdmissionPolicy> + Send + Sync + 'static,
+    {
+        check_id(id)?;
+        self.admission.insert(id, Box::new(factory));
+        Ok(())
+    }
+
+    /// Registers (or replaces) the hotness estimator built for `id`.
+    pub fn register_hotness<F>(&mut self, id: u8, factory: F) -> HkvResult<()>
+    where
+        F: Fn() -> Box<dyn HotnessEstimator> + Send + Sync + 'static,
+    {
+        check_id(id)?;
+        self.hotness.insert(id, Box::new(factory));
+        Ok(())
+    }
+
+    /// Registers (or replaces) the tenant budget policy built for `id`.
+    pub fn register_tenant_budget<F>(&mut self, id: u8, factory: F) -> HkvResult<()>
+    where
+        F: Fn() -> Box<dyn TenantBudgetPolicy> + Send + Sync + 'static,
+    {
+        check_id(id)?;
+        self.tenant.insert(id, Box::new(factory));
+        Ok(())
+    }
+
+    /// Returns true when an eviction policy is registered for `id`.
+    pub fn has_eviction(&self, id: u8) -> bool {
+        self.eviction.contains_key(&id)
+    }
+
+    /// Builds the eviction policy registered for `id`.
+    pub fn build_eviction(&self, id: u8) -> HkvResult<Box<dyn EvictionPolicy>> {
+        self.eviction
+            .get(&id)
+            .map(|f| f())
+            .ok_or(HkvError::InvalidInput)
+    }
+
+    /// Builds the admission policy registered for `id`.
+    pub fn build_admission(&self, id: u8) -> HkvResult<Box<dyn AdmissionPolicy>> {
+        self.admission
+            .get(&id)
+            .map(|f| f())
+            .ok_or(HkvError::InvalidInput)
+    }
+
+    /// Builds the hotness estimator registered for `id`.
+    pub fn build_hotness(&self, id: u8) -> HkvResult<Box<dyn HotnessEstimator>> {
+        self.hotness
+            .get(&id)
+            .map(|f| f())
+            .ok_or(HkvError::InvalidInput)
+    }
+
+    /// Builds the tenant budget policy registered for `id`.
+    pub fn build_tenant_budget(&self, id: u8) -> HkvResult<Box<dyn TenantBudgetPolicy>> {
+        self.tenant
+            .get(&id)
+            .map(|f| f())
+            .ok_or(HkvError::InvalidInput)
+    }
+
+    /// Builds the cache-wide policy set for `ids`.
+    ///
+    /// Eviction policies are per tenant domain, so only the eviction ID is
+    /// checked here; domains build their own instance.
+    pub fn build(&self, ids: PolicyIds) -> HkvResult<PolicySet> {
+        if !self.has_eviction(ids.eviction) {
+            return Err(HkvError::InvalidInput);
+        }
+        Ok(PolicySet {
+            admission: self.build_admission(ids.admission)?,
+            hotness: self.build_hotness(ids.hotness)?,
+            tenant: self.build_tenant_budget(ids.tenant)?,
+            ids,
+        })
+    }
+}
+
+impl Default for PolicyRegistry {
+    fn default() -> Self {
+        PolicyRegistry::new()
+    }
+}
+
+/// Rejects `POLICY_KEEP`, which can never name a policy.
+fn check_id(id: u8) -> HkvResult<()> {
+    if id == POLICY_KEEP {
+        return Err(HkvError::InvalidInput);
+    }
+    Ok(())
+}
+
+/// The cache-wide policies one cache is running.
+pub struct PolicySet {
+    /// Gates new keys under pressure.
+    pub admission: Box<dyn AdmissionPolicy>,
+    /// Tracks access frequency by key hash.
+    pub hotness: Box<dyn HotnessEstimator>,
+    /// Splits the byte budget between tenants.
+    pub tenant: Box<dyn TenantBudgetPolicy>,
+    ids: PolicyIds,
+}
+
+impl PolicySet {
+    /// Returns the IDs of the running policies.
+    pub fn ids(&self) -> PolicyIds {
+        self.ids
+    }
+
+    /// Swaps in every policy `request` names (non-zero ID), building them
+    /// all first so an unknown ID leaves the set untouched.
+    ///
+    /// Returns the new eviction policy ID, if one was selected; the caller
+    /// rebuilds each domain's policy with `PolicyRegistry::build_eviction`
+    /// and `migrate_eviction`. A new hotness estimator starts cold.
+    pub fn apply(
+        &mut self,
+        registry: &PolicyRegistry,
+        request: &ConfigRequest,
+    ) -> HkvResult<Option<u8>> {
+        let selected = |id: u8| (id != POLICY_KEEP).then_some(id);
+
+        let eviction = selected(request.eviction_policy);
+        if eviction.is_some_and(|id| !registry.has_eviction(id)) {
+            return Err(HkvError::InvalidInput);
+        }
+        let admission = selected(request.admission_policy)
+            .map(|id| registry.build_admission(id))
+            .transpose()?;
+        let hotness = selected(request.hotness_estimator)
+            .map(|id| registry.build_hotness(id))
+            .transpose()?;
+        let tenant = selected(request.tenant_policy)
+            .map(|id| registry.build_tenant_budget(id))
+            .transpose()?;
+
+        if let Some(id) = eviction {
+            self.ids.eviction = id;
+        }
+        if let Some(admission) = admission {
+            self.admission = admission;
+            self.ids.admission = request.admission_policy;
+        }
+        if let Some(hotness) = hotness {
+            self.hotness = hotness;
+            self.ids.hotness = request.hotness_estimator;
+        }
+        if let Some(tenant) = tenant {
+            self.tenant = tenant;
+            self.ids.tenant = request.tenant_policy;
+        }
+        Ok(eviction)
+    }
+}
+
+/// Moves every key tracked by `old` into `new`, oldest victim first, so the
+/// new policy starts from the old one's eviction order.
+pub fn migrate_eviction(old: &mut dyn EvictionPolicy, new: &mut dyn EvictionPolicy) {
+    for key in old.select_victims(usize::MAX) {
+        new.on_insert(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::{Key, POLICY_CUSTOM_BASE};
+
+    #[test]
+    fn registry_builds_every_builtin_under_its_abi_id() {
+        let registry = PolicyRegistry::new();
+        for id in EvictionPolicyId::ALL {
+            assert_eq!(
+                registry.build_eviction(id.as_u8()).unwrap().name(),
+                id.name()
+            );
+        }
+        for id in AdmissionPolicyId::ALL {
+            assert_eq!(
+                registry.build_admission(id.as_u8()).unwrap().name(),
+                id.name()
+            );
+        }
+        for id in HotnessEstimatorId::ALL {
+            assert_eq!(
+                registry.build_hotness(id.as_u8()).unwrap().name(),
+                id.name()
+            );
+        }
+        for id in TenantBudgetPolicyId::ALL {
+            assert_eq!(
+                registry.build_tenant_budget(id.as_u8()).unwrap().name(),
+                id.name()
+            );
+        }
+        assert!(registry.build_eviction(POLICY_CUSTOM_BASE).is_err());
+    }
+
+    #[test]
+    fn apply_is_all_or_nothing_and_accepts_custom_ids() {
+        let mut registry = PolicyRegistry::new();
+        assert_eq!(
+            registry.register_eviction(POLICY_KEEP, || Box::new(BuiltinPolicy::new(
+                EvictionPolicyKind::Fifo
+            ))),
+            Err(HkvError::InvalidInput)
+        );
+        registry
+            .register_eviction(POLICY_CUSTOM_BASE, || {
+                Box::new(BuiltinPolicy::new(EvictionPolicyKind::Fifo))
+            })
+            .unwrap();
+
+        let mut set = registry.build(PolicyIds::default()).unwrap();
+        let mut request = ConfigRequest::new(1024, 16, 90, 70);
+        request.eviction_policy = POLICY_CUSTOM_BASE;
+        request.admission_policy = 250;
+        assert_eq!(set.apply(&registry, &request), Err(HkvError::InvalidInput));
+        assert_eq!(set.ids(), PolicyIds::default());
+
+        let request = request.with_admission(AdmissionPolicyId::TinyLfu);
+        assert_eq!(set.apply(&registry, &request), Ok(Some(POLICY_CUSTOM_BASE)));
+        assert_eq!(set.ids().eviction, POLICY_CUSTOM_BASE);
+        assert_eq!(set.ids().admission, AdmissionPolicyId::TinyLfu.as_u8());
+        assert_eq!(
+            set.ids().hotness,
+            HotnessEstimatorId::CountMinSketch.as_u8()
+        );
+
+        let mut old = BuiltinPolicy::new(EvictionPolicyKind::Lru);
+        for name in [&b"a"[..], b"b"] {
+            old.on_insert(&Key::new(name).unwrap());
+        }
+        let mut new = registry.build_eviction(POLICY_CUSTOM_BASE).unwrap();
+        migrate_eviction(&mut old, new.as_mut());
+        assert!(old.is_empty());
+        assert_eq!(new.peek_victim(), Some(&Key::new(b"a").unwrap()));
+    }
+}
@@ -0,0 +1,161 @@
+//! Admission policies: whether a new key may displace resident ones.
+
+use super::hotness::HotnessEstimator;
+
+/// A key competing for cache space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// Hash of the key, as fed to the hotness estimator.
+    pub key_hash: u64,
+    /// Bytes the entry occupies.
+    pub size: u64,
+}
+
+/// Decides whether a new key is worth the entries it would evict.
+///
+/// Only consulted when inserting the key would cross the high watermark;
+/// replacements of resident keys are always admitted.
+pub trait AdmissionPolicy: Send {
+    /// Returns the configuration name of the policy.
+    fn name(&self) -> &'static str;
+
+    /// Returns true to admit `candidate`; `victim` is the entry the eviction
+    /// policy would drop next, if any.
+    fn admit(
+        &self,
+        candidate: Candidate,
+        victim: Option<Candidate>,
+        hotness: &dyn HotnessEstimator,
+    ) -> bool;
+}
+
+/// Admits every candidate.
+#[derive(Debug, Default)]
+pub struct AlwaysAdmit;
+
+impl AdmissionPolicy for AlwaysAdmit {
+    fn name(&self) -> &'static str {
+        "always"
+    }
+
+    fn admit(&self, _: Candidate, _: Option<Candidate>, _: &dyn HotnessEstimator) -> bool {
+        true
+    }
+}
+
+/// Admits candidates whose estimated hotness reaches `min_hotness`.
+#[derive(Debug)]
+pub struct ThresholdAdmission {
+    min_hotness: u64,
+}
+
+impl ThresholdAdmission {
+    /// Creates a policy admitting keys seen at least `min_hotness` times.
+    pub fn new(min_hotness: u64) -> Self {
+        ThresholdAdmission { min_hotness }
+    }
+}
+
+impl Default for ThresholdAdmission {
+    fn default() -> Self {
+        ThresholdAdmission::new(2)
+    }
+}
+
+impl AdmissionPolicy for ThresholdAdmission {
+    fn name(&self) -> &'static str {
+        "threshold"
+    }
+
+    fn admit(
+        &self,
+        candidate: Candidate,
+        _: Option<Candidate>,
+        hotness: &dyn HotnessEstimator,
+    ) -> bool {
+        hotness.estimate(candidate.key_hash) >= self.min_hotness
+    }
+}
+
+/// TinyLFU: admits candidates strictly hotter than the victim.
+#[derive(Debug, Default)]
+pub struct TinyLfuAdmission;
+
+impl AdmissionPolicy for TinyLfuAdmission {
+    fn name(&self) -> &'static str {
+        "tinylfu"
+    }
+
+    fn admit(
+        &self,
+        candidate: Candidate,
+        victim: Option<Candidate>,
+        hotness: &dyn HotnessEstimator,
+    ) -> bool {
+        victim.is_none_or(|victim| {
+            hotness.estimate(candidate.key_hash) > hotness.estimate(victim.key_hash)
+        })
+    }
+}
+
+/// Admits candidates with more hits per byte than the victim, so one large
+/// value cannot push out many small hot ones.
+#[derive(Debug, Default)]
+pub struct SizeAwareAdmission;
+
+impl AdmissionPolicy for SizeAwareAdmission {
+    fn name(&self) -> &'static str {
+        "size-aware"
+    }
+
+    fn admit(
+        &self,
+        candidate: Candidate,
+        victim: Option<Candidate>,
+        hotness: &dyn HotnessEstimator,
+    ) -> bool {
+        victim.is_none_or(|victim| {
+            // Cross-multiply to compare hits/byte without division.
+            let candidate_score =
+                u128::from(hotness.estimate(candidate.key_hash)) * u128::from(victim.size);
+            let victim_score =
+                u128::from(hotness.estimate(victim.key_hash)) * u128::from(candidate.size);
+            candidate_score > victim_score
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::hotness::CountMinSketch;
+
+    #[test]
+    fn policies_compare_candidate_and_victim_hotness() {
+        let mut sketch = CountMinSketch::default();
+        for _ in 0..4 {
+            sketch.record(1);
+        }
+        sketch.record(2);
+        let hot_large = Candidate {
+            key_hash: 1,
+            size: 1000,
+        };
+        let cold_small = Candidate {
+            key_hash: 2,
+            size: 100,
+        };
+
+        assert!(AlwaysAdmit.admit(cold_small, Some(hot_large), &sketch));
+        assert!(ThresholdAdmission::new(2).admit(hot_large, None, &sketch));
+        assert!(!ThresholdAdmission::new(2).admit(cold_small, None, &sketch));
+
+        assert!(TinyLfuAdmission.admit(hot_large, Some(cold_small), &sketch));
+        assert!(!TinyLfuAdmission.admit(cold_small, Some(hot_large), &sketch));
+        assert!(TinyLfuAdmission.admit(cold_small, None, &sketch));
+
+        // 4 hits / 1000 bytes loses to 1 hit / 100 bytes.
+        assert!(!SizeAwareAdmission.admit(hot_large, Some(cold_small), &sketch));
+        assert!(SizeAwareAdmission.admit(cold_small, Some(hot_large), &sketch));
+    }
+}
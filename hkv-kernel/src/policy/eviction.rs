@@ -0,0 +1,203 @@
+//! Eviction policies: which resident keys to drop under memory pressure.
+//!
+//! The built-ins are the slot-based policies in `hkv_common::eviction`, the
+//! same ones the user-space engine runs; `BuiltinPolicy` maps keys to slots.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+
+use hkv_common::Key;
+use hkv_common::eviction::{self, EvictionPolicyKind};
+
+/// Orders resident keys and picks victims.
+///
+/// The cache reports inserts, reads, and removals; `select_victims` both
+/// chooses and forgets the returned keys.
+pub trait EvictionPolicy: Send {
+    /// Returns the configuration name of the policy.
+    fn name(&self) -> &'static str;
+
+    /// Starts tracking a newly inserted key.
+    fn on_insert(&mut self, key: &Key);
+
+    /// Reports a read hit (or an in-place replacement).
+    fn on_access(&mut self, key: &Key);
+
+    /// Stops tracking a key removed by demotion, flush, or a racing eviction.
+    fn on_remove(&mut self, key: &Key);
+
+    /// Returns the key `select_victims` would pick next, without removing it.
+    fn peek_victim(&self) -> Option<&Key>;
+
+    /// Removes and returns up to `count` victims, best candidate first.
+    fn select_victims(&mut self, count: usize) -> Vec<Key>;
+
+    /// Returns the number of tracked keys.
+    fn len(&self) -> usize;
+
+    /// Returns true when no keys are tracked.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A built-in policy from `hkv_common::eviction`, tracking keys.
+///
+/// Each key holds a slot index while it is tracked; freed slots are reused,
+/// so the slot space stays as large as the most keys ever tracked at once.
+pub struct BuiltinPolicy {
+    policy: Box<dyn eviction::EvictionPolicy>,
+    slots: HashMap<Key, usize>,
+    keys: Vec<Option<Key>>,
+    free: Vec<usize>,
+    hasher: RandomState,
+}
+
+impl BuiltinPolicy {
+    /// Creates an empty policy of `kind`.
+    pub fn new(kind: EvictionPolicyKind) -> Self {
+        BuiltinPolicy {
+            policy: kind.build(),
+            slots: HashMap::new(),
+            keys: Vec::new(),
+            free: Vec::new(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns which built-in policy this is.
+    pub fn kind(&self) -> EvictionPolicyKind {
+        self.policy.kind()
+    }
+
+    /// Forgets the key in `slot` and frees the slot.
+    fn release(&mut self, slot: usize) -> Option<Key> {
+        let key = self.keys.get_mut(slot)?.take()?;
+        self.slots.remove(&key);
+        self.free.push(slot);
+        Some(key)
+    }
+}
+
+impl EvictionPolicy for BuiltinPolicy {
+    fn name(&self) -> &'static str {
+        self.kind().name()
+    }
+
+    fn on_insert(&mut self, key: &Key) {
+        let hash = self.hasher.hash_one(key);
+        if let Some(&slot) = self.slots.get(key) {
+            // Re-inserting a tracked key starts it over as a new entry.
+            self.policy.on_remove(slot);
+            self.policy.on_insert(slot, hash);
+            return;
+        }
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.keys.push(None);
+                self.keys.len() - 1
+            }
+        };
+        self.keys[slot] = Some(key.clone());
+        self.slots.insert(key.clone(), slot);
+        self.policy.on_insert(slot, hash);
+    }
+
+    fn on_access(&mut self, key: &Key) {
+        if let Some(&slot) = self.slots.get(key) {
+            self.policy.on_access(slot);
+        }
+    }
+
+    fn on_remove(&mut self, key: &Key) {
+        if let Some(&slot) = self.slots.get(key) {
+            self.policy.on_remove(slot);
+            self.release(slot);
+        }
+    }
+
+    fn peek_victim(&self) -> Option<&Key> {
+        self.keys.get(self.policy.victim()?)?.as_ref()
+    }
+
+    fn select_victims(&mut self, count: usize) -> Vec<Key> {
+        let mut victims = Vec::new();
+        while victims.len() < count {
+            let Some(slot) = self.policy.victim() else {
+                break;
+            };
+            // `on_evict` lets 2Q remember the key in its ghost queue.
+            self.policy.on_evict(slot);
+            victims.extend(self.release(slot));
+        }
+        victims
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: u32) -> Key {
+        Key::new(format!("k{id}").as_bytes()).unwrap()
+    }
+
+    fn victims(policy: &mut dyn EvictionPolicy, count: usize) -> Vec<Key> {
+        let peeked = policy.peek_victim().cloned();
+        let victims = policy.select_victims(count);
+        assert_eq!(victims.first(), peeked.as_ref());
+        victims
+    }
+
+    #[test]
+    fn lru_fifo_and_lfu_orderings() {
+        let mut lru = BuiltinPolicy::new(EvictionPolicyKind::Lru);
+        let mut fifo = BuiltinPolicy::new(EvictionPolicyKind::Fifo);
+        let mut lfu = BuiltinPolicy::new(EvictionPolicyKind::Lfu);
+        for policy in [&mut lru as &mut dyn EvictionPolicy, &mut fifo, &mut lfu] {
+            for id in 0..3 {
+                policy.on_insert(&key(id));
+            }
+            policy.on_access(&key(0));
+            policy.on_access(&key(0));
+            policy.on_access(&key(1));
+        }
+        assert_eq!(victims(&mut lru, 1), vec![key(2)]);
+        assert_eq!(victims(&mut fifo, 1), vec![key(0)]);
+        assert_eq!(victims(&mut lfu, 2), vec![key(2), key(1)]);
+        assert_eq!((lru.len(), fifo.len(), lfu.len()), (2, 2, 1));
+    }
+
+    #[test]
+    fn slru_evicts_probation_before_protected() {
+        let mut slru = BuiltinPolicy::new(EvictionPolicyKind::Slru);
+        for id in 0..5 {
+            slru.on_insert(&key(id));
+        }
+        slru.on_access(&key(0));
+        slru.on_access(&key(1));
+        slru.on_remove(&key(4));
+        assert_eq!(victims(&mut slru, 2), vec![key(2), key(3)]);
+        assert_eq!(victims(&mut slru, 5), vec![key(0), key(1)]);
+        assert!(slru.is_empty());
+    }
+
+    #[test]
+    fn reuses_slots_of_removed_keys() {
+        let mut lru = BuiltinPolicy::new(EvictionPolicyKind::Lru);
+        lru.on_insert(&key(0));
+        lru.on_insert(&key(1));
+        lru.on_remove(&key(0));
+        lru.on_insert(&key(2));
+        lru.on_insert(&key(1));
+        assert_eq!(lru.keys.len(), 2);
+        assert_eq!(lru.name(), "lru");
+        assert_eq!(victims(&mut lru, 3), vec![key(2), key(1)]);
+        assert!(lru.is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Hotness estimators: approximate access frequency by key hash.
+
+use std::collections::HashMap;
+
+use hkv_common::sketch;
+
+/// Estimates how often a key is accessed.
+///
+/// Estimators work on key hashes so admission can compare a candidate that
+/// is not resident yet against an eviction victim.
+pub trait HotnessEstimator: Send {
+    /// Returns the configuration name of the estimator.
+    fn name(&self) -> &'static str;
+
+    /// Records one access.
+    fn record(&mut self, key_hash: u64);
+
+    /// Returns the estimated recent access count.
+    fn estimate(&self, key_hash: u64) -> u64;
+
+    /// Halves all counts so estimates follow the recent access rate.
+    fn decay(&mut self);
+}
+
+/// Count-Min Sketch that halves itself every `10 * width` records.
+///
+/// Counts with the shared `hkv_common::CountMinSketch`, the same sketch the
+/// engine's admission filter and the server's hot-key tracker use.
+pub struct CountMinSketch(sketch::CountMinSketch);
+
+impl CountMinSketch {
+    /// Creates a sketch with `width` counters per row (rounded up to a power
+    /// of two).
+    pub fn new(width: usize) -> Self {
+        let sketch = sketch::CountMinSketch::new(width);
+        let sample_size = sketch.width() as u64 * 10;
+        CountMinSketch(sketch.with_sample_size(sample_size))
+    }
+}
+
+impl Default for CountMinSketch {
+    fn default() -> Self {
+        CountMinSketch::new(4096)
+    }
+}
+
+impl HotnessEstimator for CountMinSketch {
+    fn name(&self) -> &'static str {
+        "cms"
+    }
+
+    fn record(&mut self, key_hash: u64) {
+        self.0.increment(key_hash);
+    }
+
+    fn estimate(&self, key_hash: u64) -> u64 {
+        self.0.estimate(key_hash)
+    }
+
+    fn decay(&mut self) {
+        self.0.halve();
+    }
+}
+
+/// Counts one access in `rate` exactly and scales estimates back up.
+pub struct SamplingEstimator {
+    rate: u64,
+    seen: u64,
+    counts: HashMap<u64, u64>,
+    capacity: usize,
+}
+
+impl SamplingEstimator {
+    /// Creates an estimator sampling one access in `rate`, tracking at most
+    /// `capacity` keys before it decays.
+    pub fn new(rate: u64, capacity: usize) -> Self {
+        SamplingEstimator {
+            rate: rate.max(1),
+            seen: 0,
+            counts: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+impl Default for SamplingEstimator {
+    fn default() -> Self {
+        SamplingEstimator::new(8, 16 * 1024)
+    }
+}
+
+impl HotnessEstimator for SamplingEstimator {
+    fn name(&self) -> &'static str {
+        "sampling"
+    }
+
+    fn record(&mut self, key_hash: u64) {
+        self.seen += 1;
+        if !self.seen.is_multiple_of(self.rate) {
+            return;
+        }
+        *self.counts.entry(key_hash).or_default() += 1;
+        if self.counts.len() > self.capacity {
+            self.decay();
+        }
+    }
+
+    fn estimate(&self, key_hash: u64) -> u64 {
+        self.counts.get(&key_hash).copied().unwrap_or(0) * self.rate
+    }
+
+    fn decay(&mut self) {
+        self.counts.retain(|_, count| {
+            *count >>= 1;
+            *count > 0
+        });
+    }
+}
+
+/// Exact counters for a bounded set of hot keys, a sketch for the rest.
+///
+/// A key moves into the exact tier once its sketch estimate reaches
+/// `promote_at` and the tier has room.
+pub struct TieredCounters {
+    hot: HashMap<u64, u64>,
+    capacity: usize,
+    promote_at: u64,
+    cold: CountMinSketch,
+}
+
+impl TieredCounters {
+    /// Creates an estimator with `capacity` exact counters.
+    pub fn new(capacity: usize, promote_at: u64) -> Self {
+        TieredCounters {
+            hot: HashMap::new(),
+            capacity,
+            promote_at: promote_at.max(1),
+            cold: CountMinSketch::default(),
+        }
+    }
+}
+
+impl Default for TieredCounters {
+    fn default() -> Self {
+        TieredCounters::new(1024, 8)
+    }
+}
+
+impl HotnessEstimator for TieredCounters {
+    fn name(&self) -> &'static str {
+        "tiered"
+    }
+
+    fn record(&mut self, key_hash: u64) {
+        if let Some(count) = self.hot.get_mut(&key_hash) {
+            *count = count.saturating_add(1);
+            return;
+        }
+        self.cold.record(key_hash);
+        let estimate = self.cold.estimate(key_hash);
+        if estimate >= self.promote_at && self.hot.len() < self.capacity {
+            self.hot.insert(key_hash, estimate);
+        }
+    }
+
+    fn estimate(&self, key_hash: u64) -> u64 {
+        match self.hot.get(&key_hash) {
+            Some(count) => *count,
+            None => self.cold.estimate(key_hash),
+        }
+    }
+
+    fn decay(&mut self) {
+        self.hot.retain(|_, count| {
+            *count >>= 1;
+            *count > 0
+        });
+        self.cold.decay();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hammer(estimator: &mut dyn HotnessEstimator) {
+        for _ in 0..64 {
+            estimator.record(1);
+        }
+        for _ in 0..4 {
+            estimator.record(2);
+        }
+    }
+
+    #[test]
+    fn estimators_rank_hot_keys_above_cold_ones() {
+        let mut estimators: Vec<Box<dyn HotnessEstimator>> = vec![
+            Box::new(CountMinSketch::default()),
+            Box::new(SamplingEstimator::new(2, 64)),
+            Box::new(TieredCounters::new(4, 8)),
+        ];
+        for estimator in &mut estimators {
+            hammer(estimator.as_mut());
+            let (hot, cold) = (estimator.estimate(1), estimator.estimate(2));
+            assert!(hot > cold, "{}: {hot} vs {cold}", estimator.name());
+            assert_eq!(estimator.estimate(3), 0, "{}", estimator.name());
+
+            estimator.decay();
+            assert!(estimator.estimate(1) < hot, "{}", estimator.name());
+        }
+    }
+
+    #[test]
+    fn tiered_counters_count_exactly_once_promoted() {
+        let mut tiered = TieredCounters::new(1, 2);
+        for _ in 0..10 {
+            tiered.record(7);
+        }
+        assert!(tiered.hot.contains_key(&7));
+        assert_eq!(tiered.estimate(7), 10);
+
+        // The exact tier is full, so other keys stay in the sketch.
+        for _ in 0..3 {
+            tiered.record(8);
+        }
+        assert!(!tiered.hot.contains_key(&8));
+        assert_eq!(tiered.estimate(8), 3);
+    }
+}
@@ -0,0 +1,204 @@
+//! Tenant budget policies: split the cache's byte budget between tenants.
+
+/// What a tenant is entitled to and currently asks for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantShare {
+    /// Hard upper bound in bytes; zero means no quota.
+    pub quota: u64,
+    /// Bytes reserved for the tenant even under contention.
+    pub min_guarantee: u64,
+    /// Relative share of the pool left after guarantees.
+    pub weight: u32,
+    /// Higher priorities are served first by `PriorityBudget`.
+    pub priority: u8,
+    /// Bytes the tenant currently wants (usage plus pending promotions).
+    pub demand: u64,
+}
+
+impl TenantShare {
+    /// Returns the most the tenant may ever be given out of `total`.
+    pub fn cap(&self, total: u64) -> u64 {
+        if self.quota == 0 {
+            total
+        } else {
+            self.quota.min(total)
+        }
+    }
+}
+
+/// Computes per-tenant byte budgets.
+pub trait TenantBudgetPolicy: Send {
+    /// Returns the configuration name of the policy.
+    fn name(&self) -> &'static str;
+
+    /// Returns one budget per entry of `tenants`, in the same order.
+    fn allocate(&self, total: u64, tenants: &[TenantShare]) -> Vec<u64>;
+}
+
+/// Each tenant gets its quota, independent of the others.
+#[derive(Debug, Default)]
+pub struct HardQuotaBudget;
+
+impl TenantBudgetPolicy for HardQuotaBudget {
+    fn name(&self) -> &'static str {
+        "hard-quota"
+    }
+
+    fn allocate(&self, total: u64, tenants: &[TenantShare]) -> Vec<u64> {
+        tenants.iter().map(|tenant| tenant.cap(total)).collect()
+    }
+}
+
+/// Guarantees first, then the rest of the pool split by weight.
+#[derive(Debug, Default)]
+pub struct ProportionalBudget;
+
+impl TenantBudgetPolicy for ProportionalBudget {
+    fn name(&self) -> &'static str {
+        "proportional"
+    }
+
+    fn allocate(&self, total: u64, tenants: &[TenantShare]) -> Vec<u64> {
+        let mut budgets = guarantees(total, tenants);
+        let remaining = total - budgets.iter().sum::<u64>();
+        share_by_weight(total, remaining, tenants, &mut budgets);
+        budgets
+    }
+}
+
+/// Guarantees first, then outstanding demand in priority order, then any
+/// leftover by weight.
+#[derive(Debug, Default)]
+pub struct PriorityBudget;
+
+impl TenantBudgetPolicy for PriorityBudget {
+    fn name(&self) -> &'static str {
+        "priority"
+    }
+
+    fn allocate(&self, total: u64, tenants: &[TenantShare]) -> Vec<u64> {
+        let mut budgets = guarantees(total, tenants);
+        let mut remaining = total - budgets.iter().sum::<u64>();
+
+        let mut order: Vec<usize> = (0..tenants.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(tenants[index].priority));
+        for index in order {
+            let tenant = &tenants[index];
+            let wanted = tenant
+                .demand
+                .min(tenant.cap(total))
+                .saturating_sub(budgets[index]);
+            let grant = wanted.min(remaining);
+            budgets[index] += grant;
+            remaining -= grant;
+        }
+
+        share_by_weight(total, remaining, tenants, &mut budgets);
+        budgets
+    }
+}
+
+/// Returns each tenant's guarantee (capped by its quota), scaled down
+/// proportionally when the guarantees oversubscribe `total`.
+fn guarantees(total: u64, tenants: &[TenantShare]) -> Vec<u64> {
+    let wanted: Vec<u64> = tenants
+        .iter()
+        .map(|tenant| tenant.min_guarantee.min(tenant.cap(total)))
+        .collect();
+    let sum: u128 = wanted.iter().map(|&bytes| u128::from(bytes)).sum();
+    if sum <= u128::from(total) {
+        return wanted;
+    }
+    wanted
+        .into_iter()
+        .map(|bytes| (u128::from(bytes) * u128::from(total) / sum) as u64)
+        .collect()
+}
+
+/// Water-fills `remaining` bytes by weight, never exceeding a tenant's cap.
+fn share_by_weight(total: u64, mut remaining: u64, tenants: &[TenantShare], budgets: &mut [u64]) {
+    loop {
+        let open: Vec<usize> = (0..tenants.len())
+            .filter(|&index| {
+                tenants[index].weight > 0 && budgets[index] < tenants[index].cap(total)
+            })
+            .collect();
+        let weights: u128 = open
+            .iter()
+            .map(|&index| u128::from(tenants[index].weight))
+            .sum();
+        if remaining == 0 || weights == 0 {
+            return;
+        }
+
+        let mut granted = 0;
+        for index in open {
+            let share = u128::from(remaining) * u128::from(tenants[index].weight) / weights;
+            let room = tenants[index].cap(total) - budgets[index];
+            let grant = room.min(share as u64);
+            budgets[index] += grant;
+            granted += grant;
+        }
+        if granted == 0 {
+            // Only rounding dust is left.
+            return;
+        }
+        remaining -= granted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(
+        quota: u64,
+        min_guarantee: u64,
+        weight: u32,
+        priority: u8,
+        demand: u64,
+    ) -> TenantShare {
+        TenantShare {
+            quota,
+            min_guarantee,
+            weight,
+            priority,
+            demand,
+        }
+    }
+
+    #[test]
+    fn hard_quota_caps_each_tenant_independently() {
+        let tenants = [tenant(300, 0, 1, 0, 0), tenant(0, 0, 1, 0, 0)];
+        assert_eq!(HardQuotaBudget.allocate(1000, &tenants), vec![300, 1000]);
+    }
+
+    #[test]
+    fn proportional_honours_guarantees_then_weights_and_redistributes_caps() {
+        let tenants = [
+            tenant(0, 100, 1, 0, 0),
+            tenant(0, 0, 3, 0, 0),
+            tenant(150, 0, 4, 0, 0),
+        ];
+        // 900 shared bytes: tenant 2 hits its 150 cap, the excess goes 1:3.
+        let budgets = ProportionalBudget.allocate(1000, &tenants);
+        assert_eq!(budgets[2], 150);
+        assert_eq!(budgets[0], 100 + 750 / 4);
+        assert!(budgets.iter().sum::<u64>() <= 1000);
+        assert!(budgets.iter().sum::<u64>() >= 998);
+
+        // Oversubscribed guarantees are scaled down.
+        let tenants = [tenant(0, 600, 1, 0, 0), tenant(0, 600, 1, 0, 0)];
+        assert_eq!(ProportionalBudget.allocate(600, &tenants), vec![300, 300]);
+    }
+
+    #[test]
+    fn priority_serves_demand_in_priority_order() {
+        let tenants = [
+            tenant(0, 100, 1, 1, 800),
+            tenant(0, 100, 1, 9, 700),
+            tenant(0, 0, 0, 5, 0),
+        ];
+        assert_eq!(PriorityBudget.allocate(1000, &tenants), vec![300, 700, 0]);
+    }
+}
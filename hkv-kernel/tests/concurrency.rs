@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use std::thread;
+
+use hkv_common::{
+    ConfigRequest, InvalidateRequest, Key, PromoteRequest, ReadRequest, TenantConfigRequest, Ttl,
+    Value, Version,
+};
+use hkv_kernel::KernelCache;
+
+#[test]
+fn concurrent_readers_and_writers_keep_accounting_consistent() {
+    let cache =
+        Arc::new(KernelCache::with_config(&ConfigRequest::new(64 * 1024, 200, 90, 60)).unwrap());
+
+    let handles: Vec<_> = (0..4u64)
+        .map(|worker| {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                for round in 0..500u64 {
+                    let key =
+                        Key::new(format!("key:{}", (worker * 7 + round) % 300).as_bytes()).unwrap();
+                    let version = Version::new(round + 1);
+                    match round % 4 {
+                        0 | 1 => {
+                            let value = Value::new(&[worker as u8; 64]).unwrap();
+                            let request = PromoteRequest::new(key, value, version, Ttl::INFINITE);
+                            let _ = cache.promote(&request);
+                        }
+                        2 => {
+                            let _ = cache.invalidate(&InvalidateRequest::new(key, version));
+                        }
+                        _ => {
+                            let _ = cache.read(&ReadRequest::new(key));
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let stats = cache.snapshot();
+    assert!(stats.entry_count <= 200);
+    assert!(stats.used_bytes <= 64 * 1024);
+    assert_eq!(stats.lookups, stats.hits + stats.misses);
+    assert!(stats.evictions > 0);
+}
+
+#[test]
+fn tenant_accounting_adds_up_under_concurrency() {
+    let cache =
+        Arc::new(KernelCache::with_config(&ConfigRequest::new(64 * 1024, 200, 90, 60)).unwrap());
+    cache
+        .tenant_config(&TenantConfigRequest::new(1).with_quota(16 * 1024))
+        .unwrap();
+
+    let handles: Vec<_> = (0..4u16)
+        .map(|worker| {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                let tenant = worker % 2;
+                for round in 0..500u64 {
+                    let key = Key::new(format!("key:{}", round % 150).as_bytes()).unwrap();
+                    if round % 3 == 2 {
+                        let _ = cache.read(&ReadRequest::new(key).with_tenant(tenant));
+                        continue;
+                    }
+                    let value = Value::new(&[worker as u8; 64]).unwrap();
+                    let request =
+                        PromoteRequest::new(key, value, Version::new(round + 1), Ttl::INFINITE);
+                    let _ = cache.promote(&request.with_tenant(tenant));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let total = cache.snapshot();
+    let tenants: Vec<_> = cache
+        .tenants()
+        .into_iter()
+        .map(|tenant| cache.tenant_snapshot(tenant).unwrap())
+        .collect();
+    assert_eq!(
+        total.used_bytes,
+        tenants.iter().map(|stats| stats.used_bytes).sum::<u64>()
+    );
+    assert_eq!(
+        total.entry_count,
+        tenants.iter().map(|stats| stats.entry_count).sum::<u64>()
+    );
+    assert!(tenants[1].used_bytes <= 16 * 1024);
+    assert_eq!(total.lookups, total.hits + total.misses);
+}
@@ -0,0 +1,209 @@
+use std::thread;
+use std::time::Duration;
+
+use hkv_common::{
+    ConfigRequest, ConsistencyModeId, HkvError, HkvResult, InvalidateRequest, Key, PromoteRequest,
+    ReadRequest, StatsRequest, TenantConfigRequest, Ttl, Value, Version,
+};
+use hkv_kernel::{KernelCache, RefreshRequest};
+
+fn key(name: &str) -> Key {
+    Key::new(name.as_bytes()).unwrap()
+}
+
+fn config() -> ConfigRequest {
+    ConfigRequest::new(1024 * 1024, 1024, 90, 70)
+}
+
+/// Promotes `name` at version 1 in `tenant`, then invalidates it at 2.
+fn stale_entry(cache: &KernelCache, tenant: u16, name: &str) {
+    let value = Value::new(b"old").unwrap();
+    cache
+        .promote(
+            &PromoteRequest::new(key(name), value, Version::new(1), Ttl::INFINITE)
+                .with_tenant(tenant),
+        )
+        .unwrap();
+    cache
+        .invalidate(&InvalidateRequest::new(key(name), Version::new(2)).with_tenant(tenant))
+        .unwrap();
+}
+
+fn read(cache: &KernelCache, tenant: u16, name: &str) -> HkvResult<Vec<u8>> {
+    cache
+        .read(&ReadRequest::new(key(name)).with_tenant(tenant))
+        .map(|value| value.as_bytes().to_vec())
+}
+
+fn stale_hits(cache: &KernelCache, tenant: u16) -> u64 {
+    cache
+        .stats(&StatsRequest::for_tenant(tenant))
+        .unwrap()
+        .stale_hits
+}
+
+#[test]
+fn strict_mode_misses_on_stale_entries() {
+    let cache = KernelCache::with_config(
+        &config().with_consistency(ConsistencyModeId::BoundedStaleness, 60_000),
+    )
+    .unwrap();
+    cache
+        .config(&config().with_consistency(ConsistencyModeId::Strict, 0))
+        .unwrap();
+    stale_entry(&cache, 0, "k");
+
+    assert_eq!(read(&cache, 0, "k"), Err(HkvError::NotFound));
+    let stats = cache.snapshot();
+    assert_eq!((stats.hits, stats.misses, stats.stale_hits), (0, 1, 0));
+    assert!(cache.take_refresh_requests().is_empty());
+}
+
+#[test]
+fn bounded_staleness_serves_young_stale_values_only() {
+    let cache = KernelCache::with_config(
+        &config().with_consistency(ConsistencyModeId::BoundedStaleness, 60_000),
+    )
+    .unwrap();
+    stale_entry(&cache, 0, "k");
+    assert_eq!(read(&cache, 0, "k").unwrap(), b"old");
+    assert_eq!(read(&cache, 0, "k").unwrap(), b"old");
+    assert_eq!(stale_hits(&cache, 0), 2);
+
+    cache
+        .config(&config().with_consistency(ConsistencyModeId::BoundedStaleness, 1))
+        .unwrap();
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(read(&cache, 0, "k"), Err(HkvError::NotFound));
+    let stats = cache.snapshot();
+    assert_eq!((stats.hits, stats.misses, stats.stale_hits), (2, 1, 2));
+    assert!(cache.take_refresh_requests().is_empty());
+
+    // A newer promotion makes the entry fresh again; fresh hits are not stale.
+    cache
+        .promote(&PromoteRequest::new(
+            key("k"),
+            Value::new(b"new").unwrap(),
+            Version::new(2),
+            Ttl::INFINITE,
+        ))
+        .unwrap();
+    assert_eq!(read(&cache, 0, "k").unwrap(), b"new");
+    assert_eq!(stale_hits(&cache, 0), 2);
+}
+
+#[test]
+fn async_refresh_serves_stale_and_queues_one_refresh_per_key() {
+    let cache =
+        KernelCache::with_config(&config().with_consistency(ConsistencyModeId::AsyncRefresh, 0))
+            .unwrap();
+    stale_entry(&cache, 0, "a");
+    stale_entry(&cache, 0, "b");
+
+    for _ in 0..3 {
+        assert_eq!(read(&cache, 0, "a").unwrap(), b"old");
+    }
+    assert_eq!(read(&cache, 0, "b").unwrap(), b"old");
+    assert_eq!(stale_hits(&cache, 0), 4);
+    assert_eq!(
+        cache.take_refresh_requests(),
+        vec![
+            RefreshRequest {
+                tenant: 0,
+                key: key("a"),
+                version: Version::new(2),
+            },
+            RefreshRequest {
+                tenant: 0,
+                key: key("b"),
+                version: Version::new(2),
+            },
+        ]
+    );
+    assert!(cache.take_refresh_requests().is_empty());
+
+    // Each stale read after the queue drained asks again.
+    read(&cache, 0, "a").unwrap();
+    assert_eq!(cache.take_refresh_requests().len(), 1);
+    assert_eq!(cache.dropped_refreshes(), 0);
+}
+
+#[test]
+fn async_refresh_respects_a_max_age() {
+    let cache =
+        KernelCache::with_config(&config().with_consistency(ConsistencyModeId::AsyncRefresh, 1))
+            .unwrap();
+    stale_entry(&cache, 0, "k");
+    thread::sleep(Duration::from_millis(10));
+
+    assert_eq!(read(&cache, 0, "k"), Err(HkvError::NotFound));
+    assert_eq!(stale_hits(&cache, 0), 0);
+    assert!(cache.take_refresh_requests().is_empty());
+}
+
+#[test]
+fn tenants_override_the_cache_wide_mode() {
+    let cache = KernelCache::with_config(&config()).unwrap();
+    cache.tenant_config(&TenantConfigRequest::new(1)).unwrap();
+    cache.tenant_config(&TenantConfigRequest::new(2)).unwrap();
+    cache
+        .config(&config().with_tenant_consistency(1, ConsistencyModeId::BoundedStaleness, 60_000))
+        .unwrap();
+    for tenant in [0, 1, 2] {
+        stale_entry(&cache, tenant, "k");
+    }
+
+    assert_eq!(read(&cache, 0, "k"), Err(HkvError::NotFound));
+    assert_eq!(read(&cache, 1, "k").unwrap(), b"old");
+    assert_eq!(read(&cache, 2, "k"), Err(HkvError::NotFound));
+
+    // Changing the cache-wide mode leaves the override alone.
+    cache
+        .config(&config().with_consistency(ConsistencyModeId::AsyncRefresh, 0))
+        .unwrap();
+    assert_eq!(read(&cache, 2, "k").unwrap(), b"old");
+    assert_eq!(read(&cache, 1, "k").unwrap(), b"old");
+    let refreshed: Vec<u16> = cache
+        .take_refresh_requests()
+        .iter()
+        .map(|refresh| refresh.tenant)
+        .collect();
+    assert_eq!(refreshed, vec![2]);
+    assert_eq!(
+        (
+            stale_hits(&cache, 0),
+            stale_hits(&cache, 1),
+            stale_hits(&cache, 2)
+        ),
+        (0, 2, 1)
+    );
+    assert_eq!(cache.snapshot().stale_hits, 3);
+
+    // Dropping the override makes the tenant follow the cache-wide mode.
+    cache
+        .config(&config().with_inherited_consistency(1))
+        .unwrap();
+    assert_eq!(read(&cache, 1, "k").unwrap(), b"old");
+    let refreshed: Vec<u16> = cache
+        .take_refresh_requests()
+        .iter()
+        .map(|refresh| refresh.tenant)
+        .collect();
+    assert_eq!(refreshed, vec![1]);
+}
+
+#[test]
+fn invalid_consistency_settings_change_nothing() {
+    let cache = KernelCache::with_config(&config()).unwrap();
+    stale_entry(&cache, 0, "k");
+
+    let unbounded = config().with_consistency(ConsistencyModeId::BoundedStaleness, 0);
+    assert_eq!(cache.config(&unbounded), Err(HkvError::InvalidInput));
+    let unknown_tenant = config().with_tenant_consistency(9, ConsistencyModeId::AsyncRefresh, 0);
+    assert_eq!(cache.config(&unknown_tenant), Err(HkvError::InvalidInput));
+    let mut unknown_mode = config();
+    unknown_mode.consistency_mode = 42;
+    assert_eq!(cache.config(&unknown_mode), Err(HkvError::InvalidInput));
+
+    assert_eq!(read(&cache, 0, "k"), Err(HkvError::NotFound));
+}
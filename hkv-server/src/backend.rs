@@ -0,0 +1,377 @@
+//! # Kernel Cache Backends
+//!
+//! Abstract the kernel cache tier behind `CacheBackend` so the control plane
+//! (promotion, invalidation) is written once and runs against either the
+//! `/dev/hybridkv` device or an in-process stand-in.
+//!
+//! ## Design Principles
+//!
+//! 1. **ABI-Shaped Calls**: Methods take the `hkv_common::protocol` request
+//!    structs exactly as they would cross the ioctl boundary, so a device
+//!    backend is a thin `ioctl` wrapper and tests exercise the real payloads.
+//! 2. **Testable Without a Kernel**: `InProcessBackend` keeps promoted entries
+//!    in a map with the kernel's validation and version rules, so the whole
+//!    promotion loop runs on machines without the module.
+//! 3. **Newest Version Wins**: A promotion never replaces a cached entry with
+//!    an older version, mirroring the kernel's stale-write guard. Invalidating
+//!    an uncached key leaves a version floor, so a promotion read before the
+//!    write but delivered after it is rejected too.
+//! 4. **Same Events as the Kernel**: Given an event sender, `InProcessBackend`
+//!    reports promotions and invalidations as `CacheEvent` datagrams, so the
+//!    server's kernel key view works without the module as well.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use hkv_common::{
+    BatchPromoteMemory, BatchPromoteRequest, CacheEvent, CacheEventKind, HkvError, HkvResult,
+    IOCTL_MAGIC, InvalidateRequest, IoctlCommand, IoctlHeader, Key, RefreshRequest, Ttl, Version,
+};
+use tokio::sync::mpsc;
+
+/// Operations the control plane issues against the kernel cache tier.
+pub trait CacheBackend: Send + Sync {
+    /// Inserts a batch of entries, writing each entry's outcome to its
+    /// status in `user`, the memory behind the request's pointers.
+    fn batch_promote(
+        &self,
+        request: &BatchPromoteRequest,
+        user: &mut BatchPromoteMemory<'_>,
+    ) -> HkvResult<()>;
+
+    /// Drops a cached key if its cached version is older than the request's.
+    fn invalidate(&self, request: &InvalidateRequest) -> HkvResult<()>;
+
+    /// Removes and returns the refreshes queued by async-refresh reads of
+    /// stale entries, oldest first.
+    ///
+    /// Tiers that never serve stale values have none to hand out.
+    fn take_refresh_requests(&self) -> Vec<RefreshRequest> {
+        Vec::new()
+    }
+}
+
+/// One entry held by `InProcessBackend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedEntry {
+    /// Cached value bytes.
+    pub value: Vec<u8>,
+    /// Version the value was promoted with.
+    pub version: Version,
+    /// Absolute expiration, as sent in the promote request.
+    pub ttl: Ttl,
+}
+
+/// In-memory `CacheBackend` for tests and kernel-less deployments.
+pub struct InProcessBackend {
+    tier: Mutex<Tier>,
+    max_entries: usize,
+    events: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+/// Cached entries plus the invalidation floors of uncached keys.
+#[derive(Default)]
+struct Tier {
+    entries: HashMap<Vec<u8>, CachedEntry>,
+    /// Versions uncached keys were invalidated at; older promotions are stale.
+    floors: HashMap<Vec<u8>, Version>,
+    /// Keys of `floors`, oldest first; bounded by `max_entries`.
+    floor_order: VecDeque<Vec<u8>>,
+}
+
+impl Tier {
+    /// Records that `key` was invalidated at `version`.
+    fn raise_floor(&mut self, key: &[u8], version: Version, max_floors: usize) {
+        if let Some(floor) = self.floors.get_mut(key) {
+            *floor = (*floor).max(version);
+            return;
+        }
+        self.floors.insert(key.to_vec(), version);
+        self.floor_order.push_back(key.to_vec());
+        while self.floors.len() > max_floors {
+            match self.floor_order.pop_front() {
+                Some(oldest) => {
+                    self.floors.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl InProcessBackend {
+    /// Creates a backend holding at most `max_entries` keys (and as many
+    /// invalidation floors).
+    pub fn new(max_entries: usize) -> Self {
+        InProcessBackend {
+            tier: Mutex::new(Tier::default()),
+            max_entries,
+            events: None,
+        }
+    }
+
+    /// Sends a `CacheEvent` datagram to `events` for every promotion and
+    /// every invalidation that drops a cached key.
+    ///
+    /// Events are dropped, not queued, while the channel is full.
+    pub fn with_events(mut self, events: mpsc::Sender<Vec<u8>>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Returns the cached entry for `key`, if any.
+    pub fn get(&self, key: &[u8]) -> Option<CachedEntry> {
+        self.lock().entries.get(key).cloned()
+    }
+
+    /// Returns the number of cached keys.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns true when nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Tier> {
+        self.tier.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Reports a change to the event channel, if there is one.
+    fn emit(&self, kind: CacheEventKind, tenant: u16, key: &[u8], version: Version) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        if let Ok(key) = Key::new(key) {
+            let event = CacheEvent::new(kind, tenant, key, version);
+            let _ = events.try_send(event.to_bytes().to_vec());
+        }
+    }
+}
+
+impl CacheBackend for InProcessBackend {
+    fn batch_promote(
+        &self,
+        request: &BatchPromoteRequest,
+        user: &mut BatchPromoteMemory<'_>,
+    ) -> HkvResult<()> {
+        validate_header(&request.header, IoctlCommand::BatchPromote)?;
+        let descriptors = user.descriptors(request)?;
+
+        let mut tier = self.lock();
+        for (index, descriptor) in descriptors.iter().enumerate() {
+            let result = user.key(descriptor).and_then(|key| {
+                let value = user.value(descriptor)?;
+                if tier
+                    .floors
+                    .get(key)
+                    .is_some_and(|&floor| descriptor.version < floor)
+                {
+                    return Err(HkvError::StaleVersion);
+                }
+                match tier.entries.get(key) {
+                    Some(cached) if cached.version > descriptor.version => {
+                        return Err(HkvError::StaleVersion);
+                    }
+                    None if tier.entries.len() >= self.max_entries => {
+                        return Err(HkvError::CapacityExceeded);
+                    }
+                    _ => {}
+                }
+                tier.entries.insert(
+                    key.to_vec(),
+                    CachedEntry {
+                        value: value.to_vec(),
+                        version: descriptor.version,
+                        ttl: descriptor.ttl,
+                    },
+                );
+                self.emit(
+                    CacheEventKind::Promoted,
+                    request.tenant,
+                    key,
+                    descriptor.version,
+                );
+                Ok(())
+            });
+            user.set_status(index, result);
+        }
+        Ok(())
+    }
+
+    fn invalidate(&self, request: &InvalidateRequest) -> HkvResult<()> {
+        validate_header(&request.header, IoctlCommand::Invalidate)?;
+        let mut tier = self.lock();
+        let key = request.key.as_bytes();
+        match tier.entries.get(key) {
+            Some(cached) if cached.version >= request.version => {}
+            Some(_) => {
+                tier.entries.remove(key);
+                tier.raise_floor(key, request.version, self.max_entries);
+                self.emit(
+                    CacheEventKind::Invalidated,
+                    request.tenant,
+                    key,
+                    request.version,
+                );
+            }
+            None => tier.raise_floor(key, request.version, self.max_entries),
+        }
+        Ok(())
+    }
+}
+
+/// Checks magic, protocol version, and command like the kernel entry point.
+fn validate_header(header: &IoctlHeader, command: IoctlCommand) -> HkvResult<()> {
+    if header.magic != IOCTL_MAGIC || header.command != command.as_u8() {
+        return Err(HkvError::ProtocolViolation);
+    }
+    if !command.supports_version(header.version) {
+        return Err(HkvError::VersionMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::{BatchPromoteBuilder, Key, PROTOCOL_VERSION};
+
+    fn batch(entries: &[(&str, &str, u64)]) -> BatchPromoteBuilder {
+        let mut batch = BatchPromoteBuilder::new();
+        for &(key, value, version) in entries {
+            batch
+                .push(
+                    key.as_bytes(),
+                    value.as_bytes(),
+                    Version::new(version),
+                    Ttl::INFINITE,
+                )
+                .unwrap();
+        }
+        batch
+    }
+
+    fn promote(backend: &InProcessBackend, batch: &mut BatchPromoteBuilder) -> HkvResult<()> {
+        let (request, mut user) = batch.prepare();
+        backend.batch_promote(&request, &mut user)
+    }
+
+    #[test]
+    fn promotes_until_full_and_keeps_newer_versions() {
+        let backend = InProcessBackend::new(2);
+        let mut first = batch(&[("a", "1", 5), ("b", "1", 1), ("c", "1", 1)]);
+        promote(&backend, &mut first).unwrap();
+        assert_eq!(first.statuses().len(), 3);
+        assert!(first.succeeded(0) && first.succeeded(1));
+        assert_eq!(first.status(2), Err(HkvError::CapacityExceeded));
+
+        let mut second = batch(&[("a", "old", 4), ("b", "new", 2)]);
+        promote(&backend, &mut second).unwrap();
+        assert_eq!(second.status(0), Err(HkvError::StaleVersion));
+        assert!(second.succeeded(1));
+        assert_eq!(backend.get(b"a").unwrap().value, b"1");
+        assert_eq!(backend.get(b"b").unwrap().version, Version::new(2));
+        assert_eq!(backend.len(), 2);
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let backend = InProcessBackend::new(8);
+        let mut batch = batch(&[("a", "1", 1)]);
+        let (mut request, mut user) = batch.prepare();
+        request.header.version = PROTOCOL_VERSION + 1;
+        assert_eq!(
+            backend.batch_promote(&request, &mut user).unwrap_err(),
+            HkvError::VersionMismatch
+        );
+        request.header = IoctlHeader::new(IoctlCommand::Promote);
+        assert_eq!(
+            backend.batch_promote(&request, &mut user).unwrap_err(),
+            HkvError::ProtocolViolation
+        );
+        request.header = IoctlHeader::new(IoctlCommand::BatchPromote);
+        request.entries_ptr = 0;
+        assert_eq!(
+            backend.batch_promote(&request, &mut user).unwrap_err(),
+            HkvError::InvalidInput
+        );
+        assert!(backend.is_empty());
+    }
+
+    #[test]
+    fn invalidation_drops_only_older_versions() {
+        let backend = InProcessBackend::new(8);
+        promote(&backend, &mut batch(&[("a", "1", 5)])).unwrap();
+
+        let key = Key::new(b"a").unwrap();
+        backend
+            .invalidate(&InvalidateRequest::new(key.clone(), Version::new(5)))
+            .unwrap();
+        assert!(backend.get(b"a").is_some());
+        backend
+            .invalidate(&InvalidateRequest::new(key, Version::new(6)))
+            .unwrap();
+        assert!(backend.is_empty());
+    }
+
+    #[test]
+    fn invalidation_floors_reject_late_promotions() {
+        let backend = InProcessBackend::new(1);
+        let key = Key::new(b"a").unwrap();
+        backend
+            .invalidate(&InvalidateRequest::new(key.clone(), Version::new(2)))
+            .unwrap();
+
+        // Read at v1 before the write, delivered after its invalidation.
+        let mut late = batch(&[("a", "old", 1)]);
+        promote(&backend, &mut late).unwrap();
+        assert_eq!(late.status(0), Err(HkvError::StaleVersion));
+        assert!(backend.is_empty());
+
+        let mut current = batch(&[("a", "new", 2)]);
+        promote(&backend, &mut current).unwrap();
+        assert!(current.succeeded(0));
+
+        // Floors are bounded like entries; the oldest is forgotten first.
+        for name in ["b", "c"] {
+            let key = Key::new(name.as_bytes()).unwrap();
+            backend
+                .invalidate(&InvalidateRequest::new(key, Version::new(9)))
+                .unwrap();
+        }
+        let mut forgotten = batch(&[("b", "old", 1)]);
+        promote(&backend, &mut forgotten).unwrap();
+        assert_eq!(forgotten.status(0), Err(HkvError::CapacityExceeded));
+        let mut floored = batch(&[("c", "old", 1)]);
+        promote(&backend, &mut floored).unwrap();
+        assert_eq!(floored.status(0), Err(HkvError::StaleVersion));
+    }
+
+    #[test]
+    fn reports_promotions_and_invalidations_as_events() {
+        let (sender, mut receiver) = mpsc::channel(8);
+        let backend = InProcessBackend::new(8).with_events(sender);
+        promote(&backend, &mut batch(&[("a", "1", 1)])).unwrap();
+        let key = Key::new(b"a").unwrap();
+        backend
+            .invalidate(&InvalidateRequest::new(key.clone(), Version::new(1)))
+            .unwrap();
+        backend
+            .invalidate(&InvalidateRequest::new(key, Version::new(2)))
+            .unwrap();
+
+        let kinds: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|datagram| CacheEvent::from_bytes(&datagram).unwrap())
+            .map(|event| (event.event_kind(), event.version))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(CacheEventKind::Promoted), Version::new(1)),
+                (Some(CacheEventKind::Invalidated), Version::new(2)),
+            ]
+        );
+    }
+}
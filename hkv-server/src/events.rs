@@ -0,0 +1,250 @@
+//! # Kernel Cache Events
+//!
+//! Consume the kernel cache's change notifications and keep a live view of
+//! which keys the kernel tier currently serves, for `INFO` and operators.
+//!
+//! ## Design Principles
+//!
+//! 1. **Pluggable Transport**: Datagrams arrive through `EventTransport`; the
+//!    Netlink socket is one implementation and `ChannelTransport` stands in
+//!    for it in tests and in-process setups.
+//! 2. **Versioned View**: Every entry remembers the version it was promoted
+//!    at, so a late removal for an older version cannot drop a newer copy.
+//! 3. **Skip, Don't Stop**: A malformed record is counted and skipped; only a
+//!    transport error or a closed transport ends the consumer.
+//! 4. **Observable**: Per-kind event counts and decode errors feed `INFO`.
+//! 5. **Ordered View**: Keys are held sorted by tenant, then key, so listing
+//!    the first few for `INFO` clones only those, not the whole view.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use hkv_common::{CacheEvent, CacheEventKind, Version, records};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Source of kernel event datagrams.
+///
+/// Each datagram holds one or more back-to-back `CacheEvent` records, as a
+/// Netlink message would.
+pub trait EventTransport: Send {
+    /// Waits for the next datagram; `Ok(None)` means the transport closed.
+    fn recv(&mut self) -> impl Future<Output = io::Result<Option<Vec<u8>>>> + Send;
+}
+
+/// In-process transport fed by a Tokio channel.
+pub struct ChannelTransport {
+    receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    /// Creates a transport and the sender that feeds it datagrams.
+    pub fn channel(capacity: usize) -> (mpsc::Sender<Vec<u8>>, Self) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (sender, ChannelTransport { receiver })
+    }
+}
+
+impl EventTransport for ChannelTransport {
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.receiver.recv().await)
+    }
+}
+
+/// A key the kernel tier currently caches.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CachedKey {
+    /// Tenant the key belongs to.
+    pub tenant: u16,
+    /// Key bytes.
+    pub key: Vec<u8>,
+    /// Version the kernel holds.
+    pub version: Version,
+}
+
+/// Cached versions keyed by tenant, then key bytes.
+type KeyMap = BTreeMap<(u16, Vec<u8>), Version>;
+
+/// Live view of the kernel tier built from its event stream.
+#[derive(Default)]
+pub struct KernelKeyView {
+    keys: Mutex<KeyMap>,
+    events: [AtomicU64; CacheEventKind::ALL.len()],
+    decode_errors: AtomicU64,
+}
+
+impl KernelKeyView {
+    /// Creates an empty view.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies one event to the view.
+    ///
+    /// A promotion records the key unless a newer version is already known;
+    /// any other kind drops the key if the cached version is not newer than
+    /// the event's.
+    pub fn apply(&self, event: &CacheEvent) {
+        let Some(kind) = event.event_kind() else {
+            self.record_decode_error();
+            return;
+        };
+        self.events[kind as usize - 1].fetch_add(1, Ordering::Relaxed);
+
+        let id = (event.tenant, event.key.as_bytes().to_vec());
+        let mut keys = self.lock();
+        if kind.removes() {
+            if keys.get(&id).is_some_and(|cached| *cached <= event.version) {
+                keys.remove(&id);
+            }
+        } else {
+            let cached = keys.entry(id).or_insert(event.version);
+            *cached = (*cached).max(event.version);
+        }
+    }
+
+    /// Counts a record that could not be decoded.
+    pub fn record_decode_error(&self) {
+        self.decode_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the cached version of `key` in `tenant`, if any.
+    pub fn version(&self, tenant: u16, key: &[u8]) -> Option<Version> {
+        self.lock().get(&(tenant, key.to_vec())).copied()
+    }
+
+    /// Returns true when the kernel tier caches `key` in `tenant`.
+    pub fn contains(&self, tenant: u16, key: &[u8]) -> bool {
+        self.version(tenant, key).is_some()
+    }
+
+    /// Returns the number of cached keys.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true when no keys are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns up to `limit` cached keys ordered by tenant, then key.
+    pub fn cached_keys(&self, limit: usize) -> Vec<CachedKey> {
+        self.lock()
+            .iter()
+            .take(limit)
+            .map(|((tenant, key), version)| CachedKey {
+                tenant: *tenant,
+                key: key.clone(),
+                version: *version,
+            })
+            .collect()
+    }
+
+    /// Returns how many events of `kind` were applied.
+    pub fn events(&self, kind: CacheEventKind) -> u64 {
+        self.events[kind as usize - 1].load(Ordering::Relaxed)
+    }
+
+    /// Returns how many records failed to decode.
+    pub fn decode_errors(&self) -> u64 {
+        self.decode_errors.load(Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, KeyMap> {
+        self.keys.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Feeds every record from `transport` into `view` until it closes.
+///
+/// # Errors
+/// Returns the transport's I/O error; malformed records are only counted.
+pub async fn consume<T: EventTransport>(
+    mut transport: T,
+    view: Arc<KernelKeyView>,
+) -> io::Result<()> {
+    while let Some(datagram) = transport.recv().await? {
+        for record in records(&datagram) {
+            match record {
+                Ok(event) => view.apply(&event),
+                Err(_) => view.record_decode_error(),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs `consume` on a background task.
+pub fn spawn_consumer<T: EventTransport + 'static>(
+    transport: T,
+    view: Arc<KernelKeyView>,
+) -> JoinHandle<io::Result<()>> {
+    tokio::spawn(consume(transport, view))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::Key;
+
+    fn event(kind: CacheEventKind, tenant: u16, key: &[u8], version: u64) -> CacheEvent {
+        CacheEvent::new(kind, tenant, Key::new(key).unwrap(), Version::new(version))
+    }
+
+    #[test]
+    fn removals_respect_versions() {
+        let view = KernelKeyView::new();
+        view.apply(&event(CacheEventKind::Promoted, 0, b"k", 3));
+        view.apply(&event(CacheEventKind::Promoted, 0, b"k", 2));
+        assert_eq!(view.version(0, b"k"), Some(Version::new(3)));
+
+        // A removal for an older copy leaves the newer one in place.
+        view.apply(&event(CacheEventKind::Evicted, 0, b"k", 2));
+        assert!(view.contains(0, b"k"));
+        view.apply(&event(CacheEventKind::Invalidated, 0, b"k", 4));
+        assert!(!view.contains(0, b"k"));
+        assert_eq!(view.events(CacheEventKind::Promoted), 2);
+        assert_eq!(view.events(CacheEventKind::Evicted), 1);
+    }
+
+    #[test]
+    fn tenants_are_tracked_separately() {
+        let view = KernelKeyView::new();
+        view.apply(&event(CacheEventKind::Promoted, 1, b"k", 1));
+        view.apply(&event(CacheEventKind::Promoted, 0, b"k", 1));
+        view.apply(&event(CacheEventKind::Expired, 1, b"k", 1));
+
+        assert_eq!(view.len(), 1);
+        assert_eq!(
+            view.cached_keys(10),
+            vec![CachedKey {
+                tenant: 0,
+                key: b"k".to_vec(),
+                version: Version::new(1),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn consumer_skips_bad_records_and_stops_on_close() {
+        let view = Arc::new(KernelKeyView::new());
+        let (sender, transport) = ChannelTransport::channel(4);
+        let consumer = spawn_consumer(transport, Arc::clone(&view));
+
+        let mut datagram = event(CacheEventKind::Promoted, 0, b"a", 1)
+            .to_bytes()
+            .to_vec();
+        datagram.extend_from_slice(&[0u8; 7]);
+        sender.send(datagram).await.unwrap();
+        drop(sender);
+
+        consumer.await.unwrap().unwrap();
+        assert!(view.contains(0, b"a"));
+        assert_eq!(view.decode_errors(), 1);
+    }
+}
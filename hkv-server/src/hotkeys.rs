@@ -0,0 +1,344 @@
+//! # Hot-Key Tracking
+//!
+//! Estimate per-key access frequency with a Count-Min Sketch and keep the
+//! current top-K keys, the input the promotion flow needs to pick kernel
+//! cache candidates.
+//!
+//! ## Design Principles
+//!
+//! 1. **Lock-Free Counting**: Reads and writes go to two atomic Count-Min
+//!    Sketches (`hkv_common::CountMinSketch`), so recording an access never
+//!    blocks and the read ratio of any key can be estimated without storing
+//!    it.
+//! 2. **Time Decay**: Every `decay_interval` all counters are halved, so
+//!    estimates follow the recent request rate instead of all-time totals.
+//! 3. **Gated Top-K Heap**: The top-K set sits behind a mutex that is only
+//!    taken when a key's estimate beats the current minimum, so cold traffic
+//!    stays on the lock-free path.
+//! 4. **Estimates Over Exact Counts**: Sketches may overcount on collisions but
+//!    never undercount, which errs on the side of treating a key as hot.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use ahash::RandomState;
+use hkv_common::CountMinSketch;
+
+/// Kind of access being recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// A read such as `GET`.
+    Read,
+    /// A write such as `SET`.
+    Write,
+}
+
+/// Tuning knobs for `HotKeyTracker`.
+#[derive(Debug, Clone)]
+pub struct HotKeyConfig {
+    /// Counters per sketch row; rounded up to a power of two.
+    pub width: usize,
+    /// Number of keys kept in the hot set.
+    pub top_k: usize,
+    /// Interval after which all counts are halved; zero disables decay.
+    pub decay_interval: Duration,
+}
+
+impl Default for HotKeyConfig {
+    fn default() -> Self {
+        HotKeyConfig {
+            width: 4096,
+            top_k: 32,
+            decay_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// One entry of the hot set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotKey {
+    /// Key bytes.
+    pub key: Vec<u8>,
+    /// Estimated reads since decay started counting.
+    pub reads: u64,
+    /// Estimated writes since decay started counting.
+    pub writes: u64,
+}
+
+impl HotKey {
+    /// Returns the estimated total accesses.
+    pub fn frequency(&self) -> u64 {
+        self.reads + self.writes
+    }
+
+    /// Returns the fraction of accesses that were reads (0.0 when idle).
+    pub fn read_ratio(&self) -> f64 {
+        let total = self.frequency();
+        if total == 0 {
+            0.0
+        } else {
+            self.reads as f64 / total as f64
+        }
+    }
+}
+
+/// Concurrent, time-decayed hot-key tracker.
+pub struct HotKeyTracker {
+    hash_state: RandomState,
+    reads: CountMinSketch,
+    writes: CountMinSketch,
+    top: Mutex<TopK>,
+    /// Smallest frequency in a full top-K set (0 while it has room).
+    floor: AtomicU64,
+    /// Milliseconds since `started_at` of the last decay.
+    last_decay_ms: AtomicU64,
+    decay_interval: Duration,
+    started_at: Instant,
+}
+
+impl HotKeyTracker {
+    /// Creates a tracker with the given configuration.
+    pub fn new(config: HotKeyConfig) -> Self {
+        HotKeyTracker {
+            hash_state: RandomState::new(),
+            reads: CountMinSketch::new(config.width),
+            writes: CountMinSketch::new(config.width),
+            top: Mutex::new(TopK::new(config.top_k.max(1))),
+            floor: AtomicU64::new(0),
+            last_decay_ms: AtomicU64::new(0),
+            decay_interval: config.decay_interval,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Records one access to `key`.
+    pub fn record(&self, key: &[u8], access: Access) {
+        self.maybe_decay();
+        let hash = self.hash_state.hash_one(key);
+        let frequency = match access {
+            Access::Read => self.reads.increment(hash) + self.writes.estimate(hash),
+            Access::Write => self.writes.increment(hash) + self.reads.estimate(hash),
+        };
+        if frequency <= self.floor.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut top = self.top.lock().unwrap_or_else(|err| err.into_inner());
+        top.offer(key, frequency);
+        self.floor.store(top.floor(), Ordering::Relaxed);
+    }
+
+    /// Returns up to `limit` hot keys, hottest first.
+    pub fn hot_keys(&self, limit: usize) -> Vec<HotKey> {
+        self.maybe_decay();
+        let keys = {
+            let top = self.top.lock().unwrap_or_else(|err| err.into_inner());
+            top.keys()
+        };
+        let mut hot: Vec<HotKey> = keys
+            .into_iter()
+            .map(|key| {
+                let hash = self.hash_state.hash_one(&key[..]);
+                HotKey {
+                    reads: self.reads.estimate(hash),
+                    writes: self.writes.estimate(hash),
+                    key,
+                }
+            })
+            .filter(|entry| entry.frequency() > 0)
+            .collect();
+        hot.sort_by(|a, b| {
+            b.frequency()
+                .cmp(&a.frequency())
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        hot.truncate(limit);
+        hot
+    }
+
+    /// Returns how many keys the hot set currently holds.
+    pub fn tracked(&self) -> usize {
+        self.top.lock().unwrap_or_else(|err| err.into_inner()).len()
+    }
+
+    /// Halves every count once per `decay_interval`.
+    ///
+    /// Only the thread that wins the timestamp swap performs the decay.
+    fn maybe_decay(&self) {
+        let interval_ms = self.decay_interval.as_millis() as u64;
+        if interval_ms == 0 {
+            return;
+        }
+        let now_ms = self.started_at.elapsed().as_millis() as u64;
+        let last = self.last_decay_ms.load(Ordering::Relaxed);
+        if now_ms.saturating_sub(last) < interval_ms {
+            return;
+        }
+        if self
+            .last_decay_ms
+            .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+        self.reads.halve();
+        self.writes.halve();
+        let mut top = self.top.lock().unwrap_or_else(|err| err.into_inner());
+        top.halve();
+        self.floor.store(top.floor(), Ordering::Relaxed);
+    }
+}
+
+impl Default for HotKeyTracker {
+    fn default() -> Self {
+        Self::new(HotKeyConfig::default())
+    }
+}
+
+/// Bounded top-K set ordered by a lazily maintained min-heap.
+///
+/// The heap may hold stale `(frequency, key)` pairs; an entry is current
+/// only while it matches `entries`, and stale ones are dropped when they
+/// reach the top.
+struct TopK {
+    capacity: usize,
+    entries: HashMap<Vec<u8>, u64>,
+    heap: BinaryHeap<Reverse<(u64, Vec<u8>)>>,
+}
+
+impl TopK {
+    fn new(capacity: usize) -> Self {
+        TopK {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            heap: BinaryHeap::with_capacity(capacity * 2),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Updates `key` or inserts it if it beats the current minimum.
+    fn offer(&mut self, key: &[u8], frequency: u64) {
+        if let Some(current) = self.entries.get_mut(key) {
+            if *current != frequency {
+                *current = frequency;
+                self.heap.push(Reverse((frequency, key.to_vec())));
+            }
+        } else if self.entries.len() < self.capacity {
+            self.entries.insert(key.to_vec(), frequency);
+            self.heap.push(Reverse((frequency, key.to_vec())));
+        } else if self.min().is_some_and(|min| frequency > min) {
+            if let Some(Reverse((_, evicted))) = self.heap.pop() {
+                self.entries.remove(&evicted);
+            }
+            self.entries.insert(key.to_vec(), frequency);
+            self.heap.push(Reverse((frequency, key.to_vec())));
+        }
+
+        if self.heap.len() > self.capacity * 4 {
+            self.rebuild();
+        }
+    }
+
+    /// Returns the smallest current frequency, discarding stale heap entries.
+    fn min(&mut self) -> Option<u64> {
+        while let Some(Reverse((frequency, key))) = self.heap.peek() {
+            if self.entries.get(key) == Some(frequency) {
+                return Some(*frequency);
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    /// Returns the admission floor: 0 while there is room, else the minimum.
+    fn floor(&mut self) -> u64 {
+        if self.entries.len() < self.capacity {
+            0
+        } else {
+            self.min().unwrap_or(0)
+        }
+    }
+
+    fn halve(&mut self) {
+        for frequency in self.entries.values_mut() {
+            *frequency /= 2;
+        }
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        self.heap = self
+            .entries
+            .iter()
+            .map(|(key, &frequency)| Reverse((frequency, key.clone())))
+            .collect();
+    }
+
+    fn keys(&self) -> Vec<Vec<u8>> {
+        self.entries.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(top_k: usize, decay_interval: Duration) -> HotKeyTracker {
+        HotKeyTracker::new(HotKeyConfig {
+            width: 1024,
+            top_k,
+            decay_interval,
+        })
+    }
+
+    #[test]
+    fn ranks_keys_and_splits_reads_from_writes() {
+        let tracker = tracker(2, Duration::ZERO);
+        for _ in 0..9 {
+            tracker.record(b"hot", Access::Read);
+        }
+        tracker.record(b"hot", Access::Write);
+        for _ in 0..3 {
+            tracker.record(b"warm", Access::Write);
+        }
+        tracker.record(b"cold", Access::Read);
+
+        let hot = tracker.hot_keys(10);
+        let keys: Vec<&[u8]> = hot.iter().map(|entry| &entry.key[..]).collect();
+        assert_eq!(keys, vec![&b"hot"[..], b"warm"]);
+        assert_eq!((hot[0].reads, hot[0].writes), (9, 1));
+        assert!((hot[0].read_ratio() - 0.9).abs() < 1e-9);
+        assert_eq!(hot[1].read_ratio(), 0.0);
+        assert_eq!(tracker.hot_keys(1).len(), 1);
+    }
+
+    #[test]
+    fn newly_hot_keys_displace_the_minimum() {
+        let tracker = tracker(2, Duration::ZERO);
+        tracker.record(b"a", Access::Read);
+        tracker.record(b"b", Access::Read);
+        tracker.record(b"b", Access::Read);
+        for _ in 0..5 {
+            tracker.record(b"c", Access::Read);
+        }
+        let keys: Vec<Vec<u8>> = tracker.hot_keys(10).into_iter().map(|e| e.key).collect();
+        assert_eq!(keys, vec![b"c".to_vec(), b"b".to_vec()]);
+        assert_eq!(tracker.tracked(), 2);
+    }
+
+    #[test]
+    fn counts_decay_over_time() {
+        let tracker = tracker(4, Duration::from_millis(20));
+        for _ in 0..8 {
+            tracker.record(b"k", Access::Read);
+        }
+        std::thread::sleep(Duration::from_millis(30));
+        let hot = tracker.hot_keys(1);
+        assert_eq!(hot[0].reads, 4);
+    }
+}
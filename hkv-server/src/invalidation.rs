@@ -0,0 +1,177 @@
+//! # Write-Through Invalidation
+//!
+//! Keep the kernel cache tier coherent by sending an `InvalidateRequest` for
+//! every successful string write, delete, expiry change, or `*STORE` that
+//! overwrites its destination.
+//!
+//! ## Design Principles
+//!
+//! 1. **Engine First**: Invalidation runs after the engine write succeeds and
+//!    carries the version that write produced, so the kernel drops only
+//!    entries older than the new value.
+//! 2. **Fail-Open by Default**: A failed invalidation is counted but does not
+//!    fail the client's write; `FailClosed` surfaces it as an error instead.
+//! 3. **Pluggable Backend**: Requests go through `CacheBackend`; without one
+//!    the invalidator is a no-op.
+//! 4. **Observable**: Sent and failed invalidations are counted for `INFO`.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use hkv_common::{HkvResult, InvalidateRequest, Key, Version};
+
+use crate::backend::CacheBackend;
+
+/// How a failed invalidation affects the client's write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidationMode {
+    /// Count the failure and report the write as successful.
+    #[default]
+    FailOpen,
+    /// Report the failure to the client (the engine write still applied).
+    FailClosed,
+}
+
+impl InvalidationMode {
+    /// Parses `fail-open` or `fail-closed` (case-insensitive).
+    pub fn parse(input: &str) -> Option<Self> {
+        if input.eq_ignore_ascii_case("fail-open") {
+            Some(InvalidationMode::FailOpen)
+        } else if input.eq_ignore_ascii_case("fail-closed") {
+            Some(InvalidationMode::FailClosed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the configuration name of the mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            InvalidationMode::FailOpen => "fail-open",
+            InvalidationMode::FailClosed => "fail-closed",
+        }
+    }
+}
+
+/// Sends versioned invalidations to the kernel tier and counts outcomes.
+#[derive(Default)]
+pub struct Invalidator {
+    backend: Option<Arc<dyn CacheBackend>>,
+    mode: InvalidationMode,
+    sent: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl Invalidator {
+    /// Creates an invalidator issuing requests through `backend`.
+    pub fn new(backend: Arc<dyn CacheBackend>, mode: InvalidationMode) -> Self {
+        Invalidator {
+            backend: Some(backend),
+            mode,
+            sent: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates an invalidator with no backend; every call is a no-op.
+    pub fn disabled() -> Self {
+        Invalidator::default()
+    }
+
+    /// Returns true when a backend is attached.
+    pub fn is_enabled(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Returns the failure mode.
+    pub fn mode(&self) -> InvalidationMode {
+        self.mode
+    }
+
+    /// Invalidates `key` up to (excluding) `version`.
+    ///
+    /// Keys longer than `MAX_KEY_SIZE` are never promoted, so they are
+    /// skipped. Errors are returned only in `FailClosed` mode.
+    pub fn invalidate(&self, key: &[u8], version: Version) -> HkvResult<()> {
+        let Some(backend) = self.backend.as_ref() else {
+            return Ok(());
+        };
+        let Ok(key) = Key::new(key) else {
+            return Ok(());
+        };
+
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        match backend.invalidate(&InvalidateRequest::new(key, version)) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                match self.mode {
+                    InvalidationMode::FailOpen => Ok(()),
+                    InvalidationMode::FailClosed => Err(err),
+                }
+            }
+        }
+    }
+
+    /// Returns the number of invalidations sent to the backend.
+    pub fn invalidations(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of invalidations the backend rejected.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hkv_common::{BatchPromoteMemory, BatchPromoteRequest, HkvError};
+
+    struct FailingBackend;
+
+    impl CacheBackend for FailingBackend {
+        fn batch_promote(
+            &self,
+            _: &BatchPromoteRequest,
+            _: &mut BatchPromoteMemory<'_>,
+        ) -> HkvResult<()> {
+            Err(HkvError::Busy)
+        }
+
+        fn invalidate(&self, _: &InvalidateRequest) -> HkvResult<()> {
+            Err(HkvError::Busy)
+        }
+    }
+
+    #[test]
+    fn failures_are_counted_and_surface_only_when_fail_closed() {
+        let open = Invalidator::new(Arc::new(FailingBackend), InvalidationMode::FailOpen);
+        assert_eq!(open.invalidate(b"k", Version::new(1)), Ok(()));
+        assert_eq!((open.invalidations(), open.failures()), (1, 1));
+
+        let closed = Invalidator::new(Arc::new(FailingBackend), InvalidationMode::FailClosed);
+        assert_eq!(
+            closed.invalidate(b"k", Version::new(1)),
+            Err(HkvError::Busy)
+        );
+        assert_eq!(closed.failures(), 1);
+
+        let disabled = Invalidator::disabled();
+        assert_eq!(disabled.invalidate(b"k", Version::new(1)), Ok(()));
+        assert_eq!(disabled.invalidations(), 0);
+    }
+
+    #[test]
+    fn mode_names_round_trip() {
+        for mode in [InvalidationMode::FailOpen, InvalidationMode::FailClosed] {
+            assert_eq!(InvalidationMode::parse(mode.name()), Some(mode));
+        }
+        assert_eq!(
+            InvalidationMode::parse("FAIL-OPEN"),
+            Some(InvalidationMode::FailOpen)
+        );
+        assert_eq!(InvalidationMode::parse("strict"), None);
+    }
+}
@@ -1,3 +1,9 @@
+pub mod backend;
+pub mod events;
+pub mod hotkeys;
+pub mod invalidation;
 pub mod metrics;
+pub mod promotion;
 pub mod protocol;
+pub mod proxy;
 pub mod server;
@@ -14,9 +14,17 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
 
-use hkv_engine::MemoryEngine;
+use hkv_engine::{
+    AofConfig, EvictionPolicyKind, FsyncPolicy, MemoryEngine, SaveRule, SnapshotConfig,
+};
+use hkv_server::backend::InProcessBackend;
+use hkv_server::events::{self, ChannelTransport};
+use hkv_server::invalidation::{InvalidationMode, Invalidator};
 use hkv_server::metrics::Metrics;
+use hkv_server::promotion::{PromotionConfig, PromotionManager};
+use hkv_server::proxy::Proxy;
 use hkv_server::server;
 
 #[tokio::main]
@@ -24,16 +32,149 @@ async fn main() -> std::io::Result<()> {
     let addr = std::env::var("HKV_ADDR").unwrap_or_else(|_| "127.0.0.1:6379".to_string());
     let listener = TcpListener::bind(&addr).await?;
 
-    let engine = Arc::new(MemoryEngine::new());
+    let engine = Arc::new(open_engine()?);
     let metrics = Arc::new(Metrics::new());
     let _expirer = engine.start_expirer(Duration::from_secs(1));
+    let _snapshotter = engine.start_snapshotter(Duration::from_secs(1));
+    let _aof_syncer = engine.start_aof_syncer(Duration::from_secs(1));
+
+    let cache_tier = open_cache_tier(&metrics)?;
+    let _promoter = cache_tier.as_ref().map(|backend| {
+        PromotionManager::new(
+            Arc::clone(&engine),
+            Arc::clone(&metrics),
+            Arc::clone(backend) as _,
+            PromotionConfig::default(),
+        )
+        .spawn()
+    });
+    let invalidator = Arc::new(open_invalidator(cache_tier.as_ref())?);
+    let _proxy = open_proxy(cache_tier.as_ref(), &listener).await?;
 
     loop {
         let (stream, _) = listener.accept().await?;
         let engine = Arc::clone(&engine);
         let metrics = Arc::clone(&metrics);
+        let invalidator = Arc::clone(&invalidator);
         tokio::spawn(async move {
-            let _ = server::handle_connection_with_metrics(stream, engine, metrics).await;
+            let _ =
+                server::handle_connection_with_invalidator(stream, engine, metrics, invalidator)
+                    .await;
+        });
+    }
+}
+
+/// Default snapshot schedule, matching Redis' stock `save` directive.
+const DEFAULT_SAVE_RULES: &str = "3600 1 300 100 60 10000";
+
+/// Builds the engine and restores persisted state.
+///
+/// `HKV_MAXMEMORY` caps the keyspace in bytes (unlimited when unset) and
+/// `HKV_EVICTION_POLICY` picks the victim policy once it is full: `lru`
+/// (default), `lfu`, `slru`, `2q`, or `fifo`.
+///
+/// `HKV_AOF_PATH` enables the append-only file; `HKV_AOF_FSYNC` accepts
+/// `always`, `everysec` (default), or `no`. `HKV_SNAPSHOT_PATH` enables
+/// snapshots scheduled by `HKV_SAVE` (Redis `save` syntax, empty disables).
+/// When both are enabled the AOF is the startup source, as in Redis.
+fn open_engine() -> std::io::Result<MemoryEngine> {
+    let max_bytes = match std::env::var("HKV_MAXMEMORY") {
+        Ok(value) => value.parse().map_err(|_| invalid_config("HKV_MAXMEMORY"))?,
+        Err(_) => usize::MAX,
+    };
+    let policy = match std::env::var("HKV_EVICTION_POLICY") {
+        Ok(value) => EvictionPolicyKind::parse(&value)
+            .ok_or_else(|| invalid_config("HKV_EVICTION_POLICY"))?,
+        Err(_) => EvictionPolicyKind::default(),
+    };
+    let mut engine = MemoryEngine::with_capacity(max_bytes).with_eviction_policy(policy);
+
+    if let Ok(path) = std::env::var("HKV_SNAPSHOT_PATH") {
+        let spec = std::env::var("HKV_SAVE").unwrap_or_else(|_| DEFAULT_SAVE_RULES.to_string());
+        let save_rules = SaveRule::parse_list(&spec).ok_or_else(|| invalid_config("HKV_SAVE"))?;
+        engine = engine.with_snapshot(SnapshotConfig {
+            path: path.into(),
+            save_rules,
         });
     }
+
+    let path = match std::env::var("HKV_AOF_PATH") {
+        Ok(path) => path,
+        Err(_) => {
+            if std::env::var_os("HKV_SNAPSHOT_PATH").is_some() {
+                engine.load_snapshot()?;
+            }
+            return Ok(engine);
+        }
+    };
+
+    let fsync = std::env::var("HKV_AOF_FSYNC")
+        .ok()
+        .and_then(|value| FsyncPolicy::parse(&value))
+        .unwrap_or(FsyncPolicy::EverySec);
+    engine.with_aof(AofConfig {
+        path: path.into(),
+        fsync,
+    })
+}
+
+/// Cache events buffered between the in-process tier and its consumer.
+const CACHE_EVENT_BACKLOG: usize = 1024;
+
+/// Builds the in-process kernel cache tier for hosts without `/dev/hybridkv`.
+///
+/// `HKV_CACHE_ENTRIES` enables it, holding up to that many promoted keys;
+/// unset leaves the server without a cache tier. The tier's events feed the
+/// kernel key view reported by `INFO`.
+fn open_cache_tier(metrics: &Metrics) -> std::io::Result<Option<Arc<InProcessBackend>>> {
+    match std::env::var("HKV_CACHE_ENTRIES") {
+        Ok(value) => {
+            let entries = value
+                .parse()
+                .map_err(|_| invalid_config("HKV_CACHE_ENTRIES"))?;
+            let (sender, transport) = ChannelTransport::channel(CACHE_EVENT_BACKLOG);
+            events::spawn_consumer(transport, Arc::clone(metrics.kernel_keys()));
+            let backend = InProcessBackend::new(entries).with_events(sender);
+            Ok(Some(Arc::new(backend)))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Builds the write-through invalidator for the cache tier, if there is one.
+///
+/// `HKV_INVALIDATION_MODE` accepts `fail-open` (default) or `fail-closed`.
+fn open_invalidator(cache_tier: Option<&Arc<InProcessBackend>>) -> std::io::Result<Invalidator> {
+    let mode = match std::env::var("HKV_INVALIDATION_MODE") {
+        Ok(value) => InvalidationMode::parse(&value)
+            .ok_or_else(|| invalid_config("HKV_INVALIDATION_MODE"))?,
+        Err(_) => InvalidationMode::default(),
+    };
+    Ok(match cache_tier {
+        Some(backend) => Invalidator::new(Arc::clone(backend) as _, mode),
+        None => Invalidator::disabled(),
+    })
+}
+
+/// Starts the intercept proxy emulator in front of this server, if asked.
+///
+/// `HKV_PROXY_ADDR` enables it: the proxy listens there, answers `GET` hits
+/// and `PING` from the cache tier, and forwards everything else to the
+/// server's own listener. It needs the cache tier (`HKV_CACHE_ENTRIES`).
+async fn open_proxy(
+    cache_tier: Option<&Arc<InProcessBackend>>,
+    listener: &TcpListener,
+) -> std::io::Result<Option<JoinHandle<std::io::Result<()>>>> {
+    let Ok(addr) = std::env::var("HKV_PROXY_ADDR") else {
+        return Ok(None);
+    };
+    let cache = cache_tier.ok_or_else(|| invalid_config("HKV_PROXY_ADDR"))?;
+    let proxy_listener = TcpListener::bind(&addr).await?;
+    let upstream = listener.local_addr()?.to_string();
+    let proxy = Arc::new(Proxy::new(Arc::clone(cache) as _, upstream));
+    Ok(Some(tokio::spawn(proxy.serve(proxy_listener))))
+}
+
+fn invalid_config(name: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid {name}"))
 }
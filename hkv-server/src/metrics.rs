@@ -8,15 +8,23 @@
 //! 2. **Fixed Buckets**: Keep histogram buckets in a contiguous array for cache locality.
 //! 3. **Zero-Cost Access**: Expose snapshots as plain structs without heap work.
 //! 4. **FFI-Free**: Pure Rust types keep the hot path safe and portable.
+//! 5. **Per-Key Heat**: A `HotKeyTracker` rides along so handlers that
+//!    already hold the metrics can report key accesses without extra wiring.
+//! 6. **Kernel View**: The `KernelKeyView` fed by the kernel event consumer is
+//!    shared here so `INFO` can report what the kernel tier caches.
 //!
 //! ## Notes
 //! - Metrics are intentionally decoupled from the request path to keep the
 //!   server fast; wiring and sampling policy are left to the caller.
 //! - Bucket boundaries are expressed in microseconds and can be tuned later.
 
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+use crate::events::KernelKeyView;
+use crate::hotkeys::{HotKeyConfig, HotKeyTracker};
+
 /// Default latency bucket boundaries in microseconds.
 ///
 /// These are coarse on purpose to keep bucket scans short (performance-first).
@@ -63,6 +71,8 @@ pub struct Metrics {
     errors_total: AtomicU64,
     inflight: AtomicU64,
     latency: LatencyHistogram,
+    hot_keys: HotKeyTracker,
+    kernel_keys: Arc<KernelKeyView>,
     started_at: Instant,
 }
 
@@ -74,6 +84,8 @@ impl Metrics {
             errors_total: AtomicU64::new(0),
             inflight: AtomicU64::new(0),
             latency: LatencyHistogram::new(DEFAULT_LATENCY_BUCKETS_US.to_vec()),
+            hot_keys: HotKeyTracker::default(),
+            kernel_keys: Arc::new(KernelKeyView::new()),
             started_at: Instant::now(),
         }
     }
@@ -90,10 +102,30 @@ impl Metrics {
             errors_total: AtomicU64::new(0),
             inflight: AtomicU64::new(0),
             latency: LatencyHistogram::new(bounds_us),
+            hot_keys: HotKeyTracker::default(),
+            kernel_keys: Arc::new(KernelKeyView::new()),
             started_at: Instant::now(),
         }
     }
 
+    /// Replaces the hot-key tracker with one using `config`.
+    pub fn with_hot_key_config(mut self, config: HotKeyConfig) -> Self {
+        self.hot_keys = HotKeyTracker::new(config);
+        self
+    }
+
+    /// Returns the per-key access tracker.
+    pub fn hot_keys(&self) -> &HotKeyTracker {
+        &self.hot_keys
+    }
+
+    /// Returns the live view of keys cached by the kernel tier.
+    ///
+    /// Clone the `Arc` into `events::spawn_consumer` to keep it current.
+    pub fn kernel_keys(&self) -> &Arc<KernelKeyView> {
+        &self.kernel_keys
+    }
+
     /// Records the start of a request.
     ///
     /// Call this when a request is accepted to increment totals and in-flight.
@@ -127,6 +159,12 @@ impl Metrics {
     }
 }
 
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MetricsSnapshot {
     /// Returns the average queries per second since the metrics instance started.
     pub fn qps(&self) -> f64 {
@@ -0,0 +1,487 @@
+//! # Promotion Manager
+//!
+//! Periodically pick read-hot keys from the hot-key tracker and push them into
+//! the kernel cache tier as scatter-list `BatchPromoteRequest` payloads.
+//!
+//! ## Design Principles
+//!
+//! 1. **Read-Mostly Only**: A key qualifies when its decayed access count
+//!    reaches `min_frequency` and its read ratio reaches `min_read_ratio`;
+//!    write-heavy keys would only churn the kernel cache with invalidations.
+//! 2. **Versioned Payloads**: Value, version, and TTL come from one
+//!    `get_with_ttl` read, so the kernel can reject promotions that race with
+//!    newer writes and never pairs a value with another write's expiry.
+//! 3. **No Redundant Work**: Keys already promoted at their current version are
+//!    skipped until they change. The record is pruned to the current hot set
+//!    every round, so it never outgrows the tracker's top-K.
+//! 4. **Backend Agnostic**: Payloads go through `CacheBackend`, so the same
+//!    loop drives the device or `InProcessBackend` in tests.
+//! 5. **Refreshes First**: Keys the tier served stale under async refresh
+//!    are re-read and promoted at the start of each round, hot or not, so a
+//!    stale value is replaced within one interval.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use hkv_common::{
+    BatchPromoteBuilder, DEFAULT_TENANT, HkvError, HkvResult, MAX_BATCH_SIZE, Ttl, Version,
+};
+use hkv_engine::{MemoryEngine, TtlStatus};
+
+use crate::backend::CacheBackend;
+use crate::metrics::Metrics;
+
+/// Thresholds and cadence for the promotion loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromotionConfig {
+    /// Time between promotion rounds.
+    pub interval: Duration,
+    /// Minimum decayed access count for a key to qualify.
+    pub min_frequency: u64,
+    /// Minimum fraction of accesses that must be reads.
+    pub min_read_ratio: f64,
+    /// Maximum entries per round (capped at `MAX_BATCH_SIZE`).
+    pub max_batch: usize,
+}
+
+impl Default for PromotionConfig {
+    fn default() -> Self {
+        PromotionConfig {
+            interval: Duration::from_secs(5),
+            min_frequency: 100,
+            min_read_ratio: 0.9,
+            max_batch: MAX_BATCH_SIZE,
+        }
+    }
+}
+
+/// Outcome of one promotion round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PromotionReport {
+    /// Hot keys that met both thresholds.
+    pub candidates: usize,
+    /// Refresh requests taken from the backend.
+    pub refreshes: usize,
+    /// Entries the backend accepted.
+    pub promoted: usize,
+    /// Entries the backend rejected.
+    pub failed: usize,
+    /// Candidates and refreshes dropped before sending (already cached,
+    /// missing, non-string, past the kernel ABI's `MAX_EXT_VALUE_SIZE`, or
+    /// refreshes for another tenant).
+    pub skipped: usize,
+}
+
+/// Builds and issues batch promotions for the hot set.
+pub struct PromotionManager {
+    engine: Arc<MemoryEngine>,
+    metrics: Arc<Metrics>,
+    backend: Arc<dyn CacheBackend>,
+    config: PromotionConfig,
+    /// Version each hot key was last promoted at.
+    promoted: HashMap<Vec<u8>, Version>,
+}
+
+impl PromotionManager {
+    /// Creates a manager reading heat from `metrics` and values from `engine`.
+    pub fn new(
+        engine: Arc<MemoryEngine>,
+        metrics: Arc<Metrics>,
+        backend: Arc<dyn CacheBackend>,
+        config: PromotionConfig,
+    ) -> Self {
+        PromotionManager {
+            engine,
+            metrics,
+            backend,
+            config,
+            promoted: HashMap::new(),
+        }
+    }
+
+    /// Returns the active configuration.
+    pub fn config(&self) -> &PromotionConfig {
+        &self.config
+    }
+
+    /// Runs a single promotion round.
+    ///
+    /// Pending refreshes are batched ahead of the hot set. Returns without
+    /// calling the backend when nothing qualifies; backend errors are
+    /// returned as-is.
+    pub fn run_once(&mut self) -> HkvResult<PromotionReport> {
+        let mut report = PromotionReport::default();
+        let max_batch = self.config.max_batch.min(MAX_BATCH_SIZE);
+        let mut batch = BatchPromoteBuilder::new();
+        let mut keys = Vec::new();
+
+        let mut refreshed = HashSet::new();
+        for refresh in self.backend.take_refresh_requests() {
+            report.refreshes += 1;
+            // Batches go to the default tenant, the engine's only keyspace.
+            let key = refresh.key.as_bytes().to_vec();
+            if refresh.tenant != DEFAULT_TENANT
+                || batch.len() >= max_batch
+                || refreshed.contains(&key)
+            {
+                report.skipped += 1;
+                continue;
+            }
+            match self.add_entry(&mut batch, &key)? {
+                Some(version) => {
+                    refreshed.insert(key.clone());
+                    keys.push((key, version));
+                }
+                None => report.skipped += 1,
+            }
+        }
+
+        let hot = self.metrics.hot_keys().hot_keys(usize::MAX);
+        let hot_set: HashSet<&[u8]> = hot.iter().map(|hot_key| &hot_key.key[..]).collect();
+        self.promoted.retain(|key, _| hot_set.contains(&key[..]));
+
+        for hot_key in hot {
+            if hot_key.frequency() < self.config.min_frequency
+                || hot_key.read_ratio() < self.config.min_read_ratio
+                || refreshed.contains(&hot_key.key)
+            {
+                continue;
+            }
+            report.candidates += 1;
+            if batch.len() >= max_batch {
+                report.skipped += 1;
+                continue;
+            }
+            match self.add_entry(&mut batch, &hot_key.key)? {
+                Some(version) => keys.push((hot_key.key, version)),
+                None => report.skipped += 1,
+            }
+        }
+
+        if batch.is_empty() {
+            return Ok(report);
+        }
+
+        let (request, mut user) = batch.prepare();
+        self.backend.batch_promote(&request, &mut user)?;
+        for (index, (key, version)) in keys.into_iter().enumerate() {
+            if batch.succeeded(index) {
+                report.promoted += 1;
+                self.promoted.insert(key, version);
+            } else {
+                report.failed += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Starts a background thread running `run_once` every interval.
+    ///
+    /// Round errors are ignored so a flaky backend never stops promotion.
+    pub fn spawn(mut self) -> PromotionHandle {
+        let interval = if self.config.interval.is_zero() {
+            Duration::from_millis(1)
+        } else {
+            self.config.interval
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = Arc::clone(&stop);
+        let join = std::thread::spawn(move || {
+            while !stop_thread.load(Ordering::Acquire) {
+                std::thread::park_timeout(interval);
+                if stop_thread.load(Ordering::Acquire) {
+                    break;
+                }
+                let _ = self.run_once();
+            }
+        });
+
+        PromotionHandle {
+            stop,
+            join: Some(join),
+        }
+    }
+
+    /// Adds `key` to `batch` and returns its version, or `None` when it
+    /// should be skipped.
+    fn add_entry(&self, batch: &mut BatchPromoteBuilder, key: &[u8]) -> HkvResult<Option<Version>> {
+        let (value, version, ttl) = match self.engine.get_with_ttl(key) {
+            Ok(Some(found)) => found,
+            Ok(None) | Err(HkvError::WrongType) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if self.promoted.get(key) == Some(&version) {
+            return Ok(None);
+        }
+        match push_engine_entry(batch, key, &value, version, ttl) {
+            Ok(_) => Ok(Some(version)),
+            Err(HkvError::NotFound | HkvError::KeyTooLong | HkvError::ValueTooLong) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Appends an engine entry to `batch` and returns its index.
+///
+/// Takes the value, version, and `TtlStatus` from `get_with_ttl`, converting the remaining lifetime to the absolute expiry
+/// the kernel ABI carries.
+///
+/// # Errors
+/// Returns `HkvError::NotFound` for `TtlStatus::Missing` (the key expired
+/// after it was read) and the builder's limit errors otherwise.
+pub fn push_engine_entry(
+    batch: &mut BatchPromoteBuilder,
+    key: &[u8],
+    value: &[u8],
+    version: Version,
+    ttl: TtlStatus,
+) -> HkvResult<usize> {
+    let ttl = match ttl {
+        TtlStatus::Missing => return Err(HkvError::NotFound),
+        TtlStatus::NoExpiry => Ttl::INFINITE,
+        TtlStatus::ExpiresIn(remaining) => Ttl::from_duration(remaining),
+    };
+    batch.push(key, value, version, ttl)
+}
+
+/// Handle for the background promotion thread.
+///
+/// Call `stop` to signal shutdown and join the thread.
+pub struct PromotionHandle {
+    stop: Arc<AtomicBool>,
+    join: Option<JoinHandle<()>>,
+}
+
+impl PromotionHandle {
+    /// Stops the thread and waits for it to finish.
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(join) = self.join.take() {
+            join.thread().unpark();
+            let _ = join.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::InProcessBackend;
+    use crate::hotkeys::{Access, HotKeyConfig};
+    use hkv_common::{
+        BatchPromoteMemory, BatchPromoteRequest, InvalidateRequest, Key, MAX_EXT_VALUE_SIZE,
+        RefreshRequest,
+    };
+    use hkv_engine::KVEngine;
+    use std::sync::Mutex;
+
+    fn setup(backend_capacity: usize) -> (Arc<MemoryEngine>, Arc<Metrics>, Arc<InProcessBackend>) {
+        (
+            Arc::new(MemoryEngine::with_shard_count(4)),
+            Arc::new(Metrics::new()),
+            Arc::new(InProcessBackend::new(backend_capacity)),
+        )
+    }
+
+    fn config() -> PromotionConfig {
+        PromotionConfig {
+            min_frequency: 10,
+            ..PromotionConfig::default()
+        }
+    }
+
+    fn touch(metrics: &Metrics, key: &[u8], reads: usize, writes: usize) {
+        for _ in 0..reads {
+            metrics.hot_keys().record(key, Access::Read);
+        }
+        for _ in 0..writes {
+            metrics.hot_keys().record(key, Access::Write);
+        }
+    }
+
+    #[test]
+    fn promotes_only_read_hot_keys() {
+        let (engine, metrics, backend) = setup(16);
+        engine.set(b"hot".to_vec(), b"v".to_vec()).unwrap();
+        engine.set(b"writey".to_vec(), b"v".to_vec()).unwrap();
+        engine.set(b"cold".to_vec(), b"v".to_vec()).unwrap();
+        touch(&metrics, b"hot", 50, 1);
+        touch(&metrics, b"writey", 20, 20);
+        touch(&metrics, b"cold", 2, 0);
+
+        let mut manager =
+            PromotionManager::new(Arc::clone(&engine), metrics, backend.clone(), config());
+        let report = manager.run_once().unwrap();
+        assert_eq!(report.candidates, 1);
+        assert_eq!(report.promoted, 1);
+        let cached = backend.get(b"hot").unwrap();
+        assert_eq!(cached.value, b"v");
+        assert_eq!(
+            Some(cached.version),
+            engine.get_with_version(b"hot").unwrap().map(|(_, v)| v)
+        );
+        assert!(cached.ttl.is_infinite());
+        assert!(backend.get(b"writey").is_none());
+    }
+
+    #[test]
+    fn repromotes_only_after_the_version_changes() {
+        let (engine, metrics, backend) = setup(16);
+        engine.set(b"k".to_vec(), b"one".to_vec()).unwrap();
+        touch(&metrics, b"k", 40, 0);
+        let mut manager =
+            PromotionManager::new(Arc::clone(&engine), metrics, backend.clone(), config());
+
+        assert_eq!(manager.run_once().unwrap().promoted, 1);
+        let report = manager.run_once().unwrap();
+        assert_eq!((report.promoted, report.skipped), (0, 1));
+
+        engine.set(b"k".to_vec(), b"two".to_vec()).unwrap();
+        assert_eq!(manager.run_once().unwrap().promoted, 1);
+        assert_eq!(backend.get(b"k").unwrap().value, b"two");
+    }
+
+    #[test]
+    fn forgets_keys_that_leave_the_hot_set() {
+        let (engine, _, backend) = setup(16);
+        let metrics = Arc::new(Metrics::new().with_hot_key_config(HotKeyConfig {
+            top_k: 1,
+            decay_interval: Duration::ZERO,
+            ..HotKeyConfig::default()
+        }));
+        engine.set(b"a".to_vec(), b"1".to_vec()).unwrap();
+        engine.set(b"b".to_vec(), b"2".to_vec()).unwrap();
+        touch(&metrics, b"a", 20, 0);
+        let mut manager =
+            PromotionManager::new(engine, Arc::clone(&metrics), backend.clone(), config());
+        assert_eq!(manager.run_once().unwrap().promoted, 1);
+        assert!(manager.promoted.contains_key(&b"a"[..]));
+
+        touch(&metrics, b"b", 40, 0);
+        assert_eq!(manager.run_once().unwrap().promoted, 1);
+        assert_eq!(manager.promoted.len(), 1);
+        assert!(manager.promoted.contains_key(&b"b"[..]));
+    }
+
+    #[test]
+    fn skips_missing_and_oversized_keys_and_counts_failures() {
+        let (engine, metrics, backend) = setup(1);
+        engine
+            .set(b"big".to_vec(), vec![0; MAX_EXT_VALUE_SIZE + 1])
+            .unwrap();
+        engine.set(b"a".to_vec(), b"1".to_vec()).unwrap();
+        engine.set(b"b".to_vec(), b"2".to_vec()).unwrap();
+        engine.expire(b"a", Duration::from_secs(60)).unwrap();
+        for key in [&b"gone"[..], b"big", b"a", b"b"] {
+            touch(&metrics, key, 30, 0);
+        }
+
+        let mut manager = PromotionManager::new(engine, metrics, backend.clone(), config());
+        let report = manager.run_once().unwrap();
+        assert_eq!(report.candidates, 4);
+        assert_eq!(report.skipped, 2);
+        assert_eq!((report.promoted, report.failed), (1, 1));
+        assert_eq!(backend.len(), 1);
+        // Equal heat ranks by key, so "a" takes the only slot.
+        let cached = backend.get(b"a").unwrap();
+        assert!(!cached.ttl.is_infinite());
+    }
+
+    /// An in-process tier that also hands out queued refreshes.
+    struct RefreshingBackend {
+        tier: InProcessBackend,
+        refreshes: Mutex<Vec<RefreshRequest>>,
+    }
+
+    impl CacheBackend for RefreshingBackend {
+        fn batch_promote(
+            &self,
+            request: &BatchPromoteRequest,
+            user: &mut BatchPromoteMemory<'_>,
+        ) -> HkvResult<()> {
+            self.tier.batch_promote(request, user)
+        }
+
+        fn invalidate(&self, request: &InvalidateRequest) -> HkvResult<()> {
+            self.tier.invalidate(request)
+        }
+
+        fn take_refresh_requests(&self) -> Vec<RefreshRequest> {
+            std::mem::take(&mut *self.refreshes.lock().unwrap())
+        }
+    }
+
+    #[test]
+    fn refreshes_stale_keys_whether_hot_or_not() {
+        let (engine, metrics, _) = setup(16);
+        engine.set(b"cold".to_vec(), b"new".to_vec()).unwrap();
+        engine.set(b"hot".to_vec(), b"v".to_vec()).unwrap();
+        touch(&metrics, b"hot", 30, 0);
+        let refresh = |tenant, key: &[u8]| RefreshRequest {
+            tenant,
+            key: Key::new(key).unwrap(),
+            version: Version::new(1),
+        };
+        let backend = Arc::new(RefreshingBackend {
+            tier: InProcessBackend::new(16),
+            refreshes: Mutex::new(vec![
+                refresh(DEFAULT_TENANT, b"cold"),
+                refresh(DEFAULT_TENANT, b"hot"),
+                refresh(3, b"cold"),
+            ]),
+        });
+
+        let mut manager = PromotionManager::new(engine, metrics, backend.clone(), config());
+        let report = manager.run_once().unwrap();
+        assert_eq!((report.refreshes, report.skipped), (3, 1));
+        // "hot" is batched once, as a refresh, not again as a candidate.
+        assert_eq!((report.candidates, report.promoted), (0, 2));
+        assert_eq!(backend.tier.get(b"cold").unwrap().value, b"new");
+        assert!(backend.tier.get(b"hot").is_some());
+        assert_eq!(manager.run_once().unwrap().refreshes, 0);
+    }
+
+    #[test]
+    fn promotes_values_past_the_inline_buffer() {
+        let (engine, metrics, backend) = setup(4);
+        let blob = vec![7u8; 12 * 1024];
+        engine.set(b"session".to_vec(), blob.clone()).unwrap();
+        touch(&metrics, b"session", 30, 0);
+
+        let mut manager = PromotionManager::new(engine, metrics, backend.clone(), config());
+        assert_eq!(manager.run_once().unwrap().promoted, 1);
+        assert_eq!(backend.get(b"session").unwrap().value, blob);
+    }
+
+    #[test]
+    fn engine_ttls_become_absolute_expiries() {
+        let mut batch = BatchPromoteBuilder::new();
+        let version = Version::new(4);
+        assert_eq!(
+            push_engine_entry(&mut batch, b"a", b"1", version, TtlStatus::NoExpiry),
+            Ok(0)
+        );
+        let remaining = Duration::from_secs(30);
+        push_engine_entry(
+            &mut batch,
+            b"b",
+            b"2",
+            version,
+            TtlStatus::ExpiresIn(remaining),
+        )
+        .unwrap();
+        assert_eq!(
+            push_engine_entry(&mut batch, b"c", b"3", version, TtlStatus::Missing),
+            Err(HkvError::NotFound)
+        );
+
+        assert!(batch.descriptor(0).unwrap().ttl.is_infinite());
+        let expiry = batch.descriptor(1).unwrap().ttl;
+        assert!(!expiry.is_infinite() && expiry >= Ttl::from_duration(Duration::ZERO));
+        assert_eq!(batch.len(), 2);
+    }
+}
@@ -10,6 +10,9 @@
 //!    returns `None` when more data is needed.
 //! 3. **Low Allocation**: Only bulk string arguments are copied into `Vec<u8>`.
 //! 4. **Fail Fast**: Malformed frames return a protocol error immediately.
+//! 5. **Replies Are Measured, Not Built**: `ReplyScanner` only finds where a
+//!    server reply ends, so a proxy can relay it without re-encoding. It
+//!    resumes where the last read stopped instead of rescanning the reply.
 
 use bytes::{Buf, BytesMut};
 
@@ -107,6 +110,91 @@ impl RespParser {
     }
 }
 
+impl Default for RespParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the length of the first complete RESP2 reply in `buf`.
+///
+/// Accepts every RESP2 type, including nested arrays and null bulk strings
+/// and arrays. Returns `Ok(None)` if more data is required.
+pub fn reply_len(buf: &[u8]) -> Result<Option<usize>, RespError> {
+    ReplyScanner::new().scan(buf)
+}
+
+/// Incremental form of `reply_len` for replies arriving over several reads.
+///
+/// The scanner remembers the elements it has already measured, so each
+/// `scan` only looks at bytes past them. Pass the same growing buffer until
+/// a length comes back; the scanner then resets for the next reply.
+#[derive(Debug, Clone)]
+pub struct ReplyScanner {
+    /// End of the last fully measured element.
+    pos: usize,
+    /// Elements still to measure before the reply is complete.
+    pending: usize,
+}
+
+impl ReplyScanner {
+    /// Creates a scanner positioned at the start of a reply.
+    pub fn new() -> Self {
+        ReplyScanner { pos: 0, pending: 1 }
+    }
+
+    /// Returns the length of the reply at the start of `buf` once complete.
+    ///
+    /// `buf` must start with the same bytes as on the previous call.
+    pub fn scan(&mut self, buf: &[u8]) -> Result<Option<usize>, RespError> {
+        while self.pending > 0 {
+            let Some(end) = find_crlf(buf, self.pos) else {
+                return Ok(None);
+            };
+            let (kind, body) = match buf[self.pos..end].split_first() {
+                Some((kind, body)) => (*kind, body),
+                None => return Err(RespError::Protocol),
+            };
+            let mut next = end + 2;
+            let mut children = 0;
+            match kind {
+                b'+' | b'-' | b':' => {}
+                b'$' | b'*' if body == b"-1" => {}
+                b'$' => {
+                    let data_end = next.saturating_add(parse_usize(body)?);
+                    if buf.len() < data_end.saturating_add(2) {
+                        return Ok(None);
+                    }
+                    if &buf[data_end..data_end + 2] != b"\r\n" {
+                        return Err(RespError::Protocol);
+                    }
+                    next = data_end + 2;
+                }
+                b'*' => children = parse_usize(body)?,
+                _ => return Err(RespError::Protocol),
+            }
+            self.pos = next;
+            self.pending = (self.pending - 1).saturating_add(children);
+        }
+        let len = self.pos;
+        *self = ReplyScanner::new();
+        Ok(Some(len))
+    }
+}
+
+impl Default for ReplyScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn find_crlf(buf: &[u8], from: usize) -> Option<usize> {
+    buf.get(from..)?
+        .windows(2)
+        .position(|pair| pair == b"\r\n")
+        .map(|offset| from + offset)
+}
+
 fn read_line(buf: &mut BytesMut) -> Option<BytesMut> {
     let mut idx = 1;
     while idx < buf.len() {
@@ -126,7 +214,7 @@ fn parse_usize(data: &[u8]) -> Result<usize, RespError> {
     }
     let mut value: usize = 0;
     for &b in data {
-        if b < b'0' || b > b'9' {
+        if !b.is_ascii_digit() {
             return Err(RespError::Protocol);
         }
         value = value.saturating_mul(10).saturating_add((b - b'0') as usize);
@@ -157,4 +245,40 @@ mod tests {
         let cmd = parser.parse(&mut buf).unwrap().unwrap();
         assert_eq!(cmd[0], b"PING");
     }
+
+    #[test]
+    fn measures_every_reply_type() {
+        let replies: [&[u8]; 7] = [
+            b"+OK\r\n",
+            b"-ERR nope\r\n",
+            b":42\r\n",
+            b"$-1\r\n",
+            b"$5\r\nhe\r\no\r\n",
+            b"*-1\r\n",
+            b"*2\r\n*1\r\n:1\r\n$0\r\n\r\n",
+        ];
+        for reply in replies {
+            let mut buf = reply.to_vec();
+            buf.extend_from_slice(b"+NEXT\r\n");
+            assert_eq!(reply_len(&buf), Ok(Some(reply.len())));
+            for cut in 0..reply.len() {
+                assert_eq!(reply_len(&reply[..cut]), Ok(None));
+            }
+        }
+        assert_eq!(reply_len(b"?\r\n"), Err(RespError::Protocol));
+        assert_eq!(reply_len(b"$1\r\nab\r\n"), Err(RespError::Protocol));
+    }
+
+    #[test]
+    fn scanner_resumes_across_reads() {
+        let reply = b"*3\r\n$5\r\nhello\r\n*1\r\n:1\r\n+OK\r\n";
+        let mut scanner = ReplyScanner::new();
+        for cut in 0..reply.len() {
+            assert_eq!(scanner.scan(&reply[..cut]), Ok(None));
+        }
+        assert_eq!(scanner.scan(reply), Ok(Some(reply.len())));
+
+        // A finished scan starts over for the next reply.
+        assert_eq!(scanner.scan(b":7\r\n"), Ok(Some(4)));
+    }
 }
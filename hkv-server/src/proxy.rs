@@ -0,0 +1,434 @@
+//! # Intercept Proxy Emulator
+//!
+//! A user-space stand-in for the kernel intercept proxy: listen where
+//! clients expect Redis, answer `GET` hits and `PING` from the cache tier,
+//! and forward everything else to `hkv-server`.
+//!
+//! ## Design Principles
+//!
+//! 1. **Byte-for-Byte Forwarding**: Forwarded commands are relayed as the
+//!    exact bytes the client sent, and server replies are relayed unparsed,
+//!    so the proxy never changes what the server sees or answers.
+//! 2. **Order Preserved**: Replies go back in command order. Consecutive
+//!    forwarded commands are pipelined to the server as one write; a local
+//!    answer waits until every earlier forward has been answered, so a
+//!    pipelined `SET` then `GET` reads the write's invalidation.
+//! 3. **One Upstream per Client**: Each client connection owns its own
+//!    server connection, opened on first forward, so replies never mix.
+//! 4. **Observable**: Hits, misses, pings, forwards, and upstream failures
+//!    are counted like the in-kernel version would report them.
+//! 5. **Errors Are Answered**: A server that cannot be reached answers each
+//!    forward with `-ERR`; one lost mid-reply answers the command it broke
+//!    with `-ERR` and closes the client, whose later replies are unknown.
+
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bytes::BytesMut;
+use hkv_common::Ttl;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::backend::InProcessBackend;
+use crate::protocol::{ReplyScanner, RespError, RespParser};
+
+/// Address the proxy listens on by default (the Redis port).
+pub const DEFAULT_PROXY_ADDR: &str = "127.0.0.1:6379";
+
+/// Address of the user-space server behind the proxy by default.
+pub const DEFAULT_UPSTREAM_ADDR: &str = "127.0.0.1:16379";
+
+/// Cache tier the proxy answers `GET` from.
+pub trait ProxyCache: Send + Sync {
+    /// Returns the fresh cached value of `key`, or `None` to forward.
+    fn lookup(&self, key: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl ProxyCache for InProcessBackend {
+    fn lookup(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let now = Ttl::from_duration(Duration::ZERO).as_nanos();
+        self.get(key)
+            .filter(|entry| !entry.ttl.is_expired(now))
+            .map(|entry| entry.value)
+    }
+}
+
+/// Point-in-time proxy counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProxyStatsSnapshot {
+    /// `GET`s answered from the cache.
+    pub hits: u64,
+    /// `GET`s the cache could not answer (also counted in `forwarded`).
+    pub misses: u64,
+    /// `PING`s answered locally.
+    pub pings: u64,
+    /// Commands relayed to the server.
+    pub forwarded: u64,
+    /// Forwarded commands answered with an error because the server was
+    /// unreachable.
+    pub upstream_errors: u64,
+}
+
+impl ProxyStatsSnapshot {
+    /// Returns the share of `GET`s answered from the cache.
+    pub fn hit_ratio(&self) -> f64 {
+        let gets = self.hits + self.misses;
+        if gets == 0 {
+            0.0
+        } else {
+            self.hits as f64 / gets as f64
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProxyStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    pings: AtomicU64,
+    forwarded: AtomicU64,
+    upstream_errors: AtomicU64,
+}
+
+/// RESP proxy answering `GET`/`PING` locally and forwarding the rest.
+pub struct Proxy {
+    cache: Arc<dyn ProxyCache>,
+    upstream: String,
+    stats: ProxyStats,
+}
+
+impl Proxy {
+    /// Creates a proxy serving hits from `cache` and forwarding to
+    /// `upstream` (`host:port`).
+    pub fn new(cache: Arc<dyn ProxyCache>, upstream: impl Into<String>) -> Self {
+        Proxy {
+            cache,
+            upstream: upstream.into(),
+            stats: ProxyStats::default(),
+        }
+    }
+
+    /// Returns the server address commands are forwarded to.
+    pub fn upstream(&self) -> &str {
+        &self.upstream
+    }
+
+    /// Returns a snapshot of the hit and forward counters.
+    pub fn stats(&self) -> ProxyStatsSnapshot {
+        ProxyStatsSnapshot {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            pings: self.stats.pings.load(Ordering::Relaxed),
+            forwarded: self.stats.forwarded.load(Ordering::Relaxed),
+            upstream_errors: self.stats.upstream_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Accepts clients from `listener` until it fails, one task each.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let proxy = Arc::clone(&self);
+            tokio::spawn(async move {
+                let _ = proxy.handle_connection(stream).await;
+            });
+        }
+    }
+
+    /// Serves one client connection until it closes.
+    ///
+    /// # Errors
+    /// Returns client I/O errors and server I/O errors after connecting; a
+    /// server that cannot be reached is reported to the client per command,
+    /// and one that fails mid-reply is reported before the client is closed.
+    pub async fn handle_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut frames = ClientFrames::new();
+        let mut upstream = Upstream::new(&self.upstream);
+
+        loop {
+            if !frames.read_from(&mut stream).await? {
+                break;
+            }
+            let mut batch = Batch::default();
+            let answered = self.answer(&mut frames, &mut batch, &mut upstream).await;
+            stream.write_all(&batch.out).await?;
+            if !answered? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Routes every complete command in `frames` and relays the forwards.
+    ///
+    /// Returns `Ok(false)` once malformed input has been answered and the
+    /// client should be closed; replies gathered before an error stay in
+    /// `batch.out` either way.
+    async fn answer(
+        &self,
+        frames: &mut ClientFrames,
+        batch: &mut Batch,
+        upstream: &mut Upstream<'_>,
+    ) -> io::Result<bool> {
+        loop {
+            match frames.next_command() {
+                Ok(Some((args, frame))) => self.route(&args, frame, batch, upstream).await?,
+                Ok(None) => break,
+                Err(RespError::Protocol) => {
+                    self.flush(batch, upstream).await?;
+                    batch.out.extend_from_slice(b"-ERR protocol error\r\n");
+                    return Ok(false);
+                }
+            }
+        }
+        self.flush(batch, upstream).await?;
+        Ok(true)
+    }
+
+    /// Answers one command locally or queues it for the server.
+    async fn route(
+        &self,
+        args: &[Vec<u8>],
+        frame: BytesMut,
+        batch: &mut Batch,
+        upstream: &mut Upstream<'_>,
+    ) -> io::Result<()> {
+        let command = args.first().map(Vec::as_slice).unwrap_or_default();
+        if command.eq_ignore_ascii_case(b"PING") && args.len() <= 2 {
+            self.flush(batch, upstream).await?;
+            self.stats.pings.fetch_add(1, Ordering::Relaxed);
+            match args.get(1) {
+                Some(message) => push_bulk(&mut batch.out, message),
+                None => batch.out.extend_from_slice(b"+PONG\r\n"),
+            }
+            return Ok(());
+        }
+        if command.eq_ignore_ascii_case(b"GET") && args.len() == 2 {
+            // Earlier forwards may be writes to this key; let them land
+            // (and invalidate the cache) before looking it up.
+            self.flush(batch, upstream).await?;
+            if let Some(value) = self.cache.lookup(&args[1]) {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                push_bulk(&mut batch.out, &value);
+                return Ok(());
+            }
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        batch.forwards.push(frame);
+        Ok(())
+    }
+
+    /// Relays the queued forwards and appends their replies in order.
+    async fn flush(&self, batch: &mut Batch, upstream: &mut Upstream<'_>) -> io::Result<()> {
+        if batch.forwards.is_empty() {
+            return Ok(());
+        }
+        let count = batch.forwards.len() as u64;
+        self.stats.forwarded.fetch_add(count, Ordering::Relaxed);
+        let frames = std::mem::take(&mut batch.forwards);
+        match upstream.exchange(&frames, &mut batch.out).await {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                self.stats
+                    .upstream_errors
+                    .fetch_add(count, Ordering::Relaxed);
+                for _ in 0..count {
+                    batch
+                        .out
+                        .extend_from_slice(b"-ERR hybridkv upstream unavailable\r\n");
+                }
+                Ok(())
+            }
+            Err(err) => {
+                self.stats.upstream_errors.fetch_add(1, Ordering::Relaxed);
+                batch
+                    .out
+                    .extend_from_slice(b"-ERR hybridkv upstream connection lost\r\n");
+                Err(err)
+            }
+        }
+    }
+}
+
+/// A command's arguments and the exact bytes it arrived as.
+type Command = (Vec<Vec<u8>>, BytesMut);
+
+/// Client bytes split into complete commands.
+struct ClientFrames {
+    /// Bytes read but not yet consumed by the parser.
+    buffer: BytesMut,
+    /// Every byte the client sent that has not been attributed to a
+    /// finished command yet; the parser only hands back arguments.
+    raw: BytesMut,
+    /// Bytes of `raw` the parser has consumed toward the next command.
+    consumed: usize,
+    parser: RespParser,
+}
+
+impl ClientFrames {
+    fn new() -> Self {
+        ClientFrames {
+            buffer: BytesMut::with_capacity(8 * 1024),
+            raw: BytesMut::new(),
+            consumed: 0,
+            parser: RespParser::new(),
+        }
+    }
+
+    /// Reads more client bytes; returns `Ok(false)` once the client closed.
+    async fn read_from(&mut self, stream: &mut TcpStream) -> io::Result<bool> {
+        let start = self.buffer.len();
+        if stream.read_buf(&mut self.buffer).await? == 0 {
+            return Ok(false);
+        }
+        self.raw.extend_from_slice(&self.buffer[start..]);
+        Ok(true)
+    }
+
+    /// Returns the next complete command with the exact bytes it arrived as.
+    fn next_command(&mut self) -> Result<Option<Command>, RespError> {
+        let before = self.buffer.len();
+        let parsed = self.parser.parse(&mut self.buffer);
+        self.consumed += before - self.buffer.len();
+        let Some(args) = parsed? else {
+            return Ok(None);
+        };
+        let frame = self.raw.split_to(self.consumed);
+        self.consumed = 0;
+        Ok(Some((args, frame)))
+    }
+}
+
+/// Replies and pending forwards for one read from the client.
+#[derive(Default)]
+struct Batch {
+    out: Vec<u8>,
+    forwards: Vec<BytesMut>,
+}
+
+/// A client's connection to the server, opened on first use.
+struct Upstream<'a> {
+    addr: &'a str,
+    stream: Option<TcpStream>,
+    buffer: BytesMut,
+}
+
+impl<'a> Upstream<'a> {
+    fn new(addr: &'a str) -> Self {
+        Upstream {
+            addr,
+            stream: None,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Sends `frames` as one write and appends exactly one reply per frame
+    /// to `out`. Returns `Ok(false)` when the server cannot be reached.
+    async fn exchange(&mut self, frames: &[BytesMut], out: &mut Vec<u8>) -> io::Result<bool> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => match TcpStream::connect(self.addr).await {
+                Ok(stream) => self.stream.insert(stream),
+                Err(_) => return Ok(false),
+            },
+        };
+        let result = relay(stream, &mut self.buffer, frames, out).await;
+        if result.is_err() {
+            // Replies may be half-read; never reuse the connection.
+            self.stream = None;
+            self.buffer.clear();
+        }
+        result.map(|()| true)
+    }
+}
+
+async fn relay(
+    stream: &mut TcpStream,
+    buffer: &mut BytesMut,
+    frames: &[BytesMut],
+    out: &mut Vec<u8>,
+) -> io::Result<()> {
+    stream.write_all(&frames.concat()).await?;
+    let mut scanner = ReplyScanner::new();
+    for _ in frames {
+        loop {
+            match scanner.scan(buffer) {
+                Ok(Some(len)) => {
+                    out.extend_from_slice(&buffer.split_to(len));
+                    break;
+                }
+                Ok(None) => {
+                    if stream.read_buf(buffer).await? == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                }
+                Err(RespError::Protocol) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "malformed reply from upstream",
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_bulk(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl ProxyCache for Fixed {
+        fn lookup(&self, key: &[u8]) -> Option<Vec<u8>> {
+            (key == b"hot").then(|| b"cached".to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_upstream_answers_each_forward_with_an_error() {
+        // Bind then drop a listener so the port is closed.
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = closed.local_addr().unwrap().to_string();
+        drop(closed);
+
+        let proxy = Proxy::new(Arc::new(Fixed), addr);
+        let mut upstream = Upstream::new(proxy.upstream());
+        let mut batch = Batch::default();
+        for (args, frame) in [
+            (vec![b"SET".to_vec()], &b"*1\r\n$3\r\nSET\r\n"[..]),
+            (vec![b"GET".to_vec(), b"hot".to_vec()], &b"-"[..]),
+            (vec![b"DEL".to_vec()], &b"*1\r\n$3\r\nDEL\r\n"[..]),
+        ] {
+            proxy
+                .route(&args, BytesMut::from(frame), &mut batch, &mut upstream)
+                .await
+                .unwrap();
+        }
+        proxy.flush(&mut batch, &mut upstream).await.unwrap();
+
+        assert_eq!(
+            batch.out,
+            b"-ERR hybridkv upstream unavailable\r\n$6\r\ncached\r\n-ERR hybridkv upstream unavailable\r\n"
+        );
+        assert_eq!(
+            proxy.stats(),
+            ProxyStatsSnapshot {
+                hits: 1,
+                forwarded: 2,
+                upstream_errors: 2,
+                ..ProxyStatsSnapshot::default()
+            }
+        );
+    }
+}
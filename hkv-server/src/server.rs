@@ -10,11 +10,20 @@ use bytes::BytesMut;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
-use hkv_engine::{KVEngine, MemoryEngine, TtlStatus};
+use hkv_common::{CacheEventKind, HkvError, Version};
+use hkv_engine::{CasOutcome, KVEngine, MemoryEngine, TtlStatus};
 
+use crate::events::KernelKeyView;
+use crate::hotkeys::{Access, HotKeyTracker};
+use crate::invalidation::Invalidator;
 use crate::metrics::Metrics;
 use crate::protocol::{RespError, RespParser};
 
+mod hash;
+mod list;
+mod set;
+mod zset;
+
 /// Handles a single TCP client connection.
 pub async fn handle_connection(
     stream: TcpStream,
@@ -28,6 +37,18 @@ pub async fn handle_connection_with_metrics(
     stream: TcpStream,
     engine: Arc<MemoryEngine>,
     metrics: Arc<Metrics>,
+) -> std::io::Result<()> {
+    handle_connection_with_invalidator(stream, engine, metrics, Arc::new(Invalidator::disabled()))
+        .await
+}
+
+/// Handles a single TCP client connection, invalidating the kernel cache
+/// tier after every string write, delete, expiry change, and `*STORE`.
+pub async fn handle_connection_with_invalidator(
+    stream: TcpStream,
+    engine: Arc<MemoryEngine>,
+    metrics: Arc<Metrics>,
+    invalidator: Arc<Invalidator>,
 ) -> std::io::Result<()> {
     let mut stream = stream;
     let mut buffer = BytesMut::with_capacity(8 * 1024);
@@ -44,7 +65,11 @@ pub async fn handle_connection_with_metrics(
                 Ok(Some(args)) => {
                     metrics.record_request_start();
                     let started_at = Instant::now();
-                    let response = dispatch_command(&args, engine.as_ref(), metrics.as_ref());
+                    let response = if is_command(&args, b"SAVE") {
+                        handle_save(&args, &engine).await
+                    } else {
+                        dispatch_command(&args, &engine, metrics.as_ref(), invalidator.as_ref())
+                    };
                     if is_error_response(&response) {
                         metrics.record_error();
                     }
@@ -68,7 +93,12 @@ pub async fn handle_connection_with_metrics(
     Ok(())
 }
 
-fn dispatch_command(args: &[Vec<u8>], engine: &MemoryEngine, metrics: &Metrics) -> Vec<u8> {
+fn dispatch_command(
+    args: &[Vec<u8>],
+    engine: &Arc<MemoryEngine>,
+    metrics: &Metrics,
+    invalidator: &Invalidator,
+) -> Vec<u8> {
     if args.is_empty() {
         return resp_error("empty command");
     }
@@ -78,22 +108,147 @@ fn dispatch_command(args: &[Vec<u8>], engine: &MemoryEngine, metrics: &Metrics)
         return handle_ping(args);
     }
     if eq_ignore_ascii_case(cmd, b"GET") {
-        return handle_get(args, engine);
+        return handle_get(args, engine, metrics.hot_keys());
     }
     if eq_ignore_ascii_case(cmd, b"SET") {
-        return handle_set(args, engine);
+        return handle_set(args, engine, metrics.hot_keys(), invalidator);
     }
     if eq_ignore_ascii_case(cmd, b"DEL") {
-        return handle_del(args, engine);
+        return handle_del(args, engine, invalidator);
     }
     if eq_ignore_ascii_case(cmd, b"EXPIRE") {
-        return handle_expire(args, engine);
+        return handle_expire(args, engine, invalidator);
     }
     if eq_ignore_ascii_case(cmd, b"TTL") {
         return handle_ttl(args, engine);
     }
+    if eq_ignore_ascii_case(cmd, b"TYPE") {
+        return handle_type(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"GETVER") {
+        return handle_getver(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"CAS") {
+        return handle_cas(args, engine, invalidator);
+    }
+    if eq_ignore_ascii_case(cmd, b"LPUSH") {
+        return list::handle_push(args, engine, true);
+    }
+    if eq_ignore_ascii_case(cmd, b"RPUSH") {
+        return list::handle_push(args, engine, false);
+    }
+    if eq_ignore_ascii_case(cmd, b"LPOP") {
+        return list::handle_pop(args, engine, true);
+    }
+    if eq_ignore_ascii_case(cmd, b"RPOP") {
+        return list::handle_pop(args, engine, false);
+    }
+    if eq_ignore_ascii_case(cmd, b"LRANGE") {
+        return list::handle_lrange(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"LLEN") {
+        return list::handle_llen(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"LINDEX") {
+        return list::handle_lindex(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"LTRIM") {
+        return list::handle_ltrim(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HSET") {
+        return hash::handle_hset(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HGET") {
+        return hash::handle_hget(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HDEL") {
+        return hash::handle_hdel(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HGETALL") {
+        return hash::handle_hgetall(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HINCRBY") {
+        return hash::handle_hincrby(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"HSCAN") {
+        return hash::handle_hscan(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SADD") {
+        return set::handle_sadd(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SREM") {
+        return set::handle_srem(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SMEMBERS") {
+        return set::handle_smembers(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SISMEMBER") {
+        return set::handle_sismember(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SCARD") {
+        return set::handle_scard(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"SINTER") {
+        return set::handle_set_op(args, engine, set::SetOp::Inter);
+    }
+    if eq_ignore_ascii_case(cmd, b"SUNION") {
+        return set::handle_set_op(args, engine, set::SetOp::Union);
+    }
+    if eq_ignore_ascii_case(cmd, b"SDIFF") {
+        return set::handle_set_op(args, engine, set::SetOp::Diff);
+    }
+    if eq_ignore_ascii_case(cmd, b"SINTERSTORE") {
+        return set::handle_set_op_store(args, engine, invalidator, set::SetOp::Inter);
+    }
+    if eq_ignore_ascii_case(cmd, b"SUNIONSTORE") {
+        return set::handle_set_op_store(args, engine, invalidator, set::SetOp::Union);
+    }
+    if eq_ignore_ascii_case(cmd, b"SDIFFSTORE") {
+        return set::handle_set_op_store(args, engine, invalidator, set::SetOp::Diff);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZADD") {
+        return zset::handle_zadd(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZREM") {
+        return zset::handle_zrem(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZSCORE") {
+        return zset::handle_zscore(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZRANK") {
+        return zset::handle_zrank(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZCARD") {
+        return zset::handle_zcard(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZRANGE") {
+        return zset::handle_zrange(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZRANGEBYSCORE") {
+        return zset::handle_zrangebyscore(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZINCRBY") {
+        return zset::handle_zincrby(args, engine);
+    }
+    if eq_ignore_ascii_case(cmd, b"ZPOPMIN") {
+        return zset::handle_zpopmin(args, engine);
+    }
     if eq_ignore_ascii_case(cmd, b"INFO") {
-        return handle_info(metrics);
+        return handle_info(metrics, invalidator);
+    }
+    if eq_ignore_ascii_case(cmd, b"HOTKEYS") {
+        return handle_hotkeys(args, metrics.hot_keys());
+    }
+    if eq_ignore_ascii_case(cmd, b"BGREWRITEAOF") {
+        return handle_bgrewriteaof(args, engine);
+    }
+
+    if eq_ignore_ascii_case(cmd, b"BGSAVE") {
+        return handle_bgsave(args, engine);
+    }
+
+    if eq_ignore_ascii_case(cmd, b"LASTSAVE") {
+        return handle_lastsave(args, engine);
     }
 
     resp_error("unknown command")
@@ -107,73 +262,121 @@ fn handle_ping(args: &[Vec<u8>]) -> Vec<u8> {
     }
 }
 
-fn handle_get(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+fn handle_get(args: &[Vec<u8>], engine: &MemoryEngine, hot_keys: &HotKeyTracker) -> Vec<u8> {
     if args.len() != 2 {
         return resp_error("wrong number of arguments for GET");
     }
+    hot_keys.record(&args[1], Access::Read);
     match engine.get(&args[1]) {
         Ok(Some(value)) => resp_bulk(&value),
         Ok(None) => resp_null(),
-        Err(_) => resp_error("engine error"),
+        Err(err) => resp_engine_error(err),
     }
 }
 
-fn handle_set(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+/// `GETVER key`: replies `[value, version]`, or a null array when missing.
+fn handle_getver(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for GETVER");
+    }
+    match engine.get_with_version(&args[1]) {
+        Ok(Some((value, version))) => {
+            let mut buf = b"*2\r\n".to_vec();
+            buf.extend_from_slice(&resp_bulk(&value));
+            buf.extend_from_slice(&resp_integer(version.get() as i64));
+            buf
+        }
+        Ok(None) => resp_null_array(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+/// `CAS key expected_version value`: replies the new version, or a null
+/// bulk string when the key's version no longer matches (like an aborted
+/// `EXEC`). Version `0` means "only if the key does not exist".
+fn handle_cas(args: &[Vec<u8>], engine: &MemoryEngine, invalidator: &Invalidator) -> Vec<u8> {
+    if args.len() != 4 {
+        return resp_error("wrong number of arguments for CAS");
+    }
+    let expected = match parse_u64(&args[2]) {
+        Ok(value) => Version::new(value),
+        Err(resp) => return resp,
+    };
+
+    match engine.cas(args[1].clone(), expected, args[3].clone()) {
+        Ok(CasOutcome::Stored(version)) => match invalidator.invalidate(&args[1], version) {
+            Ok(()) => resp_integer(version.get() as i64),
+            Err(_) => resp_invalidation_failed(),
+        },
+        Ok(CasOutcome::Mismatch { .. }) => resp_null(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+fn handle_set(
+    args: &[Vec<u8>],
+    engine: &MemoryEngine,
+    hot_keys: &HotKeyTracker,
+    invalidator: &Invalidator,
+) -> Vec<u8> {
     if args.len() < 3 {
         return resp_error("wrong number of arguments for SET");
     }
+    hot_keys.record(&args[1], Access::Write);
 
     let key = args[1].clone();
     let value = args[2].clone();
 
-    if args.len() == 3 {
-        if engine.set(key, value).is_ok() {
-            return resp_simple("OK");
+    let version = if args.len() == 3 {
+        match engine.set_versioned(key, value) {
+            Ok(version) => version,
+            Err(err) => return resp_engine_error(err),
         }
-        return resp_error("engine error");
-    }
-
-    if args.len() == 5 && eq_ignore_ascii_case(&args[3], b"EX") {
+    } else if args.len() == 5 && eq_ignore_ascii_case(&args[3], b"EX") {
         let seconds = match parse_u64(&args[4]) {
             Ok(value) => value,
             Err(resp) => return resp,
         };
 
-        if engine.set(key, value).is_err() {
-            return resp_error("engine error");
-        }
-
-        if engine
-            .expire(&args[1], Duration::from_secs(seconds))
-            .is_err()
-        {
-            return resp_error("engine error");
+        match engine.set_ex_versioned(key, value, Duration::from_secs(seconds)) {
+            Ok(version) => version,
+            Err(err) => return resp_engine_error(err),
         }
+    } else {
+        return resp_error("unsupported SET options");
+    };
 
-        return resp_simple("OK");
+    match invalidator.invalidate(&args[1], version) {
+        Ok(()) => resp_simple("OK"),
+        Err(_) => resp_invalidation_failed(),
     }
-
-    resp_error("unsupported SET options")
 }
 
-fn handle_del(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+fn handle_del(args: &[Vec<u8>], engine: &MemoryEngine, invalidator: &Invalidator) -> Vec<u8> {
     if args.len() < 2 {
         return resp_error("wrong number of arguments for DEL");
     }
 
     let mut removed = 0i64;
+    let mut invalidated = true;
     for key in &args[1..] {
-        match engine.delete(key) {
-            Ok(true) => removed += 1,
-            Ok(false) => {}
+        match engine.delete_versioned(key) {
+            Ok(Some(version)) => {
+                removed += 1;
+                invalidated &= invalidator.invalidate(key, version).is_ok();
+            }
+            Ok(None) => {}
             Err(_) => return resp_error("engine error"),
         }
     }
 
+    if !invalidated {
+        return resp_invalidation_failed();
+    }
     resp_integer(removed)
 }
 
-fn handle_expire(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+fn handle_expire(args: &[Vec<u8>], engine: &MemoryEngine, invalidator: &Invalidator) -> Vec<u8> {
     if args.len() != 3 {
         return resp_error("wrong number of arguments for EXPIRE");
     }
@@ -183,9 +386,12 @@ fn handle_expire(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
         Err(resp) => return resp,
     };
 
-    match engine.expire(&args[1], Duration::from_secs(seconds)) {
-        Ok(()) => resp_integer(1),
-        Err(err) if err == hkv_common::HkvError::NotFound => resp_integer(0),
+    match engine.expire_versioned(&args[1], Duration::from_secs(seconds)) {
+        Ok(version) => match invalidator.invalidate(&args[1], version) {
+            Ok(()) => resp_integer(1),
+            Err(_) => resp_invalidation_failed(),
+        },
+        Err(HkvError::NotFound) => resp_integer(0),
         Err(_) => resp_error("engine error"),
     }
 }
@@ -203,14 +409,108 @@ fn handle_ttl(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
     }
 }
 
-fn handle_info(metrics: &Metrics) -> Vec<u8> {
+fn handle_type(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for TYPE");
+    }
+
+    match engine.key_type(&args[1]) {
+        Some(value_type) => resp_simple(value_type.as_str()),
+        None => resp_simple("none"),
+    }
+}
+
+fn handle_bgrewriteaof(args: &[Vec<u8>], engine: &Arc<MemoryEngine>) -> Vec<u8> {
+    if args.len() != 1 {
+        return resp_error("wrong number of arguments for BGREWRITEAOF");
+    }
+
+    match engine.spawn_aof_rewrite() {
+        Ok(()) => resp_simple("Background append only file rewriting started"),
+        Err(HkvError::InvalidInput) => resp_error("append only file is disabled"),
+        Err(HkvError::Busy) => {
+            resp_error("Background append only file rewriting already in progress")
+        }
+        Err(_) => resp_error("engine error"),
+    }
+}
+
+/// Returns true when `args` names `command` (case-insensitive).
+fn is_command(args: &[Vec<u8>], command: &[u8]) -> bool {
+    args.first()
+        .is_some_and(|cmd| eq_ignore_ascii_case(cmd, command))
+}
+
+/// Runs `SAVE` on the blocking pool; the write and fsync would otherwise
+/// stall every connection sharing this worker thread.
+async fn handle_save(args: &[Vec<u8>], engine: &Arc<MemoryEngine>) -> Vec<u8> {
+    if args.len() != 1 {
+        return resp_error("wrong number of arguments for SAVE");
+    }
+    if engine.snapshot_in_progress() {
+        return resp_error("Background save already in progress");
+    }
+
+    let engine = Arc::clone(engine);
+    match tokio::task::spawn_blocking(move || engine.save_snapshot()).await {
+        Ok(Ok(())) => resp_simple("OK"),
+        Ok(Err(err)) => resp_error(&err.to_string()),
+        Err(_) => resp_error("engine error"),
+    }
+}
+
+fn handle_bgsave(args: &[Vec<u8>], engine: &Arc<MemoryEngine>) -> Vec<u8> {
+    if args.len() != 1 {
+        return resp_error("wrong number of arguments for BGSAVE");
+    }
+
+    match engine.spawn_snapshot() {
+        Ok(()) => resp_simple("Background saving started"),
+        Err(HkvError::InvalidInput) => resp_error("snapshots are not configured"),
+        Err(HkvError::Busy) => resp_error("Background save already in progress"),
+        Err(_) => resp_error("engine error"),
+    }
+}
+
+fn handle_lastsave(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 1 {
+        return resp_error("wrong number of arguments for LASTSAVE");
+    }
+    resp_integer(engine.last_save() as i64)
+}
+
+/// `HOTKEYS [count]`: replies `[key, frequency, reads, writes]` per hot key,
+/// hottest first.
+fn handle_hotkeys(args: &[Vec<u8>], hot_keys: &HotKeyTracker) -> Vec<u8> {
+    let limit = match args.len() {
+        1 => usize::MAX,
+        2 => match parse_u64(&args[1]) {
+            Ok(value) => value as usize,
+            Err(resp) => return resp,
+        },
+        _ => return resp_error("wrong number of arguments for HOTKEYS"),
+    };
+
+    let hot = hot_keys.hot_keys(limit);
+    let mut buf = format!("*{}\r\n", hot.len()).into_bytes();
+    for entry in &hot {
+        buf.extend_from_slice(b"*4\r\n");
+        buf.extend_from_slice(&resp_bulk(&entry.key));
+        buf.extend_from_slice(&resp_integer(entry.frequency() as i64));
+        buf.extend_from_slice(&resp_integer(entry.reads as i64));
+        buf.extend_from_slice(&resp_integer(entry.writes as i64));
+    }
+    buf
+}
+
+fn handle_info(metrics: &Metrics, invalidator: &Invalidator) -> Vec<u8> {
     let snapshot = metrics.snapshot();
     let average_us = snapshot.latency.average_us().unwrap_or(0.0);
     let p50_us = snapshot.latency.percentile_us(50.0).unwrap_or(0);
     let p90_us = snapshot.latency.percentile_us(90.0).unwrap_or(0);
     let p99_us = snapshot.latency.percentile_us(99.0).unwrap_or(0);
     let p999_us = snapshot.latency.percentile_us(99.9).unwrap_or(0);
-    let info = format!(
+    let mut info = format!(
         concat!(
             "role:master\r\n",
             "engine:hybridkv\r\n",
@@ -242,9 +542,102 @@ fn handle_info(metrics: &Metrics) -> Vec<u8> {
         p99_us,
         p999_us,
     );
+    append_hotkeys_info(&mut info, metrics.hot_keys());
+    append_kernel_info(&mut info, invalidator, metrics.kernel_keys());
     resp_bulk(info.as_bytes())
 }
 
+/// Kernel-cached keys listed in the INFO section.
+const INFO_KERNEL_KEYS: usize = 10;
+
+/// Appends the `# Kernel` INFO section (write-through invalidation and the
+/// event-fed view of cached keys).
+fn append_kernel_info(info: &mut String, invalidator: &Invalidator, kernel_keys: &KernelKeyView) {
+    use std::fmt::Write;
+
+    let _ = write!(
+        info,
+        concat!(
+            "\r\n# Kernel\r\n",
+            "kernel_invalidation_enabled:{}\r\n",
+            "kernel_invalidation_mode:{}\r\n",
+            "kernel_invalidations:{}\r\n",
+            "kernel_invalidation_failures:{}\r\n"
+        ),
+        u8::from(invalidator.is_enabled()),
+        invalidator.mode().name(),
+        invalidator.invalidations(),
+        invalidator.failures(),
+    );
+    let _ = write!(info, "kernel_cached_keys:{}\r\n", kernel_keys.len());
+    for kind in CacheEventKind::ALL {
+        let _ = write!(
+            info,
+            "kernel_events_{}:{}\r\n",
+            kind.name(),
+            kernel_keys.events(*kind)
+        );
+    }
+    let _ = write!(
+        info,
+        "kernel_event_errors:{}\r\n",
+        kernel_keys.decode_errors()
+    );
+    for (index, cached) in kernel_keys.cached_keys(INFO_KERNEL_KEYS).iter().enumerate() {
+        let _ = write!(
+            info,
+            "kernel_cached_key_{index}:tenant={},key={},version={}\r\n",
+            cached.tenant,
+            info_key(&cached.key),
+            cached.version.0,
+        );
+    }
+}
+
+/// Hot keys listed in the INFO section; `HOTKEYS` returns the full set.
+const INFO_HOTKEYS: usize = 10;
+
+/// Appends the `# Hotkeys` INFO section.
+fn append_hotkeys_info(info: &mut String, hot_keys: &HotKeyTracker) {
+    use std::fmt::Write;
+
+    let _ = write!(
+        info,
+        "\r\n# Hotkeys\r\nhotkeys_tracked:{}\r\n",
+        hot_keys.tracked()
+    );
+    for (rank, entry) in hot_keys.hot_keys(INFO_HOTKEYS).iter().enumerate() {
+        let _ = write!(
+            info,
+            "hotkey_{rank}:key={},freq={},reads={},writes={},read_ratio={:.3}\r\n",
+            info_key(&entry.key),
+            entry.frequency(),
+            entry.reads,
+            entry.writes,
+            entry.read_ratio(),
+        );
+    }
+}
+
+/// Renders key bytes for an INFO line.
+///
+/// Printable ASCII passes through. Bytes that would break the line or its
+/// `field=value,` layout (CR, LF, space, `:`, `,`, `=`, `\`, non-ASCII)
+/// become `\xNN`.
+fn info_key(key: &[u8]) -> String {
+    use std::fmt::Write;
+
+    let mut rendered = String::with_capacity(key.len());
+    for &byte in key {
+        if byte.is_ascii_graphic() && !matches!(byte, b':' | b',' | b'=' | b'\\') {
+            rendered.push(byte as char);
+        } else {
+            let _ = write!(rendered, "\\x{byte:02x}");
+        }
+    }
+    rendered
+}
+
 fn resp_simple(message: &str) -> Vec<u8> {
     let mut buf = Vec::with_capacity(message.len() + 3);
     buf.extend_from_slice(b"+");
@@ -283,15 +676,46 @@ fn resp_null() -> Vec<u8> {
     b"$-1\r\n".to_vec()
 }
 
+fn resp_null_array() -> Vec<u8> {
+    b"*-1\r\n".to_vec()
+}
+
+fn resp_array(items: &[Arc<[u8]>]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"*");
+    buf.extend_from_slice(items.len().to_string().as_bytes());
+    buf.extend_from_slice(b"\r\n");
+    for item in items {
+        buf.extend_from_slice(&resp_bulk(item));
+    }
+    buf
+}
+
+/// Maps an engine error to a RESP error, keeping Redis' `WRONGTYPE` prefix.
+fn resp_engine_error(err: HkvError) -> Vec<u8> {
+    match err {
+        HkvError::WrongType => {
+            b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec()
+        }
+        HkvError::CapacityExceeded => {
+            b"-OOM command not allowed when used memory > 'maxmemory'\r\n".to_vec()
+        }
+        _ => resp_error("engine error"),
+    }
+}
+
+/// Reply for a write that applied but whose kernel invalidation failed in
+/// fail-closed mode.
+fn resp_invalidation_failed() -> Vec<u8> {
+    resp_error("kernel cache invalidation failed")
+}
+
 fn is_error_response(response: &[u8]) -> bool {
     response.first() == Some(&b'-')
 }
 
 fn eq_ignore_ascii_case(a: &[u8], b: &[u8]) -> bool {
-    a.len() == b.len()
-        && a.iter()
-            .zip(b)
-            .all(|(x, y)| x.to_ascii_lowercase() == y.to_ascii_lowercase())
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.eq_ignore_ascii_case(y))
 }
 
 fn parse_u64(arg: &[u8]) -> Result<u64, Vec<u8>> {
@@ -300,10 +724,17 @@ fn parse_u64(arg: &[u8]) -> Result<u64, Vec<u8>> {
     }
     let mut value: u64 = 0;
     for &b in arg {
-        if b < b'0' || b > b'9' {
+        if !b.is_ascii_digit() {
             return Err(resp_error("invalid integer"));
         }
         value = value.saturating_mul(10).saturating_add((b - b'0') as u64);
     }
     Ok(value)
 }
+
+fn parse_i64(arg: &[u8]) -> Result<i64, Vec<u8>> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|text| text.parse::<i64>().ok())
+        .ok_or_else(|| resp_error("invalid integer"))
+}
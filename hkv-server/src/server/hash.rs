@@ -0,0 +1,127 @@
+//! # Hash Command Handlers
+//!
+//! RESP handlers for the Redis hash family, backed by `HashEngine`.
+
+use std::sync::Arc;
+
+use hkv_common::HkvError;
+use hkv_engine::{FieldValue, HashEngine, MemoryEngine};
+
+use super::{
+    eq_ignore_ascii_case, parse_i64, parse_u64, resp_array, resp_bulk, resp_engine_error,
+    resp_error, resp_integer, resp_null,
+};
+
+/// Default `HSCAN` batch size, matching Redis.
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+pub(super) fn handle_hset(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 4 || !args.len().is_multiple_of(2) {
+        return resp_error("wrong number of arguments for HSET");
+    }
+
+    let pairs = args[2..]
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+    match engine.hset(&args[1], pairs) {
+        Ok(added) => resp_integer(added as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_hget(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 3 {
+        return resp_error("wrong number of arguments for HGET");
+    }
+
+    match engine.hget(&args[1], &args[2]) {
+        Ok(Some(value)) => resp_bulk(&value),
+        Ok(None) => resp_null(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_hdel(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 3 {
+        return resp_error("wrong number of arguments for HDEL");
+    }
+
+    match engine.hdel(&args[1], &args[2..]) {
+        Ok(removed) => resp_integer(removed as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_hgetall(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for HGETALL");
+    }
+
+    match engine.hgetall(&args[1]) {
+        Ok(pairs) => resp_array(&flatten(pairs)),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_hincrby(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 4 {
+        return resp_error("wrong number of arguments for HINCRBY");
+    }
+    let delta = match parse_i64(&args[3]) {
+        Ok(value) => value,
+        Err(resp) => return resp,
+    };
+
+    match engine.hincrby(&args[1], &args[2], delta) {
+        Ok(value) => resp_integer(value),
+        Err(HkvError::InvalidInput) => {
+            resp_error("hash value is not an integer or increment would overflow")
+        }
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_hscan(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 3 || args.len().is_multiple_of(2) {
+        return resp_error("wrong number of arguments for HSCAN");
+    }
+    let cursor = match parse_u64(&args[2]) {
+        Ok(value) => value,
+        Err(resp) => return resp,
+    };
+
+    let mut pattern = None;
+    let mut count = DEFAULT_SCAN_COUNT;
+    for option in args[3..].chunks(2) {
+        if eq_ignore_ascii_case(&option[0], b"MATCH") {
+            pattern = Some(option[1].as_slice());
+        } else if eq_ignore_ascii_case(&option[0], b"COUNT") {
+            count = match parse_u64(&option[1]) {
+                Ok(0) => return resp_error("syntax error"),
+                Ok(value) => value as usize,
+                Err(resp) => return resp,
+            };
+        } else {
+            return resp_error("syntax error");
+        }
+    }
+
+    match engine.hscan(&args[1], cursor, pattern, count) {
+        Ok((next, pairs)) => {
+            let mut buf = b"*2\r\n".to_vec();
+            buf.extend_from_slice(&resp_bulk(next.to_string().as_bytes()));
+            buf.extend_from_slice(&resp_array(&flatten(pairs)));
+            buf
+        }
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+/// Flattens pairs into the `field value field value ...` reply layout.
+fn flatten(pairs: Vec<FieldValue>) -> Vec<Arc<[u8]>> {
+    pairs
+        .into_iter()
+        .flat_map(|(field, value)| [field, value])
+        .collect()
+}
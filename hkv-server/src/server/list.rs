@@ -0,0 +1,117 @@
+//! # List Command Handlers
+//!
+//! RESP handlers for the Redis list family, backed by `ListEngine`.
+
+use hkv_engine::{ListEngine, MemoryEngine};
+
+use super::{
+    parse_i64, parse_u64, resp_array, resp_bulk, resp_engine_error, resp_error, resp_integer,
+    resp_null, resp_null_array, resp_simple,
+};
+
+pub(super) fn handle_push(args: &[Vec<u8>], engine: &MemoryEngine, head: bool) -> Vec<u8> {
+    if args.len() < 3 {
+        let name = if head { "LPUSH" } else { "RPUSH" };
+        return resp_error(&format!("wrong number of arguments for {name}"));
+    }
+
+    let values = args[2..].to_vec();
+    let result = if head {
+        engine.lpush(&args[1], values)
+    } else {
+        engine.rpush(&args[1], values)
+    };
+    match result {
+        Ok(len) => resp_integer(len as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_pop(args: &[Vec<u8>], engine: &MemoryEngine, head: bool) -> Vec<u8> {
+    if args.len() != 2 && args.len() != 3 {
+        let name = if head { "LPOP" } else { "RPOP" };
+        return resp_error(&format!("wrong number of arguments for {name}"));
+    }
+
+    let count = match args.get(2) {
+        Some(arg) => match parse_u64(arg) {
+            Ok(value) => Some(value as usize),
+            Err(resp) => return resp,
+        },
+        None => None,
+    };
+
+    let result = if head {
+        engine.lpop(&args[1], count.unwrap_or(1))
+    } else {
+        engine.rpop(&args[1], count.unwrap_or(1))
+    };
+    match (result, count) {
+        // Without a count the reply is a single bulk string, as in Redis.
+        (Ok(Some(items)), None) => match items.first() {
+            Some(item) => resp_bulk(item),
+            None => resp_null(),
+        },
+        (Ok(None), None) => resp_null(),
+        (Ok(Some(items)), Some(_)) => resp_array(&items),
+        (Ok(None), Some(_)) => resp_null_array(),
+        (Err(err), _) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_lrange(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 4 {
+        return resp_error("wrong number of arguments for LRANGE");
+    }
+    let (start, stop) = match (parse_i64(&args[2]), parse_i64(&args[3])) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        (Err(resp), _) | (_, Err(resp)) => return resp,
+    };
+
+    match engine.lrange(&args[1], start, stop) {
+        Ok(items) => resp_array(&items),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_llen(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for LLEN");
+    }
+
+    match engine.llen(&args[1]) {
+        Ok(len) => resp_integer(len as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_lindex(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 3 {
+        return resp_error("wrong number of arguments for LINDEX");
+    }
+    let index = match parse_i64(&args[2]) {
+        Ok(value) => value,
+        Err(resp) => return resp,
+    };
+
+    match engine.lindex(&args[1], index) {
+        Ok(Some(item)) => resp_bulk(&item),
+        Ok(None) => resp_null(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_ltrim(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 4 {
+        return resp_error("wrong number of arguments for LTRIM");
+    }
+    let (start, stop) = match (parse_i64(&args[2]), parse_i64(&args[3])) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        (Err(resp), _) | (_, Err(resp)) => return resp,
+    };
+
+    match engine.ltrim(&args[1], start, stop) {
+        Ok(()) => resp_simple("OK"),
+        Err(err) => resp_engine_error(err),
+    }
+}
@@ -0,0 +1,128 @@
+//! # Set Command Handlers
+//!
+//! RESP handlers for the Redis set family, backed by `SetEngine`.
+
+use hkv_engine::{MemoryEngine, SetEngine};
+
+use super::{resp_array, resp_engine_error, resp_error, resp_integer, resp_invalidation_failed};
+use crate::invalidation::Invalidator;
+
+/// Multi-key set operation selected by the command name.
+#[derive(Clone, Copy)]
+pub(super) enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl SetOp {
+    fn name(self) -> &'static str {
+        match self {
+            SetOp::Inter => "SINTER",
+            SetOp::Union => "SUNION",
+            SetOp::Diff => "SDIFF",
+        }
+    }
+}
+
+pub(super) fn handle_sadd(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 3 {
+        return resp_error("wrong number of arguments for SADD");
+    }
+
+    match engine.sadd(&args[1], args[2..].to_vec()) {
+        Ok(added) => resp_integer(added as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_srem(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 3 {
+        return resp_error("wrong number of arguments for SREM");
+    }
+
+    match engine.srem(&args[1], &args[2..]) {
+        Ok(removed) => resp_integer(removed as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_smembers(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for SMEMBERS");
+    }
+
+    match engine.smembers(&args[1]) {
+        Ok(members) => resp_array(&members),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_sismember(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 3 {
+        return resp_error("wrong number of arguments for SISMEMBER");
+    }
+
+    match engine.sismember(&args[1], &args[2]) {
+        Ok(found) => resp_integer(found as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_scard(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for SCARD");
+    }
+
+    match engine.scard(&args[1]) {
+        Ok(len) => resp_integer(len as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+/// Handles `SINTER`, `SUNION`, and `SDIFF`.
+pub(super) fn handle_set_op(args: &[Vec<u8>], engine: &MemoryEngine, op: SetOp) -> Vec<u8> {
+    if args.len() < 2 {
+        return resp_error(&format!("wrong number of arguments for {}", op.name()));
+    }
+
+    let keys = &args[1..];
+    let result = match op {
+        SetOp::Inter => engine.sinter(keys),
+        SetOp::Union => engine.sunion(keys),
+        SetOp::Diff => engine.sdiff(keys),
+    };
+    match result {
+        Ok(members) => resp_array(&members),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+/// Handles `SINTERSTORE`, `SUNIONSTORE`, and `SDIFFSTORE`.
+///
+/// The destination is overwritten whatever its type, so a string cached in
+/// the kernel tier is invalidated with the version the store produced.
+pub(super) fn handle_set_op_store(
+    args: &[Vec<u8>],
+    engine: &MemoryEngine,
+    invalidator: &Invalidator,
+    op: SetOp,
+) -> Vec<u8> {
+    if args.len() < 3 {
+        return resp_error(&format!("wrong number of arguments for {}STORE", op.name()));
+    }
+
+    let (dest, keys) = (&args[1], &args[2..]);
+    let result = match op {
+        SetOp::Inter => engine.sinterstore_versioned(dest, keys),
+        SetOp::Union => engine.sunionstore_versioned(dest, keys),
+        SetOp::Diff => engine.sdiffstore_versioned(dest, keys),
+    };
+    match result {
+        Ok((len, version)) => match invalidator.invalidate(dest, version) {
+            Ok(()) => resp_integer(len as i64),
+            Err(_) => resp_invalidation_failed(),
+        },
+        Err(err) => resp_engine_error(err),
+    }
+}
@@ -0,0 +1,209 @@
+//! # Sorted Set Command Handlers
+//!
+//! RESP handlers for the Redis sorted set family, backed by `ZSetEngine`.
+//! Scores are replied as bulk strings using the shortest representation
+//! that round-trips, with `inf`/`-inf` for infinities.
+
+use std::sync::Arc;
+
+use hkv_common::HkvError;
+use hkv_engine::{MemoryEngine, ScoreBound, ScoredMember, ZSetEngine};
+
+use super::{
+    eq_ignore_ascii_case, parse_i64, parse_u64, resp_array, resp_bulk, resp_engine_error,
+    resp_error, resp_integer, resp_null,
+};
+
+pub(super) fn handle_zadd(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 4 || !args.len().is_multiple_of(2) {
+        return resp_error("wrong number of arguments for ZADD");
+    }
+
+    let mut entries = Vec::with_capacity(args.len() / 2 - 1);
+    for pair in args[2..].chunks(2) {
+        match parse_score(&pair[0]) {
+            Ok(score) => entries.push((score, pair[1].clone())),
+            Err(resp) => return resp,
+        }
+    }
+    match engine.zadd(&args[1], entries) {
+        Ok(added) => resp_integer(added as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zrem(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 3 {
+        return resp_error("wrong number of arguments for ZREM");
+    }
+
+    match engine.zrem(&args[1], &args[2..]) {
+        Ok(removed) => resp_integer(removed as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zscore(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 3 {
+        return resp_error("wrong number of arguments for ZSCORE");
+    }
+
+    match engine.zscore(&args[1], &args[2]) {
+        Ok(Some(score)) => resp_bulk(score.to_string().as_bytes()),
+        Ok(None) => resp_null(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zrank(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 3 {
+        return resp_error("wrong number of arguments for ZRANK");
+    }
+
+    match engine.zrank(&args[1], &args[2]) {
+        Ok(Some(rank)) => resp_integer(rank as i64),
+        Ok(None) => resp_null(),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zcard(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 {
+        return resp_error("wrong number of arguments for ZCARD");
+    }
+
+    match engine.zcard(&args[1]) {
+        Ok(len) => resp_integer(len as i64),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zrange(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 4 && args.len() != 5 {
+        return resp_error("wrong number of arguments for ZRANGE");
+    }
+    let (start, stop) = match (parse_i64(&args[2]), parse_i64(&args[3])) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        (Err(resp), _) | (_, Err(resp)) => return resp,
+    };
+    let with_scores = match args.get(4) {
+        Some(option) if eq_ignore_ascii_case(option, b"WITHSCORES") => true,
+        Some(_) => return resp_error("syntax error"),
+        None => false,
+    };
+
+    match engine.zrange(&args[1], start, stop) {
+        Ok(items) => resp_scored(items, with_scores),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zrangebyscore(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() < 4 {
+        return resp_error("wrong number of arguments for ZRANGEBYSCORE");
+    }
+    let (min, max) = match (parse_bound(&args[2]), parse_bound(&args[3])) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return resp_error("min or max is not a float"),
+    };
+
+    let mut with_scores = false;
+    let mut limit = None;
+    let mut idx = 4;
+    while idx < args.len() {
+        if eq_ignore_ascii_case(&args[idx], b"WITHSCORES") {
+            with_scores = true;
+            idx += 1;
+        } else if eq_ignore_ascii_case(&args[idx], b"LIMIT") && idx + 2 < args.len() {
+            let offset = match parse_u64(&args[idx + 1]) {
+                Ok(value) => value as usize,
+                Err(resp) => return resp,
+            };
+            // A negative count means "no limit", as in Redis.
+            let count = match parse_i64(&args[idx + 2]) {
+                Ok(value) if value < 0 => usize::MAX,
+                Ok(value) => value as usize,
+                Err(resp) => return resp,
+            };
+            limit = Some((offset, count));
+            idx += 3;
+        } else {
+            return resp_error("syntax error");
+        }
+    }
+
+    match engine.zrangebyscore(&args[1], min, max, limit) {
+        Ok(items) => resp_scored(items, with_scores),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zincrby(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 4 {
+        return resp_error("wrong number of arguments for ZINCRBY");
+    }
+    let delta = match parse_score(&args[2]) {
+        Ok(value) => value,
+        Err(resp) => return resp,
+    };
+
+    match engine.zincrby(&args[1], delta, &args[3]) {
+        Ok(score) => resp_bulk(score.to_string().as_bytes()),
+        Err(HkvError::InvalidInput) => resp_error("resulting score is not a number (NaN)"),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+pub(super) fn handle_zpopmin(args: &[Vec<u8>], engine: &MemoryEngine) -> Vec<u8> {
+    if args.len() != 2 && args.len() != 3 {
+        return resp_error("wrong number of arguments for ZPOPMIN");
+    }
+    let count = match args.get(2) {
+        Some(arg) => match parse_u64(arg) {
+            Ok(value) => value as usize,
+            Err(resp) => return resp,
+        },
+        None => 1,
+    };
+
+    match engine.zpopmin(&args[1], count) {
+        Ok(items) => resp_scored(items, true),
+        Err(err) => resp_engine_error(err),
+    }
+}
+
+/// Parses a score, accepting `inf`/`-inf`/`+inf` and rejecting NaN.
+fn parse_score(arg: &[u8]) -> Result<f64, Vec<u8>> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|text| text.parse::<f64>().ok())
+        .filter(|score| !score.is_nan())
+        .ok_or_else(|| resp_error("value is not a valid float"))
+}
+
+/// Parses a `ZRANGEBYSCORE` endpoint; a leading `(` makes it exclusive.
+fn parse_bound(arg: &[u8]) -> Option<ScoreBound> {
+    let (exclusive, number) = match arg.split_first() {
+        Some((b'(', rest)) => (true, rest),
+        _ => (false, arg),
+    };
+    let score = parse_score(number).ok()?;
+    Some(if exclusive {
+        ScoreBound::Exclusive(score)
+    } else {
+        ScoreBound::Inclusive(score)
+    })
+}
+
+/// Encodes members, interleaving scores when `with_scores` is set.
+fn resp_scored(items: Vec<ScoredMember>, with_scores: bool) -> Vec<u8> {
+    let flat: Vec<Arc<[u8]>> = if with_scores {
+        items
+            .into_iter()
+            .flat_map(|(member, score)| [member, Arc::from(score.to_string().into_bytes())])
+            .collect()
+    } else {
+        items.into_iter().map(|(member, _)| member).collect()
+    };
+    resp_array(&flat)
+}
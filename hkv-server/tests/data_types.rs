@@ -0,0 +1,251 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream as StdTcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hkv_engine::MemoryEngine;
+use hkv_server::server;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+async fn spawn_test_server() -> std::io::Result<(SocketAddr, oneshot::Sender<()>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let engine = Arc::new(MemoryEngine::new());
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        let mut shutdown_rx = shutdown_rx;
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_rx => break,
+                accept = listener.accept() => {
+                    let (stream, _) = match accept {
+                        Ok(value) => value,
+                        Err(_) => break,
+                    };
+                    let engine = Arc::clone(&engine);
+                    tokio::spawn(async move {
+                        let _ = server::handle_connection(stream, engine).await;
+                    });
+                }
+            }
+        }
+    });
+
+    Ok((addr, shutdown_tx))
+}
+
+/// Encodes a command as a RESP array of bulk strings.
+fn command(args: &[&str]) -> Vec<u8> {
+    let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buf.extend_from_slice(format!("${}\r\n{arg}\r\n", arg.len()).as_bytes());
+    }
+    buf
+}
+
+fn send_raw(addr: SocketAddr, request: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+fn send(addr: SocketAddr, args: &[&str]) -> String {
+    let response = send_raw(addr, &command(args)).unwrap();
+    String::from_utf8(response).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn list_commands_follow_redis_replies() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+
+    assert_eq!(send(addr, &["RPUSH", "queue", "b", "c", "d"]), ":3\r\n");
+    assert_eq!(send(addr, &["LPUSH", "queue", "a"]), ":4\r\n");
+    assert_eq!(send(addr, &["LLEN", "queue"]), ":4\r\n");
+    assert_eq!(
+        send(addr, &["LRANGE", "queue", "0", "-1"]),
+        "*4\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\nd\r\n"
+    );
+    assert_eq!(send(addr, &["LINDEX", "queue", "-1"]), "$1\r\nd\r\n");
+    assert_eq!(send(addr, &["LINDEX", "queue", "10"]), "$-1\r\n");
+    assert_eq!(send(addr, &["LPOP", "queue"]), "$1\r\na\r\n");
+    assert_eq!(
+        send(addr, &["RPOP", "queue", "2"]),
+        "*2\r\n$1\r\nd\r\n$1\r\nc\r\n"
+    );
+    assert_eq!(send(addr, &["LTRIM", "queue", "1", "0"]), "+OK\r\n");
+    assert_eq!(send(addr, &["TYPE", "queue"]), "+none\r\n");
+    assert_eq!(send(addr, &["LPOP", "queue"]), "$-1\r\n");
+    assert_eq!(send(addr, &["LPOP", "queue", "2"]), "*-1\r\n");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wrong_type_errors_between_strings_and_lists() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+    let wrongtype = "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+    assert_eq!(send(addr, &["SET", "text", "v"]), "+OK\r\n");
+    assert_eq!(send(addr, &["LPUSH", "text", "a"]), wrongtype);
+    assert_eq!(send(addr, &["RPUSH", "queue", "a"]), ":1\r\n");
+    assert_eq!(send(addr, &["GET", "queue"]), wrongtype);
+    assert_eq!(send(addr, &["TYPE", "queue"]), "+list\r\n");
+    assert_eq!(send(addr, &["TYPE", "text"]), "+string\r\n");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn hash_commands_follow_redis_replies() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+
+    assert_eq!(
+        send(addr, &["HSET", "user:1", "name", "ann", "visits", "1"]),
+        ":2\r\n"
+    );
+    assert_eq!(send(addr, &["HSET", "user:1", "name", "bea"]), ":0\r\n");
+    assert_eq!(send(addr, &["HGET", "user:1", "name"]), "$3\r\nbea\r\n");
+    assert_eq!(send(addr, &["HGET", "user:1", "missing"]), "$-1\r\n");
+    assert_eq!(
+        send(addr, &["HINCRBY", "user:1", "visits", "41"]),
+        ":42\r\n"
+    );
+    assert!(send(addr, &["HINCRBY", "user:1", "name", "1"]).starts_with("-ERR "));
+    assert_eq!(send(addr, &["TYPE", "user:1"]), "+hash\r\n");
+
+    let all = send(addr, &["HGETALL", "user:1"]);
+    assert!(all.starts_with("*4\r\n"));
+    assert!(all.contains("$6\r\nvisits\r\n$2\r\n42\r\n"));
+
+    let scan = send(
+        addr,
+        &["HSCAN", "user:1", "0", "MATCH", "vis*", "COUNT", "100"],
+    );
+    assert_eq!(scan, "*2\r\n$1\r\n0\r\n*2\r\n$6\r\nvisits\r\n$2\r\n42\r\n");
+
+    assert_eq!(send(addr, &["HDEL", "user:1", "name", "visits"]), ":2\r\n");
+    assert_eq!(send(addr, &["HGETALL", "user:1"]), "*0\r\n");
+    assert_eq!(send(addr, &["TYPE", "user:1"]), "+none\r\n");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_commands_follow_redis_replies() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+
+    assert_eq!(send(addr, &["SADD", "a", "1", "2", "3", "2"]), ":3\r\n");
+    assert_eq!(send(addr, &["SADD", "b", "3", "4"]), ":2\r\n");
+    assert_eq!(send(addr, &["SISMEMBER", "a", "2"]), ":1\r\n");
+    assert_eq!(send(addr, &["SISMEMBER", "a", "9"]), ":0\r\n");
+    assert_eq!(send(addr, &["SCARD", "a"]), ":3\r\n");
+    assert_eq!(send(addr, &["TYPE", "a"]), "+set\r\n");
+
+    assert_eq!(send(addr, &["SINTER", "a", "b"]), "*1\r\n$1\r\n3\r\n");
+    assert!(send(addr, &["SUNION", "a", "b"]).starts_with("*4\r\n"));
+    assert!(send(addr, &["SDIFF", "a", "b"]).starts_with("*2\r\n"));
+    assert_eq!(send(addr, &["SINTER", "a", "missing"]), "*0\r\n");
+
+    assert_eq!(send(addr, &["SET", "dest", "text"]), "+OK\r\n");
+    assert_eq!(send(addr, &["SUNIONSTORE", "dest", "a", "b"]), ":4\r\n");
+    assert_eq!(send(addr, &["TYPE", "dest"]), "+set\r\n");
+    assert_eq!(send(addr, &["SDIFFSTORE", "dest", "b", "a"]), ":1\r\n");
+    assert_eq!(send(addr, &["SMEMBERS", "dest"]), "*1\r\n$1\r\n4\r\n");
+    assert_eq!(send(addr, &["SINTERSTORE", "dest", "dest", "a"]), ":0\r\n");
+    assert_eq!(send(addr, &["TYPE", "dest"]), "+none\r\n");
+
+    assert_eq!(send(addr, &["SET", "text", "v"]), "+OK\r\n");
+    assert!(send(addr, &["SUNION", "a", "text"]).starts_with("-WRONGTYPE"));
+    assert!(send(addr, &["SINTER"]).starts_with("-ERR "));
+
+    assert_eq!(send(addr, &["SREM", "a", "1", "2", "3"]), ":3\r\n");
+    assert_eq!(send(addr, &["TYPE", "a"]), "+none\r\n");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sorted_set_commands_follow_redis_replies() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+
+    assert_eq!(
+        send(
+            addr,
+            &["ZADD", "board", "30", "carl", "10", "ann", "20", "bob"]
+        ),
+        ":3\r\n"
+    );
+    assert_eq!(send(addr, &["ZADD", "board", "5", "carl"]), ":0\r\n");
+    assert_eq!(send(addr, &["TYPE", "board"]), "+zset\r\n");
+    assert_eq!(send(addr, &["ZCARD", "board"]), ":3\r\n");
+    assert_eq!(send(addr, &["ZSCORE", "board", "ann"]), "$2\r\n10\r\n");
+    assert_eq!(send(addr, &["ZSCORE", "board", "nobody"]), "$-1\r\n");
+    assert_eq!(send(addr, &["ZRANK", "board", "bob"]), ":2\r\n");
+    assert_eq!(send(addr, &["ZRANK", "board", "nobody"]), "$-1\r\n");
+
+    assert_eq!(
+        send(addr, &["ZRANGE", "board", "0", "1"]),
+        "*2\r\n$4\r\ncarl\r\n$3\r\nann\r\n"
+    );
+    assert_eq!(
+        send(addr, &["ZRANGE", "board", "-1", "-1", "WITHSCORES"]),
+        "*2\r\n$3\r\nbob\r\n$2\r\n20\r\n"
+    );
+    assert_eq!(
+        send(
+            addr,
+            &["ZRANGEBYSCORE", "board", "(5", "+inf", "LIMIT", "1", "1"]
+        ),
+        "*1\r\n$3\r\nbob\r\n"
+    );
+    assert!(send(addr, &["ZRANGEBYSCORE", "board", "x", "1"]).starts_with("-ERR "));
+
+    assert_eq!(
+        send(addr, &["ZINCRBY", "board", "1.5", "ann"]),
+        "$4\r\n11.5\r\n"
+    );
+    assert!(send(addr, &["ZADD", "board", "nan", "x"]).starts_with("-ERR "));
+    assert_eq!(
+        send(addr, &["ZPOPMIN", "board"]),
+        "*2\r\n$4\r\ncarl\r\n$1\r\n5\r\n"
+    );
+    assert_eq!(send(addr, &["ZREM", "board", "ann", "bob"]), ":2\r\n");
+    assert_eq!(send(addr, &["TYPE", "board"]), "+none\r\n");
+    assert_eq!(send(addr, &["ZPOPMIN", "board"]), "*0\r\n");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cas_applies_only_on_matching_version() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+
+    assert_eq!(send(addr, &["GETVER", "counter"]), "*-1\r\n");
+    let created = send(addr, &["CAS", "counter", "0", "1"]);
+    assert!(created.starts_with(':'));
+    let version = created.trim_start_matches(':').trim_end().to_string();
+
+    // A second create-only write loses the race.
+    assert_eq!(send(addr, &["CAS", "counter", "0", "x"]), "$-1\r\n");
+    assert_eq!(
+        send(addr, &["GETVER", "counter"]),
+        format!("*2\r\n$1\r\n1\r\n:{version}\r\n")
+    );
+
+    let updated = send(addr, &["CAS", "counter", &version, "2"]);
+    assert!(updated.starts_with(':'));
+    assert_ne!(updated, created);
+    assert_eq!(send(addr, &["CAS", "counter", &version, "3"]), "$-1\r\n");
+    assert_eq!(send(addr, &["GET", "counter"]), "$1\r\n2\r\n");
+    assert!(send(addr, &["CAS", "counter", "-1", "3"]).starts_with("-ERR "));
+
+    let _ = shutdown.send(());
+}
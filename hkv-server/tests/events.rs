@@ -0,0 +1,102 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream as StdTcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hkv_common::{CacheEvent, CacheEventKind, Key, Version};
+use hkv_engine::MemoryEngine;
+use hkv_server::events::{ChannelTransport, spawn_consumer};
+use hkv_server::metrics::Metrics;
+use hkv_server::server;
+use tokio::net::TcpListener;
+
+async fn spawn_test_server(metrics: Arc<Metrics>) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let engine = Arc::new(MemoryEngine::new());
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let engine = Arc::clone(&engine);
+            let metrics = Arc::clone(&metrics);
+            tokio::spawn(async move {
+                let _ = server::handle_connection_with_metrics(stream, engine, metrics).await;
+            });
+        }
+    });
+
+    Ok(addr)
+}
+
+fn send_raw(addr: SocketAddr, request: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+fn record(kind: CacheEventKind, tenant: u16, key: &str, version: u64) -> Vec<u8> {
+    CacheEvent::new(
+        kind,
+        tenant,
+        Key::new(key.as_bytes()).unwrap(),
+        Version::new(version),
+    )
+    .to_bytes()
+    .to_vec()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn info_reports_keys_cached_by_the_kernel() {
+    let metrics = Arc::new(Metrics::new());
+    let addr = spawn_test_server(Arc::clone(&metrics)).await.unwrap();
+    let (sender, transport) = ChannelTransport::channel(8);
+    let consumer = spawn_consumer(transport, Arc::clone(metrics.kernel_keys()));
+
+    // One datagram may batch several records.
+    let mut promotions = record(CacheEventKind::Promoted, 0, "user:1", 3);
+    promotions.extend(record(CacheEventKind::Promoted, 0, "user:2", 1));
+    promotions.extend(record(CacheEventKind::Promoted, 2, "session:9", 5));
+    promotions.extend(record(CacheEventKind::Promoted, 0, "user:3", 1));
+    sender.send(promotions).await.unwrap();
+    sender
+        .send(record(CacheEventKind::Evicted, 0, "user:2", 1))
+        .await
+        .unwrap();
+    sender
+        .send(record(CacheEventKind::Invalidated, 0, "user:3", 2))
+        .await
+        .unwrap();
+    // A stale expiry for an older version keeps the newer copy.
+    sender
+        .send(record(CacheEventKind::Expired, 0, "user:1", 2))
+        .await
+        .unwrap();
+    sender.send(vec![0u8; 16]).await.unwrap();
+    drop(sender);
+    consumer.await.unwrap().unwrap();
+
+    let info = tokio::task::spawn_blocking(move || send_raw(addr, b"*1\r\n$4\r\nINFO\r\n"))
+        .await
+        .unwrap()
+        .unwrap();
+    let info = String::from_utf8(info).unwrap();
+    for line in [
+        "kernel_cached_keys:2\r\n",
+        "kernel_events_promoted:4\r\n",
+        "kernel_events_evicted:1\r\n",
+        "kernel_events_demoted:0\r\n",
+        "kernel_events_expired:1\r\n",
+        "kernel_events_invalidated:1\r\n",
+        "kernel_event_errors:1\r\n",
+        "kernel_cached_key_0:tenant=0,key=user\\x3a1,version=3\r\n",
+        "kernel_cached_key_1:tenant=2,key=session\\x3a9,version=5\r\n",
+    ] {
+        assert!(info.contains(line), "missing {line:?} in {info}");
+    }
+    assert!(!info.contains("kernel_cached_key_2:"));
+}
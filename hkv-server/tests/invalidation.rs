@@ -0,0 +1,226 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream as StdTcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hkv_client::KVClient;
+use hkv_common::{
+    BatchPromoteBuilder, BatchPromoteMemory, BatchPromoteRequest, HkvError, HkvResult,
+    InvalidateRequest, Ttl,
+};
+use hkv_engine::MemoryEngine;
+use hkv_server::backend::{CacheBackend, InProcessBackend};
+use hkv_server::invalidation::{InvalidationMode, Invalidator};
+use hkv_server::metrics::Metrics;
+use hkv_server::server;
+use tokio::net::TcpListener;
+
+async fn spawn_test_server(
+    engine: Arc<MemoryEngine>,
+    invalidator: Arc<Invalidator>,
+) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let metrics = Arc::new(Metrics::new());
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let engine = Arc::clone(&engine);
+            let metrics = Arc::clone(&metrics);
+            let invalidator = Arc::clone(&invalidator);
+            tokio::spawn(async move {
+                let _ = server::handle_connection_with_invalidator(
+                    stream,
+                    engine,
+                    metrics,
+                    invalidator,
+                )
+                .await;
+            });
+        }
+    });
+
+    Ok(addr)
+}
+
+fn send_raw(addr: SocketAddr, request: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+/// Promotes `key` at its current engine version, like the promotion manager.
+fn promote(engine: &MemoryEngine, backend: &InProcessBackend, key: &[u8]) {
+    let version = engine.key_version(key).unwrap();
+    let mut batch = BatchPromoteBuilder::new();
+    batch.push(key, b"cached", version, Ttl::INFINITE).unwrap();
+    let (request, mut user) = batch.prepare();
+    backend.batch_promote(&request, &mut user).unwrap();
+    assert!(batch.succeeded(0));
+}
+
+struct UnavailableBackend;
+
+impl CacheBackend for UnavailableBackend {
+    fn batch_promote(
+        &self,
+        _: &BatchPromoteRequest,
+        _: &mut BatchPromoteMemory<'_>,
+    ) -> HkvResult<()> {
+        Err(HkvError::Timeout)
+    }
+
+    fn invalidate(&self, _: &InvalidateRequest) -> HkvResult<()> {
+        Err(HkvError::Timeout)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_del_and_expire_invalidate_promoted_keys() {
+    let engine = Arc::new(MemoryEngine::new());
+    let backend = Arc::new(InProcessBackend::new(64));
+    let invalidator = Arc::new(Invalidator::new(
+        backend.clone(),
+        InvalidationMode::FailOpen,
+    ));
+    let addr = spawn_test_server(Arc::clone(&engine), Arc::clone(&invalidator))
+        .await
+        .unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+
+    for key in [&b"a"[..], b"b", b"c"] {
+        client.set(key, b"v1").unwrap();
+        promote(&engine, &backend, key);
+    }
+    assert_eq!(backend.len(), 3);
+
+    client.set(b"a", b"v2").unwrap();
+    assert!(backend.get(b"a").is_none());
+
+    let response = send_raw(addr, b"*2\r\n$3\r\nDEL\r\n$1\r\nb\r\n").unwrap();
+    assert_eq!(response, b":1\r\n");
+    assert!(backend.get(b"b").is_none());
+
+    let response = send_raw(addr, b"*3\r\n$6\r\nEXPIRE\r\n$1\r\nc\r\n$2\r\n60\r\n").unwrap();
+    assert_eq!(response, b":1\r\n");
+    assert!(backend.is_empty());
+
+    // SET ... EX invalidates once, with the version that carries the TTL.
+    promote(&engine, &backend, b"a");
+    let response = send_raw(
+        addr,
+        b"*5\r\n$3\r\nSET\r\n$1\r\na\r\n$2\r\nv3\r\n$2\r\nEX\r\n$2\r\n60\r\n",
+    )
+    .unwrap();
+    assert_eq!(response, b"+OK\r\n");
+    assert!(backend.is_empty());
+    let (value, _, ttl) = engine.get_with_ttl(b"a").unwrap().unwrap();
+    assert_eq!(&value[..], b"v3");
+    assert!(matches!(ttl, hkv_engine::TtlStatus::ExpiresIn(_)));
+
+    let info = String::from_utf8(client.info().unwrap()).unwrap();
+    assert!(info.contains("kernel_invalidation_enabled:1"), "{info}");
+    assert!(info.contains("kernel_invalidations:7"), "{info}");
+    assert!(info.contains("kernel_invalidation_failures:0"), "{info}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failed_invalidations_are_counted_and_fail_open_by_default() {
+    let engine = Arc::new(MemoryEngine::new());
+    let invalidator = Arc::new(Invalidator::new(
+        Arc::new(UnavailableBackend),
+        InvalidationMode::default(),
+    ));
+    let addr = spawn_test_server(Arc::clone(&engine), Arc::clone(&invalidator))
+        .await
+        .unwrap();
+
+    let response = send_raw(addr, b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n").unwrap();
+    assert_eq!(response, b"+OK\r\n");
+    assert_eq!(invalidator.failures(), 1);
+
+    let client = KVClient::connect(addr.to_string()).unwrap();
+    let info = String::from_utf8(client.info().unwrap()).unwrap();
+    assert!(
+        info.contains("kernel_invalidation_mode:fail-open"),
+        "{info}"
+    );
+    assert!(info.contains("kernel_invalidation_failures:1"), "{info}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fail_closed_reports_the_failure_after_applying_the_write() {
+    let engine = Arc::new(MemoryEngine::new());
+    let invalidator = Arc::new(Invalidator::new(
+        Arc::new(UnavailableBackend),
+        InvalidationMode::FailClosed,
+    ));
+    let addr = spawn_test_server(Arc::clone(&engine), invalidator)
+        .await
+        .unwrap();
+
+    let response = send_raw(addr, b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n").unwrap();
+    assert_eq!(response, b"-ERR kernel cache invalidation failed\r\n");
+    assert!(engine.key_version(b"k").is_some());
+
+    let response = send_raw(addr, b"*2\r\n$3\r\nDEL\r\n$1\r\nk\r\n").unwrap();
+    assert_eq!(response, b"-ERR kernel cache invalidation failed\r\n");
+    assert!(engine.key_version(b"k").is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_store_commands_invalidate_their_destination() {
+    let engine = Arc::new(MemoryEngine::new());
+    let backend = Arc::new(InProcessBackend::new(64));
+    let invalidator = Arc::new(Invalidator::new(
+        backend.clone(),
+        InvalidationMode::FailOpen,
+    ));
+    let addr = spawn_test_server(Arc::clone(&engine), Arc::clone(&invalidator))
+        .await
+        .unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+    let response = send_raw(
+        addr,
+        b"*4\r\n$4\r\nSADD\r\n$1\r\ns\r\n$1\r\n1\r\n$1\r\n2\r\n",
+    )
+    .unwrap();
+    assert_eq!(response, b":2\r\n");
+
+    // SINTERSTORE with a missing source stores nothing and deletes `dest`.
+    let cases: [(&[u8], &[u8]); 3] = [
+        (
+            b"*4\r\n$11\r\nSINTERSTORE\r\n$4\r\ndest\r\n$1\r\ns\r\n$7\r\nmissing\r\n",
+            b":0\r\n",
+        ),
+        (
+            b"*3\r\n$11\r\nSUNIONSTORE\r\n$4\r\ndest\r\n$1\r\ns\r\n",
+            b":2\r\n",
+        ),
+        (
+            b"*3\r\n$10\r\nSDIFFSTORE\r\n$4\r\ndest\r\n$1\r\ns\r\n",
+            b":2\r\n",
+        ),
+    ];
+    for (command, reply) in cases {
+        client.set(b"dest", b"text").unwrap();
+        promote(&engine, &backend, b"dest");
+        let before = engine.key_version(b"dest").unwrap();
+
+        assert_eq!(send_raw(addr, command).unwrap(), reply);
+        assert!(backend.get(b"dest").is_none());
+
+        // A promotion read before the store cannot bring the string back.
+        let mut batch = BatchPromoteBuilder::new();
+        batch.push(b"dest", b"text", before, Ttl::INFINITE).unwrap();
+        let (request, mut user) = batch.prepare();
+        backend.batch_promote(&request, &mut user).unwrap();
+        assert_eq!(batch.status(0), Err(HkvError::StaleVersion));
+    }
+    assert_eq!(invalidator.failures(), 0);
+}
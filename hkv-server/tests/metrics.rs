@@ -99,3 +99,49 @@ async fn protocol_errors_are_counted_in_metrics() {
 
     let _ = shutdown.send(());
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn hotkeys_reports_the_hot_set_with_read_ratios() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+
+    client.set(b"hot", b"v").unwrap();
+    for _ in 0..3 {
+        client.get(b"hot").unwrap();
+    }
+    client.set(b"warm", b"v").unwrap();
+
+    let response = send_raw(addr, b"*2\r\n$7\r\nHOTKEYS\r\n$1\r\n1\r\n").unwrap();
+    assert_eq!(response, b"*1\r\n*4\r\n$3\r\nhot\r\n:4\r\n:3\r\n:1\r\n");
+    let response = send_raw(addr, b"*1\r\n$7\r\nHOTKEYS\r\n").unwrap();
+    assert!(response.starts_with(b"*2\r\n"));
+
+    let info = String::from_utf8(client.info().unwrap()).unwrap();
+    assert!(
+        info.contains("# Hotkeys\r\nhotkeys_tracked:2\r\n"),
+        "{info}"
+    );
+    assert!(
+        info.contains("hotkey_0:key=hot,freq=4,reads=3,writes=1,read_ratio=0.750"),
+        "{info}"
+    );
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn info_escapes_hot_key_bytes() {
+    let (addr, shutdown) = spawn_test_server().await.unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+
+    client.set(b"a:b\r\nhotkey_9:key=x", b"v").unwrap();
+
+    let info = String::from_utf8(client.info().unwrap()).unwrap();
+    assert!(
+        info.contains("hotkey_0:key=a\\x3ab\\x0d\\x0ahotkey_9\\x3akey\\x3dx,freq=1,"),
+        "{info}"
+    );
+    assert!(!info.contains("hotkey_9:"), "{info}");
+
+    let _ = shutdown.send(());
+}
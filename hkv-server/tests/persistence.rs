@@ -0,0 +1,169 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream as StdTcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hkv_client::KVClient;
+use hkv_engine::{AofConfig, FsyncPolicy, KVEngine, MemoryEngine, SnapshotConfig};
+use hkv_server::server;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+async fn spawn_test_server(
+    engine: Arc<MemoryEngine>,
+) -> std::io::Result<(SocketAddr, oneshot::Sender<()>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        let mut shutdown_rx = shutdown_rx;
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_rx => break,
+                accept = listener.accept() => {
+                    let (stream, _) = match accept {
+                        Ok(value) => value,
+                        Err(_) => break,
+                    };
+                    let engine = Arc::clone(&engine);
+                    tokio::spawn(async move {
+                        let _ = server::handle_connection(stream, engine).await;
+                    });
+                }
+            }
+        }
+    });
+
+    Ok((addr, shutdown_tx))
+}
+
+fn send_raw(addr: SocketAddr, request: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("hkv-server-{name}-{}-{nanos}", std::process::id()))
+}
+
+fn aof_engine(path: &Path) -> MemoryEngine {
+    MemoryEngine::with_shard_count(4)
+        .with_aof(AofConfig {
+            path: path.to_path_buf(),
+            fsync: FsyncPolicy::Always,
+        })
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bgrewriteaof_compacts_and_preserves_data() {
+    let path = temp_path("bgrewriteaof");
+    let engine = Arc::new(aof_engine(&path));
+    let (addr, shutdown) = spawn_test_server(Arc::clone(&engine)).await.unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+
+    for round in 0..20 {
+        client
+            .set(b"counter", round.to_string().as_bytes())
+            .unwrap();
+    }
+    client.set(b"session", b"token").unwrap();
+    client.expire(b"session", Duration::from_secs(60)).unwrap();
+    let before = std::fs::metadata(&path).unwrap().len();
+
+    let response = send_raw(addr, b"*1\r\n$12\r\nBGREWRITEAOF\r\n").unwrap();
+    assert_eq!(
+        response,
+        b"+Background append only file rewriting started\r\n"
+    );
+
+    while engine.aof_rewrite_in_progress() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    let after = std::fs::metadata(&path).unwrap().len();
+    assert!(
+        after < before,
+        "rewrite did not shrink: {after} >= {before}"
+    );
+
+    let _ = shutdown.send(());
+    drop(client);
+
+    let restored = aof_engine(&path);
+    assert_eq!(&*restored.get(b"counter").unwrap().unwrap(), b"19");
+    assert_eq!(&*restored.get(b"session").unwrap().unwrap(), b"token");
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bgrewriteaof_requires_aof() {
+    let engine = Arc::new(MemoryEngine::new());
+    let (addr, shutdown) = spawn_test_server(engine).await.unwrap();
+
+    let response = send_raw(addr, b"*1\r\n$12\r\nBGREWRITEAOF\r\n").unwrap();
+    assert_eq!(response, b"-ERR append only file is disabled\r\n");
+
+    let _ = shutdown.send(());
+}
+
+fn snapshot_engine(path: &Path) -> MemoryEngine {
+    MemoryEngine::with_shard_count(4).with_snapshot(SnapshotConfig {
+        path: path.to_path_buf(),
+        save_rules: Vec::new(),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn save_and_bgsave_write_snapshots() {
+    let path = temp_path("bgsave");
+    let engine = Arc::new(snapshot_engine(&path));
+    let (addr, shutdown) = spawn_test_server(Arc::clone(&engine)).await.unwrap();
+    let client = KVClient::connect(addr.to_string()).unwrap();
+
+    client.set(b"alpha", b"1").unwrap();
+    let response = send_raw(addr, b"*1\r\n$4\r\nSAVE\r\n").unwrap();
+    assert_eq!(response, b"+OK\r\n");
+    assert_eq!(engine.changes_since_save(), 0);
+
+    client.set(b"beta", b"2").unwrap();
+    let response = send_raw(addr, b"*1\r\n$6\r\nBGSAVE\r\n").unwrap();
+    assert_eq!(response, b"+Background saving started\r\n");
+    while engine.snapshot_in_progress() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let response = send_raw(addr, b"*1\r\n$8\r\nLASTSAVE\r\n").unwrap();
+    assert_eq!(response, format!(":{}\r\n", engine.last_save()).as_bytes());
+
+    let _ = shutdown.send(());
+    drop(client);
+
+    let restored = snapshot_engine(&path);
+    assert_eq!(restored.load_snapshot().unwrap(), 2);
+    assert_eq!(&*restored.get(b"beta").unwrap().unwrap(), b"2");
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn bgsave_requires_snapshot_config() {
+    let engine = Arc::new(MemoryEngine::new());
+    let (addr, shutdown) = spawn_test_server(engine).await.unwrap();
+
+    let response = send_raw(addr, b"*1\r\n$6\r\nBGSAVE\r\n").unwrap();
+    assert_eq!(response, b"-ERR snapshots are not configured\r\n");
+
+    let _ = shutdown.send(());
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hkv_client::KVClient;
+use hkv_engine::MemoryEngine;
+use hkv_server::backend::InProcessBackend;
+use hkv_server::metrics::Metrics;
+use hkv_server::promotion::{PromotionConfig, PromotionManager};
+use hkv_server::server;
+use tokio::net::TcpListener;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn background_promotion_moves_read_hot_keys_into_the_backend() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let engine = Arc::new(MemoryEngine::new());
+    let metrics = Arc::new(Metrics::new());
+    let backend = Arc::new(InProcessBackend::new(128));
+
+    let server_engine = Arc::clone(&engine);
+    let server_metrics = Arc::clone(&metrics);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let engine = Arc::clone(&server_engine);
+            let metrics = Arc::clone(&server_metrics);
+            tokio::spawn(async move {
+                let _ = server::handle_connection_with_metrics(stream, engine, metrics).await;
+            });
+        }
+    });
+
+    let client = KVClient::connect(addr.to_string()).unwrap();
+    client.set(b"user:1", b"alice").unwrap();
+    client.set(b"counter", b"0").unwrap();
+    for _ in 0..20 {
+        client.get(b"user:1").unwrap();
+        client.set(b"counter", b"1").unwrap();
+    }
+
+    let handle = PromotionManager::new(
+        Arc::clone(&engine),
+        Arc::clone(&metrics),
+        backend.clone(),
+        PromotionConfig {
+            interval: Duration::from_millis(10),
+            min_frequency: 10,
+            ..PromotionConfig::default()
+        },
+    )
+    .spawn();
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while backend.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    handle.stop();
+
+    assert_eq!(backend.get(b"user:1").unwrap().value, b"alice");
+    assert!(backend.get(b"counter").is_none());
+    assert_eq!(backend.len(), 1);
+}
@@ -0,0 +1,242 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream as StdTcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hkv_common::{BatchPromoteBuilder, Ttl};
+use hkv_engine::{KVEngine, MemoryEngine};
+use hkv_server::backend::{CacheBackend, InProcessBackend};
+use hkv_server::invalidation::{InvalidationMode, Invalidator};
+use hkv_server::metrics::Metrics;
+use hkv_server::proxy::{Proxy, ProxyStatsSnapshot};
+use hkv_server::server;
+use tokio::net::TcpListener;
+
+struct Harness {
+    engine: Arc<MemoryEngine>,
+    backend: Arc<InProcessBackend>,
+    proxy: Arc<Proxy>,
+    addr: SocketAddr,
+}
+
+/// Starts a server invalidating `backend` and a proxy in front of it.
+async fn spawn_proxy() -> std::io::Result<Harness> {
+    let engine = Arc::new(MemoryEngine::new());
+    let backend = Arc::new(InProcessBackend::new(64));
+    let invalidator = Arc::new(Invalidator::new(
+        backend.clone(),
+        InvalidationMode::FailOpen,
+    ));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let upstream = listener.local_addr()?;
+    let server_engine = Arc::clone(&engine);
+    tokio::spawn(async move {
+        let metrics = Arc::new(Metrics::new());
+        while let Ok((stream, _)) = listener.accept().await {
+            let engine = Arc::clone(&server_engine);
+            let metrics = Arc::clone(&metrics);
+            let invalidator = Arc::clone(&invalidator);
+            tokio::spawn(async move {
+                let _ = server::handle_connection_with_invalidator(
+                    stream,
+                    engine,
+                    metrics,
+                    invalidator,
+                )
+                .await;
+            });
+        }
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let proxy = Arc::new(Proxy::new(backend.clone(), upstream.to_string()));
+    tokio::spawn(Arc::clone(&proxy).serve(listener));
+
+    Ok(Harness {
+        engine,
+        backend,
+        proxy,
+        addr,
+    })
+}
+
+/// Writes `chunks` with a pause between them, then reads until EOF.
+fn send_chunks(addr: SocketAddr, chunks: &[&[u8]]) -> std::io::Result<Vec<u8>> {
+    let mut stream = StdTcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    for chunk in chunks {
+        stream.write_all(chunk)?;
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+async fn send(addr: SocketAddr, chunks: &[&'static [u8]]) -> Vec<u8> {
+    let chunks = chunks.to_vec();
+    tokio::task::spawn_blocking(move || send_chunks(addr, &chunks))
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn promote(harness: &Harness, key: &[u8], value: &[u8]) {
+    let version = harness.engine.key_version(key).unwrap();
+    let mut batch = BatchPromoteBuilder::new();
+    batch.push(key, value, version, Ttl::INFINITE).unwrap();
+    let (request, mut user) = batch.prepare();
+    harness.backend.batch_promote(&request, &mut user).unwrap();
+    assert!(batch.succeeded(0));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pipelined_commands_are_answered_in_order() {
+    let harness = spawn_proxy().await.unwrap();
+    harness
+        .engine
+        .set(b"hot".to_vec(), b"engine".to_vec())
+        .unwrap();
+    promote(&harness, b"hot", b"kernel");
+
+    let response = send(
+        harness.addr,
+        &[concat!(
+            "*1\r\n$4\r\nPING\r\n",
+            "*3\r\n$3\r\nSET\r\n$4\r\ncold\r\n$1\r\nv\r\n",
+            "*2\r\n$3\r\nGET\r\n$3\r\nhot\r\n",
+            "*2\r\n$3\r\nGET\r\n$4\r\ncold\r\n",
+            "*2\r\n$3\r\nDEL\r\n$4\r\ncold\r\n",
+            "*2\r\n$3\r\nDEL\r\n$4\r\ncold\r\n",
+            "*2\r\n$4\r\nPING\r\n$2\r\nhi\r\n",
+            "*2\r\n$3\r\nGET\r\n$7\r\nmissing\r\n",
+        )
+        .as_bytes()],
+    )
+    .await;
+
+    assert_eq!(
+        response,
+        concat!(
+            "+PONG\r\n",
+            "+OK\r\n",
+            "$6\r\nkernel\r\n",
+            "$1\r\nv\r\n",
+            ":1\r\n",
+            ":0\r\n",
+            "$2\r\nhi\r\n",
+            "$-1\r\n",
+        )
+        .as_bytes()
+    );
+    assert_eq!(
+        harness.proxy.stats(),
+        ProxyStatsSnapshot {
+            hits: 1,
+            misses: 2,
+            pings: 2,
+            forwarded: 5,
+            upstream_errors: 0,
+        }
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pipelined_write_invalidates_before_the_following_get() {
+    let harness = spawn_proxy().await.unwrap();
+    harness.engine.set(b"k".to_vec(), b"v1".to_vec()).unwrap();
+    promote(&harness, b"k", b"v1");
+
+    let response = send(
+        harness.addr,
+        &[concat!(
+            "*2\r\n$3\r\nGET\r\n$1\r\nk\r\n",
+            "*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$2\r\nv2\r\n",
+            "*2\r\n$3\r\nGET\r\n$1\r\nk\r\n",
+        )
+        .as_bytes()],
+    )
+    .await;
+
+    assert_eq!(response, b"$2\r\nv1\r\n+OK\r\n$2\r\nv2\r\n");
+    let stats = harness.proxy.stats();
+    assert_eq!((stats.hits, stats.misses, stats.forwarded), (1, 1, 2));
+    assert_eq!(stats.hit_ratio(), 0.5);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn commands_split_across_reads_are_forwarded_intact() {
+    let harness = spawn_proxy().await.unwrap();
+
+    let response = send(
+        harness.addr,
+        &[
+            b"*3\r\n$3\r\nSET\r\n$5\r\nspl",
+            b"it\r\n$11\r\nhello ",
+            b"world\r\n*2\r\n$3\r\nGET\r\n$5\r\nsplit\r\n*1\r\n$4\r\nPI",
+            b"NG\r\n",
+        ],
+    )
+    .await;
+
+    assert_eq!(response, b"+OK\r\n$11\r\nhello world\r\n+PONG\r\n");
+    assert_eq!(harness.proxy.stats().forwarded, 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn malformed_input_answers_earlier_commands_then_closes() {
+    let harness = spawn_proxy().await.unwrap();
+
+    let response = send(
+        harness.addr,
+        &[b"*2\r\n$3\r\nGET\r\n$1\r\nx\r\nGARBAGE\r\n"],
+    )
+    .await;
+
+    assert_eq!(response, b"$-1\r\n-ERR protocol error\r\n");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn upstream_lost_mid_reply_answers_with_an_error_then_closes() {
+    // An upstream that sends half a reply and hangs up.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let upstream = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 64];
+        let _ = stream.read(&mut request).await.unwrap();
+        stream.write_all(b"+OK\r\n$5\r\nhe").await.unwrap();
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let proxy = Arc::new(Proxy::new(
+        Arc::new(InProcessBackend::new(8)),
+        upstream.to_string(),
+    ));
+    tokio::spawn(Arc::clone(&proxy).serve(listener));
+
+    let response = send(
+        addr,
+        &[concat!(
+            "*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+            "*2\r\n$3\r\nGET\r\n$1\r\nk\r\n",
+            "*2\r\n$3\r\nDEL\r\n$1\r\nk\r\n",
+        )
+        .as_bytes()],
+    )
+    .await;
+
+    assert_eq!(
+        response,
+        b"+OK\r\n-ERR hybridkv upstream connection lost\r\n"
+    );
+    let stats = proxy.stats();
+    assert_eq!((stats.misses, stats.upstream_errors), (1, 1));
+}